@@ -8,17 +8,16 @@ extern crate merlin;
 extern crate rand;
 extern crate sha3;
 
-use ark_bls12_381::G1Projective;
 use ark_ec::ProjectiveCurve;
 use libspartan::{Instance, NIZKGens, NIZK};
 use merlin::Transcript;
 
 use criterion::*;
 
-fn nizk_prove_benchmark<G: ProjectiveCurve>(c: &mut Criterion) {
+fn nizk_prove_benchmark<G: ProjectiveCurve>(c: &mut Criterion, curve: &str) {
   for &s in [10, 12, 16].iter() {
     let plot_config = PlotConfiguration::default().summary_scale(AxisScale::Logarithmic);
-    let mut group = c.benchmark_group("NIZK_prove_benchmark");
+    let mut group = c.benchmark_group(format!("NIZK_prove_benchmark_{}", curve));
     group.plot_config(plot_config);
 
     let num_vars = (2_usize).pow(s as u32);
@@ -40,17 +39,18 @@ fn nizk_prove_benchmark<G: ProjectiveCurve>(c: &mut Criterion) {
           black_box(&inputs),
           black_box(&gens),
           black_box(&mut prover_transcript),
-        );
+        )
+        .unwrap();
       });
     });
     group.finish();
   }
 }
 
-fn nizk_verify_benchmark<G: ProjectiveCurve>(c: &mut Criterion) {
+fn nizk_verify_benchmark<G: ProjectiveCurve>(c: &mut Criterion, curve: &str) {
   for &s in [10, 12, 16].iter() {
     let plot_config = PlotConfiguration::default().summary_scale(AxisScale::Logarithmic);
-    let mut group = c.benchmark_group("NIZK_verify_benchmark");
+    let mut group = c.benchmark_group(format!("NIZK_verify_benchmark_{}", curve));
     group.plot_config(plot_config);
 
     let num_vars = (2_usize).pow(s as u32);
@@ -63,7 +63,7 @@ fn nizk_verify_benchmark<G: ProjectiveCurve>(c: &mut Criterion) {
 
     // produce a proof of satisfiability
     let mut prover_transcript = Transcript::new(b"example");
-    let proof = NIZK::prove(&inst, vars, &inputs, &gens, &mut prover_transcript);
+    let proof = NIZK::prove(&inst, vars, &inputs, &gens, &mut prover_transcript).unwrap();
 
     let name = format!("NIZK_verify_{}", num_cons);
     group.bench_function(&name, move |b| {
@@ -87,10 +87,28 @@ fn set_duration() -> Criterion {
   Criterion::default().sample_size(10)
 }
 
+fn nizk_benchmarks<G: ProjectiveCurve>(c: &mut Criterion, curve: &str) {
+  nizk_prove_benchmark::<G>(c, curve);
+  nizk_verify_benchmark::<G>(c, curve);
+}
+
+// benchmarks BLS12-381 and each curve enabled by a feature
+fn curve_benchmarks(c: &mut Criterion) {
+  nizk_benchmarks::<ark_bls12_381::G1Projective>(c, "BLS12-381");
+  #[cfg(feature = "bn254")]
+  nizk_benchmarks::<ark_bn254::G1Projective>(c, "BN254");
+  #[cfg(feature = "pallas")]
+  nizk_benchmarks::<ark_pallas::Projective>(c, "Pallas");
+  #[cfg(feature = "vesta")]
+  nizk_benchmarks::<ark_vesta::Projective>(c, "Vesta");
+  #[cfg(feature = "secp256k1")]
+  nizk_benchmarks::<libspartan::curves::secp256k1::Projective>(c, "secp256k1");
+}
+
 criterion_group! {
 name = benches_nizk;
 config = set_duration();
-targets = nizk_prove_benchmark::<G1Projective>, nizk_verify_benchmark::<G1Projective>
+targets = curve_benchmarks
 }
 
 criterion_main!(benches_nizk);
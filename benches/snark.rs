@@ -2,17 +2,16 @@
 extern crate libspartan;
 extern crate merlin;
 
-use ark_bls12_381::G1Projective;
 use ark_ec::ProjectiveCurve;
 use libspartan::{Instance, SNARKGens, SNARK};
 use merlin::Transcript;
 
 use criterion::*;
 
-fn snark_encode_benchmark<G: ProjectiveCurve>(c: &mut Criterion) {
+fn snark_encode_benchmark<G: ProjectiveCurve>(c: &mut Criterion, curve: &str) {
   for s in 10..21 {
     let plot_config = PlotConfiguration::default().summary_scale(AxisScale::Logarithmic);
-    let mut group = c.benchmark_group("SNARK_encode_benchmark");
+    let mut group = c.benchmark_group(format!("SNARK_encode_benchmark_{}", curve));
     group.plot_config(plot_config);
 
     let num_vars = (2_usize).pow(s as u32);
@@ -35,10 +34,10 @@ fn snark_encode_benchmark<G: ProjectiveCurve>(c: &mut Criterion) {
   }
 }
 
-fn snark_prove_benchmark<G: ProjectiveCurve>(c: &mut Criterion) {
+fn snark_prove_benchmark<G: ProjectiveCurve>(c: &mut Criterion, curve: &str) {
   for s in 9..21 {
     let plot_config = PlotConfiguration::default().summary_scale(AxisScale::Logarithmic);
-    let mut group = c.benchmark_group("SNARK_prove_benchmark");
+    let mut group = c.benchmark_group(format!("SNARK_prove_benchmark_{}", curve));
     group.plot_config(plot_config);
 
     let num_vars = (2_usize).pow(s as u32);
@@ -67,17 +66,18 @@ fn snark_prove_benchmark<G: ProjectiveCurve>(c: &mut Criterion) {
           black_box(&inputs),
           black_box(&gens),
           black_box(&mut prover_transcript),
-        );
+        )
+        .unwrap();
       });
     });
     group.finish();
   }
 }
 
-fn snark_verify_benchmark<G: ProjectiveCurve>(c: &mut Criterion) {
+fn snark_verify_benchmark<G: ProjectiveCurve>(c: &mut Criterion, curve: &str) {
   for s in 10..21 {
     let plot_config = PlotConfiguration::default().summary_scale(AxisScale::Logarithmic);
-    let mut group = c.benchmark_group("SNARK_verify_benchmark");
+    let mut group = c.benchmark_group(format!("SNARK_verify_benchmark_{}", curve));
     group.plot_config(plot_config);
 
     let num_vars = (2_usize).pow(s as u32);
@@ -102,7 +102,8 @@ fn snark_verify_benchmark<G: ProjectiveCurve>(c: &mut Criterion) {
       &inputs,
       &gens,
       &mut prover_transcript,
-    );
+    )
+    .unwrap();
 
     // verify the proof
     let name = format!("SNARK_verify_{}", num_cons);
@@ -127,11 +128,29 @@ fn set_duration() -> Criterion {
   Criterion::default().sample_size(10)
 }
 
+fn snark_benchmarks<G: ProjectiveCurve>(c: &mut Criterion, curve: &str) {
+  snark_encode_benchmark::<G>(c, curve);
+  snark_prove_benchmark::<G>(c, curve);
+  snark_verify_benchmark::<G>(c, curve);
+}
+
+// benchmarks BLS12-381 and each curve enabled by a feature
+fn curve_benchmarks(c: &mut Criterion) {
+  snark_benchmarks::<ark_bls12_381::G1Projective>(c, "BLS12-381");
+  #[cfg(feature = "bn254")]
+  snark_benchmarks::<ark_bn254::G1Projective>(c, "BN254");
+  #[cfg(feature = "pallas")]
+  snark_benchmarks::<ark_pallas::Projective>(c, "Pallas");
+  #[cfg(feature = "vesta")]
+  snark_benchmarks::<ark_vesta::Projective>(c, "Vesta");
+  #[cfg(feature = "secp256k1")]
+  snark_benchmarks::<libspartan::curves::secp256k1::Projective>(c, "secp256k1");
+}
+
 criterion_group! {
 name = benches_snark;
 config = set_duration();
-targets = snark_encode_benchmark::<G1Projective>,
-snark_prove_benchmark::<G1Projective>, snark_verify_benchmark::<G1Projective>
+targets = curve_benchmarks
 }
 
 criterion_main!(benches_snark);
@@ -137,7 +137,8 @@ fn main() {
     &assignment_inputs,
     &gens,
     &mut prover_transcript,
-  );
+  )
+  .unwrap();
 
   // verify the proof of satisfiability
   let mut verifier_transcript = Transcript::new(b"snark_example");
@@ -44,7 +44,8 @@ pub fn main() {
       &inputs,
       &gens,
       &mut prover_transcript,
-    );
+    )
+    .unwrap();
 
     let mut proof_encoded = vec![];
     proof.serialize(&mut proof_encoded).unwrap();
@@ -0,0 +1,509 @@
+use super::dense_mlpoly::EqPolynomial;
+use super::errors::{ProofVerifyError, R1CSError};
+use super::math::Math;
+use super::r1csinstance::R1CSInstance;
+use super::r1csproof::{R1CSGens, R1CSProof};
+use super::timer::Timer;
+use super::transcript::{AppendToTranscript, ProofTranscript};
+use super::{append_statement, start_proof, InputsAssignment, Instance, VarsAssignment};
+use ark_ec::ProjectiveCurve;
+use ark_ff::PrimeField;
+use ark_serialize::*;
+use core::cmp::max;
+use merlin::Transcript;
+
+/// `BoundaryValue` is the value that a boundary constraint fixes a cell of the trace to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoundaryValue<F> {
+  /// a constant known when the `AIR` is constructed
+  Constant(F),
+  /// the public input with the given index
+  Input(usize),
+}
+
+/// `AIR` holds an algebraic intermediate representation of a computation over a trace of
+/// `num_rows` rows and `num_cols` columns. A transition constraint system in R1CS form applies
+/// to every pair of adjacent rows, and boundary constraints fix individual cells of the trace
+/// to constants or public inputs. The transition matrices use columns `0..num_cols` for the
+/// current row, `num_cols..2 * num_cols` for the next row and `2 * num_cols` for the constant one.
+///
+/// The statement is proven with the Spartan NIZK over the expanded R1CS instance, but the
+/// verifier evaluates the expanded matrices directly from the transition matrices, in time
+/// logarithmic in the number of rows, so the repeated matrices are never committed to with Spark.
+pub struct AIR<F: PrimeField> {
+  num_rows: usize,
+  num_cols: usize,
+  num_inputs: usize,
+  num_cons: usize,
+  A: Vec<(usize, usize, F)>,
+  B: Vec<(usize, usize, F)>,
+  C: Vec<(usize, usize, F)>,
+  boundaries: Vec<(usize, usize, BoundaryValue<F>)>,
+}
+
+impl<F: PrimeField> AIR<F> {
+  /// Constructs a new `AIR` with `num_cons` transition constraints given by `A`, `B` and `C`;
+  /// `num_rows` must be a power of two and at least 2
+  pub fn new(
+    num_rows: usize,
+    num_cols: usize,
+    num_inputs: usize,
+    num_cons: usize,
+    A: &[(usize, usize, F)],
+    B: &[(usize, usize, F)],
+    C: &[(usize, usize, F)],
+  ) -> Result<Self, R1CSError> {
+    if num_rows < 2 || !num_rows.is_power_of_two() || num_cols == 0 {
+      return Err(R1CSError::InvalidTrace);
+    }
+    if num_inputs >= num_rows * num_cols.next_power_of_two() {
+      return Err(R1CSError::InvalidNumberOfInputs);
+    }
+    for &(row, col, _val) in A.iter().chain(B.iter()).chain(C.iter()) {
+      if row >= num_cons || col > 2 * num_cols {
+        return Err(R1CSError::InvalidIndex);
+      }
+    }
+
+    Ok(AIR {
+      num_rows,
+      num_cols,
+      num_inputs,
+      num_cons,
+      A: A.to_vec(),
+      B: B.to_vec(),
+      C: C.to_vec(),
+      boundaries: Vec::new(),
+    })
+  }
+
+  /// Adds a boundary constraint fixing the cell of the trace at `row` and `col` to `value`
+  pub fn add_boundary(
+    &mut self,
+    row: usize,
+    col: usize,
+    value: BoundaryValue<F>,
+  ) -> Result<(), R1CSError> {
+    if row >= self.num_rows || col >= self.num_cols {
+      return Err(R1CSError::InvalidIndex);
+    }
+    if let BoundaryValue::Input(index) = value {
+      if index >= self.num_inputs {
+        return Err(R1CSError::InvalidNumberOfInputs);
+      }
+    }
+    self.boundaries.push((row, col, value));
+    Ok(())
+  }
+
+  // each row of the trace occupies a power-of-two block of variables, and each step a
+  // power-of-two block of constraints; the boundary constraints live in the block of the last
+  // step, which has no transition since there is no next row
+  fn num_cols_padded(&self) -> usize {
+    self.num_cols.next_power_of_two()
+  }
+
+  fn num_cons_per_step(&self) -> usize {
+    max(max(self.num_cons, self.boundaries.len()), 1).next_power_of_two()
+  }
+
+  /// Returns the number of variables of the expanded R1CS instance
+  pub fn get_num_vars(&self) -> usize {
+    self.num_rows * self.num_cols_padded()
+  }
+
+  /// Returns the number of constraints of the expanded R1CS instance
+  pub fn get_num_cons(&self) -> usize {
+    self.num_rows * self.num_cons_per_step()
+  }
+
+  /// Lays out a trace given as `num_rows` rows of `num_cols` values into a `VarsAssignment`
+  pub fn assign_trace(&self, trace: &[Vec<F>]) -> Result<VarsAssignment<F>, R1CSError> {
+    if trace.len() != self.num_rows || trace.iter().any(|row| row.len() != self.num_cols) {
+      return Err(R1CSError::InvalidTrace);
+    }
+    let mut vars = vec![F::zero(); self.get_num_vars()];
+    for (t, row) in trace.iter().enumerate() {
+      vars[t * self.num_cols_padded()..t * self.num_cols_padded() + self.num_cols]
+        .copy_from_slice(row);
+    }
+    VarsAssignment::new(&vars)
+  }
+
+  // expands the transition and boundary constraints into an R1CS instance over the whole trace
+  fn expand(&self) -> R1CSInstance<F> {
+    let (num_vars, num_cols_padded, num_cons_per_step) = (
+      self.get_num_vars(),
+      self.num_cols_padded(),
+      self.num_cons_per_step(),
+    );
+    let expand_transition = |M: &[(usize, usize, F)]| -> Vec<(usize, usize, F)> {
+      let mut mat = Vec::new();
+      for t in 0..self.num_rows - 1 {
+        for &(row, col, val) in M.iter() {
+          let col = if col < self.num_cols {
+            t * num_cols_padded + col
+          } else if col < 2 * self.num_cols {
+            (t + 1) * num_cols_padded + col - self.num_cols
+          } else {
+            num_vars
+          };
+          mat.push((t * num_cons_per_step + row, col, val));
+        }
+      }
+      mat
+    };
+
+    let (mut A, mut B, C) = (
+      expand_transition(&self.A),
+      expand_transition(&self.B),
+      expand_transition(&self.C),
+    );
+    let (A_boundary, B_boundary) = self.boundary_entries();
+    A.extend(A_boundary);
+    B.extend(B_boundary);
+
+    R1CSInstance::new(self.get_num_cons(), num_vars, self.num_inputs, &A, &B, &C)
+  }
+
+  // each boundary constraint (z[cell] - value) * 1 = 0 is a row of the last step
+  #[allow(clippy::type_complexity)]
+  fn boundary_entries(&self) -> (Vec<(usize, usize, F)>, Vec<(usize, usize, F)>) {
+    let num_vars = self.get_num_vars();
+    let offset = (self.num_rows - 1) * self.num_cons_per_step();
+    let mut A = Vec::new();
+    let mut B = Vec::new();
+    for (i, &(row, col, value)) in self.boundaries.iter().enumerate() {
+      A.push((offset + i, row * self.num_cols_padded() + col, F::one()));
+      match value {
+        BoundaryValue::Constant(c) => A.push((offset + i, num_vars, -c)),
+        BoundaryValue::Input(index) => A.push((offset + i, num_vars + 1 + index, -F::one())),
+      }
+      B.push((offset + i, num_vars, F::one()));
+    }
+    (A, B)
+  }
+
+  /// Expands the `AIR` into an `Instance`, which can be proven with `SNARK` or `NIZK`
+  pub fn instance(&self) -> Instance<F> {
+    let inst = self.expand();
+    Instance {
+      num_vars: inst.get_num_vars(),
+      inst,
+      lookups: Vec::new(),
+      memories: Vec::new(),
+      phases: None,
+    }
+  }
+
+  /// Checks if the trace laid out in `vars` satisfies the `AIR` with the given inputs
+  pub fn is_sat(
+    &self,
+    vars: &VarsAssignment<F>,
+    inputs: &InputsAssignment<F>,
+  ) -> Result<bool, R1CSError> {
+    if vars.assignment.len() != self.get_num_vars() {
+      return Err(R1CSError::InvalidNumberOfVars);
+    }
+    if inputs.assignment.len() != self.num_inputs {
+      return Err(R1CSError::InvalidNumberOfInputs);
+    }
+    Ok(self.expand().is_sat(&vars.assignment, &inputs.assignment))
+  }
+
+  // evaluates the multilinear extensions of the expanded A, B and C at (rx, ry) without
+  // expanding them; rx splits into (step, constraint) and ry into (constant flag, row, column)
+  fn evaluate(&self, rx: &[F], ry: &[F]) -> (F, F, F) {
+    let num_row_bits = self.num_rows.log_2();
+    let (rx_t, rx_i) = rx.split_at(num_row_bits);
+    let (ry_0, ry_rest) = (ry[0], &ry[1..]);
+    let (ry_t, ry_j) = ry_rest.split_at(num_row_bits);
+
+    let eq_i = EqPolynomial::new(rx_i.to_vec()).evals();
+    let eq_j = EqPolynomial::new(ry_j.to_vec()).evals();
+
+    // the last row has all bits set, and has no transition
+    let last_x: F = rx_t.iter().product();
+    let last_y: F = ry_t.iter().product();
+    let same = EqPolynomial::new(rx_t.to_vec()).evaluate(ry_t) - last_x * last_y;
+    let next = Self::eval_next(rx_t, ry_t);
+    let first_col: F = ry_rest.iter().map(|r| F::one() - r).product();
+
+    let eval_transition = |M: &[(usize, usize, F)]| -> F {
+      let (mut cur_sum, mut next_sum, mut const_sum) = (F::zero(), F::zero(), F::zero());
+      for &(row, col, val) in M.iter() {
+        if col < self.num_cols {
+          cur_sum += val * eq_i[row] * eq_j[col];
+        } else if col < 2 * self.num_cols {
+          next_sum += val * eq_i[row] * eq_j[col - self.num_cols];
+        } else {
+          const_sum += val * eq_i[row];
+        }
+      }
+      (F::one() - ry_0) * (same * cur_sum + next * next_sum)
+        + ry_0 * first_col * (F::one() - last_x) * const_sum
+    };
+
+    let eval_boundary = |M: &[(usize, usize, F)]| -> F {
+      M.iter()
+        .map(|&(row, col, val)| val * Self::eval_eq_at(rx, row) * Self::eval_eq_at(ry, col))
+        .sum()
+    };
+
+    let (A_boundary, B_boundary) = self.boundary_entries();
+    (
+      eval_transition(&self.A) + eval_boundary(&A_boundary),
+      eval_transition(&self.B) + eval_boundary(&B_boundary),
+      eval_transition(&self.C),
+    )
+  }
+
+  // evaluates eq(r, index) for the bits of index in the canonical order
+  fn eval_eq_at(r: &[F], index: usize) -> F {
+    index
+      .get_bits(r.len())
+      .iter()
+      .zip(r.iter())
+      .map(|(&bit, r)| if bit { *r } else { F::one() - r })
+      .product()
+  }
+
+  // evaluates sum_{t < 2^n - 1} eq(a, t) * eq(b, t + 1), summing over the position k of the
+  // lowest zero bit of t, below which t has ones and t + 1 has zeros
+  fn eval_next(a: &[F], b: &[F]) -> F {
+    let n = a.len();
+    (0..n)
+      .map(|k| {
+        // bit positions are counted from the least significant bit, which comes last in r
+        let low: F = (0..k)
+          .map(|p| a[n - 1 - p] * (F::one() - b[n - 1 - p]))
+          .product();
+        let high: F = (k + 1..n)
+          .map(|p| {
+            a[n - 1 - p] * b[n - 1 - p] + (F::one() - a[n - 1 - p]) * (F::one() - b[n - 1 - p])
+          })
+          .product();
+        low * (F::one() - a[n - 1 - k]) * b[n - 1 - k] * high
+      })
+      .sum()
+  }
+}
+
+impl<G: ProjectiveCurve> AppendToTranscript<G> for AIR<G::ScalarField> {
+  fn append_to_transcript(&self, _label: &'static [u8], transcript: &mut Transcript) {
+    transcript.append_u64(b"num_rows", self.num_rows as u64);
+    transcript.append_u64(b"num_cols", self.num_cols as u64);
+    transcript.append_u64(b"num_inputs", self.num_inputs as u64);
+    transcript.append_u64(b"num_cons", self.num_cons as u64);
+    for M in [&self.A, &self.B, &self.C] {
+      transcript.append_u64(b"num_entries", M.len() as u64);
+      for &(row, col, val) in M.iter() {
+        transcript.append_u64(b"row", row as u64);
+        transcript.append_u64(b"col", col as u64);
+        <Transcript as ProofTranscript<G>>::append_scalar(transcript, b"val", &val);
+      }
+    }
+    transcript.append_u64(b"num_boundaries", self.boundaries.len() as u64);
+    for &(row, col, value) in self.boundaries.iter() {
+      transcript.append_u64(b"row", row as u64);
+      transcript.append_u64(b"col", col as u64);
+      match value {
+        BoundaryValue::Constant(c) => {
+          <Transcript as ProofTranscript<G>>::append_scalar(transcript, b"constant", &c)
+        }
+        BoundaryValue::Input(index) => transcript.append_u64(b"input", index as u64),
+      }
+    }
+  }
+}
+
+/// `AIRSNARKGens` holds public parameters for producing and verifying proofs of `AIR`
+/// satisfiability
+pub struct AIRSNARKGens<G: ProjectiveCurve> {
+  gens_r1cs_sat: R1CSGens<G>,
+}
+
+impl<G: ProjectiveCurve> AIRSNARKGens<G> {
+  /// Constructs a new `AIRSNARKGens` given the shape of the trace, the number of transition
+  /// constraints and the number of boundary constraints
+  pub fn new(num_rows: usize, num_cols: usize, num_cons: usize, num_boundaries: usize) -> Self {
+    let num_vars = num_rows * num_cols.next_power_of_two();
+    let num_cons = num_rows * max(max(num_cons, num_boundaries), 1).next_power_of_two();
+    let gens_r1cs_sat = R1CSGens::<G>::new(b"gens_air_sat", num_cons, num_vars);
+    AIRSNARKGens { gens_r1cs_sat }
+  }
+}
+
+/// `AIRSNARK` holds a proof of the satisfiability of an `AIR`, which the verifier checks
+/// without a commitment to the expanded instance
+#[derive(CanonicalSerialize, CanonicalDeserialize, Debug)]
+pub struct AIRSNARK<G: ProjectiveCurve> {
+  r1cs_sat_proof: R1CSProof<G>,
+  r: (Vec<G::ScalarField>, Vec<G::ScalarField>),
+}
+
+impl<G: ProjectiveCurve> AIRSNARK<G> {
+  fn protocol_name() -> &'static [u8] {
+    b"Spartan AIR proof"
+  }
+
+  /// A method to produce a proof of the satisfiability of an `AIR`
+  pub fn prove(
+    air: &AIR<G::ScalarField>,
+    vars: VarsAssignment<G::ScalarField>,
+    inputs: &InputsAssignment<G::ScalarField>,
+    gens: &AIRSNARKGens<G>,
+    transcript: &mut Transcript,
+  ) -> Self {
+    let timer_prove = Timer::new("AIRSNARK::prove");
+    let mut random_tape =
+      start_proof::<G, _>(AIRSNARK::<G>::protocol_name(), b"air", air, transcript);
+
+    let (r1cs_sat_proof, rx, ry, _witness) = R1CSProof::prove(
+      &air.expand(),
+      vars.assignment,
+      &inputs.assignment,
+      &gens.gens_r1cs_sat,
+      transcript,
+      &mut random_tape,
+    );
+
+    timer_prove.stop();
+    AIRSNARK {
+      r1cs_sat_proof,
+      r: (rx, ry),
+    }
+  }
+
+  /// A method to verify a proof of the satisfiability of an `AIR`
+  pub fn verify(
+    &self,
+    air: &AIR<G::ScalarField>,
+    inputs: &InputsAssignment<G::ScalarField>,
+    transcript: &mut Transcript,
+    gens: &AIRSNARKGens<G>,
+  ) -> Result<(), ProofVerifyError> {
+    let timer_verify = Timer::new("AIRSNARK::verify");
+    if inputs.assignment.len() != air.num_inputs {
+      return Err(ProofVerifyError::InternalError);
+    }
+
+    append_statement::<G, _>(AIRSNARK::<G>::protocol_name(), b"air", air, transcript);
+
+    let (claimed_rx, claimed_ry) = &self.r;
+    if claimed_rx.len() != air.get_num_cons().log_2()
+      || claimed_ry.len() != (2 * air.get_num_vars()).log_2()
+    {
+      return Err(ProofVerifyError::InternalError);
+    }
+    let timer_eval = Timer::new("eval_air");
+    let inst_evals = air.evaluate(claimed_rx, claimed_ry);
+    timer_eval.stop();
+
+    let (rx, ry) = self.r1cs_sat_proof.verify(
+      air.get_num_vars(),
+      air.get_num_cons(),
+      &inputs.assignment,
+      &inst_evals,
+      transcript,
+      &gens.gens_r1cs_sat,
+    )?;
+    if rx != *claimed_rx || ry != *claimed_ry {
+      return Err(ProofVerifyError::InternalError);
+    }
+
+    timer_verify.stop();
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ark_std::test_rng;
+
+  // a Fibonacci trace with columns (a, b, a * b), where a' = b and b' = a + b; the first row is
+  // fixed to (1, 1) and the public input is the value of b in the last row
+  fn fibonacci<F: PrimeField>(num_rows: usize) -> (AIR<F>, Vec<Vec<F>>) {
+    let (num_cols, one) = (3, F::one());
+    let (a, b, c, a_next, b_next, one_col) = (0, 1, 2, 3, 4, 2 * num_cols);
+    let A = vec![
+      (0, a_next, one),
+      (0, b, -one),
+      (1, b_next, one),
+      (1, a, -one),
+      (1, b, -one),
+      (2, a, one),
+    ];
+    let B = vec![(0, one_col, one), (1, one_col, one), (2, b, one)];
+    let C = vec![(2, c, one)];
+    let mut air = AIR::new(num_rows, num_cols, 1, 3, &A, &B, &C).unwrap();
+    air
+      .add_boundary(0, a, BoundaryValue::Constant(one))
+      .unwrap();
+    air
+      .add_boundary(0, b, BoundaryValue::Constant(one))
+      .unwrap();
+    air
+      .add_boundary(num_rows - 1, b, BoundaryValue::Input(0))
+      .unwrap();
+
+    let mut trace = Vec::new();
+    let (mut x, mut y) = (one, one);
+    for _ in 0..num_rows {
+      trace.push(vec![x, y, x * y]);
+      let next = x + y;
+      x = y;
+      y = next;
+    }
+    (air, trace)
+  }
+
+  crate::curves::curve_tests!(field: check_air_evaluate, check_air_evaluate_helper);
+
+  fn check_air_evaluate_helper<F: PrimeField>() {
+    let mut rng = test_rng();
+    let (air, _trace) = fibonacci::<F>(8);
+    let inst = air.expand();
+    let rx = (0..air.get_num_cons().log_2())
+      .map(|_| F::rand(&mut rng))
+      .collect::<Vec<F>>();
+    let ry = (0..(2 * air.get_num_vars()).log_2())
+      .map(|_| F::rand(&mut rng))
+      .collect::<Vec<F>>();
+    assert_eq!(air.evaluate(&rx, &ry), inst.evaluate(&rx, &ry));
+  }
+
+  crate::curves::curve_tests!(group: check_air_snark, check_air_snark_helper);
+
+  fn check_air_snark_helper<G: ProjectiveCurve>() {
+    let num_rows = 16;
+    let (air, mut trace) = fibonacci::<G::ScalarField>(num_rows);
+    let output = trace[num_rows - 1][1];
+    let vars = air.assign_trace(&trace).unwrap();
+    let inputs = InputsAssignment::new(&[output]).unwrap();
+    assert!(air.is_sat(&vars, &inputs).unwrap());
+    assert!(air.instance().is_sat(&vars, &inputs).unwrap());
+
+    let gens = AIRSNARKGens::<G>::new(num_rows, 3, 3, 3);
+    let mut prover_transcript = Transcript::new(b"example");
+    let proof = AIRSNARK::prove(&air, vars, &inputs, &gens, &mut prover_transcript);
+
+    let mut verifier_transcript = Transcript::new(b"example");
+    assert!(proof
+      .verify(&air, &inputs, &mut verifier_transcript, &gens)
+      .is_ok());
+
+    // the proof does not verify with a different output
+    let wrong_inputs = InputsAssignment::new(&[output + G::ScalarField::from(1u64)]).unwrap();
+    let mut verifier_transcript = Transcript::new(b"example");
+    assert!(proof
+      .verify(&air, &wrong_inputs, &mut verifier_transcript, &gens)
+      .is_err());
+
+    // a trace that breaks a transition does not satisfy the AIR
+    trace[5][0] += G::ScalarField::from(1u64);
+    let bad_vars = air.assign_trace(&trace).unwrap();
+    assert!(!air.is_sat(&bad_vars, &inputs).unwrap());
+  }
+}
@@ -0,0 +1,291 @@
+use super::errors::{ProofVerifyError, R1CSError};
+use super::r1csinstance::{R1CSCommitmentGens, R1CSEvalProof};
+use super::r1csproof::{R1CSGens, R1CSProof};
+use super::timer::Timer;
+use super::{
+  append_inst_evals, append_statement, start_proof, ComputationCommitment, ComputationDecommitment,
+  InputsAssignment, Instance, VarsAssignment,
+};
+use ark_ec::ProjectiveCurve;
+use ark_serialize::*;
+use core::cmp::max;
+use merlin::Transcript;
+
+/// `BatchedSNARKGens` holds public parameters for producing and verifying a single proof
+/// of the satisfiability of many assignments to the same R1CS instance
+pub struct BatchedSNARKGens<G: ProjectiveCurve> {
+  gens_r1cs_sat: R1CSGens<G>,
+  gens_r1cs_eval: R1CSCommitmentGens<G>,
+  num_instances: usize,
+}
+
+impl<G: ProjectiveCurve> BatchedSNARKGens<G> {
+  /// Constructs a new `BatchedSNARKGens` given the size of the R1CS statement and the number
+  /// of assignments proven together; batches of any size that pads to the same power of two
+  /// can be proven with the same generators
+  pub fn new(
+    num_cons: usize,
+    num_vars: usize,
+    num_inputs: usize,
+    num_nz_entries: usize,
+    num_instances: usize,
+  ) -> Self {
+    let num_vars_padded = max(num_vars, num_inputs + 1).next_power_of_two();
+    let num_instances = num_instances.next_power_of_two();
+
+    // the assignments are committed together, so the witness polynomial spans all of them
+    let gens_r1cs_sat = R1CSGens::<G>::new(
+      b"gens_r1cs_batched_sat",
+      num_cons,
+      num_instances * num_vars_padded,
+    );
+    let gens_r1cs_eval = R1CSCommitmentGens::new(
+      b"gens_r1cs_eval",
+      num_cons,
+      num_vars_padded,
+      num_inputs,
+      num_nz_entries,
+    );
+    BatchedSNARKGens {
+      gens_r1cs_sat,
+      gens_r1cs_eval,
+      num_instances,
+    }
+  }
+}
+
+/// `BatchedSNARK` holds a single proof of the satisfiability of many assignments to the
+/// same R1CS instance, produced with data-parallel Spartan: the sum-checks run over all
+/// assignments at once, and a single Spark proof of the evaluations of the instance is shared
+#[derive(CanonicalSerialize, CanonicalDeserialize, Debug)]
+pub struct BatchedSNARK<G: ProjectiveCurve> {
+  r1cs_sat_proof: R1CSProof<G>,
+  inst_evals: (G::ScalarField, G::ScalarField, G::ScalarField),
+  r1cs_eval_proof: R1CSEvalProof<G>,
+}
+
+impl<G: ProjectiveCurve> BatchedSNARK<G> {
+  fn protocol_name() -> &'static [u8] {
+    b"Spartan batched SNARK proof"
+  }
+
+  /// A public computation to create a commitment to an R1CS instance; the commitment is the
+  /// same as the one produced by `SNARK::encode` for an instance of the same size
+  pub fn encode(
+    inst: &Instance<G::ScalarField>,
+    gens: &BatchedSNARKGens<G>,
+  ) -> (
+    ComputationCommitment<G>,
+    ComputationDecommitment<G::ScalarField>,
+  ) {
+    let timer_encode = Timer::new("BatchedSNARK::encode");
+    let (comm, decomm) = inst.inst.commit(&gens.gens_r1cs_eval);
+    timer_encode.stop();
+    (
+      ComputationCommitment {
+        comm,
+        lookups: inst.lookups.clone(),
+        memories: inst.memories.clone(),
+        phases: None,
+      },
+      ComputationDecommitment { decomm },
+    )
+  }
+
+  /// A method to produce a single proof of the satisfiability of an R1CS instance by each of
+  /// the given assignments; instances with lookups, memories or a two-phase witness are
+  /// not supported, and the number of assignments must pad to that of the generators
+  pub fn prove(
+    inst: &Instance<G::ScalarField>,
+    comm: &ComputationCommitment<G>,
+    decomm: &ComputationDecommitment<G::ScalarField>,
+    vars: Vec<VarsAssignment<G::ScalarField>>,
+    inputs: &[InputsAssignment<G::ScalarField>],
+    gens: &BatchedSNARKGens<G>,
+    transcript: &mut Transcript,
+  ) -> Result<Self, R1CSError> {
+    let timer_prove = Timer::new("BatchedSNARK::prove");
+    inst.check_plain()?;
+    if vars.len() != inputs.len() {
+      return Err(R1CSError::InvalidNumberOfInputs);
+    }
+    if vars.is_empty() || vars.len().next_power_of_two() != gens.num_instances {
+      return Err(R1CSError::InvalidNumberOfInstances);
+    }
+    for (vars, inputs) in vars.iter().zip(inputs.iter()) {
+      inst.check_assignment(vars, inputs)?;
+    }
+
+    let mut random_tape = start_proof::<G, _>(
+      BatchedSNARK::<G>::protocol_name(),
+      b"comm",
+      &comm.comm,
+      transcript,
+    );
+
+    // we might need to pad variables
+    let num_padded_vars = inst.inst.get_num_vars();
+    let padded_vars = vars
+      .into_iter()
+      .map(|vars| vars.pad_to(num_padded_vars).assignment)
+      .collect::<Vec<_>>();
+    let inputs = inputs
+      .iter()
+      .map(|input| input.assignment.clone())
+      .collect::<Vec<_>>();
+
+    let (r1cs_sat_proof, rx, ry, _witness) = R1CSProof::prove_batched(
+      &[&inst.inst],
+      padded_vars,
+      &inputs,
+      &gens.gens_r1cs_sat,
+      transcript,
+      &mut random_tape,
+    );
+
+    // We send evaluations of A, B, C at r = (rx, ry) as claims
+    // to enable the verifier complete the first sum-check
+    let timer_eval = Timer::new("eval_sparse_polys");
+    let inst_evals = inst.inst.evaluate(&rx, &ry);
+    append_inst_evals::<G>(transcript, &inst_evals);
+    timer_eval.stop();
+
+    let r1cs_eval_proof = R1CSEvalProof::prove(
+      &decomm.decomm,
+      &rx,
+      &ry,
+      &inst_evals,
+      &gens.gens_r1cs_eval,
+      transcript,
+      &mut random_tape,
+    );
+
+    timer_prove.stop();
+    Ok(BatchedSNARK {
+      r1cs_sat_proof,
+      inst_evals,
+      r1cs_eval_proof,
+    })
+  }
+
+  /// A method to verify a single proof of the satisfiability of an R1CS instance by
+  /// assignments with the given inputs
+  pub fn verify(
+    &self,
+    comm: &ComputationCommitment<G>,
+    inputs: &[InputsAssignment<G::ScalarField>],
+    transcript: &mut Transcript,
+    gens: &BatchedSNARKGens<G>,
+  ) -> Result<(), ProofVerifyError> {
+    let timer_verify = Timer::new("BatchedSNARK::verify");
+    if !comm.lookups.is_empty()
+      || !comm.memories.is_empty()
+      || inputs.is_empty()
+      || inputs.len().next_power_of_two() != gens.num_instances
+      || inputs
+        .iter()
+        .any(|input| input.assignment.len() != comm.comm.get_num_inputs())
+    {
+      return Err(ProofVerifyError::InternalError);
+    }
+
+    append_statement::<G, _>(
+      BatchedSNARK::<G>::protocol_name(),
+      b"comm",
+      &comm.comm,
+      transcript,
+    );
+
+    let timer_sat_proof = Timer::new("verify_sat_proof");
+    let inputs = inputs
+      .iter()
+      .map(|input| input.assignment.clone())
+      .collect::<Vec<_>>();
+    let (rx, ry) = self.r1cs_sat_proof.verify_batched(
+      comm.comm.get_num_vars(),
+      comm.comm.get_num_cons(),
+      &inputs,
+      &[self.inst_evals],
+      transcript,
+      &gens.gens_r1cs_sat,
+    )?;
+    timer_sat_proof.stop();
+
+    let timer_eval_proof = Timer::new("verify_eval_proof");
+    append_inst_evals::<G>(transcript, &self.inst_evals);
+    self.r1cs_eval_proof.verify(
+      &comm.comm,
+      &rx,
+      &ry,
+      &self.inst_evals,
+      &gens.gens_r1cs_eval,
+      transcript,
+    )?;
+    timer_eval_proof.stop();
+    timer_verify.stop();
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::tests::{cubic, cubic_assignment};
+  use ark_std::One;
+
+  crate::curves::curve_tests!(group: check_batched_snark, check_batched_snark_helper);
+
+  fn check_batched_snark_helper<G: ProjectiveCurve>() {
+    let inst = cubic::<G::ScalarField>();
+    // five assignments are padded to a batch of eight
+    let (vars, inputs): (Vec<_>, Vec<_>) =
+      (1..6u64).map(cubic_assignment::<G::ScalarField>).unzip();
+    for (vars, inputs) in vars.iter().zip(inputs.iter()) {
+      assert!(inst.is_sat(vars, inputs).unwrap());
+    }
+
+    let gens = BatchedSNARKGens::<G>::new(3, 4, 1, 5, inputs.len());
+    let (comm, decomm) = BatchedSNARK::encode(&inst, &gens);
+
+    // a batch that does not pad to the size of the generators is rejected
+    let mut prover_transcript = Transcript::new(b"example");
+    assert!(matches!(
+      BatchedSNARK::prove(
+        &inst,
+        &comm,
+        &decomm,
+        vars[..2].to_vec(),
+        &inputs[..2],
+        &gens,
+        &mut prover_transcript,
+      ),
+      Err(R1CSError::InvalidNumberOfInstances)
+    ));
+
+    let mut prover_transcript = Transcript::new(b"example");
+    let proof = BatchedSNARK::prove(
+      &inst,
+      &comm,
+      &decomm,
+      vars,
+      &inputs,
+      &gens,
+      &mut prover_transcript,
+    )
+    .unwrap();
+
+    let mut verifier_transcript = Transcript::new(b"example");
+    assert!(proof
+      .verify(&comm, &inputs, &mut verifier_transcript, &gens)
+      .is_ok());
+
+    // the proof does not verify if any one of the inputs differs
+    let mut wrong_inputs = inputs.clone();
+    wrong_inputs[3] =
+      InputsAssignment::new(&[G::ScalarField::from(73u64) + G::ScalarField::one()]).unwrap();
+    let mut verifier_transcript = Transcript::new(b"example");
+    assert!(proof
+      .verify(&comm, &wrong_inputs, &mut verifier_transcript, &gens)
+      .is_err());
+  }
+}
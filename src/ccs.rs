@@ -0,0 +1,441 @@
+use super::ccsinstance::{
+  CCSCommitment, CCSCommitmentGens, CCSDecommitment, CCSEvalProof, CCSInstance,
+};
+use super::errors::{ProofVerifyError, R1CSError};
+use super::r1csproof::{R1CSGens, R1CSProof};
+use super::timer::Timer;
+use super::transcript::ProofTranscript;
+use super::{append_statement, start_proof, InputsAssignment, Instance, VarsAssignment};
+use ark_ec::ProjectiveCurve;
+use ark_ff::PrimeField;
+use ark_serialize::*;
+use core::cmp::max;
+use merlin::Transcript;
+
+/// `CCS` holds a customizable constraint system: matrices `M_0, ..., M_{t-1}` and terms
+/// `(c_j, S_j)`, where each `S_j` is a multiset of matrix indices. An assignment
+/// `z = (vars, 1, inputs)` satisfies it if `sum_j c_j * prod_{i in S_j} M_i z = 0` entry-wise,
+/// where the products are entry-wise. R1CS is the special case `Az * Bz - Cz = 0`.
+pub struct CCS<F: PrimeField> {
+  inst: CCSInstance<F>,
+}
+
+impl<F: PrimeField> CCS<F> {
+  /// Constructs a new `CCS` from matrices given as `(row, col, val)` entries, with columns
+  /// laid out as in `Instance::new`, and terms given as a coefficient and a multiset of matrices
+  pub fn new(
+    num_cons: usize,
+    num_vars: usize,
+    num_inputs: usize,
+    matrices: &[Vec<(usize, usize, F)>],
+    terms: &[(F, Vec<usize>)],
+  ) -> Result<Self, R1CSError> {
+    if matrices.is_empty()
+      || terms.is_empty()
+      || terms
+        .iter()
+        .any(|(_c, multiset)| multiset.is_empty() || multiset.iter().any(|&i| i >= matrices.len()))
+    {
+      return Err(R1CSError::InvalidTerm);
+    }
+
+    // pad the number of variables and constraints as `Instance::new` does
+    let num_vars_padded = max(num_vars, num_inputs + 1).next_power_of_two();
+    let num_cons_padded = max(num_cons, 2).next_power_of_two();
+
+    let mut mats = Vec::new();
+    for M in matrices.iter() {
+      let mut mat: Vec<(usize, usize, F)> = Vec::new();
+      for &(row, col, val) in M.iter() {
+        if row >= num_cons || col >= num_vars + 1 + num_inputs {
+          return Err(R1CSError::InvalidIndex);
+        }
+        if col >= num_vars {
+          mat.push((row, col + num_vars_padded - num_vars, val));
+        } else {
+          mat.push((row, col, val));
+        }
+      }
+      mats.push(mat);
+    }
+
+    let (coeffs, multisets): (Vec<F>, Vec<Vec<usize>>) = terms.iter().cloned().unzip();
+    let inst = CCSInstance::new(
+      num_cons_padded,
+      num_vars_padded,
+      num_inputs,
+      &mats,
+      &coeffs,
+      &multisets,
+    );
+
+    Ok(CCS { inst })
+  }
+
+  /// Converts the R1CS constraints of an `Instance` into a `CCS`; instances with lookups,
+  /// memories or a two-phase witness are rejected
+  pub fn from_instance(inst: &Instance<F>) -> Result<Self, R1CSError> {
+    if !inst.lookups.is_empty() {
+      return Err(R1CSError::InvalidLookup);
+    }
+    if !inst.memories.is_empty() {
+      return Err(R1CSError::InvalidMemory);
+    }
+    if inst.phases.is_some() {
+      return Err(R1CSError::InvalidPhases);
+    }
+    Ok(CCS {
+      inst: CCSInstance::from_r1cs(&inst.inst),
+    })
+  }
+
+  /// Returns the number of matrices
+  pub fn get_num_matrices(&self) -> usize {
+    self.inst.get_num_mats()
+  }
+
+  /// Returns the largest number of matrices multiplied together in a term
+  pub fn get_degree(&self) -> usize {
+    self.inst.get_degree()
+  }
+
+  /// Checks if the `CCS` is satisfiable with given variables and inputs assignments
+  pub fn is_sat(
+    &self,
+    vars: &VarsAssignment<F>,
+    inputs: &InputsAssignment<F>,
+  ) -> Result<bool, R1CSError> {
+    if vars.assignment.len() > self.inst.get_num_vars() {
+      return Err(R1CSError::InvalidNumberOfVars);
+    }
+
+    if inputs.assignment.len() != self.inst.get_num_inputs() {
+      return Err(R1CSError::InvalidNumberOfInputs);
+    }
+
+    let padded_vars = vars.clone().pad_to(self.inst.get_num_vars());
+
+    Ok(
+      self
+        .inst
+        .is_sat(&padded_vars.assignment, &inputs.assignment),
+    )
+  }
+}
+
+/// `CCSSNARKGens` holds public parameters for producing and verifying proofs of `CCS`
+/// satisfiability with the Spartan SNARK
+pub struct CCSSNARKGens<G: ProjectiveCurve> {
+  gens_ccs_sat: R1CSGens<G>,
+  gens_ccs_eval: CCSCommitmentGens<G>,
+}
+
+impl<G: ProjectiveCurve> CCSSNARKGens<G> {
+  /// Constructs a new `CCSSNARKGens` given the size of the `CCS`, where `num_nz_entries`
+  /// specifies the maximum number of non-zero entries in any of the `num_matrices` matrices
+  /// and `degree` is the largest number of matrices multiplied together in a term
+  pub fn new(
+    num_cons: usize,
+    num_vars: usize,
+    num_inputs: usize,
+    num_nz_entries: usize,
+    num_matrices: usize,
+    degree: usize,
+  ) -> Self {
+    let num_vars_padded = max(num_vars, num_inputs + 1).next_power_of_two();
+    let num_cons_padded = max(num_cons, 2).next_power_of_two();
+
+    let gens_ccs_sat =
+      R1CSGens::new_with_degree(b"gens_ccs_sat", num_cons_padded, num_vars_padded, degree);
+    let gens_ccs_eval = CCSCommitmentGens::new(
+      b"gens_ccs_eval",
+      num_cons_padded,
+      num_vars_padded,
+      num_inputs,
+      num_nz_entries,
+      num_matrices,
+    );
+    CCSSNARKGens {
+      gens_ccs_sat,
+      gens_ccs_eval,
+    }
+  }
+}
+
+/// `CCSComputationCommitment` holds a public preprocessed `CCS`
+pub struct CCSComputationCommitment<G: ProjectiveCurve> {
+  comm: CCSCommitment<G>,
+}
+
+/// `CCSComputationDecommitment` holds information to decommit `CCSComputationCommitment`
+pub struct CCSComputationDecommitment<F> {
+  decomm: CCSDecommitment<F>,
+}
+
+/// `CCSSNARK` holds a proof of the satisfiability of a `CCS` produced by Spartan SNARK
+#[derive(CanonicalSerialize, CanonicalDeserialize, Debug)]
+pub struct CCSSNARK<G: ProjectiveCurve> {
+  ccs_sat_proof: R1CSProof<G>,
+  inst_evals: Vec<G::ScalarField>,
+  ccs_eval_proof: CCSEvalProof<G>,
+}
+
+impl<G: ProjectiveCurve> CCSSNARK<G> {
+  fn protocol_name() -> &'static [u8] {
+    b"Spartan CCS SNARK proof"
+  }
+
+  /// A public computation to create a commitment to a `CCS`
+  pub fn encode(
+    inst: &CCS<G::ScalarField>,
+    gens: &CCSSNARKGens<G>,
+  ) -> (
+    CCSComputationCommitment<G>,
+    CCSComputationDecommitment<G::ScalarField>,
+  ) {
+    let timer_encode = Timer::new("CCSSNARK::encode");
+    let (comm, decomm) = inst.inst.commit(&gens.gens_ccs_eval);
+    timer_encode.stop();
+    (
+      CCSComputationCommitment { comm },
+      CCSComputationDecommitment { decomm },
+    )
+  }
+
+  /// A method to produce a SNARK proof of the satisfiability of a `CCS`
+  pub fn prove(
+    inst: &CCS<G::ScalarField>,
+    comm: &CCSComputationCommitment<G>,
+    decomm: &CCSComputationDecommitment<G::ScalarField>,
+    vars: VarsAssignment<G::ScalarField>,
+    inputs: &InputsAssignment<G::ScalarField>,
+    gens: &CCSSNARKGens<G>,
+    transcript: &mut Transcript,
+  ) -> Self {
+    let timer_prove = Timer::new("CCSSNARK::prove");
+
+    let mut random_tape = start_proof::<G, _>(
+      CCSSNARK::<G>::protocol_name(),
+      b"comm",
+      &comm.comm,
+      transcript,
+    );
+
+    // we might need to pad variables
+    let padded_vars = vars.pad_to(inst.inst.get_num_vars());
+
+    let (ccs_sat_proof, rx, ry, _witness) = R1CSProof::prove(
+      &inst.inst,
+      padded_vars.assignment,
+      &inputs.assignment,
+      &gens.gens_ccs_sat,
+      transcript,
+      &mut random_tape,
+    );
+
+    // We send evaluations of the matrices at r = (rx, ry) as claims
+    // to enable the verifier complete the sum-checks
+    let timer_eval = Timer::new("eval_sparse_polys");
+    let inst_evals = inst.inst.evaluate(&rx, &ry);
+    <Transcript as ProofTranscript<G>>::append_scalars(transcript, b"Mr_claims", &inst_evals);
+    timer_eval.stop();
+
+    let ccs_eval_proof = CCSEvalProof::prove(
+      &decomm.decomm,
+      &rx,
+      &ry,
+      &inst_evals,
+      &gens.gens_ccs_eval,
+      transcript,
+      &mut random_tape,
+    );
+
+    timer_prove.stop();
+    CCSSNARK {
+      ccs_sat_proof,
+      inst_evals,
+      ccs_eval_proof,
+    }
+  }
+
+  /// A method to verify the SNARK proof of the satisfiability of a `CCS`
+  pub fn verify(
+    &self,
+    comm: &CCSComputationCommitment<G>,
+    input: &InputsAssignment<G::ScalarField>,
+    transcript: &mut Transcript,
+    gens: &CCSSNARKGens<G>,
+  ) -> Result<(), ProofVerifyError> {
+    let timer_verify = Timer::new("CCSSNARK::verify");
+    // append a commitment to the computation to the transcript
+    append_statement::<G, _>(
+      CCSSNARK::<G>::protocol_name(),
+      b"comm",
+      &comm.comm,
+      transcript,
+    );
+
+    if input.assignment.len() != comm.comm.get_num_inputs()
+      || self.inst_evals.len() != comm.comm.get_num_mats()
+    {
+      return Err(ProofVerifyError::InternalError);
+    }
+
+    let timer_sat_proof = Timer::new("verify_sat_proof");
+    let (rx, ry) = self.ccs_sat_proof.verify_ccs(
+      comm.comm.get_num_vars(),
+      comm.comm.get_num_cons(),
+      &input.assignment,
+      comm.comm.get_terms(),
+      &self.inst_evals,
+      transcript,
+      &gens.gens_ccs_sat,
+    )?;
+    timer_sat_proof.stop();
+
+    let timer_eval_proof = Timer::new("verify_eval_proof");
+    <Transcript as ProofTranscript<G>>::append_scalars(transcript, b"Mr_claims", &self.inst_evals);
+    self.ccs_eval_proof.verify(
+      &comm.comm,
+      &rx,
+      &ry,
+      &self.inst_evals,
+      &gens.gens_ccs_eval,
+      transcript,
+    )?;
+    timer_eval_proof.stop();
+    timer_verify.stop();
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ark_std::One;
+
+  crate::curves::curve_tests!(group: check_ccs_snark, check_ccs_snark_helper);
+
+  fn check_ccs_snark_helper<G: ProjectiveCurve>() {
+    // a Plonkish gate q_L * a + q_R * b + q_M * a * b + q_O * c + q_C = 0 over selector
+    // matrices, with (a, b, c) in rows (0, 1, 2), (2, 3, 4), (4, 0, 5) of the variables:
+    // z_2 = z_0 + z_1, z_4 = z_2 * z_3, and z_5 = z_4 * z_0 + x
+    let (num_cons, num_vars, num_inputs) = (3, 6, 1);
+    let one = G::ScalarField::one();
+    let wires = [(0, 1, 2), (2, 3, 4), (4, 0, 5)];
+    let a = wires
+      .iter()
+      .enumerate()
+      .map(|(r, w)| (r, w.0, one))
+      .collect();
+    let b = wires
+      .iter()
+      .enumerate()
+      .map(|(r, w)| (r, w.1, one))
+      .collect();
+    let c = wires
+      .iter()
+      .enumerate()
+      .map(|(r, w)| (r, w.2, one))
+      .collect();
+    // selectors are multiplied by the constant column
+    let const_col = num_vars;
+    let q_L = vec![(0, const_col, one)];
+    let q_R = vec![(0, const_col, one)];
+    let q_M = vec![(1, const_col, one), (2, const_col, one)];
+    let q_O = vec![
+      (0, const_col, -one),
+      (1, const_col, -one),
+      (2, const_col, -one),
+    ];
+    // the public input is added in the last gate
+    let q_X = vec![(2, num_vars + 1, one)];
+    let matrices = vec![a, b, c, q_L, q_R, q_M, q_O, q_X];
+    let terms = vec![
+      (one, vec![3, 0]),
+      (one, vec![4, 1]),
+      (one, vec![5, 0, 1]),
+      (one, vec![6, 2]),
+      (one, vec![7]),
+    ];
+    let inst = CCS::new(num_cons, num_vars, num_inputs, &matrices, &terms).unwrap();
+    assert_eq!(inst.get_degree(), 3);
+
+    let assignment = |z_5_offset: u64| {
+      let z = [2u64, 3, 5, 7, 35, 70 + 11 + z_5_offset]
+        .iter()
+        .map(|v| G::ScalarField::from(*v))
+        .collect::<Vec<_>>();
+      (
+        VarsAssignment::new(&z).unwrap(),
+        InputsAssignment::new(&[G::ScalarField::from(11u64)]).unwrap(),
+      )
+    };
+    let (vars, inputs) = assignment(0);
+    assert!(inst.is_sat(&vars, &inputs).unwrap());
+    let (bad_vars, _inputs) = assignment(1);
+    assert!(!inst.is_sat(&bad_vars, &inputs).unwrap());
+
+    let gens = CCSSNARKGens::<G>::new(
+      num_cons,
+      num_vars,
+      num_inputs,
+      num_cons,
+      inst.get_num_matrices(),
+      inst.get_degree(),
+    );
+    let (comm, decomm) = CCSSNARK::encode(&inst, &gens);
+
+    let mut prover_transcript = Transcript::new(b"example");
+    let proof = CCSSNARK::prove(
+      &inst,
+      &comm,
+      &decomm,
+      vars,
+      &inputs,
+      &gens,
+      &mut prover_transcript,
+    );
+
+    let mut verifier_transcript = Transcript::new(b"example");
+    assert!(proof
+      .verify(&comm, &inputs, &mut verifier_transcript, &gens)
+      .is_ok());
+
+    // the proof does not verify against other inputs
+    let mut verifier_transcript = Transcript::new(b"example");
+    let other_inputs = InputsAssignment::new(&[G::ScalarField::from(12u64)]).unwrap();
+    assert!(proof
+      .verify(&comm, &other_inputs, &mut verifier_transcript, &gens)
+      .is_err());
+  }
+
+  crate::curves::curve_tests!(group: check_r1cs_as_ccs, check_r1cs_as_ccs_helper);
+
+  fn check_r1cs_as_ccs_helper<G: ProjectiveCurve>() {
+    let (num_cons, num_vars, num_inputs, num_nz_entries) = (256, 256, 10, 256);
+    let (inst, vars, inputs) =
+      Instance::<G::ScalarField>::produce_synthetic_r1cs(num_cons, num_vars, num_inputs);
+    let ccs = CCS::from_instance(&inst).unwrap();
+    assert!(ccs.is_sat(&vars, &inputs).unwrap());
+
+    let gens = CCSSNARKGens::<G>::new(num_cons, num_vars, num_inputs, num_nz_entries, 3, 2);
+    let (comm, decomm) = CCSSNARK::encode(&ccs, &gens);
+
+    let mut prover_transcript = Transcript::new(b"example");
+    let proof = CCSSNARK::prove(
+      &ccs,
+      &comm,
+      &decomm,
+      vars,
+      &inputs,
+      &gens,
+      &mut prover_transcript,
+    );
+
+    let mut verifier_transcript = Transcript::new(b"example");
+    assert!(proof
+      .verify(&comm, &inputs, &mut verifier_transcript, &gens)
+      .is_ok());
+  }
+}
@@ -0,0 +1,401 @@
+use super::dense_mlpoly::DensePolynomial;
+use super::errors::ProofVerifyError;
+use super::math::Math;
+use super::r1csinstance::R1CSInstance;
+use super::random::RandomTape;
+use super::sparse_mlpoly::{
+  MultiSparseMatPolynomialAsDense, SparseMatEntry, SparseMatPolyCommitment,
+  SparseMatPolyCommitmentGens, SparseMatPolyEvalProof, SparseMatPolynomial,
+};
+use super::timer::Timer;
+use super::transcript::{AppendToTranscript, ProofTranscript};
+use ark_ec::ProjectiveCurve;
+use ark_ff::PrimeField;
+use ark_serialize::*;
+use merlin::Transcript;
+
+// A customizable constraint system over matrices M_0, ..., M_{t-1}, which is satisfied by
+// z = (vars, 1, inputs) if sum_j coeffs[j] * prod_{i in multisets[j]} (M_i z) = 0 entry-wise
+#[derive(Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct CCSInstance<F: PrimeField> {
+  num_cons: usize,
+  num_vars: usize,
+  num_inputs: usize,
+  mats: Vec<SparseMatPolynomial<F>>,
+  coeffs: Vec<F>,
+  multisets: Vec<Vec<usize>>,
+}
+
+impl<G: ProjectiveCurve> AppendToTranscript<G> for CCSInstance<G::ScalarField> {
+  fn append_to_transcript(&self, _label: &'static [u8], transcript: &mut Transcript) {
+    let mut data = vec![];
+    self.serialize(&mut data).unwrap();
+
+    transcript.append_message(b"CCSInstance", &data);
+  }
+}
+
+// the view of a constraint system that the Spartan prover needs: the products M_i z of its
+// matrices with an assignment, and the terms that combine them into the constraints
+pub trait CCSMatrices<F: PrimeField> {
+  fn get_num_cons(&self) -> usize;
+
+  fn get_num_vars(&self) -> usize;
+
+  fn get_terms(&self) -> (Vec<F>, Vec<Vec<usize>>);
+
+  fn multiply_vec_all(&self, num_rows: usize, num_cols: usize, z: &[F]) -> Vec<DensePolynomial<F>>;
+
+  fn compute_eval_tables(&self, num_rows: usize, num_cols: usize, evals: &[F]) -> Vec<Vec<F>>;
+}
+
+// R1CS is the special case Az * Bz - Cz = 0
+pub fn r1cs_terms<F: PrimeField>() -> (Vec<F>, Vec<Vec<usize>>) {
+  (vec![F::one(), -F::one()], vec![vec![0, 1], vec![2]])
+}
+
+impl<F: PrimeField> CCSMatrices<F> for R1CSInstance<F> {
+  fn get_num_cons(&self) -> usize {
+    self.get_num_cons()
+  }
+
+  fn get_num_vars(&self) -> usize {
+    self.get_num_vars()
+  }
+
+  fn get_terms(&self) -> (Vec<F>, Vec<Vec<usize>>) {
+    r1cs_terms()
+  }
+
+  fn multiply_vec_all(&self, num_rows: usize, num_cols: usize, z: &[F]) -> Vec<DensePolynomial<F>> {
+    let (Az, Bz, Cz) = self.multiply_vec(num_rows, num_cols, z);
+    vec![Az, Bz, Cz]
+  }
+
+  fn compute_eval_tables(&self, num_rows: usize, num_cols: usize, evals: &[F]) -> Vec<Vec<F>> {
+    let (evals_A, evals_B, evals_C) = self.compute_eval_table_sparse(num_rows, num_cols, evals);
+    vec![evals_A, evals_B, evals_C]
+  }
+}
+
+impl<F: PrimeField> CCSMatrices<F> for CCSInstance<F> {
+  fn get_num_cons(&self) -> usize {
+    self.num_cons
+  }
+
+  fn get_num_vars(&self) -> usize {
+    self.num_vars
+  }
+
+  fn get_terms(&self) -> (Vec<F>, Vec<Vec<usize>>) {
+    (self.coeffs.clone(), self.multisets.clone())
+  }
+
+  fn multiply_vec_all(&self, num_rows: usize, num_cols: usize, z: &[F]) -> Vec<DensePolynomial<F>> {
+    self.multiply_vec(num_rows, num_cols, z)
+  }
+
+  fn compute_eval_tables(&self, num_rows: usize, num_cols: usize, evals: &[F]) -> Vec<Vec<F>> {
+    self.compute_eval_table_sparse(num_rows, num_cols, evals)
+  }
+}
+
+pub struct CCSCommitmentGens<G: ProjectiveCurve> {
+  gens: SparseMatPolyCommitmentGens<G>,
+}
+
+impl<G: ProjectiveCurve> CCSCommitmentGens<G> {
+  pub fn new(
+    label: &'static [u8],
+    num_cons: usize,
+    num_vars: usize,
+    num_inputs: usize,
+    num_nz_entries: usize,
+    num_mats: usize,
+  ) -> CCSCommitmentGens<G> {
+    assert!(num_inputs < num_vars);
+    let num_poly_vars_x = num_cons.log_2() as usize;
+    let num_poly_vars_y = (2 * num_vars).log_2() as usize;
+    let gens = SparseMatPolyCommitmentGens::new(
+      label,
+      num_poly_vars_x,
+      num_poly_vars_y,
+      num_nz_entries,
+      num_mats,
+    );
+    CCSCommitmentGens { gens }
+  }
+}
+
+// the terms of the instance are public and are committed to in the clear
+#[derive(Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct CCSCommitment<G: ProjectiveCurve> {
+  num_cons: usize,
+  num_vars: usize,
+  num_inputs: usize,
+  coeffs: Vec<G::ScalarField>,
+  multisets: Vec<Vec<usize>>,
+  comm: SparseMatPolyCommitment<G>,
+}
+
+impl<G: ProjectiveCurve> AppendToTranscript<G> for CCSCommitment<G> {
+  fn append_to_transcript(&self, _label: &'static [u8], transcript: &mut Transcript) {
+    transcript.append_u64(b"num_cons", self.num_cons as u64);
+    transcript.append_u64(b"num_vars", self.num_vars as u64);
+    transcript.append_u64(b"num_inputs", self.num_inputs as u64);
+    <Transcript as ProofTranscript<G>>::append_scalars(transcript, b"coeffs", &self.coeffs);
+    for multiset in self.multisets.iter() {
+      transcript.append_u64(b"multiset_len", multiset.len() as u64);
+      for &i in multiset.iter() {
+        transcript.append_u64(b"multiset_entry", i as u64);
+      }
+    }
+    self.comm.append_to_transcript(b"comm", transcript);
+  }
+}
+
+pub struct CCSDecommitment<F> {
+  dense: MultiSparseMatPolynomialAsDense<F>,
+}
+
+impl<G: ProjectiveCurve> CCSCommitment<G> {
+  pub fn get_num_cons(&self) -> usize {
+    self.num_cons
+  }
+
+  pub fn get_num_vars(&self) -> usize {
+    self.num_vars
+  }
+
+  pub fn get_num_inputs(&self) -> usize {
+    self.num_inputs
+  }
+
+  pub fn get_num_mats(&self) -> usize {
+    self.comm.get_batch_size()
+  }
+
+  pub fn get_terms(&self) -> (&[G::ScalarField], &[Vec<usize>]) {
+    (&self.coeffs, &self.multisets)
+  }
+}
+
+impl<F: PrimeField> CCSInstance<F> {
+  pub fn new(
+    num_cons: usize,
+    num_vars: usize,
+    num_inputs: usize,
+    mats: &[Vec<(usize, usize, F)>],
+    coeffs: &[F],
+    multisets: &[Vec<usize>],
+  ) -> CCSInstance<F> {
+    Timer::print(&format!("number_of_constraints {}", num_cons));
+    Timer::print(&format!("number_of_variables {}", num_vars));
+    Timer::print(&format!("number_of_inputs {}", num_inputs));
+    Timer::print(&format!("number_of_matrices {}", mats.len()));
+
+    // check that num_cons and num_vars are powers of 2, and that number_inputs + 1 <= num_vars
+    assert_eq!(num_cons.next_power_of_two(), num_cons);
+    assert_eq!(num_vars.next_power_of_two(), num_vars);
+    assert!(num_inputs < num_vars);
+
+    // check that every term is a non-empty multiset of existing matrices
+    assert!(!mats.is_empty());
+    assert_eq!(coeffs.len(), multisets.len());
+    assert!(multisets
+      .iter()
+      .all(|multiset| !multiset.is_empty() && multiset.iter().all(|&i| i < mats.len())));
+
+    let num_poly_vars_x = num_cons.log_2() as usize;
+    let num_poly_vars_y = (2 * num_vars).log_2() as usize;
+    let mats = mats
+      .iter()
+      .map(|M| {
+        let entries = M
+          .iter()
+          .map(|&(row, col, val)| SparseMatEntry::new(row, col, val))
+          .collect::<Vec<SparseMatEntry<F>>>();
+        SparseMatPolynomial::new(num_poly_vars_x, num_poly_vars_y, entries)
+      })
+      .collect::<Vec<SparseMatPolynomial<F>>>();
+
+    CCSInstance {
+      num_cons,
+      num_vars,
+      num_inputs,
+      mats,
+      coeffs: coeffs.to_vec(),
+      multisets: multisets.to_vec(),
+    }
+  }
+
+  pub fn from_r1cs(inst: &R1CSInstance<F>) -> CCSInstance<F> {
+    let (A, B, C) = inst.get_matrices();
+    let (coeffs, multisets) = r1cs_terms();
+    CCSInstance::new(
+      inst.get_num_cons(),
+      inst.get_num_vars(),
+      inst.get_num_inputs(),
+      &[A, B, C],
+      &coeffs,
+      &multisets,
+    )
+  }
+
+  pub fn get_num_vars(&self) -> usize {
+    self.num_vars
+  }
+
+  pub fn get_num_cons(&self) -> usize {
+    self.num_cons
+  }
+
+  pub fn get_num_inputs(&self) -> usize {
+    self.num_inputs
+  }
+
+  pub fn get_num_mats(&self) -> usize {
+    self.mats.len()
+  }
+
+  // the largest number of matrices multiplied together in a term
+  pub fn get_degree(&self) -> usize {
+    self
+      .multisets
+      .iter()
+      .map(|multiset| multiset.len())
+      .max()
+      .unwrap_or(1)
+  }
+
+  pub fn get_terms(&self) -> (&[F], &[Vec<usize>]) {
+    (&self.coeffs, &self.multisets)
+  }
+
+  // evaluates sum_j coeffs[j] * prod_{i in multisets[j]} vals[i]
+  pub fn combine(coeffs: &[F], multisets: &[Vec<usize>], vals: &[F]) -> F {
+    coeffs
+      .iter()
+      .zip(multisets.iter())
+      .map(|(c, multiset)| multiset.iter().fold(*c, |prod, &i| prod * vals[i]))
+      .sum()
+  }
+
+  pub fn is_sat(&self, vars: &[F], input: &[F]) -> bool {
+    assert_eq!(vars.len(), self.num_vars);
+    assert_eq!(input.len(), self.num_inputs);
+
+    let z = {
+      let mut z = vars.to_vec();
+      z.extend(&vec![F::one()]);
+      z.extend(input);
+      z
+    };
+
+    let Mz = self
+      .mats
+      .iter()
+      .map(|M| M.multiply_vec(self.num_cons, self.num_vars + self.num_inputs + 1, &z))
+      .collect::<Vec<Vec<F>>>();
+
+    (0..self.num_cons).all(|row| {
+      let vals = Mz.iter().map(|v| v[row]).collect::<Vec<F>>();
+      CCSInstance::combine(&self.coeffs, &self.multisets, &vals).is_zero()
+    })
+  }
+
+  pub fn multiply_vec(&self, num_rows: usize, num_cols: usize, z: &[F]) -> Vec<DensePolynomial<F>> {
+    assert_eq!(num_rows, self.num_cons);
+    assert_eq!(z.len(), num_cols);
+    assert!(num_cols > self.num_vars);
+    self
+      .mats
+      .iter()
+      .map(|M| DensePolynomial::new(M.multiply_vec(num_rows, num_cols, z)))
+      .collect()
+  }
+
+  pub fn compute_eval_table_sparse(
+    &self,
+    num_rows: usize,
+    num_cols: usize,
+    evals: &[F],
+  ) -> Vec<Vec<F>> {
+    assert_eq!(num_rows, self.num_cons);
+    assert!(num_cols > self.num_vars);
+    self
+      .mats
+      .iter()
+      .map(|M| M.compute_eval_table_sparse(evals, num_rows, num_cols))
+      .collect()
+  }
+
+  pub fn evaluate(&self, rx: &[F], ry: &[F]) -> Vec<F> {
+    SparseMatPolynomial::multi_evaluate(&self.mats.iter().collect::<Vec<_>>(), rx, ry)
+  }
+
+  pub fn commit<G: ProjectiveCurve<ScalarField = F>>(
+    &self,
+    gens: &CCSCommitmentGens<G>,
+  ) -> (CCSCommitment<G>, CCSDecommitment<F>) {
+    let (comm, dense) =
+      SparseMatPolynomial::multi_commit(&self.mats.iter().collect::<Vec<_>>(), &gens.gens);
+    let ccs_comm = CCSCommitment {
+      num_cons: self.num_cons,
+      num_vars: self.num_vars,
+      num_inputs: self.num_inputs,
+      coeffs: self.coeffs.clone(),
+      multisets: self.multisets.clone(),
+      comm,
+    };
+
+    let ccs_decomm = CCSDecommitment { dense };
+
+    (ccs_comm, ccs_decomm)
+  }
+}
+
+#[derive(Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct CCSEvalProof<G: ProjectiveCurve> {
+  proof: SparseMatPolyEvalProof<G>,
+}
+
+impl<G: ProjectiveCurve> CCSEvalProof<G> {
+  pub fn prove(
+    decomm: &CCSDecommitment<G::ScalarField>,
+    rx: &[G::ScalarField], // point at which the polynomial is evaluated
+    ry: &[G::ScalarField],
+    evals: &[G::ScalarField],
+    gens: &CCSCommitmentGens<G>,
+    transcript: &mut Transcript,
+    random_tape: &mut RandomTape<G>,
+  ) -> CCSEvalProof<G> {
+    let timer = Timer::new("CCSEvalProof::prove");
+    let proof = SparseMatPolyEvalProof::prove(
+      &decomm.dense,
+      rx,
+      ry,
+      evals,
+      &gens.gens,
+      transcript,
+      random_tape,
+    );
+    timer.stop();
+
+    CCSEvalProof { proof }
+  }
+
+  pub fn verify(
+    &self,
+    comm: &CCSCommitment<G>,
+    rx: &[G::ScalarField], // point at which the CCS matrix polynomials are evaluated
+    ry: &[G::ScalarField],
+    evals: &[G::ScalarField],
+    gens: &CCSCommitmentGens<G>,
+    transcript: &mut Transcript,
+  ) -> Result<(), ProofVerifyError> {
+    self
+      .proof
+      .verify(&comm.comm, rx, ry, evals, &gens.gens, transcript)
+  }
+}
@@ -1,8 +1,11 @@
+use super::errors::ProofVerifyError;
+use super::transcript::ProofTranscript;
 use ark_ec::msm::VariableBaseMSM;
 use ark_ec::ProjectiveCurve;
 use ark_ff::PrimeField;
 use ark_std::rand::SeedableRng;
 use digest::{ExtendableOutput, Input};
+use merlin::Transcript;
 use rand_chacha::ChaCha20Rng;
 use sha3::Shake256;
 use std::io::Read;
@@ -88,3 +91,70 @@ impl<G: ProjectiveCurve> Commitments<G> for G::ScalarField {
     VariableBaseMSM::multi_scalar_mul(bases.as_ref(), scalars.as_ref())
   }
 }
+
+// collects verification equations of the form \sum_i s_i * P_i = 0; without a transcript, each
+// equation is checked on its own as it is added, and otherwise the equations are weighted by
+// challenges drawn from the transcript and checked with a single multi-scalar multiplication,
+// so the transcript must already bind every proof whose equations are collected
+pub struct MSMAccumulator<G: ProjectiveCurve> {
+  transcript: Option<Transcript>,
+  scalars: Vec<G::ScalarField>,
+  bases: Vec<G>,
+}
+
+impl<G: ProjectiveCurve> MSMAccumulator<G> {
+  pub fn new() -> Self {
+    MSMAccumulator {
+      transcript: None,
+      scalars: Vec::new(),
+      bases: Vec::new(),
+    }
+  }
+
+  pub fn new_batched(transcript: Transcript) -> Self {
+    MSMAccumulator {
+      transcript: Some(transcript),
+      ..MSMAccumulator::new()
+    }
+  }
+
+  fn msm(scalars: &[G::ScalarField], bases: &[G]) -> G {
+    let bases = G::batch_normalization_into_affine(bases);
+    let scalars = scalars.iter().map(|s| s.into_repr()).collect::<Vec<_>>();
+    VariableBaseMSM::multi_scalar_mul(bases.as_ref(), scalars.as_ref())
+  }
+
+  // adds an equation, which fails right away if it is checked on its own and does not hold
+  pub fn append(&mut self, terms: &[(G::ScalarField, G)]) -> Result<(), ProofVerifyError> {
+    match self.transcript.as_mut() {
+      None => {
+        let (scalars, bases): (Vec<_>, Vec<_>) = terms.iter().cloned().unzip();
+        if !MSMAccumulator::msm(&scalars, &bases).is_zero() {
+          return Err(ProofVerifyError::InternalError);
+        }
+      }
+      Some(transcript) => {
+        let weight = <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"weight");
+        for (scalar, base) in terms.iter() {
+          self.scalars.push(weight * scalar);
+          self.bases.push(*base);
+        }
+      }
+    }
+    Ok(())
+  }
+
+  pub fn check(&self) -> Result<(), ProofVerifyError> {
+    if MSMAccumulator::msm(&self.scalars, &self.bases).is_zero() {
+      Ok(())
+    } else {
+      Err(ProofVerifyError::InternalError)
+    }
+  }
+}
+
+impl<G: ProjectiveCurve> Default for MSMAccumulator<G> {
+  fn default() -> Self {
+    MSMAccumulator::new()
+  }
+}
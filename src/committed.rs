@@ -0,0 +1,246 @@
+use super::commitments::Commitments;
+use super::errors::{ProofVerifyError, R1CSError};
+use super::timer::Timer;
+use super::{
+  ComputationCommitment, ComputationDecommitment, InputsAssignment, Instance, SNARKGens,
+  VarsAssignment, SNARK,
+};
+use ark_ec::ProjectiveCurve;
+use ark_serialize::*;
+use merlin::Transcript;
+
+/// `SegmentCommitment` holds a commitment to a run of consecutive variables of an R1CS
+/// instance in the form of the commitment to the witness: the variables are split into rows
+/// of `SNARKGens::get_segment_row_len` values, and each row has a Pedersen commitment under
+/// the generators of the witness commitment with a blind of its own
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct SegmentCommitment<G: ProjectiveCurve> {
+  offset: usize,
+  rows: Vec<G>,
+}
+
+impl<G: ProjectiveCurve> SegmentCommitment<G> {
+  /// Wraps row commitments made elsewhere to the variables starting at `offset`
+  pub fn new(offset: usize, rows: Vec<G>) -> Self {
+    SegmentCommitment { offset, rows }
+  }
+
+  /// Returns the index of the first committed variable
+  pub fn get_offset(&self) -> usize {
+    self.offset
+  }
+
+  /// Returns the commitments to the rows of the segment
+  pub fn get_rows(&self) -> &[G] {
+    &self.rows
+  }
+}
+
+impl<G: ProjectiveCurve> SNARKGens<G> {
+  /// Returns the number of variables committed together in a row of the witness commitment;
+  /// committed segments must start and end at a multiple of it
+  pub fn get_segment_row_len(&self) -> usize {
+    self.gens_r1cs_sat.gens_pc.gens.gens_n.n
+  }
+
+  /// Commits to `values` as the variables starting at `offset`, with one blind per row
+  pub fn commit_segment(
+    &self,
+    offset: usize,
+    values: &[G::ScalarField],
+    blinds: &[G::ScalarField],
+  ) -> Result<SegmentCommitment<G>, R1CSError> {
+    let row_len = self.get_segment_row_len();
+    if offset % row_len != 0
+      || values.len() % row_len != 0
+      || values.len() / row_len != blinds.len()
+    {
+      return Err(R1CSError::InvalidSegment);
+    }
+    let gens_n = &self.gens_r1cs_sat.gens_pc.gens.gens_n;
+    let rows = values
+      .chunks(row_len)
+      .zip(blinds.iter())
+      .map(|(row, blind)| Commitments::batch_commit(row, blind, gens_n))
+      .collect();
+    Ok(SegmentCommitment { offset, rows })
+  }
+}
+
+impl<G: ProjectiveCurve> SNARK<G> {
+  /// A method to produce a SNARK proof of the satisfiability of an R1CS instance whose
+  /// witness contains segments committed beforehand: each segment comes with its commitment
+  /// and the blinds of its rows, and the commitment to the witness reuses them. Returns
+  /// `R1CSError::InvalidSegment` if a segment does not commit to the supplied variables
+  #[allow(clippy::too_many_arguments)]
+  pub fn prove_committed(
+    inst: &Instance<G::ScalarField>,
+    comm: &ComputationCommitment<G>,
+    decomm: &ComputationDecommitment<G::ScalarField>,
+    vars: VarsAssignment<G::ScalarField>,
+    inputs: &InputsAssignment<G::ScalarField>,
+    segments: &[(&SegmentCommitment<G>, &[G::ScalarField])],
+    gens: &SNARKGens<G>,
+    transcript: &mut Transcript,
+  ) -> Result<Self, R1CSError> {
+    let timer_prove = Timer::new("SNARK::prove_committed");
+    if inst.phases.is_some() {
+      return Err(R1CSError::InvalidPhases);
+    }
+    inst.check_assignment(&vars, inputs)?;
+
+    // the segments must commit to the supplied variables with the supplied blinds
+    let row_len = gens.get_segment_row_len();
+    let padded_vars = vars.clone().pad_to(inst.inst.get_num_vars()).assignment;
+    for (segment, blinds) in segments {
+      let end = segment.offset + segment.rows.len() * row_len;
+      if end > padded_vars.len()
+        || gens.commit_segment(segment.offset, &padded_vars[segment.offset..end], blinds)?
+          != **segment
+      {
+        return Err(R1CSError::InvalidSegment);
+      }
+    }
+    let fixed_blinds = segments
+      .iter()
+      .map(|(segment, blinds)| (segment.offset / row_len, *blinds))
+      .collect::<Vec<_>>();
+
+    let (proof, _witness) = SNARK::prove_with_fixed_blinds(
+      inst,
+      comm,
+      decomm,
+      vars,
+      inputs,
+      &fixed_blinds,
+      gens,
+      transcript,
+    )?;
+    timer_prove.stop();
+    Ok(proof)
+  }
+
+  /// A method to verify the SNARK proof of the satisfiability of an R1CS instance whose
+  /// witness contains the given committed segments
+  pub fn verify_committed(
+    &self,
+    comm: &ComputationCommitment<G>,
+    input: &InputsAssignment<G::ScalarField>,
+    segments: &[SegmentCommitment<G>],
+    transcript: &mut Transcript,
+    gens: &SNARKGens<G>,
+  ) -> Result<(), ProofVerifyError> {
+    let row_len = gens.get_segment_row_len();
+    let rows = self.r1cs_sat_proof.get_comm_vars().get_rows();
+    if !segments
+      .iter()
+      .all(|segment| segment.matches(rows, row_len))
+    {
+      return Err(ProofVerifyError::InternalError);
+    }
+    self.verify(comm, input, transcript, gens)
+  }
+}
+
+impl<G: ProjectiveCurve> SegmentCommitment<G> {
+  // checks that the segment is aligned to rows and equals the commitments to those rows
+  fn matches(&self, rows: &[G], row_len: usize) -> bool {
+    let start = self.offset / row_len;
+    self.offset % row_len == 0
+      && start + self.rows.len() <= rows.len()
+      && rows[start..start + self.rows.len()] == self.rows[..]
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ark_ff::PrimeField;
+  use ark_std::{test_rng, One, UniformRand, Zero};
+
+  // a * b = y for a public y, where (a, b, c, d) are variables 4..8 committed beforehand
+  fn product<F: PrimeField>() -> Instance<F> {
+    let (num_cons, num_vars, num_inputs) = (2, 8, 1);
+    let one = F::one();
+    let A = vec![(0, 4, one), (1, 0, one)];
+    let B = vec![(0, 5, one), (1, num_vars, one)];
+    let C = vec![(0, 0, one), (1, num_vars + 1, one)];
+    Instance::new(num_cons, num_vars, num_inputs, &A, &B, &C).unwrap()
+  }
+
+  crate::curves::curve_tests!(group: check_committed_snark, check_committed_snark_helper);
+
+  fn check_committed_snark_helper<G: ProjectiveCurve>() {
+    let mut rng = test_rng();
+    let inst = product::<G::ScalarField>();
+    let values = [6u64, 7, 8, 9]
+      .iter()
+      .map(|v| G::ScalarField::from(*v))
+      .collect::<Vec<_>>();
+    let vars = [
+      vec![G::ScalarField::from(42u64); 1],
+      vec![G::ScalarField::zero(); 3],
+      values.clone(),
+    ]
+    .concat();
+    let vars = VarsAssignment::new(&vars).unwrap();
+    let inputs = InputsAssignment::new(&[G::ScalarField::from(42u64)]).unwrap();
+    assert!(inst.is_sat(&vars, &inputs).unwrap());
+
+    let gens = SNARKGens::<G>::new(2, 8, 1, 2);
+    assert_eq!(gens.get_segment_row_len(), 4);
+    let blinds = vec![G::ScalarField::rand(&mut rng)];
+    let segment = gens.commit_segment(4, &values, &blinds).unwrap();
+    assert!(gens.commit_segment(2, &values, &blinds).is_err());
+
+    let (comm, decomm) = SNARK::encode(&inst, &gens);
+
+    // a segment that does not commit to the variables is rejected before proving
+    let wrong_blinds = vec![blinds[0] + G::ScalarField::one()];
+    let mut prover_transcript = Transcript::new(b"example");
+    assert!(matches!(
+      SNARK::prove_committed(
+        &inst,
+        &comm,
+        &decomm,
+        vars.clone(),
+        &inputs,
+        &[(&segment, &wrong_blinds)],
+        &gens,
+        &mut prover_transcript,
+      ),
+      Err(R1CSError::InvalidSegment)
+    ));
+
+    let mut prover_transcript = Transcript::new(b"example");
+    let proof = SNARK::prove_committed(
+      &inst,
+      &comm,
+      &decomm,
+      vars,
+      &inputs,
+      &[(&segment, &blinds)],
+      &gens,
+      &mut prover_transcript,
+    )
+    .unwrap();
+
+    let mut verifier_transcript = Transcript::new(b"example");
+    assert!(proof
+      .verify_committed(&comm, &inputs, &[segment], &mut verifier_transcript, &gens)
+      .is_ok());
+
+    // the proof does not verify against a commitment to other values
+    let other_values = [
+      values[0] + G::ScalarField::one(),
+      values[1],
+      values[2],
+      values[3],
+    ];
+    let other = gens.commit_segment(4, &other_values, &blinds).unwrap();
+    let mut verifier_transcript = Transcript::new(b"example");
+    assert!(proof
+      .verify_committed(&comm, &inputs, &[other], &mut verifier_transcript, &gens)
+      .is_err());
+  }
+}
@@ -0,0 +1,360 @@
+use super::errors::R1CSError;
+use super::{Column, InputsAssignment, Instance, VarsAssignment};
+use ark_ff::PrimeField;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// `InstanceComposer` merges several `Instance`s into a single `Instance` whose constraints are
+/// the union of their constraints, identifying the variables and inputs declared as shared.
+pub struct InstanceComposer<'a, F: PrimeField> {
+  components: Vec<&'a Instance<F>>,
+  offsets: Vec<usize>,
+  parent: Vec<usize>,
+}
+
+/// `Composition` describes where the variables and inputs of each component of a composed
+/// instance ended up, and merges assignments of the components into an assignment of the
+/// composed instance
+#[derive(Clone, Debug)]
+pub struct Composition {
+  num_vars: usize,
+  num_inputs: usize,
+  vars: Vec<Vec<Option<Column>>>,
+  inputs: Vec<Vec<Column>>,
+}
+
+impl<'a, F: PrimeField> Default for InstanceComposer<'a, F> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<'a, F: PrimeField> InstanceComposer<'a, F> {
+  /// Constructs a new composer without any components
+  pub fn new() -> Self {
+    InstanceComposer {
+      components: Vec::new(),
+      offsets: vec![0],
+      parent: Vec::new(),
+    }
+  }
+
+  /// Adds an instance as a component and returns its index
+  pub fn add_instance(&mut self, inst: &'a Instance<F>) -> usize {
+    let num_cols = inst.inst.get_num_vars() + inst.inst.get_num_inputs();
+    let offset = *self.offsets.last().unwrap();
+    self.parent.extend(offset..offset + num_cols);
+    self.offsets.push(offset + num_cols);
+    self.components.push(inst);
+    self.components.len() - 1
+  }
+
+  // the node that represents `col` of the component `comp`
+  fn node(&self, comp: usize, col: &Column) -> Result<usize, R1CSError> {
+    if comp >= self.components.len() {
+      return Err(R1CSError::InvalidIndex);
+    }
+    let inst = &self.components[comp].inst;
+    let index = match *col {
+      Column::Var(i) if i < inst.get_num_vars() => i,
+      Column::Input(i) if i < inst.get_num_inputs() => inst.get_num_vars() + i,
+      _ => return Err(R1CSError::InvalidIndex),
+    };
+    Ok(self.offsets[comp] + index)
+  }
+
+  fn find(&self, mut node: usize) -> usize {
+    while self.parent[node] != node {
+      node = self.parent[node];
+    }
+    node
+  }
+
+  /// Declares that the column `a.1` of the component `a.0` and the column `b.1` of the component
+  /// `b.0` hold the same value. Either column may be a variable or an input; a class of shared
+  /// columns that contains an input becomes an input of the composed instance.
+  pub fn share(&mut self, a: (usize, Column), b: (usize, Column)) -> Result<(), R1CSError> {
+    let root_a = self.find(self.node(a.0, &a.1)?);
+    let root_b = self.find(self.node(b.0, &b.1)?);
+    // the smaller node, which comes first in the order of components, represents the class
+    if root_a < root_b {
+      self.parent[root_b] = root_a;
+    } else {
+      self.parent[root_a] = root_b;
+    }
+    Ok(())
+  }
+
+  /// Composes the components into a single `Instance`. Constraints are laid out one component
+  /// after another, inputs are numbered in the order of the components, and variables that no
+  /// constraint refers to and that are not shared are dropped. Components with lookups or memories
+  /// are not supported and result in `R1CSError::InvalidLookup` or `R1CSError::InvalidMemory`,
+  /// and components with a two-phase witness result in `R1CSError::InvalidPhases`.
+  pub fn compose(&self) -> Result<(Instance<F>, Composition), R1CSError> {
+    if self.components.iter().any(|inst| !inst.lookups.is_empty()) {
+      return Err(R1CSError::InvalidLookup);
+    }
+    if self.components.iter().any(|inst| !inst.memories.is_empty()) {
+      return Err(R1CSError::InvalidMemory);
+    }
+    if self.components.iter().any(|inst| inst.phases.is_some()) {
+      return Err(R1CSError::InvalidPhases);
+    }
+
+    let mut class_size: BTreeMap<usize, usize> = BTreeMap::new();
+    for node in 0..self.parent.len() {
+      *class_size.entry(self.find(node)).or_insert(0) += 1;
+    }
+
+    let matrices = self
+      .components
+      .iter()
+      .map(|inst| inst.inst.get_matrices())
+      .collect::<Vec<_>>();
+
+    // a class of shared columns is an input if any of its members is
+    let mut classes: BTreeMap<usize, Column> = BTreeMap::new();
+    let mut num_inputs = 0;
+    for (comp, inst) in self.components.iter().enumerate() {
+      for i in 0..inst.inst.get_num_inputs() {
+        let root = self.find(self.node(comp, &Column::Input(i))?);
+        classes.entry(root).or_insert_with(|| {
+          num_inputs += 1;
+          Column::Input(num_inputs - 1)
+        });
+      }
+    }
+
+    let mut num_vars = 0;
+    for (comp, inst) in self.components.iter().enumerate() {
+      let num_local_vars = inst.inst.get_num_vars();
+      let (A, B, C) = &matrices[comp];
+      let referenced = A
+        .iter()
+        .chain(B.iter())
+        .chain(C.iter())
+        .filter(|(_row, col, val)| *col < num_local_vars && !val.is_zero())
+        .map(|(_row, col, _val)| *col)
+        .collect::<BTreeSet<usize>>();
+      for i in 0..num_local_vars {
+        let root = self.find(self.node(comp, &Column::Var(i))?);
+        if referenced.contains(&i) || class_size[&root] > 1 {
+          classes.entry(root).or_insert_with(|| {
+            num_vars += 1;
+            Column::Var(num_vars - 1)
+          });
+        }
+      }
+    }
+
+    let mut composition = Composition {
+      num_vars,
+      num_inputs,
+      vars: Vec::new(),
+      inputs: Vec::new(),
+    };
+    for (comp, inst) in self.components.iter().enumerate() {
+      let vars = (0..inst.inst.get_num_vars())
+        .map(|i| {
+          let root = self.find(self.node(comp, &Column::Var(i)).unwrap());
+          classes.get(&root).copied()
+        })
+        .collect();
+      let inputs = (0..inst.inst.get_num_inputs())
+        .map(|i| classes[&self.find(self.node(comp, &Column::Input(i)).unwrap())])
+        .collect();
+      composition.vars.push(vars);
+      composition.inputs.push(inputs);
+    }
+
+    // re-index the rows and columns of every component, merging entries that now coincide
+    let global_col = |col: Column| match col {
+      Column::Var(i) => i,
+      Column::One => num_vars,
+      Column::Input(i) => num_vars + 1 + i,
+    };
+    let mut mats: [BTreeMap<(usize, usize), F>; 3] =
+      [BTreeMap::new(), BTreeMap::new(), BTreeMap::new()];
+    let mut num_cons = 0;
+    for (comp, inst) in self.components.iter().enumerate() {
+      let num_local_vars = inst.inst.get_num_vars();
+      let (A, B, C) = &matrices[comp];
+      let mut num_rows = 0;
+      for (mat, entries) in mats.iter_mut().zip([A, B, C].iter()) {
+        for &(row, col, val) in entries.iter() {
+          if val.is_zero() {
+            continue;
+          }
+          let col = match col.cmp(&num_local_vars) {
+            Ordering::Less => composition.vars[comp][col].unwrap(),
+            Ordering::Equal => Column::One,
+            Ordering::Greater => composition.inputs[comp][col - num_local_vars - 1],
+          };
+          *mat
+            .entry((num_cons + row, global_col(col)))
+            .or_insert_with(F::zero) += val;
+          num_rows = num_rows.max(row + 1);
+        }
+      }
+      num_cons += num_rows;
+    }
+
+    let [A, B, C] = mats.map(|mat| {
+      mat
+        .into_iter()
+        .map(|((row, col), val)| (row, col, val))
+        .collect::<Vec<(usize, usize, F)>>()
+    });
+    let inst = Instance::new(num_cons, num_vars, num_inputs, &A, &B, &C)?;
+    Ok((inst, composition))
+  }
+}
+
+impl Composition {
+  /// Returns the number of variables of the composed instance, before padding
+  pub fn get_num_vars(&self) -> usize {
+    self.num_vars
+  }
+
+  /// Returns the number of inputs of the composed instance
+  pub fn get_num_inputs(&self) -> usize {
+    self.num_inputs
+  }
+
+  /// Returns the column of the composed instance that holds the column `col` of the component
+  /// `comp`, or `None` if it was dropped because no constraint refers to it
+  pub fn get_column(&self, comp: usize, col: Column) -> Option<Column> {
+    match col {
+      Column::Var(i) => self.vars.get(comp)?.get(i).copied().flatten(),
+      Column::One => Some(Column::One),
+      Column::Input(i) => self.inputs.get(comp)?.get(i).copied(),
+    }
+  }
+
+  /// Merges assignments to the variables and inputs of every component, in the order in which
+  /// the components were added, into assignments for the composed instance. Returns
+  /// `R1CSError::InconsistentAssignment` if shared columns are assigned different values.
+  pub fn merge_assignments<F: PrimeField>(
+    &self,
+    assignments: &[(&VarsAssignment<F>, &InputsAssignment<F>)],
+  ) -> Result<(VarsAssignment<F>, InputsAssignment<F>), R1CSError> {
+    if assignments.len() != self.vars.len() {
+      return Err(R1CSError::InvalidIndex);
+    }
+
+    let mut vars: Vec<Option<F>> = vec![None; self.num_vars];
+    let mut inputs: Vec<Option<F>> = vec![None; self.num_inputs];
+    for (comp, (comp_vars, comp_inputs)) in assignments.iter().enumerate() {
+      if comp_vars.assignment.len() > self.vars[comp].len() {
+        return Err(R1CSError::InvalidNumberOfVars);
+      }
+      if comp_inputs.assignment.len() != self.inputs[comp].len() {
+        return Err(R1CSError::InvalidNumberOfInputs);
+      }
+
+      // variables that are not supplied are zero, as with padding
+      let values = (0..self.vars[comp].len())
+        .map(|i| comp_vars.assignment.get(i).copied().unwrap_or_else(F::zero))
+        .zip(self.vars[comp].iter().copied())
+        .chain(
+          comp_inputs
+            .assignment
+            .iter()
+            .copied()
+            .zip(self.inputs[comp].iter().copied().map(Some)),
+        );
+      for (val, col) in values {
+        let entry = match col {
+          Some(Column::Var(i)) => &mut vars[i],
+          Some(Column::Input(i)) => &mut inputs[i],
+          _ => continue,
+        };
+        match entry {
+          Some(prev) if *prev != val => return Err(R1CSError::InconsistentAssignment),
+          _ => *entry = Some(val),
+        }
+      }
+    }
+
+    Ok((
+      VarsAssignment {
+        assignment: vars.into_iter().map(|v| v.unwrap()).collect(),
+      },
+      InputsAssignment {
+        assignment: inputs.into_iter().map(|v| v.unwrap()).collect(),
+      },
+    ))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  crate::curves::curve_tests!(field: check_compose, check_compose_helper);
+
+  fn check_compose_helper<F: PrimeField>() {
+    let one = F::one();
+
+    // z_0 = x * x and z_1 = z_0 * x
+    let num_vars = 2;
+    let A = vec![(0, num_vars + 1, one), (1, 0, one)];
+    let B = vec![(0, num_vars + 1, one), (1, num_vars + 1, one)];
+    let C = vec![(0, 0, one), (1, 1, one)];
+    let cube = Instance::new(2, num_vars, 1, &A, &B, &C).unwrap();
+
+    // z_1 = z_0 * z_0 + y
+    let num_vars = 2;
+    let A = vec![(0, 0, one)];
+    let B = vec![(0, 0, one)];
+    let C = vec![(0, 1, one), (0, num_vars + 1, -one)];
+    let square = Instance::new(1, num_vars, 1, &A, &B, &C).unwrap();
+
+    // the cube of x is squared, and both components share the input
+    let mut composer = InstanceComposer::new();
+    let c0 = composer.add_instance(&cube);
+    let c1 = composer.add_instance(&square);
+    composer
+      .share((c0, Column::Var(1)), (c1, Column::Var(0)))
+      .unwrap();
+    composer
+      .share((c0, Column::Input(0)), (c1, Column::Input(0)))
+      .unwrap();
+    assert!(composer
+      .share((c1, Column::Var(0)), (2, Column::Var(0)))
+      .is_err());
+    let (inst, composition) = composer.compose().unwrap();
+
+    assert_eq!(composition.get_num_vars(), 3);
+    assert_eq!(composition.get_num_inputs(), 1);
+    assert_eq!(
+      composition.get_column(c1, Column::Var(0)),
+      Some(Column::Var(1))
+    );
+    assert_eq!(
+      composition.get_column(c1, Column::Var(1)),
+      Some(Column::Var(2))
+    );
+    assert_eq!(composition.get_column(c0, Column::Var(3)), None);
+    assert_eq!(
+      composition.get_column(c1, Column::Input(0)),
+      Some(Column::Input(0))
+    );
+
+    let x = F::from(3u64);
+    let cube_vars = VarsAssignment::new(&[x * x, x * x * x]).unwrap();
+    let square_vars = VarsAssignment::new(&[x * x * x, x.pow([6]) + x]).unwrap();
+    let inputs = InputsAssignment::new(&[x]).unwrap();
+    let (vars, merged_inputs) = composition
+      .merge_assignments(&[(&cube_vars, &inputs), (&square_vars, &inputs)])
+      .unwrap();
+    assert_eq!(vars.assignment.len(), 3);
+    assert!(inst.is_sat(&vars, &merged_inputs).unwrap());
+
+    // shared columns must agree
+    let other_inputs = InputsAssignment::new(&[x + one]).unwrap();
+    assert!(composition
+      .merge_assignments(&[(&cube_vars, &inputs), (&square_vars, &other_inputs)])
+      .is_err());
+  }
+}
@@ -0,0 +1,100 @@
+//! The curves over which the proof systems can be instantiated, each enabled by the cargo
+//! feature of the same name. The proof systems themselves are generic over `ProjectiveCurve`;
+//! the pairing-friendly curves BLS12-381 and BN254 also support `MultilinearKZG`.
+
+/// The BLS12-381 pairing-friendly curve
+#[cfg(feature = "bls12-381")]
+pub use ark_bls12_381 as bls12_381;
+
+/// The BN254 pairing-friendly curve, whose pairing is supported by EVM precompiles
+#[cfg(feature = "bn254")]
+pub use ark_bn254 as bn254;
+
+/// The Pallas curve, which forms a cycle with Vesta
+#[cfg(feature = "pallas")]
+pub use ark_pallas as pallas;
+
+/// The Vesta curve, which forms a cycle with Pallas
+#[cfg(feature = "vesta")]
+pub use ark_vesta as vesta;
+
+#[cfg(feature = "secp256k1")]
+pub mod secp256k1;
+
+// expands a test whose helper is generic over the curve, the scalar field or the pairing engine
+// into a module with one test per curve: BLS12-381, which the tests always use, and each
+// curve enabled by a feature
+#[cfg(test)]
+macro_rules! curve_tests {
+  (group: $name:ident, $helper:ident) => {
+    crate::curves::curve_tests!(
+      @expand $name,
+      $helper,
+      ark_bls12_381::G1Projective,
+      ark_bn254::G1Projective,
+      ark_pallas::Projective,
+      ark_vesta::Projective,
+      crate::curves::secp256k1::Projective
+    );
+  };
+  (field: $name:ident, $helper:ident) => {
+    crate::curves::curve_tests!(
+      @expand $name,
+      $helper,
+      ark_bls12_381::Fr,
+      ark_bn254::Fr,
+      ark_pallas::Fr,
+      ark_vesta::Fr,
+      crate::curves::secp256k1::Fr
+    );
+  };
+  (pairing: $name:ident, $helper:ident) => {
+    mod $name {
+      #[test]
+      fn bls12_381() {
+        super::$helper::<ark_bls12_381::Bls12_381>()
+      }
+
+      #[cfg(feature = "bn254")]
+      #[test]
+      fn bn254() {
+        super::$helper::<ark_bn254::Bn254>()
+      }
+    }
+  };
+  (@expand $name:ident, $helper:ident, $bls12_381:ty, $bn254:ty, $pallas:ty, $vesta:ty, $secp256k1:ty) => {
+    mod $name {
+      #[test]
+      fn bls12_381() {
+        super::$helper::<$bls12_381>()
+      }
+
+      #[cfg(feature = "bn254")]
+      #[test]
+      fn bn254() {
+        super::$helper::<$bn254>()
+      }
+
+      #[cfg(feature = "pallas")]
+      #[test]
+      fn pallas() {
+        super::$helper::<$pallas>()
+      }
+
+      #[cfg(feature = "vesta")]
+      #[test]
+      fn vesta() {
+        super::$helper::<$vesta>()
+      }
+
+      #[cfg(feature = "secp256k1")]
+      #[test]
+      fn secp256k1() {
+        super::$helper::<$secp256k1>()
+      }
+    }
+  };
+}
+
+#[cfg(test)]
+pub(crate) use curve_tests;
@@ -0,0 +1,320 @@
+//! The secp256k1 curve y^2 = x^3 + 7, for which arkworks 0.3 has no crate.
+//!
+//! Both of its fields have 256-bit moduli, while the four-limb arithmetic of ark-ff 0.3 assumes
+//! that the modulus leaves the top bit of the representation free, so the fields are backed by
+//! five limbs instead. With five limbs, `Field::from_random_bytes` and the
+//! `PrimeField::from_*_bytes_mod_order` conversions built on it are not supported; the
+//! transcripts of this crate derive their challenges without them.
+use ark_ec::models::{
+  short_weierstrass_jacobian::{GroupAffine, GroupProjective},
+  ModelParameters, SWModelParameters,
+};
+use ark_ff::{
+  biginteger::BigInteger320 as BigInteger,
+  field_new,
+  fields::{FftParameters, Fp320, Fp320Parameters, FpParameters},
+  Zero,
+};
+
+/// The base field of secp256k1
+pub type Fq = Fp320<FqParameters>;
+
+/// The parameters of the base field of secp256k1
+pub struct FqParameters;
+
+impl Fp320Parameters for FqParameters {}
+impl FftParameters for FqParameters {
+  type BigInt = BigInteger;
+
+  const TWO_ADICITY: u32 = 1;
+
+  // TWO_ADIC_ROOT_OF_UNITY = GENERATOR^T, in Montgomery form
+  #[rustfmt::skip]
+  const TWO_ADIC_ROOT_OF_UNITY: BigInteger = BigInteger([
+    0xfffffffefffffc2f,
+    0xfffffffefffffc2e,
+    0xffffffffffffffff,
+    0xffffffffffffffff,
+    0x0000000000000000,
+  ]);
+}
+impl FpParameters for FqParameters {
+  // MODULUS = 2^256 - 2^32 - 977
+  //         = 115792089237316195423570985008687907853269984665640564039457584007908834671663
+  #[rustfmt::skip]
+  const MODULUS: BigInteger = BigInteger([
+    0xfffffffefffffc2f,
+    0xffffffffffffffff,
+    0xffffffffffffffff,
+    0xffffffffffffffff,
+    0x0000000000000000,
+  ]);
+
+  const MODULUS_BITS: u32 = 256;
+
+  const CAPACITY: u32 = Self::MODULUS_BITS - 1;
+
+  const REPR_SHAVE_BITS: u32 = 64;
+
+  // R = 2^320 % MODULUS
+  #[rustfmt::skip]
+  const R: BigInteger = BigInteger([
+    0x0000000000000000,
+    0x00000001000003d1,
+    0x0000000000000000,
+    0x0000000000000000,
+    0x0000000000000000,
+  ]);
+
+  // R2 = R * R % MODULUS
+  #[rustfmt::skip]
+  const R2: BigInteger = BigInteger([
+    0x0000000000000000,
+    0x0000000000000000,
+    0x000007a2000e90a1,
+    0x0000000000000001,
+    0x0000000000000000,
+  ]);
+
+  // INV = -MODULUS^{-1} % 2^64
+  const INV: u64 = 15580212934572586289u64;
+
+  // GENERATOR = 3, in Montgomery form
+  #[rustfmt::skip]
+  const GENERATOR: BigInteger = BigInteger([
+    0x0000000000000000,
+    0x0000000300000b73,
+    0x0000000000000000,
+    0x0000000000000000,
+    0x0000000000000000,
+  ]);
+
+  #[rustfmt::skip]
+  const MODULUS_MINUS_ONE_DIV_TWO: BigInteger = BigInteger([
+    0xffffffff7ffffe17,
+    0xffffffffffffffff,
+    0xffffffffffffffff,
+    0x7fffffffffffffff,
+    0x0000000000000000,
+  ]);
+
+  // T = (MODULUS - 1) / 2^TWO_ADICITY
+  #[rustfmt::skip]
+  const T: BigInteger = BigInteger([
+    0xffffffff7ffffe17,
+    0xffffffffffffffff,
+    0xffffffffffffffff,
+    0x7fffffffffffffff,
+    0x0000000000000000,
+  ]);
+
+  #[rustfmt::skip]
+  const T_MINUS_ONE_DIV_TWO: BigInteger = BigInteger([
+    0xffffffffbfffff0b,
+    0xffffffffffffffff,
+    0xffffffffffffffff,
+    0x3fffffffffffffff,
+    0x0000000000000000,
+  ]);
+}
+
+/// The scalar field of secp256k1
+pub type Fr = Fp320<FrParameters>;
+
+/// The parameters of the scalar field of secp256k1
+pub struct FrParameters;
+
+impl Fp320Parameters for FrParameters {}
+impl FftParameters for FrParameters {
+  type BigInt = BigInteger;
+
+  const TWO_ADICITY: u32 = 6;
+
+  // TWO_ADIC_ROOT_OF_UNITY = GENERATOR^T, in Montgomery form
+  #[rustfmt::skip]
+  const TWO_ADIC_ROOT_OF_UNITY: BigInteger = BigInteger([
+    0x0112cb0f605a214a,
+    0x92225daffb794500,
+    0x7e42003a6ccb6212,
+    0x55980b07bc222114,
+    0x0000000000000000,
+  ]);
+}
+impl FpParameters for FrParameters {
+  // MODULUS = 115792089237316195423570985008687907852837564279074904382605163141518161494337
+  #[rustfmt::skip]
+  const MODULUS: BigInteger = BigInteger([
+    0xbfd25e8cd0364141,
+    0xbaaedce6af48a03b,
+    0xfffffffffffffffe,
+    0xffffffffffffffff,
+    0x0000000000000000,
+  ]);
+
+  const MODULUS_BITS: u32 = 256;
+
+  const CAPACITY: u32 = Self::MODULUS_BITS - 1;
+
+  const REPR_SHAVE_BITS: u32 = 64;
+
+  // R = 2^320 % MODULUS
+  #[rustfmt::skip]
+  const R: BigInteger = BigInteger([
+    0x0000000000000000,
+    0x402da1732fc9bebf,
+    0x4551231950b75fc4,
+    0x0000000000000001,
+    0x0000000000000000,
+  ]);
+
+  // R2 = R * R % MODULUS
+  #[rustfmt::skip]
+  const R2: BigInteger = BigInteger([
+    0x1e004f504dfd7f79,
+    0x08fcf59774a052ea,
+    0x27c4120fc94e1653,
+    0x3c1a6191e5702644,
+    0x0000000000000000,
+  ]);
+
+  // INV = -MODULUS^{-1} % 2^64
+  const INV: u64 = 5408259542528602431u64;
+
+  // GENERATOR = 7, in Montgomery form
+  #[rustfmt::skip]
+  const GENERATOR: BigInteger = BigInteger([
+    0x0000000000000000,
+    0xc13f6a264e843739,
+    0xe537f5b135039e5d,
+    0x0000000000000008,
+    0x0000000000000000,
+  ]);
+
+  #[rustfmt::skip]
+  const MODULUS_MINUS_ONE_DIV_TWO: BigInteger = BigInteger([
+    0xdfe92f46681b20a0,
+    0x5d576e7357a4501d,
+    0xffffffffffffffff,
+    0x7fffffffffffffff,
+    0x0000000000000000,
+  ]);
+
+  // T = (MODULUS - 1) / 2^TWO_ADICITY
+  #[rustfmt::skip]
+  const T: BigInteger = BigInteger([
+    0xeeff497a3340d905,
+    0xfaeabb739abd2280,
+    0xffffffffffffffff,
+    0x03ffffffffffffff,
+    0x0000000000000000,
+  ]);
+
+  #[rustfmt::skip]
+  const T_MINUS_ONE_DIV_TWO: BigInteger = BigInteger([
+    0x777fa4bd19a06c82,
+    0xfd755db9cd5e9140,
+    0xffffffffffffffff,
+    0x01ffffffffffffff,
+    0x0000000000000000,
+  ]);
+}
+
+/// The parameters of the secp256k1 curve
+#[derive(Copy, Clone, Default, PartialEq, Eq)]
+pub struct Secp256k1Parameters;
+
+impl ModelParameters for Secp256k1Parameters {
+  type BaseField = Fq;
+  type ScalarField = Fr;
+}
+
+/// A point of secp256k1 in affine coordinates
+pub type Affine = GroupAffine<Secp256k1Parameters>;
+
+/// A point of secp256k1 in Jacobian coordinates
+pub type Projective = GroupProjective<Secp256k1Parameters>;
+
+impl SWModelParameters for Secp256k1Parameters {
+  const COEFF_A: Fq = field_new!(Fq, "0");
+
+  const COEFF_B: Fq = field_new!(Fq, "7");
+
+  const COFACTOR: &'static [u64] = &[0x1];
+
+  const COFACTOR_INV: Fr = field_new!(Fr, "1");
+
+  const AFFINE_GENERATOR_COEFFS: (Self::BaseField, Self::BaseField) =
+    (G_GENERATOR_X, G_GENERATOR_Y);
+
+  #[inline(always)]
+  fn mul_by_a(_: &Self::BaseField) -> Self::BaseField {
+    Self::BaseField::zero()
+  }
+}
+
+/// The x-coordinate of the standard generator of secp256k1
+pub const G_GENERATOR_X: Fq = field_new!(
+  Fq,
+  "55066263022277343669578718895168534326250603453777594175500187360389116729240"
+);
+
+/// The y-coordinate of the standard generator of secp256k1
+pub const G_GENERATOR_Y: Fq = field_new!(
+  Fq,
+  "32670510020758816978083085130507043184471273380659243275938904335757337482424"
+);
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ark_ec::{AffineCurve, ProjectiveCurve};
+  use ark_ff::{FftField, Field, One, PrimeField, SquareRootField, UniformRand};
+  use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+  #[test]
+  fn check_secp256k1_fields() {
+    let mut rng = ark_std::test_rng();
+    // sums that overflow 256 bits are reduced
+    let minus_one = -Fq::one();
+    assert_eq!(minus_one + minus_one, -Fq::from(2u64));
+    assert_eq!(minus_one.double(), -Fq::from(2u64));
+    assert_eq!(
+      Fq::from(7u64).inverse().unwrap() * Fq::from(7u64),
+      Fq::one()
+    );
+    assert_eq!(-Fr::one() + Fr::from(2u64), Fr::one());
+
+    let root = Fr::two_adic_root_of_unity();
+    assert_eq!(root.pow([1u64 << 5]), -Fr::one());
+    assert_eq!(root.pow([1u64 << 6]), Fr::one());
+
+    for _ in 0..16 {
+      let a = Fr::rand(&mut rng);
+      let b = Fr::rand(&mut rng);
+      assert_eq!((a + b) * (a - b), a.square() - b.square());
+      let square = a.square();
+      let sqrt = square.sqrt().unwrap();
+      assert!(sqrt == a || sqrt == -a);
+    }
+  }
+
+  #[test]
+  fn check_secp256k1_group() {
+    let mut rng = ark_std::test_rng();
+    let g = Affine::prime_subgroup_generator();
+    assert!(g.is_on_curve());
+    assert!(g.mul(-Fr::one()).add_mixed(&g).is_zero());
+
+    let p = Projective::rand(&mut rng);
+    let (a, b) = (Fr::rand(&mut rng), Fr::rand(&mut rng));
+    assert_eq!(
+      p.mul(a.into_repr()) + p.mul(b.into_repr()),
+      p.mul((a + b).into_repr())
+    );
+
+    let mut buf = vec![];
+    p.into_affine().serialize(&mut buf).unwrap();
+    assert_eq!(buf.len(), 33);
+    assert_eq!(Affine::deserialize(&buf[..]).unwrap(), p.into_affine());
+  }
+}
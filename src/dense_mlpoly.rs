@@ -1,5 +1,5 @@
 #![allow(clippy::too_many_arguments)]
-use super::commitments::{Commitments, MultiCommitGens};
+use super::commitments::{Commitments, MSMAccumulator, MultiCommitGens};
 use super::errors::ProofVerifyError;
 use super::math::Math;
 use super::nizk::{DotProductProofGens, DotProductProofLog};
@@ -10,6 +10,7 @@ use ark_ec::ProjectiveCurve;
 use ark_ff::PrimeField;
 use ark_serialize::*;
 use ark_std::Zero;
+use core::cmp::min;
 use core::ops::Index;
 use merlin::Transcript;
 
@@ -30,21 +31,85 @@ pub struct PolyCommitmentGens<G> {
 impl<G: ProjectiveCurve> PolyCommitmentGens<G> {
   // the number of variables in the multilinear polynomial
   pub fn new(num_vars: usize, label: &'static [u8]) -> Self {
-    let (_left, right) = EqPolynomial::<G::ScalarField>::compute_factored_lens(num_vars);
+    PolyCommitmentGens::new_with_ratio(num_vars, 0, label)
+  }
+
+  // lays the polynomial out as a matrix with about 2^log_ratio times as many rows as columns,
+  // as in `EqPolynomial::compute_factored_lens_with_ratio`: more rows make the commitment
+  // larger and the dot product proof shorter
+  pub fn new_with_ratio(num_vars: usize, log_ratio: isize, label: &'static [u8]) -> Self {
+    let (_left, right) =
+      EqPolynomial::<G::ScalarField>::compute_factored_lens_with_ratio(num_vars, log_ratio);
     let gens = DotProductProofGens::new(right.pow2(), label);
     PolyCommitmentGens { gens }
   }
+
+  // the numbers of variables indexing the rows and the columns of a polynomial with
+  // `num_vars` variables, whose rows have the length of the generators, or hold the whole
+  // polynomial if it is shorter
+  pub fn factored_lens(&self, num_vars: usize) -> (usize, usize) {
+    let right_num_vars = min(self.gens.gens_n.n.log_2(), num_vars);
+    (num_vars - right_num_vars, right_num_vars)
+  }
+
+  // calls `f` with the generators for rows of `2^right_num_vars` evaluations, which are the
+  // first of these generators
+  fn with_row_gens<T, F>(&self, right_num_vars: usize, f: F) -> T
+  where
+    F: FnOnce(&DotProductProofGens<G>) -> T,
+  {
+    if right_num_vars.pow2() == self.gens.gens_n.n {
+      f(&self.gens)
+    } else {
+      f(&self.gens.prefix(right_num_vars.pow2()))
+    }
+  }
 }
 
+#[derive(Clone)]
 pub struct PolyCommitmentBlinds<F> {
   blinds: Vec<F>,
 }
 
-#[derive(Debug, CanonicalSerialize, CanonicalDeserialize)]
+impl<F: PrimeField> PolyCommitmentBlinds<F> {
+  // returns the blinds of the commitment to `poly + r * other` given the blinds of both
+  pub fn fold(&self, other: &PolyCommitmentBlinds<F>, r: &F) -> PolyCommitmentBlinds<F> {
+    assert_eq!(self.blinds.len(), other.blinds.len());
+    PolyCommitmentBlinds {
+      blinds: (0..self.blinds.len())
+        .map(|i| self.blinds[i] + *r * other.blinds[i])
+        .collect(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
 pub struct PolyCommitment<G: ProjectiveCurve> {
   C: Vec<G>,
 }
 
+impl<G: ProjectiveCurve> PolyCommitment<G> {
+  // returns a commitment to `poly + r * other` given commitments to both polynomials
+  pub fn fold(&self, other: &PolyCommitment<G>, r: &G::ScalarField) -> PolyCommitment<G> {
+    assert_eq!(self.C.len(), other.C.len());
+    PolyCommitment {
+      C: (0..self.C.len())
+        .map(|i| self.C[i] + other.C[i].mul(r.into_repr()))
+        .collect(),
+    }
+  }
+
+  // the number of row commitments
+  pub fn num_rows(&self) -> usize {
+    self.C.len()
+  }
+
+  // the row commitments, each to a run of consecutive evaluations
+  pub fn get_rows(&self) -> &[G] {
+    &self.C
+  }
+}
+
 #[derive(Debug, CanonicalSerialize, CanonicalDeserialize)]
 pub struct ConstPolyCommitment<G: ProjectiveCurve> {
   C: G,
@@ -85,13 +150,26 @@ impl<F: PrimeField> EqPolynomial<F> {
   }
 
   pub fn compute_factored_lens(ell: usize) -> (usize, usize) {
-    (ell / 2, ell - ell / 2)
+    Self::compute_factored_lens_with_ratio(ell, 0)
+  }
+
+  // splits the variables so that there are about 2^log_ratio times as many rows as columns,
+  // keeping at least one variable for the columns
+  pub fn compute_factored_lens_with_ratio(ell: usize, log_ratio: isize) -> (usize, usize) {
+    let left = (ell as isize + log_ratio).div_euclid(2);
+    let left = left.clamp(0, ell.saturating_sub(1) as isize) as usize;
+    (left, ell - left)
   }
 
   pub fn compute_factored_evals(&self) -> (Vec<F>, Vec<F>) {
-    let ell = self.r.len();
-    let (left_num_vars, _right_num_vars) = Self::compute_factored_lens(ell);
+    let (left_num_vars, _right_num_vars) = Self::compute_factored_lens(self.r.len());
+    self.compute_factored_evals_at(left_num_vars)
+  }
 
+  // the evaluations of the eq polynomials over the first `left_num_vars` variables and over
+  // the rest
+  pub fn compute_factored_evals_at(&self, left_num_vars: usize) -> (Vec<F>, Vec<F>) {
+    let ell = self.r.len();
     let L = EqPolynomial::new(self.r[..left_num_vars].to_vec()).evals();
     let R = EqPolynomial::new(self.r[left_num_vars..ell].to_vec()).evals();
 
@@ -195,7 +273,7 @@ impl<F: PrimeField> DensePolynomial<F> {
     let ell = self.get_num_vars();
     assert_eq!(n, ell.pow2());
 
-    let (left_num_vars, right_num_vars) = EqPolynomial::<F>::compute_factored_lens(ell);
+    let (left_num_vars, right_num_vars) = gens.factored_lens(ell);
     let L_size = left_num_vars.pow2();
     let R_size = right_num_vars.pow2();
     assert_eq!(L_size * R_size, n);
@@ -210,14 +288,56 @@ impl<F: PrimeField> DensePolynomial<F> {
       }
     };
 
-    (self.commit_inner(&blinds.blinds, &gens.gens.gens_n), blinds)
+    let C = gens.with_row_gens(right_num_vars, |g| {
+      self.commit_inner(&blinds.blinds, &g.gens_n)
+    });
+    (C, blinds)
   }
 
-  pub fn bound(&self, L: &[F]) -> Vec<F> {
-    let (left_num_vars, right_num_vars) =
-      EqPolynomial::<F>::compute_factored_lens(self.get_num_vars());
+  // commits like `commit` with random blinds, except that the rows starting at each given
+  // index use the given blinds, so that they reproduce commitments made elsewhere
+  pub fn commit_with_fixed_blinds<G>(
+    &self,
+    gens: &PolyCommitmentGens<G>,
+    random_tape: &mut RandomTape<G>,
+    fixed_blinds: &[(usize, &[F])],
+  ) -> (PolyCommitment<G>, PolyCommitmentBlinds<F>)
+  where
+    G: ProjectiveCurve<ScalarField = F>,
+  {
+    let (left_num_vars, right_num_vars) = gens.factored_lens(self.get_num_vars());
     let L_size = left_num_vars.pow2();
-    let R_size = right_num_vars.pow2();
+    let mut blinds = random_tape.random_vector(b"poly_blinds", L_size);
+    for (row, fixed) in fixed_blinds {
+      assert!(row + fixed.len() <= L_size);
+      blinds[*row..row + fixed.len()].copy_from_slice(fixed);
+    }
+    let blinds = PolyCommitmentBlinds { blinds };
+    let C = gens.with_row_gens(right_num_vars, |g| {
+      self.commit_inner(&blinds.blinds, &g.gens_n)
+    });
+    (C, blinds)
+  }
+
+  // recomputes the commitment produced by `commit` from its blinds
+  pub fn commit_with_blinds<G>(
+    &self,
+    blinds: &PolyCommitmentBlinds<F>,
+    gens: &PolyCommitmentGens<G>,
+  ) -> PolyCommitment<G>
+  where
+    G: ProjectiveCurve<ScalarField = F>,
+  {
+    let (_left_num_vars, right_num_vars) = gens.factored_lens(self.get_num_vars());
+    gens.with_row_gens(right_num_vars, |g| {
+      self.commit_inner(&blinds.blinds, &g.gens_n)
+    })
+  }
+
+  // the product of L with the evaluations laid out as a matrix with L.len() rows
+  pub fn bound(&self, L: &[F]) -> Vec<F> {
+    let L_size = L.len();
+    let R_size = self.Z.len() / L_size;
     (0..R_size)
       .map(|i| (0..L_size).map(|j| L[j] * self.Z[j * R_size + i]).sum())
       .collect()
@@ -336,8 +456,7 @@ impl<G: ProjectiveCurve> PolyEvalProof<G> {
     // assert vectors are of the right size
     assert_eq!(poly.get_num_vars(), r.len());
 
-    let (left_num_vars, right_num_vars) =
-      EqPolynomial::<G::ScalarField>::compute_factored_lens(r.len());
+    let (left_num_vars, right_num_vars) = gens.factored_lens(r.len());
     let L_size = left_num_vars.pow2();
     let R_size = right_num_vars.pow2();
 
@@ -353,7 +472,7 @@ impl<G: ProjectiveCurve> PolyEvalProof<G> {
 
     // compute the L and R vectors
     let eq = EqPolynomial::new(r.to_vec());
-    let (L, R) = eq.compute_factored_evals();
+    let (L, R) = eq.compute_factored_evals_at(left_num_vars);
     assert_eq!(L.len(), L_size);
     assert_eq!(R.len(), R_size);
 
@@ -363,16 +482,9 @@ impl<G: ProjectiveCurve> PolyEvalProof<G> {
     let LZ_blind: G::ScalarField = (0..L.len()).map(|i| blinds.blinds[i] * L[i]).sum();
 
     // a dot product proof of size R_size
-    let (proof, _C_LR, C_Zr_prime) = DotProductProofLog::prove(
-      &gens.gens,
-      transcript,
-      random_tape,
-      &LZ,
-      &LZ_blind,
-      &R,
-      Zr,
-      blind_Zr,
-    );
+    let (proof, _C_LR, C_Zr_prime) = gens.with_row_gens(right_num_vars, |g| {
+      DotProductProofLog::prove(g, transcript, random_tape, &LZ, &LZ_blind, &R, Zr, blind_Zr)
+    });
 
     (PolyEvalProof { proof }, C_Zr_prime)
   }
@@ -384,15 +496,35 @@ impl<G: ProjectiveCurve> PolyEvalProof<G> {
     r: &[G::ScalarField], // point at which the polynomial is evaluated
     C_Zr: &G,             // commitment to \widetilde{Z}(r)
     comm: &PolyCommitment<G>,
+  ) -> Result<(), ProofVerifyError> {
+    let mut acc = MSMAccumulator::new();
+    self.verify_deferred(gens, transcript, r, C_Zr, comm, &mut acc)?;
+    acc.check()
+  }
+
+  // adds the verification equation of the underlying dot product proof to `acc` instead of
+  // checking it
+  pub fn verify_deferred(
+    &self,
+    gens: &PolyCommitmentGens<G>,
+    transcript: &mut Transcript,
+    r: &[G::ScalarField],
+    C_Zr: &G,
+    comm: &PolyCommitment<G>,
+    acc: &mut MSMAccumulator<G>,
   ) -> Result<(), ProofVerifyError> {
     <Transcript as ProofTranscript<G>>::append_protocol_name(
       transcript,
       PolyEvalProof::<G>::protocol_name(),
     );
 
-    // compute L and R
+    // compute L and R with the rows of the commitment, which have the length of the generators
+    let (left_num_vars, right_num_vars) = gens.factored_lens(r.len());
+    if comm.C.len() != left_num_vars.pow2() {
+      return Err(ProofVerifyError::InternalError);
+    }
     let eq = EqPolynomial::new(r.to_vec());
-    let (L, R) = eq.compute_factored_evals();
+    let (L, R) = eq.compute_factored_evals_at(left_num_vars);
 
     // compute a weighted sum of commitments and L
     let C_affine = G::batch_normalization_into_affine(&comm.C);
@@ -400,9 +532,11 @@ impl<G: ProjectiveCurve> PolyEvalProof<G> {
     let L_repr = L.iter().map(|x| x.into_repr()).collect::<Vec<_>>();
     let C_LZ = VariableBaseMSM::multi_scalar_mul(C_affine.as_ref(), L_repr.as_ref());
 
-    self
-      .proof
-      .verify(R.len(), &gens.gens, transcript, &R, &C_LZ, C_Zr)
+    gens.with_row_gens(right_num_vars, |g| {
+      self
+        .proof
+        .verify(R.len(), g, transcript, &R, &C_LZ, C_Zr, acc)
+    })
   }
 
   pub fn verify_plain(
@@ -418,13 +552,26 @@ impl<G: ProjectiveCurve> PolyEvalProof<G> {
 
     self.verify(gens, transcript, r, &C_Zr, comm)
   }
+
+  pub fn verify_plain_deferred(
+    &self,
+    gens: &PolyCommitmentGens<G>,
+    transcript: &mut Transcript,
+    r: &[G::ScalarField],
+    Zr: &G::ScalarField,
+    comm: &PolyCommitment<G>,
+    acc: &mut MSMAccumulator<G>,
+  ) -> Result<(), ProofVerifyError> {
+    // compute a commitment to Zr with a blind of zero
+    let C_Zr = Zr.commit(&G::ScalarField::zero(), &gens.gens.gens_1);
+
+    self.verify_deferred(gens, transcript, r, &C_Zr, comm, acc)
+  }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
-  use ark_bls12_381::Fr;
-  use ark_bls12_381::G1Projective;
   use ark_std::test_rng;
   use ark_std::One;
   use ark_std::UniformRand;
@@ -453,10 +600,10 @@ mod tests {
     DotProductProofLog::<G>::compute_dotproduct(&LZ, &R)
   }
 
-  #[test]
-  fn check_polynomial_evaluation() {
-    check_polynomial_evaluation_helper::<G1Projective>()
-  }
+  crate::curves::curve_tests!(
+    group: check_polynomial_evaluation,
+    check_polynomial_evaluation_helper
+  );
 
   fn check_polynomial_evaluation_helper<G: ProjectiveCurve>() {
     // Z = [1, 2, 1, 4]
@@ -546,10 +693,7 @@ mod tests {
       .collect::<Vec<F>>()
   }
 
-  #[test]
-  fn check_memoized_chis() {
-    check_memoized_chis_helper::<G1Projective>()
-  }
+  crate::curves::curve_tests!(group: check_memoized_chis, check_memoized_chis_helper);
 
   fn check_memoized_chis_helper<G: ProjectiveCurve>() {
     let mut prng = test_rng();
@@ -564,10 +708,7 @@ mod tests {
     assert_eq!(chis, chis_m);
   }
 
-  #[test]
-  fn check_factored_chis() {
-    check_factored_chis_helper::<Fr>()
-  }
+  crate::curves::curve_tests!(field: check_factored_chis, check_factored_chis_helper);
 
   fn check_factored_chis_helper<F: PrimeField>() {
     let mut prng = test_rng();
@@ -583,10 +724,10 @@ mod tests {
     assert_eq!(chis, O);
   }
 
-  #[test]
-  fn check_memoized_factored_chis() {
-    check_memoized_factored_chis_helper::<Fr>()
-  }
+  crate::curves::curve_tests!(
+    field: check_memoized_factored_chis,
+    check_memoized_factored_chis_helper
+  );
 
   fn check_memoized_factored_chis_helper<F: PrimeField>() {
     let mut prng = test_rng();
@@ -603,10 +744,10 @@ mod tests {
     assert_eq!(R, R2);
   }
 
-  #[test]
-  fn check_polynomial_commit() {
-    check_polynomial_commit_helper::<G1Projective>()
-  }
+  crate::curves::curve_tests!(
+    group: check_polynomial_commit,
+    check_polynomial_commit_helper
+  );
 
   fn check_polynomial_commit_helper<G: ProjectiveCurve>() {
     let Z = vec![
@@ -644,4 +785,96 @@ mod tests {
       .verify(&gens, &mut verifier_transcript, &r, &C_Zr, &poly_commitment)
       .is_ok());
   }
+
+  crate::curves::curve_tests!(
+    group: check_polynomial_commit_with_ratio,
+    check_polynomial_commit_with_ratio_helper
+  );
+
+  fn check_polynomial_commit_with_ratio_helper<G: ProjectiveCurve>() {
+    let mut prng = test_rng();
+    let num_vars = 7;
+    let poly = DensePolynomial::new(
+      (0..num_vars.pow2())
+        .map(|_| G::ScalarField::rand(&mut prng))
+        .collect::<Vec<_>>(),
+    );
+    let r = (0..num_vars)
+      .map(|_| G::ScalarField::rand(&mut prng))
+      .collect::<Vec<_>>();
+    let eval = poly.evaluate::<G>(&r);
+
+    // from a single row to rows of two entries, clamped beyond either end
+    for (log_ratio, num_rows) in [(-9, 1), (-3, 4), (0, 8), (3, 32), (9, 64)] {
+      let gens = PolyCommitmentGens::<G>::new_with_ratio(num_vars, log_ratio, b"test-ratio");
+      let (poly_commitment, blinds) = poly.commit(&gens, None);
+      assert_eq!(poly_commitment.num_rows(), num_rows);
+
+      let mut random_tape = RandomTape::new(b"proof");
+      let (proof, C_Zr) = PolyEvalProof::prove(
+        &poly,
+        Some(&blinds),
+        &r,
+        &eval,
+        None,
+        &gens,
+        &mut Transcript::new(b"example"),
+        &mut random_tape,
+      );
+      assert!(proof
+        .verify(
+          &gens,
+          &mut Transcript::new(b"example"),
+          &r,
+          &C_Zr,
+          &poly_commitment
+        )
+        .is_ok());
+    }
+  }
+
+  crate::curves::curve_tests!(
+    group: check_polynomial_commit_short,
+    check_polynomial_commit_short_helper
+  );
+
+  fn check_polynomial_commit_short_helper<G: ProjectiveCurve>() {
+    // rows of 2^4 evaluations, which polynomials with fewer variables do not fill
+    let mut prng = test_rng();
+    let gens = PolyCommitmentGens::<G>::new(7, b"test-short");
+    for num_vars in 1..=7 {
+      let poly = DensePolynomial::new(
+        (0..num_vars.pow2())
+          .map(|_| G::ScalarField::rand(&mut prng))
+          .collect::<Vec<_>>(),
+      );
+      let r = (0..num_vars)
+        .map(|_| G::ScalarField::rand(&mut prng))
+        .collect::<Vec<_>>();
+      let eval = poly.evaluate::<G>(&r);
+      let (poly_commitment, blinds) = poly.commit(&gens, None);
+      assert_eq!(poly_commitment.num_rows(), (num_vars.max(4) - 4).pow2());
+
+      let mut random_tape = RandomTape::new(b"proof");
+      let (proof, C_Zr) = PolyEvalProof::prove(
+        &poly,
+        Some(&blinds),
+        &r,
+        &eval,
+        None,
+        &gens,
+        &mut Transcript::new(b"example"),
+        &mut random_tape,
+      );
+      assert!(proof
+        .verify(
+          &gens,
+          &mut Transcript::new(b"example"),
+          &r,
+          &C_Zr,
+          &poly_commitment
+        )
+        .is_ok());
+    }
+  }
 }
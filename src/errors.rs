@@ -2,12 +2,18 @@ use ark_serialize::SerializationError;
 use core::fmt::Debug;
 use thiserror::Error;
 
+/// `ProofVerifyError` is returned when a proof fails to verify
 #[derive(Error, Debug)]
 pub enum ProofVerifyError {
+  /// returned if a check of the verifier fails
   #[error("Proof verification failed")]
   InternalError,
+  /// returned if a compressed group element fails to decompress
   #[error("Compressed group element failed to decompress: {0:?}")]
   DecompressionError([u8; 32]),
+  /// returned by batch verification with the index of the first proof that fails to verify
+  #[error("Proof {0} in the batch failed to verify")]
+  InvalidProofInBatch(usize),
 }
 
 impl Default for ProofVerifyError {
@@ -16,6 +22,7 @@ impl Default for ProofVerifyError {
   }
 }
 
+/// `R1CSError` is returned when an R1CS instance or an assignment is malformed
 #[derive(Debug)]
 pub enum R1CSError {
   /// returned if the number of constraints is not a power of 2
@@ -30,6 +37,36 @@ pub enum R1CSError {
   InvalidScalar,
   /// returned if the supplied row or col in (row,col,val) tuple is out of range
   InvalidIndex,
+  /// returned if a lookup refers to an invalid range of variables or a table that is empty or too large, or a looked-up value is not in its table
+  InvalidLookup,
+  /// returned if a memory refers to an invalid range of variables or has no cells, or its accesses are inconsistent
+  InvalidMemory,
+  /// returned if a program refers to invalid registers or instructions, or is run for an invalid number of steps
+  InvalidProgram,
+  /// returned if a program accesses memory out of range, runs past its code, or does not halt
+  ExecutionFault,
+  /// returned if the witness solver cannot determine the values of the given variables
+  UnsolvableVars(Vec<usize>),
+  /// returned if a hint of the witness solver refers to a column out of range, or returns the wrong number of values
+  InvalidHint,
+  /// returned if assignments to columns shared between composed instances disagree
+  InconsistentAssignment,
+  /// returned if a CCS has no matrices or terms, or a term is empty or refers to a missing matrix
+  InvalidTerm,
+  /// returned if an AIR has no columns or a trace length that is not a power of two (at least 2), or a trace does not match its shape
+  InvalidTrace,
+  /// returned if a Plonkish gate has no terms or refers to a missing selector or wire, or a selector does not have one value per gate
+  InvalidGate,
+  /// returned if a committed segment of the witness does not start and end at a row of the witness commitment, or has the wrong number of blinds
+  InvalidSegment,
+  /// returned if the phase-one variables of a two-phase witness do not fill whole rows of the witness commitment, or the challenges do not fit in the inputs
+  InvalidPhases,
+  /// returned if the powers of tau of a setup are too few, or are not powers of the same tau in both groups, or the public parameters are too small for an instance
+  InvalidSetup,
+  /// returned if the indices of hidden inputs are out of range or repeated
+  InvalidHiddenInputs,
+  /// returned if the number of assignments or instances proven together does not match the generators
+  InvalidNumberOfInstances,
   /// Ark serialization error
   ArkSerializationError(SerializationError),
 }
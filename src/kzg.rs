@@ -0,0 +1,725 @@
+#![allow(clippy::too_many_arguments)]
+use super::commitments::{MSMAccumulator, MultiCommitGens};
+use super::dense_mlpoly::DensePolynomial;
+use super::errors::{ProofVerifyError, R1CSError};
+use super::pcs::{
+  fold_challenge, fold_polys, with_bounded_setup, HidingMultilinearPCS, MultilinearPCS,
+};
+use super::random::RandomTape;
+use super::transcript::{AppendToTranscript, ProofTranscript};
+use super::{NIZKGens, SNARKGens};
+use ark_ec::msm::VariableBaseMSM;
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{One, PrimeField, Zero};
+use ark_serialize::*;
+use core::marker::PhantomData;
+use merlin::Transcript;
+
+/// `PowersOfTau` holds the output of a powers-of-tau ceremony, from which the public parameters
+/// of `MultilinearKZG` for polynomials with up to `max_num_vars` variables are derived
+pub struct PowersOfTau<E: PairingEngine> {
+  max_num_vars: usize,
+  // tau^i G for i up to 2^max_num_vars
+  powers_g1: Vec<E::G1Affine>,
+  // tau^(2^i) H for i up to max_num_vars
+  powers_g2: Vec<E::G2Affine>,
+  h_g2: E::G2Affine,
+}
+
+impl<E: PairingEngine> PowersOfTau<E> {
+  // produces powers of a tau derived deterministically from `label`; anyone can compute tau, so
+  // commitments made with them are not binding, and they are only meant for tests
+  #[cfg(test)]
+  pub(crate) fn new_insecure(max_num_vars: usize, label: &'static [u8]) -> Self {
+    use ark_ec::msm::FixedBaseMSM;
+    use ark_ff::FpParameters;
+
+    let mut transcript = Transcript::new(b"insecure powers of tau");
+    transcript.append_message(b"label", label);
+    let tau =
+      <Transcript as ProofTranscript<E::G1Projective>>::challenge_scalar(&mut transcript, b"tau");
+
+    let num_powers = (1 << max_num_vars) + 1;
+    let mut powers = vec![E::Fr::one(); num_powers];
+    for i in 1..num_powers {
+      powers[i] = powers[i - 1] * tau;
+    }
+    let scalar_size = <E::Fr as PrimeField>::Params::MODULUS_BITS as usize;
+    let window = FixedBaseMSM::get_mul_window_size(num_powers);
+    let table = FixedBaseMSM::get_window_table(
+      scalar_size,
+      window,
+      E::G1Projective::prime_subgroup_generator(),
+    );
+    let powers_g1 =
+      ProjectiveCurve::batch_normalization_into_affine(&FixedBaseMSM::multi_scalar_mul::<
+        E::G1Projective,
+      >(
+        scalar_size, window, &table, &powers
+      ));
+
+    let h = E::G2Projective::prime_subgroup_generator();
+    let powers_g2 = ProjectiveCurve::batch_normalization_into_affine(
+      &(0..=max_num_vars)
+        .map(|i| h.mul(powers[1 << i].into_repr()))
+        .collect::<Vec<_>>(),
+    );
+
+    PowersOfTau {
+      max_num_vars,
+      powers_g1,
+      powers_g2,
+      h_g2: h.into_affine(),
+    }
+  }
+
+  /// Takes the consecutive powers tau^i G and tau^i H of a ceremony, starting with i = 0, and
+  /// checks that they are consistent; the powers beyond the largest `max_num_vars` for which
+  /// tau^(2^max_num_vars) is known in both groups are dropped
+  pub fn new(
+    mut powers_g1: Vec<E::G1Affine>,
+    powers_g2: Vec<E::G2Affine>,
+  ) -> Result<Self, R1CSError> {
+    let num_powers = powers_g1.len().min(powers_g2.len());
+    if num_powers < 2 || powers_g1[0].is_zero() || powers_g2[0].is_zero() {
+      return Err(R1CSError::InvalidSetup);
+    }
+    let max_num_vars = (usize::BITS - 1 - (num_powers - 1).leading_zeros()) as usize;
+    powers_g1.truncate((1 << max_num_vars) + 1);
+    let h_g2 = powers_g2[0];
+    let powers_g2 = (0..=max_num_vars)
+      .map(|i| powers_g2[1 << i])
+      .collect::<Vec<_>>();
+
+    // the powers are checked with a random linear combination that binds all of them
+    let mut transcript = Transcript::new(b"powers of tau");
+    let mut buf = vec![];
+    powers_g1.serialize(&mut buf)?;
+    h_g2.serialize(&mut buf)?;
+    powers_g2.serialize(&mut buf)?;
+    transcript.append_message(b"powers", &buf);
+    let rho =
+      <Transcript as ProofTranscript<E::G1Projective>>::challenge_scalar(&mut transcript, b"rho");
+    let mut rho_powers = vec![E::Fr::one(); 1 << max_num_vars];
+    for i in 1..rho_powers.len() {
+      rho_powers[i] = rho_powers[i - 1] * rho;
+    }
+    let rho_powers = rho_powers.iter().map(|r| r.into_repr()).collect::<Vec<_>>();
+
+    // each power in G1 is tau times the previous one, with tau given by tau H
+    let comb: E::G1Projective =
+      VariableBaseMSM::multi_scalar_mul(&powers_g1[..powers_g1.len() - 1], &rho_powers);
+    let comb_next: E::G1Projective =
+      VariableBaseMSM::multi_scalar_mul(&powers_g1[1..], &rho_powers);
+    let is_valid_g1 = E::product_of_pairings(&[
+      (comb_next.into_affine().into(), h_g2.into()),
+      ((-comb).into_affine().into(), powers_g2[0].into()),
+    ]) == E::Fqk::one();
+
+    // each tau^(2^i) H matches tau^(2^i) G
+    let rho_powers = &rho_powers[..=max_num_vars];
+    let bases_g1 = (0..=max_num_vars)
+      .map(|i| powers_g1[1 << i])
+      .collect::<Vec<_>>();
+    let comb_g1: E::G1Projective = VariableBaseMSM::multi_scalar_mul(&bases_g1, rho_powers);
+    let comb_g2: E::G2Projective = VariableBaseMSM::multi_scalar_mul(&powers_g2, rho_powers);
+    let is_valid_g2 = E::product_of_pairings(&[
+      (comb_g1.into_affine().into(), h_g2.into()),
+      ((-powers_g1[0]).into(), comb_g2.into_affine().into()),
+    ]) == E::Fqk::one();
+
+    if !is_valid_g1 || !is_valid_g2 {
+      return Err(R1CSError::InvalidSetup);
+    }
+    Ok(PowersOfTau {
+      max_num_vars,
+      powers_g1,
+      powers_g2,
+      h_g2,
+    })
+  }
+
+  /// Reads the consecutive powers tau^i G and tau^i H of a ceremony, serialized one after the
+  /// other as vectors of compressed points, and checks them as `new` does
+  pub fn load<R: Read>(mut reader: R) -> Result<Self, R1CSError> {
+    let powers_g1 = Vec::<E::G1Affine>::deserialize(&mut reader)?;
+    let powers_g2 = Vec::<E::G2Affine>::deserialize(&mut reader)?;
+    PowersOfTau::new(powers_g1, powers_g2)
+  }
+
+  /// Returns the largest number of variables of the polynomials the powers can commit to
+  pub fn max_num_vars(&self) -> usize {
+    self.max_num_vars
+  }
+
+  /// Derives the public parameters for polynomials with up to `num_vars` variables; returns
+  /// `R1CSError::InvalidSetup` if `num_vars` exceeds `max_num_vars`
+  pub fn gens(&self, num_vars: usize) -> Result<MultilinearKZGGens<E>, R1CSError> {
+    if num_vars > self.max_num_vars {
+      return Err(R1CSError::InvalidSetup);
+    }
+    Ok(self.derive_gens(num_vars))
+  }
+
+  // like `gens`, for a `num_vars` that is known not to exceed `max_num_vars`
+  fn derive_gens(&self, num_vars: usize) -> MultilinearKZGGens<E> {
+    // the eq polynomial over i variables at (tau^(2^(i-1)), ..., tau^2, tau) is spelled out over
+    // the powers of tau one variable at a time, since 1 - tau^(2^j) and tau^(2^j) combine
+    // the powers with and without the bit j
+    let bases = (0..=num_vars)
+      .map(|i| {
+        let mut basis = self.powers_g1[..1 << i]
+          .iter()
+          .map(|p| p.into_projective())
+          .collect::<Vec<_>>();
+        for bit in 0..i {
+          let step = 1 << bit;
+          for j in (0..basis.len()).filter(|j| j & step == 0) {
+            let high = basis[j | step];
+            basis[j] -= high;
+          }
+        }
+        ProjectiveCurve::batch_normalization_into_affine(&basis)
+      })
+      .collect::<Vec<_>>();
+
+    MultilinearKZGGens {
+      num_vars,
+      bases,
+      powers_g1: (0..num_vars)
+        .map(|i| self.powers_g1[1 << i].into_projective())
+        .collect(),
+      gens_eval: MultiCommitGens {
+        n: 1,
+        G: vec![self.powers_g1[0].into_projective()],
+        h: self.powers_g1[1 << num_vars].into_projective(),
+      },
+      h_g2: self.h_g2.into(),
+      powers_g2: self.powers_g2[..num_vars]
+        .iter()
+        .map(|p| (*p).into())
+        .collect(),
+      gamma_g2: self.powers_g2[num_vars].into(),
+    }
+  }
+}
+
+impl<E: PairingEngine> SNARKGens<E::G1Projective, MultilinearKZG<E>> {
+  /// Constructs a new `SNARKGens` for `MultilinearKZG` given the size of the R1CS statement and
+  /// the powers of a ceremony; returns an error if the powers are too short for the statement
+  pub fn new_with_powers(
+    num_cons: usize,
+    num_vars: usize,
+    num_inputs: usize,
+    num_nz_entries: usize,
+    powers: &PowersOfTau<E>,
+  ) -> Result<Self, R1CSError> {
+    with_bounded_setup(
+      powers.max_num_vars(),
+      |num_vars| powers.derive_gens(num_vars),
+      |setup| SNARKGens::new_with_setup(num_cons, num_vars, num_inputs, num_nz_entries, setup),
+    )
+  }
+}
+
+impl<E: PairingEngine> NIZKGens<E::G1Projective, MultilinearKZG<E>> {
+  /// Constructs a new `NIZKGens` for `MultilinearKZG` given the size of the R1CS statement and
+  /// the powers of a ceremony; returns an error if the powers are too short for the statement
+  pub fn new_with_powers(
+    num_cons: usize,
+    num_vars: usize,
+    num_inputs: usize,
+    powers: &PowersOfTau<E>,
+  ) -> Result<Self, R1CSError> {
+    with_bounded_setup(
+      powers.max_num_vars(),
+      |num_vars| powers.derive_gens(num_vars),
+      |setup| NIZKGens::new_with_setup(num_cons, num_vars, num_inputs, setup),
+    )
+  }
+}
+
+/// `MultilinearKZGGens` holds the public parameters of `MultilinearKZG` for polynomials with up
+/// to a given number of variables
+pub struct MultilinearKZGGens<E: PairingEngine> {
+  num_vars: usize,
+  // the eq polynomials over i variables at the powers of tau, for i up to num_vars
+  bases: Vec<Vec<E::G1Affine>>,
+  // tau^(2^i) G for i < num_vars
+  powers_g1: Vec<E::G1Projective>,
+  // G and the hiding generator tau^(2^num_vars) G
+  gens_eval: MultiCommitGens<E::G1Projective>,
+  h_g2: E::G2Prepared,
+  // tau^(2^i) H for i < num_vars
+  powers_g2: Vec<E::G2Prepared>,
+  // tau^(2^num_vars) H
+  gamma_g2: E::G2Prepared,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct KZGCommitment<G: ProjectiveCurve> {
+  C: G,
+}
+
+impl<G: ProjectiveCurve> AppendToTranscript<G> for KZGCommitment<G> {
+  fn append_to_transcript(&self, label: &'static [u8], transcript: &mut Transcript) {
+    <Transcript as ProofTranscript<G>>::append_point(transcript, label, &self.C);
+  }
+}
+
+#[derive(Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct KZGEvalProof<G: ProjectiveCurve> {
+  // commitments to the quotients by each variable
+  proofs: Vec<G>,
+  // the quotient by the hiding generator
+  proof_blind: G,
+}
+
+/// `MultilinearKZG` is the pairing-based commitment scheme for multilinear polynomials of
+/// Papamanthou, Shi and Tamassia: a commitment is a single group element, and an evaluation
+/// proof holds one element per variable and is checked with one product of pairings
+#[derive(Debug)]
+pub struct MultilinearKZG<E> {
+  _engine: PhantomData<E>,
+}
+
+impl<E: PairingEngine> MultilinearKZG<E> {
+  fn protocol_name() -> &'static [u8] {
+    b"multilinear KZG evaluation proof"
+  }
+
+  // the i-th variable of a polynomial with m variables is evaluated at tau^(2^(m-1-i)), so
+  // the quotient by it has the m-1-i remaining variables, and polynomials with fewer variables
+  // than the public parameters use the smaller bases
+  fn open(
+    poly: &DensePolynomial<E::Fr>,
+    blind: &E::Fr,
+    r: &[E::Fr],
+    blind_eval: &E::Fr,
+    gens: &MultilinearKZGGens<E>,
+    transcript: &mut Transcript,
+    mut random_tape: Option<&mut RandomTape<E::G1Projective>>,
+  ) -> KZGEvalProof<E::G1Projective> {
+    <Transcript as ProofTranscript<E::G1Projective>>::append_protocol_name(
+      transcript,
+      MultilinearKZG::<E>::protocol_name(),
+    );
+    let num_vars = r.len();
+    assert!(num_vars <= gens.num_vars);
+    assert_eq!(poly.get_num_vars(), num_vars);
+
+    let mut Z = (0..poly.len()).map(|i| poly[i]).collect::<Vec<_>>();
+    let mut proofs = Vec::with_capacity(num_vars);
+    // the quotient by the hiding generator makes up for the blinds of the commitment, the
+    // evaluation and the quotients
+    let mut blind_proof = *blind - blind_eval;
+    let mut proof_blind = E::G1Projective::zero();
+    for (i, r_i) in r.iter().enumerate() {
+      let half = Z.len() / 2;
+      let quotient = (0..half).map(|j| Z[half + j] - Z[j]).collect::<Vec<_>>();
+      let blind_quotient = random_tape
+        .as_mut()
+        .map_or(E::Fr::zero(), |t| t.random_scalar(b"blind_quotient"));
+      let scalars = quotient.iter().map(|q| q.into_repr()).collect::<Vec<_>>();
+      proofs.push(
+        VariableBaseMSM::multi_scalar_mul(&gens.bases[num_vars - 1 - i], &scalars)
+          + gens.gens_eval.h.mul(blind_quotient.into_repr()),
+      );
+      blind_proof += blind_quotient * r_i;
+      proof_blind -= gens.powers_g1[num_vars - 1 - i].mul(blind_quotient.into_repr());
+      Z = (0..half).map(|j| Z[j] + *r_i * quotient[j]).collect();
+    }
+    let proof_blind = proof_blind + gens.gens_eval.G[0].mul(blind_proof.into_repr());
+
+    <Transcript as ProofTranscript<E::G1Projective>>::append_points(transcript, b"proofs", &proofs);
+    KZGEvalProof {
+      proofs,
+      proof_blind,
+    }
+  }
+
+  // checks that the polynomial committed in `comm` evaluates at `r` to the value committed in
+  // `comm_eval` with the generators of `gens_eval`
+  fn check(
+    proof: &KZGEvalProof<E::G1Projective>,
+    gens: &MultilinearKZGGens<E>,
+    transcript: &mut Transcript,
+    r: &[E::Fr],
+    comm_eval: &E::G1Projective,
+    comm: &KZGCommitment<E::G1Projective>,
+  ) -> Result<(), ProofVerifyError> {
+    <Transcript as ProofTranscript<E::G1Projective>>::append_protocol_name(
+      transcript,
+      MultilinearKZG::<E>::protocol_name(),
+    );
+    let num_vars = r.len();
+    if num_vars > gens.num_vars || proof.proofs.len() != num_vars {
+      return Err(ProofVerifyError::InternalError);
+    }
+    <Transcript as ProofTranscript<E::G1Projective>>::append_points(
+      transcript,
+      b"proofs",
+      &proof.proofs,
+    );
+
+    // C - C_v = sum_i (tau_i - r_i) proof_i + gamma proof_blind, with the multiples of r_i
+    // moved to the side paired with H
+    let mut lhs = comm.C - comm_eval;
+    for (p, r_i) in proof.proofs.iter().zip(r.iter()) {
+      lhs += p.mul(r_i.into_repr());
+    }
+    let mut pairs = vec![((-lhs).into_affine().into(), gens.h_g2.clone())];
+    for (i, p) in proof.proofs.iter().enumerate() {
+      pairs.push((
+        p.into_affine().into(),
+        gens.powers_g2[num_vars - 1 - i].clone(),
+      ));
+    }
+    pairs.push((
+      proof.proof_blind.into_affine().into(),
+      gens.gamma_g2.clone(),
+    ));
+
+    if E::product_of_pairings(&pairs) == E::Fqk::one() {
+      Ok(())
+    } else {
+      Err(ProofVerifyError::InternalError)
+    }
+  }
+}
+
+impl<E: PairingEngine> MultilinearPCS<E::G1Projective> for MultilinearKZG<E> {
+  type Gens = MultilinearKZGGens<E>;
+  type Commitment = KZGCommitment<E::G1Projective>;
+  type Blinds = E::Fr;
+  type EvalProof = KZGEvalProof<E::G1Projective>;
+  type BatchEvalProof = KZGEvalProof<E::G1Projective>;
+
+  fn max_num_vars(gens: &Self::Gens) -> Option<usize> {
+    Some(gens.num_vars)
+  }
+
+  fn gens_eval(gens: &Self::Gens, _label: &'static [u8]) -> MultiCommitGens<E::G1Projective> {
+    gens.gens_eval.clone()
+  }
+
+  fn commit(
+    poly: &DensePolynomial<E::Fr>,
+    gens: &Self::Gens,
+    random_tape: Option<&mut RandomTape<E::G1Projective>>,
+  ) -> (Self::Commitment, Self::Blinds) {
+    let num_vars = poly.get_num_vars();
+    assert!(num_vars <= gens.num_vars);
+    let blind = random_tape.map_or(E::Fr::zero(), |t| t.random_scalar(b"poly_blind"));
+    let scalars = (0..poly.len())
+      .map(|i| poly[i].into_repr())
+      .collect::<Vec<_>>();
+    let C = VariableBaseMSM::multi_scalar_mul(&gens.bases[num_vars], &scalars)
+      + gens.gens_eval.h.mul(blind.into_repr());
+    (KZGCommitment { C }, blind)
+  }
+
+  fn prove(
+    poly: &DensePolynomial<E::Fr>,
+    blinds: Option<&Self::Blinds>,
+    r: &[E::Fr],
+    _eval: &E::Fr,
+    gens: &Self::Gens,
+    transcript: &mut Transcript,
+    _random_tape: &mut RandomTape<E::G1Projective>,
+  ) -> Self::EvalProof {
+    let zero = E::Fr::zero();
+    let blind = blinds.unwrap_or(&zero);
+    MultilinearKZG::open(poly, blind, r, &zero, gens, transcript, None)
+  }
+
+  fn verify(
+    proof: &Self::EvalProof,
+    gens: &Self::Gens,
+    transcript: &mut Transcript,
+    r: &[E::Fr],
+    eval: &E::Fr,
+    comm: &Self::Commitment,
+    _acc: &mut MSMAccumulator<E::G1Projective>,
+  ) -> Result<(), ProofVerifyError> {
+    let comm_eval = gens.gens_eval.G[0].mul(eval.into_repr());
+    MultilinearKZG::check(proof, gens, transcript, r, &comm_eval, comm)
+  }
+
+  fn prove_batched(
+    polys: &[&DensePolynomial<E::Fr>],
+    blinds: Option<&[&Self::Blinds]>,
+    r: &[E::Fr],
+    evals: &[E::Fr],
+    gens: &Self::Gens,
+    transcript: &mut Transcript,
+    random_tape: &mut RandomTape<E::G1Projective>,
+  ) -> Self::BatchEvalProof {
+    assert!(!polys.is_empty());
+    assert_eq!(polys.len(), evals.len());
+    let powers =
+      fold_challenge::<E::G1Projective>(MultilinearKZG::<E>::protocol_name(), evals, transcript);
+    let poly = fold_polys(polys, &powers);
+    let blind = blinds.map(|blinds| {
+      assert_eq!(blinds.len(), polys.len());
+      (0..blinds.len()).map(|i| powers[i] * blinds[i]).sum()
+    });
+    let eval = (0..evals.len()).map(|i| powers[i] * evals[i]).sum();
+    MultilinearKZG::prove(
+      &poly,
+      blind.as_ref(),
+      r,
+      &eval,
+      gens,
+      transcript,
+      random_tape,
+    )
+  }
+
+  fn verify_batched(
+    proof: &Self::BatchEvalProof,
+    gens: &Self::Gens,
+    transcript: &mut Transcript,
+    r: &[E::Fr],
+    evals: &[E::Fr],
+    comms: &[&Self::Commitment],
+    acc: &mut MSMAccumulator<E::G1Projective>,
+  ) -> Result<(), ProofVerifyError> {
+    if comms.is_empty() || comms.len() != evals.len() {
+      return Err(ProofVerifyError::InternalError);
+    }
+    let powers =
+      fold_challenge::<E::G1Projective>(MultilinearKZG::<E>::protocol_name(), evals, transcript);
+    let comm = KZGCommitment {
+      C: (0..comms.len())
+        .map(|i| comms[i].C.mul(powers[i].into_repr()))
+        .sum(),
+    };
+    let eval = (0..evals.len()).map(|i| powers[i] * evals[i]).sum();
+    MultilinearKZG::verify(proof, gens, transcript, r, &eval, &comm, acc)
+  }
+}
+
+impl<E: PairingEngine> HidingMultilinearPCS<E::G1Projective> for MultilinearKZG<E> {
+  fn prove_hiding(
+    poly: &DensePolynomial<E::Fr>,
+    blinds: Option<&Self::Blinds>,
+    r: &[E::Fr],
+    eval: &E::Fr,
+    blind_eval: &E::Fr,
+    gens: &Self::Gens,
+    transcript: &mut Transcript,
+    random_tape: &mut RandomTape<E::G1Projective>,
+  ) -> (Self::EvalProof, E::G1Projective) {
+    let zero = E::Fr::zero();
+    let blind = blinds.unwrap_or(&zero);
+    let proof = MultilinearKZG::open(
+      poly,
+      blind,
+      r,
+      blind_eval,
+      gens,
+      transcript,
+      Some(random_tape),
+    );
+    let comm_eval =
+      gens.gens_eval.G[0].mul(eval.into_repr()) + gens.gens_eval.h.mul(blind_eval.into_repr());
+    (proof, comm_eval)
+  }
+
+  fn verify_hiding(
+    proof: &Self::EvalProof,
+    gens: &Self::Gens,
+    transcript: &mut Transcript,
+    r: &[E::Fr],
+    comm_eval: &E::G1Projective,
+    comm: &Self::Commitment,
+    _acc: &mut MSMAccumulator<E::G1Projective>,
+  ) -> Result<(), ProofVerifyError> {
+    MultilinearKZG::check(proof, gens, transcript, r, comm_eval, comm)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::pcs::tests::check_batched_eval_proof_helper;
+  use crate::{Instance, NIZK, SNARK};
+  use ark_std::{test_rng, UniformRand};
+
+  crate::curves::curve_tests!(
+    pairing: check_kzg_batched_eval_proof,
+    check_kzg_batched_eval_proof_helper
+  );
+
+  fn check_kzg_batched_eval_proof_helper<E: PairingEngine>() {
+    let gens = PowersOfTau::<E>::new_insecure(5, b"test_srs")
+      .gens(5)
+      .unwrap();
+    check_batched_eval_proof_helper::<E::G1Projective, MultilinearKZG<E>>(5, &gens)
+  }
+
+  crate::curves::curve_tests!(pairing: check_kzg_snark, check_kzg_snark_helper);
+
+  fn check_kzg_snark_helper<E: PairingEngine>() {
+    let num_vars = 16;
+    let num_cons = num_vars;
+    let num_inputs = 3;
+    let (inst, vars, inputs) = Instance::produce_synthetic_r1cs(num_cons, num_vars, num_inputs);
+
+    // all the public parameters come from the same powers of tau
+    let srs = PowersOfTau::<E>::new_insecure(10, b"test_srs");
+    let gens = SNARKGens::<E::G1Projective, MultilinearKZG<E>>::new_with_powers(
+      num_cons, num_vars, num_inputs, num_cons, &srs,
+    )
+    .unwrap();
+    let (comm, decomm) = SNARK::encode(&inst, &gens);
+    let proof = SNARK::prove(
+      &inst,
+      &comm,
+      &decomm,
+      vars.clone(),
+      &inputs,
+      &gens,
+      &mut Transcript::new(b"example"),
+    )
+    .unwrap();
+    assert!(proof
+      .verify(&comm, &inputs, &mut Transcript::new(b"example"), &gens)
+      .is_ok());
+
+    let gens = NIZKGens::<E::G1Projective, MultilinearKZG<E>>::new_with_powers(
+      num_cons, num_vars, num_inputs, &srs,
+    )
+    .unwrap();
+    let proof = NIZK::prove(
+      &inst,
+      vars,
+      &inputs,
+      &gens,
+      &mut Transcript::new(b"example"),
+    )
+    .unwrap();
+    assert!(proof
+      .verify(&inst, &inputs, &mut Transcript::new(b"example"), &gens)
+      .is_ok());
+
+    // an instance larger than the generators is rejected before it is committed to
+    let (large_inst, large_vars, large_inputs) =
+      Instance::produce_synthetic_r1cs(num_cons, 2 * num_vars, num_inputs);
+    assert!(matches!(
+      NIZK::prove(
+        &large_inst,
+        large_vars,
+        &large_inputs,
+        &gens,
+        &mut Transcript::new(b"example"),
+      ),
+      Err(R1CSError::InvalidSetup)
+    ));
+  }
+
+  crate::curves::curve_tests!(
+    pairing: check_kzg_short_powers,
+    check_kzg_short_powers_helper
+  );
+
+  fn check_kzg_short_powers_helper<E: PairingEngine>() {
+    let (num_cons, num_vars, num_inputs) = (16, 16, 3);
+
+    // the generators for the lookups and memories need one more variable than the witness
+    let srs = PowersOfTau::<E>::new_insecure(4, b"test_srs");
+    assert!(matches!(
+      SNARKGens::<E::G1Projective, MultilinearKZG<E>>::new_with_powers(
+        num_cons, num_vars, num_inputs, num_cons, &srs
+      ),
+      Err(R1CSError::InvalidSetup)
+    ));
+    assert!(matches!(
+      NIZKGens::<E::G1Projective, MultilinearKZG<E>>::new_with_powers(
+        num_cons, num_vars, num_inputs, &srs
+      ),
+      Err(R1CSError::InvalidSetup)
+    ));
+
+    // which the powers of one more variable suffice for
+    let srs = PowersOfTau::<E>::new_insecure(5, b"test_srs");
+    assert!(
+      NIZKGens::<E::G1Projective, MultilinearKZG<E>>::new_with_powers(
+        num_cons, num_vars, num_inputs, &srs
+      )
+      .is_ok()
+    );
+  }
+
+  crate::curves::curve_tests!(pairing: check_powers_of_tau, check_powers_of_tau_helper);
+
+  fn check_powers_of_tau_helper<E: PairingEngine>() {
+    let mut rng = test_rng();
+    let tau = E::Fr::rand(&mut rng);
+    let num_powers = 10;
+    let mut powers = vec![E::Fr::one(); num_powers];
+    for i in 1..num_powers {
+      powers[i] = powers[i - 1] * tau;
+    }
+    let powers_g1 = powers
+      .iter()
+      .map(|p| {
+        E::G1Affine::prime_subgroup_generator()
+          .mul(*p)
+          .into_affine()
+      })
+      .collect::<Vec<_>>();
+    let powers_g2 = powers
+      .iter()
+      .map(|p| {
+        E::G2Affine::prime_subgroup_generator()
+          .mul(*p)
+          .into_affine()
+      })
+      .collect::<Vec<_>>();
+    let mut buf = vec![];
+    powers_g1.serialize(&mut buf).unwrap();
+    powers_g2.serialize(&mut buf).unwrap();
+
+    // 9 powers of tau in each group suffice for polynomials with 3 variables
+    let srs = PowersOfTau::<E>::load(&buf[..]).unwrap();
+    assert_eq!(srs.max_num_vars(), 3);
+    let gens = srs.gens(3).unwrap();
+    assert!(matches!(srs.gens(4), Err(R1CSError::InvalidSetup)));
+    let poly = DensePolynomial::new((0..8).map(|_| E::Fr::rand(&mut rng)).collect::<Vec<_>>());
+    let mut random_tape = RandomTape::new(b"proof");
+    let (comm, blind) = MultilinearKZG::commit(&poly, &gens, Some(&mut random_tape));
+    let r = (0..3).map(|_| E::Fr::rand(&mut rng)).collect::<Vec<_>>();
+    let eval = poly.evaluate::<E::G1Projective>(&r);
+    let blind_eval = E::Fr::rand(&mut rng);
+    let (proof, comm_eval) = MultilinearKZG::prove_hiding(
+      &poly,
+      Some(&blind),
+      &r,
+      &eval,
+      &blind_eval,
+      &gens,
+      &mut Transcript::new(b"example"),
+      &mut random_tape,
+    );
+    assert!(MultilinearKZG::verify_hiding(
+      &proof,
+      &gens,
+      &mut Transcript::new(b"example"),
+      &r,
+      &comm_eval,
+      &comm,
+      &mut MSMAccumulator::new()
+    )
+    .is_ok());
+
+    // powers that are not of the same tau are rejected
+    let mut wrong_powers_g1 = powers_g1.clone();
+    wrong_powers_g1[5] = wrong_powers_g1[4];
+    assert!(PowersOfTau::<E>::new(wrong_powers_g1, powers_g2.clone()).is_err());
+    let mut wrong_powers_g2 = powers_g2.clone();
+    wrong_powers_g2[4] = wrong_powers_g2[2];
+    assert!(PowersOfTau::<E>::new(powers_g1, wrong_powers_g2).is_err());
+  }
+}
@@ -12,38 +12,94 @@ extern crate sha3;
 #[cfg(feature = "multicore")]
 extern crate rayon;
 
+mod air;
+mod batch;
+mod ccs;
+mod ccsinstance;
 mod commitments;
+mod committed;
+mod compose;
+pub mod curves;
 mod dense_mlpoly;
 mod errors;
+mod kzg;
+mod ligero;
+mod lookup;
 mod math;
+mod memory;
+mod multi;
 mod nizk;
+mod nova;
+mod opening;
+mod pcs;
+mod phased;
+mod plain;
+mod plonkish;
 mod product_tree;
+mod r1cscheck;
 mod r1csinstance;
+mod r1csopt;
 mod r1csproof;
 mod random;
+mod solver;
 mod sparse_mlpoly;
+mod stats;
 mod sumcheck;
 mod timer;
 mod transcript;
+mod uniform;
 mod unipoly;
+mod zkvm;
 
 use ark_ec::ProjectiveCurve;
 use ark_ff::PrimeField;
 use ark_serialize::*;
+use commitments::{Commitments, MSMAccumulator};
 use core::cmp::max;
-use errors::{ProofVerifyError, R1CSError};
+use dense_mlpoly::{DensePolynomial, PolyCommitmentGens};
+use lookup::{Lookup, LookupProof};
+use math::Math;
+use memory::{Memory, MemoryProof};
 use merlin::Transcript;
+use pcs::check_num_vars;
+use phased::Phases;
 use r1csinstance::{
   R1CSCommitment, R1CSCommitmentGens, R1CSDecommitment, R1CSEvalProof, R1CSInstance,
 };
-use r1csproof::{R1CSGens, R1CSProof};
+use r1csopt::R1CSOptimizer;
+use r1csproof::{are_valid_hidden, R1CSGens, R1CSProof};
 use random::RandomTape;
 use timer::Timer;
 use transcript::{AppendToTranscript, ProofTranscript};
 
+pub use air::{AIRSNARKGens, BoundaryValue, AIR, AIRSNARK};
+pub use batch::{BatchedSNARK, BatchedSNARKGens};
+pub use ccs::{CCSComputationCommitment, CCSComputationDecommitment, CCSSNARKGens, CCS, CCSSNARK};
+pub use committed::SegmentCommitment;
+pub use compose::{Composition, InstanceComposer};
+pub use errors::{ProofVerifyError, R1CSError};
+pub use kzg::{MultilinearKZG, MultilinearKZGGens, PowersOfTau};
+pub use ligero::{Ligero, LigeroGens};
+pub use lookup::LookupTable;
+pub use multi::{MultiSNARK, MultiSNARKGens};
+pub use nova::{FoldingGens, FoldingProof, RelaxedInstance, RelaxedNIZK, RelaxedWitness};
+pub use opening::{WitnessCommitment, WitnessEvalProof, WitnessGens, WitnessOpening};
+pub use pcs::{HidingMultilinearPCS, Hyrax, MultilinearPCS, TransparentMultilinearPCS};
+pub use plain::{PlainNIZK, PlainSNARK};
+pub use plonkish::{PlonkishCircuit, PlonkishNIZK, PlonkishNIZKGens};
+pub use r1cscheck::{Column, ColumnValue, Labels, SatReport, UnsatisfiedConstraint};
+pub use r1csopt::{OptimizationReport, WitnessMap};
+pub use solver::WitnessSolver;
+pub use stats::{InstanceStats, PhaseCost};
+pub use uniform::{UniformSNARK, UniformSNARKGens};
+pub use zkvm::{Instruction, Program};
+
 /// `ComputationCommitment` holds a public preprocessed NP statement (e.g., R1CS)
-pub struct ComputationCommitment<G: ProjectiveCurve> {
-  comm: R1CSCommitment<G>,
+pub struct ComputationCommitment<G: ProjectiveCurve, PCS: MultilinearPCS<G> = Hyrax<G>> {
+  comm: R1CSCommitment<G, PCS>,
+  lookups: Vec<Lookup<G::ScalarField>>,
+  memories: Vec<Memory<G::ScalarField>>,
+  phases: Option<Phases>,
 }
 
 /// `ComputationDecommitment` holds information to decommit `ComputationCommitment`
@@ -90,6 +146,15 @@ impl<F: PrimeField> Assignment<F> {
       assignment: padded_assignment,
     }
   }
+
+  // pads the assignment with zeros to `len` entries, unless it already has as many
+  fn pad_to(self, len: usize) -> VarsAssignment<F> {
+    if len > self.assignment.len() {
+      self.pad(len)
+    } else {
+      self
+    }
+  }
 }
 
 /// `VarsAssignment` holds an assignment of values to variables in an `Instance`
@@ -98,9 +163,64 @@ pub type VarsAssignment<F> = Assignment<F>;
 /// `InputsAssignment` holds an assignment of values to variables in an `Instance`
 pub type InputsAssignment<F> = Assignment<F>;
 
+// appends the name of the protocol and its statement, a commitment to the computation or the
+// computation itself, to the transcript, which both the prover and the verifier do first
+fn append_statement<G: ProjectiveCurve, S: AppendToTranscript<G>>(
+  protocol_name: &'static [u8],
+  label: &'static [u8],
+  statement: &S,
+  transcript: &mut Transcript,
+) {
+  <Transcript as ProofTranscript<G>>::append_protocol_name(transcript, protocol_name);
+  statement.append_to_transcript(label, transcript);
+}
+
+// like `append_statement`, but also creates the random tape from which the prover draws its
+// randomness, which is seeded with fresh randomness rather than derived from the transcript
+fn start_proof<G: ProjectiveCurve, S: AppendToTranscript<G>>(
+  protocol_name: &'static [u8],
+  label: &'static [u8],
+  statement: &S,
+  transcript: &mut Transcript,
+) -> RandomTape<G> {
+  let random_tape = RandomTape::new(b"proof");
+  append_statement(protocol_name, label, statement, transcript);
+  random_tape
+}
+
+// appends the claimed evaluations of A, B and C at r = (rx, ry), with which the verifier
+// completes the sum-checks, to the transcript
+fn append_inst_evals<G: ProjectiveCurve>(
+  transcript: &mut Transcript,
+  evals: &(G::ScalarField, G::ScalarField, G::ScalarField),
+) {
+  let (Ar, Br, Cr) = evals;
+  <Transcript as ProofTranscript<G>>::append_scalar(transcript, b"Ar_claim", Ar);
+  <Transcript as ProofTranscript<G>>::append_scalar(transcript, b"Ar_claim", Br);
+  <Transcript as ProofTranscript<G>>::append_scalar(transcript, b"Ar_claim", Cr);
+}
+
+// checks that the hidden inputs of a proof are at distinct indices of the inputs
+fn check_hidden<F: PrimeField>(
+  hidden: &[(usize, F)],
+  input: &InputsAssignment<F>,
+) -> Result<(), R1CSError> {
+  let indices = hidden.iter().map(|(i, _blind)| *i).collect::<Vec<_>>();
+  if are_valid_hidden(&indices, input.assignment.len()) {
+    Ok(())
+  } else {
+    Err(R1CSError::InvalidHiddenInputs)
+  }
+}
+
 /// `Instance` holds the description of R1CS matrices
 pub struct Instance<F: PrimeField> {
   inst: R1CSInstance<F>,
+  // the number of variables before padding
+  num_vars: usize,
+  lookups: Vec<Lookup<F>>,
+  memories: Vec<Memory<F>>,
+  phases: Option<Phases>,
 }
 
 impl<F: PrimeField> Instance<F> {
@@ -201,7 +321,45 @@ impl<F: PrimeField> Instance<F> {
       &C_scalar.unwrap(),
     );
 
-    Ok(Instance { inst })
+    Ok(Instance {
+      inst,
+      num_vars,
+      lookups: Vec::new(),
+      memories: Vec::new(),
+      phases: None,
+    })
+  }
+
+  /// Adds a lookup argument asserting that each of the variables `offset..offset + num_lookups`
+  /// takes a value in `table`; `num_lookups` must be a power of two (at least 2) that divides `offset`.
+  ///
+  /// **Proofs of an instance with lookups are not zero-knowledge**: the lookup argument reveals
+  /// the evaluation of the multilinear extension of the looked-up variables at a random point,
+  /// which a verifier can combine across lookups and proofs to learn about their values. Only
+  /// look up variables that may be disclosed.
+  pub fn add_lookup(
+    &mut self,
+    table: LookupTable<F>,
+    offset: usize,
+    num_lookups: usize,
+  ) -> Result<(), R1CSError> {
+    let lookup = Lookup::new(table, offset, num_lookups, self.inst.get_num_vars())?;
+    self.lookups.push(lookup);
+    Ok(())
+  }
+
+  /// Adds a memory-checking argument over a read-write memory with initial contents `init`,
+  /// accessed `num_ops` times; see `Memory` for the layout of the accesses in the variables
+  /// starting at `offset`, which `4 * num_ops` must divide. The memory is padded to a power
+  /// of two with zero-initialized cells.
+  ///
+  /// **Proofs of an instance with memories are not zero-knowledge**: as with lookups, the
+  /// memory-checking argument reveals the evaluations of the multilinear extensions of the
+  /// addresses, values, and timestamps of the accesses at a random point.
+  pub fn add_memory(&mut self, init: &[F], offset: usize, num_ops: usize) -> Result<(), R1CSError> {
+    let memory = Memory::new(init, offset, num_ops, self.inst.get_num_vars())?;
+    self.memories.push(memory);
+    Ok(())
   }
 
   /// Checks if a given R1CSInstance is satisfiable with a given variables and inputs assignments
@@ -219,23 +377,145 @@ impl<F: PrimeField> Instance<F> {
     }
 
     // we might need to pad variables
-    let padded_vars = {
-      let num_padded_vars = self.inst.get_num_vars();
-      let num_vars = vars.assignment.len();
-      if num_padded_vars > num_vars {
-        vars.pad(num_padded_vars)
-      } else {
-        vars.clone()
-      }
-    };
+    let padded_vars = vars.clone().pad_to(self.inst.get_num_vars());
 
     Ok(
       self
         .inst
-        .is_sat(&padded_vars.assignment, &inputs.assignment),
+        .is_sat(&padded_vars.assignment, &inputs.assignment)
+        && self
+          .lookups
+          .iter()
+          .all(|lookup| lookup.is_sat(&padded_vars.assignment))
+        && self
+          .memories
+          .iter()
+          .all(|memory| memory.is_sat(&padded_vars.assignment)),
     )
   }
 
+  /// Checks a given variables and inputs assignments against the instance and reports
+  /// every unsatisfied constraint, together with the values of `Az`, `Bz`, and `Cz` at
+  /// that constraint and of the columns it references; `labels` optionally names them
+  pub fn check(
+    &self,
+    vars: &VarsAssignment<F>,
+    inputs: &InputsAssignment<F>,
+    labels: Option<&Labels>,
+  ) -> Result<SatReport<F>, R1CSError> {
+    if vars.assignment.len() > self.inst.get_num_vars() {
+      return Err(R1CSError::InvalidNumberOfVars);
+    }
+
+    if inputs.assignment.len() != self.inst.get_num_inputs() {
+      return Err(R1CSError::InvalidNumberOfInputs);
+    }
+
+    // we might need to pad variables
+    let padded_vars = vars.clone().pad_to(self.inst.get_num_vars());
+
+    Ok(r1cscheck::check(
+      &self.inst,
+      &padded_vars.assignment,
+      &inputs.assignment,
+      labels,
+    ))
+  }
+
+  // checks that the instance has no lookups, memories or two-phase witness, which the proofs
+  // of many assignments or instances at once do not support
+  fn check_plain(&self) -> Result<(), R1CSError> {
+    if !self.lookups.is_empty() {
+      return Err(R1CSError::InvalidLookup);
+    }
+    if !self.memories.is_empty() {
+      return Err(R1CSError::InvalidMemory);
+    }
+    if self.phases.is_some() {
+      return Err(R1CSError::InvalidPhases);
+    }
+    Ok(())
+  }
+
+  // checks that an assignment has no more variables than the instance and exactly its inputs
+  fn check_assignment(
+    &self,
+    vars: &VarsAssignment<F>,
+    inputs: &InputsAssignment<F>,
+  ) -> Result<(), R1CSError> {
+    if vars.assignment.len() > self.inst.get_num_vars() {
+      return Err(R1CSError::InvalidNumberOfVars);
+    }
+    if inputs.assignment.len() != self.inst.get_num_inputs() {
+      return Err(R1CSError::InvalidNumberOfInputs);
+    }
+    Ok(())
+  }
+
+  /// Computes the sizes of public parameters and proofs for this instance, including its lookups
+  /// and memories, along with estimates of prover and verifier costs; see `InstanceStats::new`
+  pub fn stats<G: ProjectiveCurve<ScalarField = F>>(&self) -> InstanceStats {
+    self.stats_with_ratio::<G>(0)
+  }
+
+  /// Computes statistics like `stats`, for generators constructed with the given `log_ratio`;
+  /// see `InstanceStats::new_with_ratio`
+  pub fn stats_with_ratio<G: ProjectiveCurve<ScalarField = F>>(
+    &self,
+    log_ratio: isize,
+  ) -> InstanceStats {
+    let (A, B, C) = self.inst.get_matrices();
+    let lookups = self
+      .lookups
+      .iter()
+      .map(|lookup| (lookup.get_num_lookups(), lookup.get_table_len()))
+      .collect::<Vec<(usize, usize)>>();
+    let memories = self
+      .memories
+      .iter()
+      .map(|memory| (memory.get_num_ops(), memory.get_num_cells()))
+      .collect::<Vec<(usize, usize)>>();
+    InstanceStats::from_padded::<G>(
+      self.inst.get_num_cons(),
+      self.inst.get_num_vars(),
+      self.inst.get_num_inputs(),
+      (A.len(), B.len(), C.len()),
+      &lookups,
+      &memories,
+      log_ratio,
+    )
+  }
+
+  /// Optimizes the instance by merging duplicate entries, dropping zero entries and constraints
+  /// that are trivially satisfied or repeated, substituting out variables defined by linear
+  /// constraints, and renumbering the remaining variables. Returns the optimized instance,
+  /// a `WitnessMap` that maps satisfying assignments of this instance to satisfying assignments
+  /// of the optimized instance, and an `OptimizationReport`. Variables used in lookups or
+  /// memories are never eliminated, and if there are any, variables are not renumbered.
+  /// Instances with a two-phase witness are rejected with `R1CSError::InvalidPhases`.
+  pub fn optimize(&self) -> Result<(Instance<F>, WitnessMap, OptimizationReport), R1CSError> {
+    if self.phases.is_some() {
+      return Err(R1CSError::InvalidPhases);
+    }
+    let pinned = self
+      .lookups
+      .iter()
+      .flat_map(|lookup| lookup.get_vars())
+      .chain(self.memories.iter().flat_map(|memory| memory.get_vars()))
+      .collect::<Vec<usize>>();
+
+    let mut optimizer = R1CSOptimizer::new(&self.inst, &pinned);
+    optimizer.eliminate_linear_constraints();
+    optimizer.remove_redundant_constraints();
+    let (num_cons, num_vars, A, B, C, witness_map, report) =
+      optimizer.finalize(self.lookups.is_empty() && self.memories.is_empty());
+
+    let mut inst = Instance::new(num_cons, num_vars, self.inst.get_num_inputs(), &A, &B, &C)?;
+    inst.lookups = self.lookups.clone();
+    inst.memories = self.memories.clone();
+    Ok((inst, witness_map, report))
+  }
+
   /// Constructs a new synthetic R1CS `Instance` and an associated satisfying assignment
   pub fn produce_synthetic_r1cs(
     num_cons: usize,
@@ -244,23 +524,51 @@ impl<F: PrimeField> Instance<F> {
   ) -> (Instance<F>, VarsAssignment<F>, InputsAssignment<F>) {
     let (inst, vars, inputs) = R1CSInstance::produce_synthetic_r1cs(num_cons, num_vars, num_inputs);
     (
-      Instance { inst },
+      Instance {
+        inst,
+        num_vars,
+        lookups: Vec::new(),
+        memories: Vec::new(),
+        phases: None,
+      },
       VarsAssignment { assignment: vars },
       InputsAssignment { assignment: inputs },
     )
   }
 }
 
-/// `SNARKGens` holds public parameters for producing and verifying proofs with the Spartan SNARK
-pub struct SNARKGens<G> {
-  gens_r1cs_sat: R1CSGens<G>,
-  gens_r1cs_eval: R1CSCommitmentGens<G>,
+/// `SNARKGens` holds public parameters for producing and verifying proofs with the Spartan SNARK,
+/// whose witness and sparse matrices are committed with the scheme `PCS`
+pub struct SNARKGens<G: ProjectiveCurve, PCS: MultilinearPCS<G> = Hyrax<G>> {
+  gens_r1cs_sat: R1CSGens<G, PCS>,
+  gens_r1cs_eval: R1CSCommitmentGens<G, PCS>,
+  gens_aux: PCS::Gens,
 }
 
-impl<G: ProjectiveCurve> SNARKGens<G> {
+impl<G: ProjectiveCurve, PCS: TransparentMultilinearPCS<G>> SNARKGens<G, PCS> {
   /// Constructs a new `SNARKGens` given the size of the R1CS statement
   /// `num_nz_entries` specifies the maximum number of non-zero entries in any of the three R1CS matrices
   pub fn new(num_cons: usize, num_vars: usize, num_inputs: usize, num_nz_entries: usize) -> Self {
+    SNARKGens::new_with_setup(num_cons, num_vars, num_inputs, num_nz_entries, PCS::setup)
+  }
+}
+
+impl<G: ProjectiveCurve, PCS: MultilinearPCS<G>> SNARKGens<G, PCS> {
+  /// Constructs a new `SNARKGens` like `new`, except that the public parameters of `PCS` for a
+  /// number of variables and a label are produced by `setup`, for instance from the powers of
+  /// a ceremony as `new_with_powers` does. Lookups and memories are committed with the
+  /// parameters for twice as many variables as the witness has, so with a scheme that bounds
+  /// the size of the committed polynomials, their tables and memories must fit in those.
+  pub fn new_with_setup<S>(
+    num_cons: usize,
+    num_vars: usize,
+    num_inputs: usize,
+    num_nz_entries: usize,
+    setup: S,
+  ) -> Self
+  where
+    S: Fn(usize, &'static [u8]) -> PCS::Gens,
+  {
     let num_vars_padded = {
       let mut num_vars_padded = max(num_vars, num_inputs + 1);
       if num_vars_padded != num_vars_padded.next_power_of_two() {
@@ -269,113 +577,238 @@ impl<G: ProjectiveCurve> SNARKGens<G> {
       num_vars_padded
     };
 
-    let gens_r1cs_sat = R1CSGens::<G>::new(b"gens_r1cs_sat", num_cons, num_vars_padded);
-    let gens_r1cs_eval = R1CSCommitmentGens::new(
+    let gens_r1cs_sat =
+      R1CSGens::new_with_setup(b"gens_r1cs_sat", num_cons, num_vars_padded, 2, &setup);
+    let gens_r1cs_eval = R1CSCommitmentGens::new_with_setup(
       b"gens_r1cs_eval",
       num_cons,
       num_vars_padded,
       num_inputs,
       num_nz_entries,
+      &setup,
     );
+    let gens_aux = setup((2 * num_vars_padded).log_2(), b"gens_aux");
     SNARKGens {
       gens_r1cs_sat,
       gens_r1cs_eval,
+      gens_aux,
     }
   }
+
+  /// Returns a Pedersen commitment to an input with the given blind, in the form expected by
+  /// `SNARK::verify_hidden` for a hidden input
+  pub fn commit_input(&self, value: &G::ScalarField, blind: &G::ScalarField) -> G {
+    value.commit(blind, &self.gens_r1cs_sat.gens_sc.gens_1)
+  }
+}
+
+impl<G: ProjectiveCurve> SNARKGens<G> {
+  /// Constructs a new `SNARKGens` like `new`, except that Hyrax lays each committed polynomial
+  /// out as a matrix with about `2^log_ratio` times as many rows as columns instead of a
+  /// near-square one: a negative `log_ratio` gives smaller commitments and longer evaluation
+  /// proofs, and a positive one larger commitments and shorter evaluation proofs, which are
+  /// faster to verify
+  pub fn new_with_ratio(
+    num_cons: usize,
+    num_vars: usize,
+    num_inputs: usize,
+    num_nz_entries: usize,
+    log_ratio: isize,
+  ) -> Self {
+    SNARKGens::new_with_setup(
+      num_cons,
+      num_vars,
+      num_inputs,
+      num_nz_entries,
+      |num_vars, label| PolyCommitmentGens::new_with_ratio(num_vars, log_ratio, label),
+    )
+  }
 }
 
 /// `SNARK` holds a proof produced by Spartan SNARK
 #[derive(CanonicalSerialize, CanonicalDeserialize, Debug)]
-pub struct SNARK<G: ProjectiveCurve> {
-  r1cs_sat_proof: R1CSProof<G>,
+pub struct SNARK<G: ProjectiveCurve, PCS: HidingMultilinearPCS<G> = Hyrax<G>> {
+  r1cs_sat_proof: R1CSProof<G, PCS>,
   inst_evals: (G::ScalarField, G::ScalarField, G::ScalarField),
-  r1cs_eval_proof: R1CSEvalProof<G>,
+  r1cs_eval_proof: R1CSEvalProof<G, PCS>,
+  lookup_proofs: Vec<LookupProof<G, PCS>>,
+  memory_proofs: Vec<MemoryProof<G, PCS>>,
 }
 
-impl<G: ProjectiveCurve> SNARK<G> {
+impl<G: ProjectiveCurve, PCS: HidingMultilinearPCS<G>> SNARK<G, PCS> {
   fn protocol_name() -> &'static [u8] {
     b"Spartan SNARK proof"
   }
 
+  fn protocol_name_batch_verify() -> &'static [u8] {
+    b"Spartan SNARK batch verification"
+  }
+
   /// A public computation to create a commitment to an R1CS instance
   pub fn encode(
     inst: &Instance<G::ScalarField>,
-    gens: &SNARKGens<G>,
+    gens: &SNARKGens<G, PCS>,
   ) -> (
-    ComputationCommitment<G>,
+    ComputationCommitment<G, PCS>,
     ComputationDecommitment<G::ScalarField>,
   ) {
     let timer_encode = Timer::new("SNARK::encode");
     let (comm, decomm) = inst.inst.commit(&gens.gens_r1cs_eval);
     timer_encode.stop();
     (
-      ComputationCommitment { comm },
+      ComputationCommitment {
+        comm,
+        lookups: inst.lookups.clone(),
+        memories: inst.memories.clone(),
+        phases: inst.phases.clone(),
+      },
       ComputationDecommitment { decomm },
     )
   }
 
-  /// A method to produce a SNARK proof of the satisfiability of an R1CS instance
+  /// A method to produce a SNARK proof of the satisfiability of an R1CS instance; it fails with
+  /// `R1CSError::InvalidLookup` if a looked-up variable is not in its table, and with
+  /// `R1CSError::InvalidMemory` if the accesses to a memory are inconsistent
   pub fn prove(
     inst: &Instance<G::ScalarField>,
-    comm: &ComputationCommitment<G>,
+    comm: &ComputationCommitment<G, PCS>,
     decomm: &ComputationDecommitment<G::ScalarField>,
     vars: VarsAssignment<G::ScalarField>,
     inputs: &InputsAssignment<G::ScalarField>,
-    gens: &SNARKGens<G>,
+    gens: &SNARKGens<G, PCS>,
     transcript: &mut Transcript,
-  ) -> Self {
+  ) -> Result<Self, R1CSError> {
+    SNARK::prove_hidden(inst, comm, decomm, vars, inputs, &[], gens, transcript)
+  }
+
+  /// A method to produce a SNARK proof of the satisfiability of an R1CS instance in which the
+  /// inputs at the given indices are hidden from the verifier, who only gets commitments to
+  /// them made with `SNARKGens::commit_input` and the given blinds; it fails with
+  /// `R1CSError::InvalidHiddenInputs` if the indices are out of range or repeated, and with
+  /// `R1CSError::InvalidPhases` if the instance has a two-phase witness
+  #[allow(clippy::too_many_arguments)]
+  pub fn prove_hidden(
+    inst: &Instance<G::ScalarField>,
+    comm: &ComputationCommitment<G, PCS>,
+    decomm: &ComputationDecommitment<G::ScalarField>,
+    vars: VarsAssignment<G::ScalarField>,
+    inputs: &InputsAssignment<G::ScalarField>,
+    hidden: &[(usize, G::ScalarField)],
+    gens: &SNARKGens<G, PCS>,
+    transcript: &mut Transcript,
+  ) -> Result<Self, R1CSError> {
+    if inst.phases.is_some() {
+      return Err(R1CSError::InvalidPhases);
+    }
+    let (proof, _witness) = SNARK::prove_with_commit(
+      inst,
+      comm,
+      decomm,
+      vars,
+      inputs,
+      |poly, gens, random_tape| PCS::commit(poly, gens, Some(random_tape)),
+      hidden,
+      gens,
+      transcript,
+    )?;
+    Ok(proof)
+  }
+
+  // produces a proof in which the witness is committed with `commit`, along with the witness
+  // polynomial and the blinds of its commitment
+  #[allow(clippy::too_many_arguments, clippy::type_complexity)]
+  fn prove_with_commit<C>(
+    inst: &Instance<G::ScalarField>,
+    comm: &ComputationCommitment<G, PCS>,
+    decomm: &ComputationDecommitment<G::ScalarField>,
+    vars: VarsAssignment<G::ScalarField>,
+    inputs: &InputsAssignment<G::ScalarField>,
+    commit: C,
+    hidden: &[(usize, G::ScalarField)],
+    gens: &SNARKGens<G, PCS>,
+    transcript: &mut Transcript,
+  ) -> Result<(Self, (DensePolynomial<G::ScalarField>, PCS::Blinds)), R1CSError>
+  where
+    C: FnOnce(
+      &DensePolynomial<G::ScalarField>,
+      &PCS::Gens,
+      &mut RandomTape<G>,
+    ) -> (PCS::Commitment, PCS::Blinds),
+  {
+    check_hidden(hidden, inputs)?;
+    check_num_vars::<G, PCS>(
+      &gens.gens_r1cs_sat.gens_pc,
+      inst.inst.get_num_vars().log_2(),
+    )?;
     let timer_prove = Timer::new("SNARK::prove");
 
-    // we create a Transcript object seeded with a random F
-    // to aid the prover produce its randomness
-    let mut random_tape = RandomTape::<G>::new(b"proof");
-    <Transcript as ProofTranscript<G>>::append_protocol_name(
+    let mut random_tape = start_proof::<G, _>(
+      SNARK::<G, PCS>::protocol_name(),
+      b"comm",
+      &comm.comm,
       transcript,
-      SNARK::<G>::protocol_name(),
     );
-    comm.comm.append_to_transcript(b"comm", transcript);
-
-    let (r1cs_sat_proof, rx, ry) = {
-      let (proof, rx, ry) = {
-        // we might need to pad variables
-        let padded_vars = {
-          let num_padded_vars = inst.inst.get_num_vars();
-          let num_vars = vars.assignment.len();
-          if num_padded_vars > num_vars {
-            vars.pad(num_padded_vars)
-          } else {
-            vars
-          }
-        };
 
-        R1CSProof::prove(
-          &inst.inst,
-          padded_vars.assignment,
-          &inputs.assignment,
-          &gens.gens_r1cs_sat,
-          transcript,
-          &mut random_tape,
-        )
-      };
+    // we might need to pad variables
+    let padded_vars = vars.pad_to(inst.inst.get_num_vars());
+
+    let (r1cs_sat_proof, rx, ry, witness) = {
+      let (proof, rx, ry, witness) = R1CSProof::prove_committed(
+        &inst.inst,
+        padded_vars.assignment.clone(),
+        &inputs.assignment,
+        commit,
+        hidden,
+        &gens.gens_r1cs_sat,
+        transcript,
+        &mut random_tape,
+      );
 
       let mut proof_encoded = vec![];
       proof.serialize(&mut proof_encoded).unwrap();
 
       Timer::print(&format!("len_r1cs_sat_proof {:?}", proof_encoded.len()));
 
-      (proof, rx, ry)
+      (proof, rx, ry, witness)
     };
 
+    let lookup_proofs = inst
+      .lookups
+      .iter()
+      .map(|lookup| {
+        LookupProof::prove(
+          lookup,
+          &witness,
+          &padded_vars.assignment,
+          &gens.gens_r1cs_sat.gens_pc,
+          &gens.gens_aux,
+          transcript,
+          &mut random_tape,
+        )
+      })
+      .collect::<Result<Vec<LookupProof<G, PCS>>, R1CSError>>()?;
+
+    let memory_proofs = inst
+      .memories
+      .iter()
+      .map(|memory| {
+        MemoryProof::prove(
+          memory,
+          &witness,
+          &padded_vars.assignment,
+          &gens.gens_r1cs_sat.gens_pc,
+          &gens.gens_aux,
+          transcript,
+          &mut random_tape,
+        )
+      })
+      .collect::<Result<Vec<MemoryProof<G, PCS>>, R1CSError>>()?;
+
     // We send evaluations of A, B, C at r = (rx, ry) as claims
     // to enable the verifier complete the first sum-check
     let timer_eval = Timer::new("eval_sparse_polys");
-    let inst_evals = {
-      let (Ar, Br, Cr) = inst.inst.evaluate(&rx, &ry);
-      <Transcript as ProofTranscript<G>>::append_scalar(transcript, b"Ar_claim", &Ar);
-      <Transcript as ProofTranscript<G>>::append_scalar(transcript, b"Ar_claim", &Br);
-      <Transcript as ProofTranscript<G>>::append_scalar(transcript, b"Ar_claim", &Cr);
-      (Ar, Br, Cr)
-    };
+    let inst_evals = inst.inst.evaluate(&rx, &ry);
+    append_inst_evals::<G>(transcript, &inst_evals);
     timer_eval.stop();
 
     let r1cs_eval_proof = {
@@ -397,69 +830,241 @@ impl<G: ProjectiveCurve> SNARK<G> {
     };
 
     timer_prove.stop();
-    SNARK {
-      r1cs_sat_proof,
-      inst_evals,
-      r1cs_eval_proof,
-    }
+    Ok((
+      SNARK {
+        r1cs_sat_proof,
+        inst_evals,
+        r1cs_eval_proof,
+        lookup_proofs,
+        memory_proofs,
+      },
+      witness,
+    ))
   }
 
   /// A method to verify the SNARK proof of the satisfiability of an R1CS instance
   pub fn verify(
     &self,
-    comm: &ComputationCommitment<G>,
+    comm: &ComputationCommitment<G, PCS>,
     input: &InputsAssignment<G::ScalarField>,
     transcript: &mut Transcript,
-    gens: &SNARKGens<G>,
+    gens: &SNARKGens<G, PCS>,
+  ) -> Result<(), ProofVerifyError> {
+    self.verify_hidden(comm, input, &[], transcript, gens)
+  }
+
+  /// A method to verify a SNARK proof of the satisfiability of an R1CS instance in which the
+  /// inputs at the given indices are replaced by the given commitments; the values of those
+  /// inputs in `input` are ignored
+  pub fn verify_hidden(
+    &self,
+    comm: &ComputationCommitment<G, PCS>,
+    input: &InputsAssignment<G::ScalarField>,
+    hidden: &[(usize, G)],
+    transcript: &mut Transcript,
+    gens: &SNARKGens<G, PCS>,
   ) -> Result<(), ProofVerifyError> {
     let timer_verify = Timer::new("SNARK::verify");
-    <Transcript as ProofTranscript<G>>::append_protocol_name(
-      transcript,
-      SNARK::<G>::protocol_name(),
-    );
+    let mut acc = MSMAccumulator::new();
+    self.verify_deferred(comm, input, hidden, transcript, gens, &mut acc)?;
+    acc.check()?;
+    timer_verify.stop();
+    Ok(())
+  }
+
+  /// A method to verify many SNARK proofs of the satisfiability of the same R1CS instance,
+  /// each produced with a transcript in the given state; the group equations of all proofs are
+  /// folded with random weights into a single multi-scalar multiplication, and if that check
+  /// fails, the proofs are verified one by one to report the index of the first invalid one
+  pub fn batch_verify(
+    proofs: &[SNARK<G, PCS>],
+    comm: &ComputationCommitment<G, PCS>,
+    inputs: &[InputsAssignment<G::ScalarField>],
+    transcript: &Transcript,
+    gens: &SNARKGens<G, PCS>,
+  ) -> Result<(), ProofVerifyError> {
+    let timer_verify = Timer::new("SNARK::batch_verify");
+    if proofs.len() != inputs.len() {
+      return Err(ProofVerifyError::InternalError);
+    }
+
+    // the weights must be chosen after every proof is fixed, so they are drawn from a
+    // transcript that binds all of the proofs and their inputs
+    let mut acc = {
+      let mut weights = transcript.clone();
+      <Transcript as ProofTranscript<G>>::append_protocol_name(
+        &mut weights,
+        SNARK::<G, PCS>::protocol_name_batch_verify(),
+      );
+      comm.comm.append_to_transcript(b"comm", &mut weights);
+      for (proof, input) in proofs.iter().zip(inputs.iter()) {
+        let mut proof_encoded = vec![];
+        proof.serialize(&mut proof_encoded).unwrap();
+        weights.append_message(b"proof", &proof_encoded);
+        <Transcript as ProofTranscript<G>>::append_scalars(
+          &mut weights,
+          b"input",
+          &input.assignment,
+        );
+      }
+      MSMAccumulator::new_batched(weights)
+    };
+
+    let is_valid = proofs.iter().zip(inputs.iter()).all(|(proof, input)| {
+      proof
+        .verify_deferred(comm, input, &[], &mut transcript.clone(), gens, &mut acc)
+        .is_ok()
+    }) && acc.check().is_ok();
+
+    if !is_valid {
+      // locate the first invalid proof
+      for (i, (proof, input)) in proofs.iter().zip(inputs.iter()).enumerate() {
+        if proof
+          .verify(comm, input, &mut transcript.clone(), gens)
+          .is_err()
+        {
+          return Err(ProofVerifyError::InvalidProofInBatch(i));
+        }
+      }
+      return Err(ProofVerifyError::InternalError);
+    }
+    timer_verify.stop();
+    Ok(())
+  }
 
+  // verifies the proof, except for the group equations of its satisfiability and evaluation
+  // proofs, which are added to `acc`
+  fn verify_deferred(
+    &self,
+    comm: &ComputationCommitment<G, PCS>,
+    input: &InputsAssignment<G::ScalarField>,
+    hidden: &[(usize, G)],
+    transcript: &mut Transcript,
+    gens: &SNARKGens<G, PCS>,
+    acc: &mut MSMAccumulator<G>,
+  ) -> Result<(), ProofVerifyError> {
+    let input = &self.verify_phases(comm, input, transcript)?;
     // append a commitment to the computation to the transcript
-    comm.comm.append_to_transcript(b"comm", transcript);
+    append_statement::<G, _>(
+      SNARK::<G, PCS>::protocol_name(),
+      b"comm",
+      &comm.comm,
+      transcript,
+    );
 
     let timer_sat_proof = Timer::new("verify_sat_proof");
     assert_eq!(input.assignment.len(), comm.comm.get_num_inputs());
-    let (rx, ry) = self.r1cs_sat_proof.verify(
+    let (rx, ry) = self.r1cs_sat_proof.verify_deferred(
       comm.comm.get_num_vars(),
       comm.comm.get_num_cons(),
-      &input.assignment,
-      &self.inst_evals,
+      &[input.assignment.clone()],
+      hidden,
+      &[self.inst_evals],
       transcript,
       &gens.gens_r1cs_sat,
+      acc,
     )?;
     timer_sat_proof.stop();
 
+    let timer_lookup_proof = Timer::new("verify_lookup_proofs");
+    if self.lookup_proofs.len() != comm.lookups.len() {
+      return Err(ProofVerifyError::InternalError);
+    }
+    for (proof, lookup) in self.lookup_proofs.iter().zip(comm.lookups.iter()) {
+      proof.verify(
+        lookup,
+        comm.comm.get_num_vars(),
+        self.r1cs_sat_proof.get_comm_vars(),
+        &gens.gens_r1cs_sat.gens_pc,
+        &gens.gens_aux,
+        transcript,
+      )?;
+    }
+    timer_lookup_proof.stop();
+
+    let timer_memory_proof = Timer::new("verify_memory_proofs");
+    if self.memory_proofs.len() != comm.memories.len() {
+      return Err(ProofVerifyError::InternalError);
+    }
+    for (proof, memory) in self.memory_proofs.iter().zip(comm.memories.iter()) {
+      proof.verify(
+        memory,
+        comm.comm.get_num_vars(),
+        self.r1cs_sat_proof.get_comm_vars(),
+        &gens.gens_r1cs_sat.gens_pc,
+        &gens.gens_aux,
+        transcript,
+      )?;
+    }
+    timer_memory_proof.stop();
+
     let timer_eval_proof = Timer::new("verify_eval_proof");
-    let (Ar, Br, Cr) = &self.inst_evals;
-    <Transcript as ProofTranscript<G>>::append_scalar(transcript, b"Ar_claim", Ar);
-    <Transcript as ProofTranscript<G>>::append_scalar(transcript, b"Ar_claim", Br);
-    <Transcript as ProofTranscript<G>>::append_scalar(transcript, b"Ar_claim", Cr);
-    self.r1cs_eval_proof.verify(
+    append_inst_evals::<G>(transcript, &self.inst_evals);
+    self.r1cs_eval_proof.verify_deferred(
       &comm.comm,
       &rx,
       &ry,
       &self.inst_evals,
       &gens.gens_r1cs_eval,
       transcript,
+      acc,
     )?;
     timer_eval_proof.stop();
-    timer_verify.stop();
     Ok(())
   }
 }
 
-/// `NIZKGens` holds public parameters for producing and verifying proofs with the Spartan NIZK
-pub struct NIZKGens<G> {
-  gens_r1cs_sat: R1CSGens<G>,
+impl<G: ProjectiveCurve> SNARK<G> {
+  // produces a proof in which the rows of the commitment to the witness starting at each given
+  // index are committed with the given blinds, along with the opening of that commitment
+  #[allow(clippy::too_many_arguments)]
+  fn prove_with_fixed_blinds(
+    inst: &Instance<G::ScalarField>,
+    comm: &ComputationCommitment<G>,
+    decomm: &ComputationDecommitment<G::ScalarField>,
+    vars: VarsAssignment<G::ScalarField>,
+    inputs: &InputsAssignment<G::ScalarField>,
+    fixed_blinds: &[(usize, &[G::ScalarField])],
+    gens: &SNARKGens<G>,
+    transcript: &mut Transcript,
+  ) -> Result<(Self, WitnessOpening<G::ScalarField>), R1CSError> {
+    let (proof, witness) = SNARK::prove_with_commit(
+      inst,
+      comm,
+      decomm,
+      vars,
+      inputs,
+      |poly, gens, random_tape| poly.commit_with_fixed_blinds(gens, random_tape, fixed_blinds),
+      &[],
+      gens,
+      transcript,
+    )?;
+    Ok((proof, WitnessOpening::new(witness)))
+  }
 }
 
-impl<G: ProjectiveCurve> NIZKGens<G> {
+/// `NIZKGens` holds public parameters for producing and verifying proofs with the Spartan NIZK,
+/// whose witness is committed with the scheme `PCS`
+pub struct NIZKGens<G: ProjectiveCurve, PCS: MultilinearPCS<G> = Hyrax<G>> {
+  gens_r1cs_sat: R1CSGens<G, PCS>,
+  gens_aux: PCS::Gens,
+}
+
+impl<G: ProjectiveCurve, PCS: TransparentMultilinearPCS<G>> NIZKGens<G, PCS> {
   /// Constructs a new `NIZKGens` given the size of the R1CS statement
   pub fn new(num_cons: usize, num_vars: usize, num_inputs: usize) -> Self {
+    NIZKGens::new_with_setup(num_cons, num_vars, num_inputs, PCS::setup)
+  }
+}
+
+impl<G: ProjectiveCurve, PCS: MultilinearPCS<G>> NIZKGens<G, PCS> {
+  /// Constructs a new `NIZKGens` like `new`, except that the public parameters of `PCS` for a
+  /// number of variables and a label are produced by `setup`, with lookups and memories
+  /// committed as in `SNARKGens::new_with_setup`
+  pub fn new_with_setup<S>(num_cons: usize, num_vars: usize, num_inputs: usize, setup: S) -> Self
+  where
+    S: Fn(usize, &'static [u8]) -> PCS::Gens,
+  {
     let num_vars_padded = {
       let mut num_vars_padded = max(num_vars, num_inputs + 1);
       if num_vars_padded != num_vars_padded.next_power_of_two() {
@@ -468,60 +1073,134 @@ impl<G: ProjectiveCurve> NIZKGens<G> {
       num_vars_padded
     };
 
-    let gens_r1cs_sat = R1CSGens::<G>::new(b"gens_r1cs_sat", num_cons, num_vars_padded);
-    NIZKGens { gens_r1cs_sat }
+    let gens_r1cs_sat =
+      R1CSGens::new_with_setup(b"gens_r1cs_sat", num_cons, num_vars_padded, 2, &setup);
+    let gens_aux = setup((2 * num_vars_padded).log_2(), b"gens_aux");
+    NIZKGens {
+      gens_r1cs_sat,
+      gens_aux,
+    }
+  }
+
+  /// Returns a Pedersen commitment to an input with the given blind, in the form expected by
+  /// `NIZK::verify_hidden` for a hidden input
+  pub fn commit_input(&self, value: &G::ScalarField, blind: &G::ScalarField) -> G {
+    value.commit(blind, &self.gens_r1cs_sat.gens_sc.gens_1)
+  }
+}
+
+impl<G: ProjectiveCurve> NIZKGens<G> {
+  /// Constructs a new `NIZKGens` like `new`, except that Hyrax lays the witness out as a matrix
+  /// with about `2^log_ratio` times as many rows as columns, as in `SNARKGens::new_with_ratio`
+  pub fn new_with_ratio(
+    num_cons: usize,
+    num_vars: usize,
+    num_inputs: usize,
+    log_ratio: isize,
+  ) -> Self {
+    NIZKGens::new_with_setup(num_cons, num_vars, num_inputs, |num_vars, label| {
+      PolyCommitmentGens::new_with_ratio(num_vars, log_ratio, label)
+    })
   }
 }
 
 /// `NIZK` holds a proof produced by Spartan NIZK
 #[derive(CanonicalSerialize, CanonicalDeserialize, Debug)]
-pub struct NIZK<G: ProjectiveCurve> {
-  r1cs_sat_proof: R1CSProof<G>,
+pub struct NIZK<G: ProjectiveCurve, PCS: HidingMultilinearPCS<G> = Hyrax<G>> {
+  r1cs_sat_proof: R1CSProof<G, PCS>,
   r: (Vec<G::ScalarField>, Vec<G::ScalarField>),
+  lookup_proofs: Vec<LookupProof<G, PCS>>,
+  memory_proofs: Vec<MemoryProof<G, PCS>>,
 }
 
-impl<G: ProjectiveCurve> NIZK<G> {
+impl<G: ProjectiveCurve, PCS: HidingMultilinearPCS<G>> NIZK<G, PCS> {
   fn protocol_name() -> &'static [u8] {
     b"Spartan NIZK proof"
   }
 
-  /// A method to produce a NIZK proof of the satisfiability of an R1CS instance
+  /// A method to produce a NIZK proof of the satisfiability of an R1CS instance; it fails with
+  /// `R1CSError::InvalidLookup` if a looked-up variable is not in its table, and with
+  /// `R1CSError::InvalidMemory` if the accesses to a memory are inconsistent
   pub fn prove(
     inst: &Instance<G::ScalarField>,
     vars: VarsAssignment<G::ScalarField>,
     input: &InputsAssignment<G::ScalarField>,
-    gens: &NIZKGens<G>,
+    gens: &NIZKGens<G, PCS>,
     transcript: &mut Transcript,
-  ) -> Self {
-    let timer_prove = Timer::new("NIZK::prove");
-    // we create a Transcript object seeded with a random F
-    // to aid the prover produce its randomness
-    let mut random_tape = RandomTape::new(b"proof");
+  ) -> Result<Self, R1CSError> {
+    NIZK::prove_hidden(inst, vars, input, &[], gens, transcript)
+  }
 
-    <Transcript as ProofTranscript<G>>::append_protocol_name(
+  /// A method to produce a NIZK proof of the satisfiability of an R1CS instance in which the
+  /// inputs at the given indices are hidden from the verifier, who only gets commitments to
+  /// them made with `NIZKGens::commit_input` and the given blinds; it fails with
+  /// `R1CSError::InvalidHiddenInputs` if the indices are out of range or repeated, and with
+  /// `R1CSError::InvalidPhases` if the instance has a two-phase witness
+  pub fn prove_hidden(
+    inst: &Instance<G::ScalarField>,
+    vars: VarsAssignment<G::ScalarField>,
+    input: &InputsAssignment<G::ScalarField>,
+    hidden: &[(usize, G::ScalarField)],
+    gens: &NIZKGens<G, PCS>,
+    transcript: &mut Transcript,
+  ) -> Result<Self, R1CSError> {
+    if inst.phases.is_some() {
+      return Err(R1CSError::InvalidPhases);
+    }
+    let (proof, _witness) = NIZK::prove_with_commit(
+      inst,
+      vars,
+      input,
+      |poly, gens, random_tape| PCS::commit(poly, gens, Some(random_tape)),
+      hidden,
+      gens,
+      transcript,
+    )?;
+    Ok(proof)
+  }
+
+  // produces a proof in which the witness is committed with `commit`, along with the witness
+  // polynomial and the blinds of its commitment
+  #[allow(clippy::type_complexity)]
+  fn prove_with_commit<C>(
+    inst: &Instance<G::ScalarField>,
+    vars: VarsAssignment<G::ScalarField>,
+    input: &InputsAssignment<G::ScalarField>,
+    commit: C,
+    hidden: &[(usize, G::ScalarField)],
+    gens: &NIZKGens<G, PCS>,
+    transcript: &mut Transcript,
+  ) -> Result<(Self, (DensePolynomial<G::ScalarField>, PCS::Blinds)), R1CSError>
+  where
+    C: FnOnce(
+      &DensePolynomial<G::ScalarField>,
+      &PCS::Gens,
+      &mut RandomTape<G>,
+    ) -> (PCS::Commitment, PCS::Blinds),
+  {
+    check_hidden(hidden, input)?;
+    check_num_vars::<G, PCS>(
+      &gens.gens_r1cs_sat.gens_pc,
+      inst.inst.get_num_vars().log_2(),
+    )?;
+    let timer_prove = Timer::new("NIZK::prove");
+    let mut random_tape = start_proof::<G, _>(
+      NIZK::<G, PCS>::protocol_name(),
+      b"inst",
+      &inst.inst,
       transcript,
-      NIZK::<G>::protocol_name(),
-    );
-    <R1CSInstance<G::ScalarField> as AppendToTranscript<G>>::append_to_transcript(
-      &inst.inst, b"inst", transcript,
     );
 
-    let (r1cs_sat_proof, rx, ry) = {
-      // we might need to pad variables
-      let padded_vars = {
-        let num_padded_vars = inst.inst.get_num_vars();
-        let num_vars = vars.assignment.len();
-        if num_padded_vars > num_vars {
-          vars.pad(num_padded_vars)
-        } else {
-          vars
-        }
-      };
+    // we might need to pad variables
+    let padded_vars = vars.pad_to(inst.inst.get_num_vars());
 
-      let (proof, rx, ry) = R1CSProof::prove(
+    let (r1cs_sat_proof, rx, ry, witness) = {
+      let (proof, rx, ry, witness) = R1CSProof::prove_committed(
         &inst.inst,
-        padded_vars.assignment,
+        padded_vars.assignment.clone(),
         &input.assignment,
+        commit,
+        hidden,
         &gens.gens_r1cs_sat,
         transcript,
         &mut random_tape,
@@ -531,14 +1210,51 @@ impl<G: ProjectiveCurve> NIZK<G> {
       proof.serialize(&mut proof_encoded).unwrap();
 
       Timer::print(&format!("len_r1cs_sat_proof {:?}", proof_encoded.len()));
-      (proof, rx, ry)
+      (proof, rx, ry, witness)
     };
 
+    let lookup_proofs = inst
+      .lookups
+      .iter()
+      .map(|lookup| {
+        LookupProof::prove(
+          lookup,
+          &witness,
+          &padded_vars.assignment,
+          &gens.gens_r1cs_sat.gens_pc,
+          &gens.gens_aux,
+          transcript,
+          &mut random_tape,
+        )
+      })
+      .collect::<Result<Vec<LookupProof<G, PCS>>, R1CSError>>()?;
+
+    let memory_proofs = inst
+      .memories
+      .iter()
+      .map(|memory| {
+        MemoryProof::prove(
+          memory,
+          &witness,
+          &padded_vars.assignment,
+          &gens.gens_r1cs_sat.gens_pc,
+          &gens.gens_aux,
+          transcript,
+          &mut random_tape,
+        )
+      })
+      .collect::<Result<Vec<MemoryProof<G, PCS>>, R1CSError>>()?;
+
     timer_prove.stop();
-    NIZK {
-      r1cs_sat_proof,
-      r: (rx, ry),
-    }
+    Ok((
+      NIZK {
+        r1cs_sat_proof,
+        r: (rx, ry),
+        lookup_proofs,
+        memory_proofs,
+      },
+      witness,
+    ))
   }
 
   /// A method to verify a NIZK proof of the satisfiability of an R1CS instance
@@ -547,16 +1263,30 @@ impl<G: ProjectiveCurve> NIZK<G> {
     inst: &Instance<G::ScalarField>,
     input: &InputsAssignment<G::ScalarField>,
     transcript: &mut Transcript,
-    gens: &NIZKGens<G>,
+    gens: &NIZKGens<G, PCS>,
+  ) -> Result<(), ProofVerifyError> {
+    self.verify_hidden(inst, input, &[], transcript, gens)
+  }
+
+  /// A method to verify a NIZK proof of the satisfiability of an R1CS instance in which the
+  /// inputs at the given indices are replaced by the given commitments; the values of those
+  /// inputs in `input` are ignored
+  pub fn verify_hidden(
+    &self,
+    inst: &Instance<G::ScalarField>,
+    input: &InputsAssignment<G::ScalarField>,
+    hidden: &[(usize, G)],
+    transcript: &mut Transcript,
+    gens: &NIZKGens<G, PCS>,
   ) -> Result<(), ProofVerifyError> {
     let timer_verify = Timer::new("NIZK::verify");
+    let input = &self.verify_phases(inst, input, transcript)?;
 
-    <Transcript as ProofTranscript<G>>::append_protocol_name(
+    append_statement::<G, _>(
+      NIZK::<G, PCS>::protocol_name(),
+      b"inst",
+      &inst.inst,
       transcript,
-      NIZK::<G>::protocol_name(),
-    );
-    <R1CSInstance<G::ScalarField> as AppendToTranscript<G>>::append_to_transcript(
-      &inst.inst, b"inst", transcript,
     );
 
     // We send evaluations of A, B, C at r = (rx, ry) as claims
@@ -568,36 +1298,123 @@ impl<G: ProjectiveCurve> NIZK<G> {
 
     let timer_sat_proof = Timer::new("verify_sat_proof");
     assert_eq!(input.assignment.len(), inst.inst.get_num_inputs());
-    let (rx, ry) = self.r1cs_sat_proof.verify(
+    let mut acc = MSMAccumulator::new();
+    let (rx, ry) = self.r1cs_sat_proof.verify_deferred(
       inst.inst.get_num_vars(),
       inst.inst.get_num_cons(),
-      &input.assignment,
-      &inst_evals,
+      &[input.assignment.clone()],
+      hidden,
+      &[inst_evals],
       transcript,
       &gens.gens_r1cs_sat,
+      &mut acc,
     )?;
+    acc.check()?;
 
     // verify if claimed rx and ry are correct
     assert_eq!(rx, *claimed_rx);
     assert_eq!(ry, *claimed_ry);
     timer_sat_proof.stop();
+
+    let timer_lookup_proof = Timer::new("verify_lookup_proofs");
+    if self.lookup_proofs.len() != inst.lookups.len() {
+      return Err(ProofVerifyError::InternalError);
+    }
+    for (proof, lookup) in self.lookup_proofs.iter().zip(inst.lookups.iter()) {
+      proof.verify(
+        lookup,
+        inst.inst.get_num_vars(),
+        self.r1cs_sat_proof.get_comm_vars(),
+        &gens.gens_r1cs_sat.gens_pc,
+        &gens.gens_aux,
+        transcript,
+      )?;
+    }
+    timer_lookup_proof.stop();
+
+    let timer_memory_proof = Timer::new("verify_memory_proofs");
+    if self.memory_proofs.len() != inst.memories.len() {
+      return Err(ProofVerifyError::InternalError);
+    }
+    for (proof, memory) in self.memory_proofs.iter().zip(inst.memories.iter()) {
+      proof.verify(
+        memory,
+        inst.inst.get_num_vars(),
+        self.r1cs_sat_proof.get_comm_vars(),
+        &gens.gens_r1cs_sat.gens_pc,
+        &gens.gens_aux,
+        transcript,
+      )?;
+    }
+    timer_memory_proof.stop();
     timer_verify.stop();
 
     Ok(())
   }
 }
 
+impl<G: ProjectiveCurve> NIZK<G> {
+  // produces a proof in which the rows of the commitment to the witness starting at each given
+  // index are committed with the given blinds, along with the opening of that commitment
+  fn prove_with_fixed_blinds(
+    inst: &Instance<G::ScalarField>,
+    vars: VarsAssignment<G::ScalarField>,
+    input: &InputsAssignment<G::ScalarField>,
+    fixed_blinds: &[(usize, &[G::ScalarField])],
+    hidden: &[(usize, G::ScalarField)],
+    gens: &NIZKGens<G>,
+    transcript: &mut Transcript,
+  ) -> Result<(Self, WitnessOpening<G::ScalarField>), R1CSError> {
+    let (proof, witness) = NIZK::prove_with_commit(
+      inst,
+      vars,
+      input,
+      |poly, gens, random_tape| poly.commit_with_fixed_blinds(gens, random_tape, fixed_blinds),
+      hidden,
+      gens,
+      transcript,
+    )?;
+    Ok((proof, WitnessOpening::new(witness)))
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
-  use ark_bls12_381::{Fr, G1Projective};
   use ark_std::One;
-  use ark_std::Zero;
+  use ark_std::{UniformRand, Zero};
+
+  // x^3 + x + 5 = y for a public y, over the variables (x, x^2, x^3)
+  pub(crate) fn cubic<F: PrimeField>() -> Instance<F> {
+    let (num_cons, num_vars, num_inputs) = (3, 4, 1);
+    let one = F::one();
+    let A = vec![
+      (0, 0, one),
+      (1, 1, one),
+      (2, 2, one),
+      (2, 0, one),
+      (2, num_vars, F::from(5u64)),
+    ];
+    let B = vec![(0, 0, one), (1, 0, one), (2, num_vars, one)];
+    let C = vec![(0, 1, one), (1, 2, one), (2, num_vars + 1, one)];
+    Instance::new(num_cons, num_vars, num_inputs, &A, &B, &C).unwrap()
+  }
 
-  #[test]
-  pub fn check_snark() {
-    check_snark_helper::<G1Projective>()
+  // the assignment that satisfies `cubic` for the given x
+  pub(crate) fn cubic_assignment<F: PrimeField>(
+    x: u64,
+  ) -> (VarsAssignment<F>, InputsAssignment<F>) {
+    let vars = [x, x * x, x * x * x, 0]
+      .iter()
+      .map(|v| F::from(*v))
+      .collect::<Vec<_>>();
+    (
+      VarsAssignment::new(&vars).unwrap(),
+      InputsAssignment::new(&[F::from(x * x * x + x + 5)]).unwrap(),
+    )
   }
+
+  crate::curves::curve_tests!(group: check_snark, check_snark_helper);
   pub fn check_snark_helper<G: ProjectiveCurve>() {
     let num_vars = 256;
     let num_cons = num_vars;
@@ -622,7 +1439,8 @@ mod tests {
       &inputs,
       &gens,
       &mut prover_transcript,
-    );
+    )
+    .unwrap();
 
     // verify the proof
     let mut verifier_transcript = Transcript::new(b"example");
@@ -631,11 +1449,93 @@ mod tests {
       .is_ok());
   }
 
-  #[test]
-  pub fn check_r1cs_invalid_index() {
-    check_r1cs_invalid_index_helper::<Fr>();
+  crate::curves::curve_tests!(group: check_snark_with_ratio, check_snark_with_ratio_helper);
+
+  fn check_snark_with_ratio_helper<G: ProjectiveCurve>() {
+    let num_vars = 64;
+    let num_cons = num_vars;
+    let num_inputs = 3;
+    let (inst, vars, inputs) = Instance::produce_synthetic_r1cs(num_cons, num_vars, num_inputs);
+
+    for log_ratio in [-2, 2] {
+      let gens =
+        SNARKGens::<G>::new_with_ratio(num_cons, num_vars, num_inputs, num_cons, log_ratio);
+      let (comm, decomm) = SNARK::encode(&inst, &gens);
+      let proof = SNARK::prove(
+        &inst,
+        &comm,
+        &decomm,
+        vars.clone(),
+        &inputs,
+        &gens,
+        &mut Transcript::new(b"example"),
+      )
+      .unwrap();
+      assert!(proof
+        .verify(&comm, &inputs, &mut Transcript::new(b"example"), &gens)
+        .is_ok());
+
+      let gens = NIZKGens::<G>::new_with_ratio(num_cons, num_vars, num_inputs, log_ratio);
+      let proof = NIZK::prove(
+        &inst,
+        vars.clone(),
+        &inputs,
+        &gens,
+        &mut Transcript::new(b"example"),
+      )
+      .unwrap();
+      assert!(proof
+        .verify(&inst, &inputs, &mut Transcript::new(b"example"), &gens)
+        .is_ok());
+    }
   }
 
+  crate::curves::curve_tests!(
+    group: check_snark_batch_verify,
+    check_snark_batch_verify_helper
+  );
+
+  fn check_snark_batch_verify_helper<G: ProjectiveCurve>() {
+    let inst = cubic::<G::ScalarField>();
+    let gens = SNARKGens::<G>::new(3, 4, 1, 5);
+    let (comm, decomm) = SNARK::encode(&inst, &gens);
+
+    let (proofs, inputs): (Vec<_>, Vec<_>) = (1..5u64)
+      .map(|x| {
+        let (vars, inputs) = cubic_assignment::<G::ScalarField>(x);
+
+        let mut prover_transcript = Transcript::new(b"example");
+        let proof = SNARK::prove(
+          &inst,
+          &comm,
+          &decomm,
+          vars,
+          &inputs,
+          &gens,
+          &mut prover_transcript,
+        )
+        .unwrap();
+        (proof, inputs)
+      })
+      .unzip();
+
+    let verifier_transcript = Transcript::new(b"example");
+    assert!(SNARK::batch_verify(&proofs, &comm, &inputs, &verifier_transcript, &gens).is_ok());
+
+    // the failing proof is located if one of the inputs differs
+    let mut wrong_inputs = inputs;
+    wrong_inputs[2] = InputsAssignment::new(&[G::ScalarField::from(36u64)]).unwrap();
+    assert!(matches!(
+      SNARK::batch_verify(&proofs, &comm, &wrong_inputs, &verifier_transcript, &gens),
+      Err(ProofVerifyError::InvalidProofInBatch(2))
+    ));
+  }
+
+  crate::curves::curve_tests!(
+    field: check_r1cs_invalid_index,
+    check_r1cs_invalid_index_helper
+  );
+
   pub fn check_r1cs_invalid_index_helper<F: PrimeField>() {
     let num_cons = 4;
     let num_vars = 8;
@@ -674,10 +1574,10 @@ mod tests {
   //   // assert_eq!(inst.err(), Some(R1CSError::InvalidF));
   // }
 
-  #[test]
-  fn test_padded_constraints() {
-    test_padded_constraints_helper::<G1Projective>()
-  }
+  crate::curves::curve_tests!(
+    group: test_padded_constraints,
+    test_padded_constraints_helper
+  );
 
   fn test_padded_constraints_helper<G: ProjectiveCurve>() {
     // parameters of the R1CS instance
@@ -735,7 +1635,8 @@ mod tests {
       &assignment_inputs,
       &gens,
       &mut prover_transcript,
-    );
+    )
+    .unwrap();
 
     // verify the SNARK
     let mut verifier_transcript = Transcript::new(b"snark_example");
@@ -754,7 +1655,8 @@ mod tests {
       &assignment_inputs,
       &gens,
       &mut prover_transcript,
-    );
+    )
+    .unwrap();
 
     // verify the NIZK
     let mut verifier_transcript = Transcript::new(b"nizk_example");
@@ -762,4 +1664,283 @@ mod tests {
       .verify(&inst, &assignment_inputs, &mut verifier_transcript, &gens)
       .is_ok());
   }
+
+  crate::curves::curve_tests!(
+    group: check_nizk_hidden_inputs,
+    check_nizk_hidden_inputs_helper
+  );
+
+  fn check_nizk_hidden_inputs_helper<G: ProjectiveCurve>() {
+    // a^2 + b + 13 = z for a public z and hidden inputs a and b
+    let (num_cons, num_vars, num_inputs) = (1, 0, 3);
+    let one = G::ScalarField::one();
+    let A = vec![(0, num_vars + 2, one)];
+    let B = vec![(0, num_vars + 2, one)];
+    let C = vec![
+      (0, num_vars + 1, one),
+      (0, num_vars, -G::ScalarField::from(13u64)),
+      (0, num_vars + 3, -one),
+    ];
+    let inst = Instance::new(num_cons, num_vars, num_inputs, &A, &B, &C).unwrap();
+
+    let values = [16u64, 1, 2]
+      .iter()
+      .map(|v| G::ScalarField::from(*v))
+      .collect::<Vec<_>>();
+    let inputs = InputsAssignment::new(&values).unwrap();
+    let vars = VarsAssignment::new(&[]).unwrap();
+    assert!(inst.is_sat(&vars, &inputs).unwrap());
+
+    let gens = NIZKGens::<G>::new(num_cons, num_vars, num_inputs);
+    let mut rng = ark_std::test_rng();
+    let blinds = [
+      G::ScalarField::rand(&mut rng),
+      G::ScalarField::rand(&mut rng),
+    ];
+    let hidden = vec![
+      (1, gens.commit_input(&values[1], &blinds[0])),
+      (2, gens.commit_input(&values[2], &blinds[1])),
+    ];
+
+    let mut prover_transcript = Transcript::new(b"nizk_example");
+    let proof = NIZK::prove_hidden(
+      &inst,
+      vars,
+      &inputs,
+      &[(1, blinds[0]), (2, blinds[1])],
+      &gens,
+      &mut prover_transcript,
+    )
+    .unwrap();
+
+    // the verifier only knows z
+    let public_inputs =
+      InputsAssignment::new(&[values[0], G::ScalarField::zero(), G::ScalarField::zero()]).unwrap();
+    let mut verifier_transcript = Transcript::new(b"nizk_example");
+    assert!(proof
+      .verify_hidden(
+        &inst,
+        &public_inputs,
+        &hidden,
+        &mut verifier_transcript,
+        &gens
+      )
+      .is_ok());
+
+    // the proof does not verify against a commitment to another value of a
+    let mut wrong_hidden = hidden.clone();
+    wrong_hidden[0].1 = gens.commit_input(&(values[1] + one), &blinds[0]);
+    let mut verifier_transcript = Transcript::new(b"nizk_example");
+    assert!(proof
+      .verify_hidden(
+        &inst,
+        &public_inputs,
+        &wrong_hidden,
+        &mut verifier_transcript,
+        &gens
+      )
+      .is_err());
+  }
+
+  crate::curves::curve_tests!(
+    group: check_nizk_invalid_hidden_inputs,
+    check_nizk_invalid_hidden_inputs_helper
+  );
+
+  fn check_nizk_invalid_hidden_inputs_helper<G: ProjectiveCurve>() {
+    let (num_cons, num_vars, num_inputs) = (4, 4, 2);
+    let (inst, vars, inputs) =
+      Instance::<G::ScalarField>::produce_synthetic_r1cs(num_cons, num_vars, num_inputs);
+    let gens = NIZKGens::<G>::new(num_cons, num_vars, num_inputs);
+    let blind = G::ScalarField::one();
+
+    // an index past the inputs
+    assert!(matches!(
+      NIZK::prove_hidden(
+        &inst,
+        vars.clone(),
+        &inputs,
+        &[(num_inputs, blind)],
+        &gens,
+        &mut Transcript::new(b"nizk_example"),
+      ),
+      Err(R1CSError::InvalidHiddenInputs)
+    ));
+
+    // a repeated index
+    assert!(matches!(
+      NIZK::prove_hidden(
+        &inst,
+        vars,
+        &inputs,
+        &[(0, blind), (0, blind)],
+        &gens,
+        &mut Transcript::new(b"nizk_example"),
+      ),
+      Err(R1CSError::InvalidHiddenInputs)
+    ));
+  }
+
+  crate::curves::curve_tests!(
+    group: check_snark_hidden_inputs,
+    check_snark_hidden_inputs_helper
+  );
+
+  fn check_snark_hidden_inputs_helper<G: ProjectiveCurve>() {
+    let (num_cons, num_vars, num_inputs) = (256, 256, 10);
+    let (inst, vars, inputs) =
+      Instance::<G::ScalarField>::produce_synthetic_r1cs(num_cons, num_vars, num_inputs);
+    let gens = SNARKGens::<G>::new(num_cons, num_vars, num_inputs, num_cons);
+    let (comm, decomm) = SNARK::encode(&inst, &gens);
+
+    let mut rng = ark_std::test_rng();
+    let blind = G::ScalarField::rand(&mut rng);
+    let hidden = vec![(3, gens.commit_input(&inputs.assignment[3], &blind))];
+
+    let mut prover_transcript = Transcript::new(b"snark_example");
+    let proof = SNARK::prove_hidden(
+      &inst,
+      &comm,
+      &decomm,
+      vars,
+      &inputs,
+      &[(3, blind)],
+      &gens,
+      &mut prover_transcript,
+    )
+    .unwrap();
+
+    // the verifier does not know the hidden input
+    let mut public_inputs = inputs.clone();
+    public_inputs.assignment[3] = G::ScalarField::zero();
+    let mut verifier_transcript = Transcript::new(b"snark_example");
+    assert!(proof
+      .verify_hidden(
+        &comm,
+        &public_inputs,
+        &hidden,
+        &mut verifier_transcript,
+        &gens
+      )
+      .is_ok());
+
+    // the proof does not verify if the commitment is claimed for another input
+    let mut verifier_transcript = Transcript::new(b"snark_example");
+    assert!(proof
+      .verify_hidden(
+        &comm,
+        &public_inputs,
+        &[(4, hidden[0].1)],
+        &mut verifier_transcript,
+        &gens
+      )
+      .is_err());
+  }
+
+  crate::curves::curve_tests!(group: check_lookups, check_lookups_helper);
+
+  fn check_lookups_helper<G: ProjectiveCurve>() {
+    let num_cons = 2;
+    let num_vars = 8;
+    let num_inputs = 1;
+
+    let one = G::ScalarField::one();
+
+    // constraints z_0 * 1 = z_1 and z_2 * 1 = z_2
+    let A = vec![(0, 0, one), (1, 2, one)];
+    let B = vec![(0, num_vars, one), (1, num_vars, one)];
+    let C = vec![(0, 1, one), (1, 2, one)];
+
+    // z_0..z_4 are 4-bit values and z_4..z_8 are packed 2-bit XOR triples
+    let pack = |a: u64, b: u64| G::ScalarField::from((a << 4) + (b << 2) + (a ^ b));
+    let vars = vec![
+      G::ScalarField::from(3u64),
+      G::ScalarField::from(3u64),
+      G::ScalarField::from(5u64),
+      G::ScalarField::from(15u64),
+      pack(1, 2),
+      pack(3, 3),
+      pack(0, 1),
+      pack(2, 2),
+    ];
+    let assignment_vars = VarsAssignment::new(&vars).unwrap();
+    let assignment_inputs = InputsAssignment::new(&[G::ScalarField::from(7u64)]).unwrap();
+
+    let mut inst = Instance::new(num_cons, num_vars, num_inputs, &A, &B, &C).unwrap();
+    assert!(inst
+      .add_lookup(LookupTable::range(4).unwrap(), 2, 4)
+      .is_err());
+    inst
+      .add_lookup(LookupTable::range(4).unwrap(), 0, 4)
+      .unwrap();
+    inst.add_lookup(LookupTable::xor(2).unwrap(), 4, 4).unwrap();
+    assert!(inst.is_sat(&assignment_vars, &assignment_inputs).unwrap());
+
+    // the XOR triples are not 4-bit values
+    let mut bad_inst = Instance::new(num_cons, num_vars, num_inputs, &A, &B, &C).unwrap();
+    bad_inst
+      .add_lookup(LookupTable::range(4).unwrap(), 4, 4)
+      .unwrap();
+    assert!(!bad_inst
+      .is_sat(&assignment_vars, &assignment_inputs)
+      .unwrap());
+
+    // SNARK
+    let gens = SNARKGens::<G>::new(num_cons, num_vars, num_inputs, num_cons);
+    let (comm, decomm) = SNARK::encode(&inst, &gens);
+    let mut prover_transcript = Transcript::new(b"lookup_example");
+    let proof = SNARK::prove(
+      &inst,
+      &comm,
+      &decomm,
+      assignment_vars.clone(),
+      &assignment_inputs,
+      &gens,
+      &mut prover_transcript,
+    )
+    .unwrap();
+    let mut verifier_transcript = Transcript::new(b"lookup_example");
+    assert!(proof
+      .verify(&comm, &assignment_inputs, &mut verifier_transcript, &gens)
+      .is_ok());
+
+    // NIZK
+    let gens = NIZKGens::<G>::new(num_cons, num_vars, num_inputs);
+    let mut prover_transcript = Transcript::new(b"lookup_example");
+    let proof = NIZK::prove(
+      &inst,
+      assignment_vars,
+      &assignment_inputs,
+      &gens,
+      &mut prover_transcript,
+    )
+    .unwrap();
+    let mut verifier_transcript = Transcript::new(b"lookup_example");
+    assert!(proof
+      .verify(&inst, &assignment_inputs, &mut verifier_transcript, &gens)
+      .is_ok());
+
+    // the proof does not verify against an instance with other lookups
+    let mut verifier_transcript = Transcript::new(b"lookup_example");
+    assert!(proof
+      .verify(
+        &bad_inst,
+        &assignment_inputs,
+        &mut verifier_transcript,
+        &gens
+      )
+      .is_err());
+
+    // the prover rejects a witness whose looked-up values are not in the table
+    assert!(matches!(
+      NIZK::prove(
+        &bad_inst,
+        VarsAssignment::new(&vars).unwrap(),
+        &assignment_inputs,
+        &gens,
+        &mut Transcript::new(b"lookup_example"),
+      ),
+      Err(R1CSError::InvalidLookup)
+    ));
+  }
 }
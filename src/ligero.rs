@@ -0,0 +1,629 @@
+#![allow(clippy::too_many_arguments)]
+use super::commitments::{MSMAccumulator, MultiCommitGens};
+use super::dense_mlpoly::{DensePolynomial, EqPolynomial};
+use super::errors::{ProofVerifyError, R1CSError};
+use super::math::Math;
+use super::pcs::{fold_challenge, with_bounded_setup, MultilinearPCS};
+use super::random::RandomTape;
+use super::transcript::{AppendToTranscript, ProofTranscript};
+use super::{NIZKGens, SNARKGens};
+use ark_ec::ProjectiveCurve;
+use ark_ff::{FftField, FftParameters, PrimeField};
+use ark_serialize::*;
+use ark_std::{One, Zero};
+use core::marker::PhantomData;
+use digest::Digest;
+use merlin::Transcript;
+use sha3::Sha3_256;
+
+// the rows are encoded with a Reed-Solomon code of rate 1/RATE_INV, so a combination of rows
+// that is not close to a codeword disagrees with the openings in at least a quarter of the
+// columns, and each opened column catches it with probability at least 1/4
+const RATE_INV: usize = 4;
+const NUM_COLUMN_OPENINGS: usize = 256;
+
+/// `LigeroGens` holds the public parameters of `Ligero`, which need no setup beyond the
+/// number of variables
+pub struct LigeroGens<G> {
+  num_vars: usize,
+  _group: PhantomData<G>,
+}
+
+impl<G: ProjectiveCurve> LigeroGens<G> {
+  /// Constructs the public parameters of `Ligero` for polynomials with up to `num_vars`
+  /// variables; returns an error if the scalar field has too few roots of unity to encode
+  /// their rows
+  pub fn new(num_vars: usize) -> Result<Self, R1CSError> {
+    if num_vars > LigeroGens::<G>::max_supported_num_vars() {
+      return Err(R1CSError::InvalidSetup);
+    }
+    Ok(LigeroGens {
+      num_vars,
+      _group: PhantomData,
+    })
+  }
+
+  // the largest number of variables whose rows, encoded at rate 1/RATE_INV, have a length that
+  // divides the order of the largest power-of-two subgroup of the scalar field
+  fn max_supported_num_vars() -> usize {
+    let two_adicity = <G::ScalarField as FftField>::FftParams::TWO_ADICITY as usize;
+    (0..usize::BITS as usize)
+      .take_while(|num_vars| {
+        let (_left, right) = EqPolynomial::<G::ScalarField>::compute_factored_lens(*num_vars);
+        right + RATE_INV.log_2() <= two_adicity
+      })
+      .last()
+      .unwrap_or(0)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct LigeroCommitment {
+  root: Vec<u8>,
+}
+
+impl<G: ProjectiveCurve> AppendToTranscript<G> for LigeroCommitment {
+  fn append_to_transcript(&self, label: &'static [u8], transcript: &mut Transcript) {
+    transcript.append_message(label, &self.root);
+  }
+}
+
+#[derive(Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct LigeroEvalProof<F: PrimeField> {
+  // a random combination of the rows of all polynomials, to test that they are codewords
+  row_proximity: Vec<F>,
+  // the rows of the folded polynomial combined with the evaluations of the eq polynomial
+  row_eval: Vec<F>,
+  // the opened columns of each polynomial, with their authentication paths
+  columns: Vec<Vec<Vec<F>>>,
+  paths: Vec<Vec<Vec<Vec<u8>>>>,
+}
+
+// a Merkle tree over the columns of the encoded rows, with its leaves as the first layer
+struct MerkleTree {
+  layers: Vec<Vec<Vec<u8>>>,
+}
+
+impl MerkleTree {
+  fn new<F: PrimeField>(rows: &[Vec<F>]) -> Self {
+    let leaves = (0..rows[0].len())
+      .map(|j| hash_column(&rows.iter().map(|row| row[j]).collect::<Vec<_>>()))
+      .collect::<Vec<_>>();
+    let mut layers = vec![leaves];
+    while layers[layers.len() - 1].len() > 1 {
+      let layer = layers[layers.len() - 1]
+        .chunks(2)
+        .map(|pair| hash_pair(&pair[0], &pair[1]))
+        .collect::<Vec<_>>();
+      layers.push(layer);
+    }
+    MerkleTree { layers }
+  }
+
+  fn root(&self) -> Vec<u8> {
+    self.layers[self.layers.len() - 1][0].clone()
+  }
+
+  fn path(&self, index: usize) -> Vec<Vec<u8>> {
+    (0..self.layers.len() - 1)
+      .map(|i| self.layers[i][(index >> i) ^ 1].clone())
+      .collect()
+  }
+
+  // checks the path from the leaf at `index` of a tree with `num_leaves` leaves to the root
+  fn verify_path(
+    root: &[u8],
+    leaf: Vec<u8>,
+    index: usize,
+    path: &[Vec<u8>],
+    num_leaves: usize,
+  ) -> bool {
+    if path.len() != num_leaves.log_2() {
+      return false;
+    }
+    let node = path.iter().enumerate().fold(leaf, |node, (i, sibling)| {
+      if (index >> i) & 1 == 0 {
+        hash_pair(&node, sibling)
+      } else {
+        hash_pair(sibling, &node)
+      }
+    });
+    node == root
+  }
+}
+
+// the leaves and the inner nodes are hashed with distinct prefixes, so that no column hashes
+// to the same leaf as a pair of nodes
+const LEAF_TAG: &[u8] = b"ligero leaf";
+const NODE_TAG: &[u8] = b"ligero node";
+
+fn hash_column<F: PrimeField>(column: &[F]) -> Vec<u8> {
+  let mut buf = vec![];
+  column.serialize(&mut buf).unwrap();
+  Sha3_256::new()
+    .chain(LEAF_TAG)
+    .chain(&buf)
+    .result()
+    .to_vec()
+}
+
+fn hash_pair(left: &[u8], right: &[u8]) -> Vec<u8> {
+  Sha3_256::new()
+    .chain(NODE_TAG)
+    .chain(left)
+    .chain(right)
+    .result()
+    .to_vec()
+}
+
+// evaluates the polynomial with the coefficients in `row` at the roots of unity of order
+// RATE_INV times the length of the row, with a radix-2 FFT; `LigeroGens::new` ensures that
+// the scalar field has them for every row the public parameters allow
+fn encode<F: PrimeField>(row: &[F]) -> Vec<F> {
+  let n = row.len() * RATE_INV;
+  let log_n = n.log_2();
+  let mut evals = row.to_vec();
+  evals.resize(n, F::zero());
+  for i in 0..n {
+    let j = i.reverse_bits() >> (usize::BITS as usize - log_n);
+    if i < j {
+      evals.swap(i, j);
+    }
+  }
+  let mut len = 2;
+  while len <= n {
+    let omega = F::get_root_of_unity(len).unwrap();
+    for start in (0..n).step_by(len) {
+      let mut omega_j = F::one();
+      for j in start..start + len / 2 {
+        let t = evals[j + len / 2] * omega_j;
+        evals[j + len / 2] = evals[j] - t;
+        evals[j] += t;
+        omega_j *= omega;
+      }
+    }
+    len *= 2;
+  }
+  evals
+}
+
+// the evaluations of a polynomial laid out as a matrix whose rows are indexed by its first
+// variables, as with Hyrax
+fn rows<F: PrimeField>(poly: &DensePolynomial<F>) -> Vec<Vec<F>> {
+  let (_left_num_vars, right_num_vars) =
+    EqPolynomial::<F>::compute_factored_lens(poly.get_num_vars());
+  let row_len = right_num_vars.pow2();
+  (0..poly.len() / row_len)
+    .map(|i| (0..row_len).map(|j| poly[i * row_len + j]).collect())
+    .collect()
+}
+
+/// `Ligero` is a commitment scheme for multilinear polynomials that relies only on hash
+/// functions: a polynomial is laid out as a matrix whose rows are encoded with a Reed-Solomon
+/// code, and the commitment is the root of a Merkle tree over the columns of the encoded rows.
+/// An evaluation is proven with a combination of the rows that the verifier encodes itself and
+/// checks against randomly opened columns, together with a random combination of the rows that
+/// tests that they are codewords. It does not hide the committed polynomials, so it can only be
+/// used by proofs without zero-knowledge, such as `PlainSNARK`
+#[derive(Debug)]
+pub struct Ligero<G> {
+  _group: PhantomData<G>,
+}
+
+impl<G: ProjectiveCurve> Ligero<G> {
+  fn protocol_name() -> &'static [u8] {
+    b"Ligero evaluation proof"
+  }
+
+  fn column_indices(num_columns: usize, transcript: &mut Transcript) -> Vec<usize> {
+    (0..NUM_COLUMN_OPENINGS)
+      .map(|_| {
+        let index = <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"column");
+        index.into_repr().as_ref()[0] as usize % num_columns
+      })
+      .collect()
+  }
+
+  // proves that the combination of `polys` with `powers` evaluates at `r` to the same
+  // combination of their evaluations
+  fn open(
+    polys: &[&DensePolynomial<G::ScalarField>],
+    powers: &[G::ScalarField],
+    r: &[G::ScalarField],
+    gens: &LigeroGens<G>,
+    transcript: &mut Transcript,
+  ) -> LigeroEvalProof<G::ScalarField> {
+    <Transcript as ProofTranscript<G>>::append_protocol_name(
+      transcript,
+      Ligero::<G>::protocol_name(),
+    );
+    assert!(r.len() <= gens.num_vars);
+    assert!(polys.iter().all(|poly| poly.get_num_vars() == r.len()));
+
+    // the prover encodes the rows again rather than keeping them with the commitments
+    let rows = polys.iter().map(|poly| rows(poly)).collect::<Vec<_>>();
+    let encoded = rows
+      .iter()
+      .map(|rows| rows.iter().map(|row| encode(row)).collect::<Vec<_>>())
+      .collect::<Vec<_>>();
+    let trees = encoded
+      .iter()
+      .map(|encoded| MerkleTree::new(encoded))
+      .collect::<Vec<_>>();
+
+    let (num_rows, row_len) = (rows[0].len(), rows[0][0].len());
+    let challenges = <Transcript as ProofTranscript<G>>::challenge_vector(
+      transcript,
+      b"challenge_proximity",
+      polys.len() * num_rows,
+    );
+    let (L, _R) = EqPolynomial::new(r.to_vec()).compute_factored_evals();
+    let mut row_proximity = vec![G::ScalarField::zero(); row_len];
+    let mut row_eval = vec![G::ScalarField::zero(); row_len];
+    for (k, rows) in rows.iter().enumerate() {
+      for (i, row) in rows.iter().enumerate() {
+        let coeff_eval = powers[k] * L[i];
+        for j in 0..row_len {
+          row_proximity[j] += challenges[k * num_rows + i] * row[j];
+          row_eval[j] += coeff_eval * row[j];
+        }
+      }
+    }
+    <Transcript as ProofTranscript<G>>::append_scalars(
+      transcript,
+      b"row_proximity",
+      &row_proximity,
+    );
+    <Transcript as ProofTranscript<G>>::append_scalars(transcript, b"row_eval", &row_eval);
+
+    let indices = Ligero::<G>::column_indices(row_len * RATE_INV, transcript);
+    let columns = indices
+      .iter()
+      .map(|j| {
+        encoded
+          .iter()
+          .map(|encoded| encoded.iter().map(|row| row[*j]).collect())
+          .collect()
+      })
+      .collect();
+    let paths = indices
+      .iter()
+      .map(|j| trees.iter().map(|tree| tree.path(*j)).collect())
+      .collect();
+
+    LigeroEvalProof {
+      row_proximity,
+      row_eval,
+      columns,
+      paths,
+    }
+  }
+
+  fn check(
+    proof: &LigeroEvalProof<G::ScalarField>,
+    powers: &[G::ScalarField],
+    gens: &LigeroGens<G>,
+    transcript: &mut Transcript,
+    r: &[G::ScalarField],
+    eval: &G::ScalarField,
+    comms: &[&LigeroCommitment],
+  ) -> Result<(), ProofVerifyError> {
+    <Transcript as ProofTranscript<G>>::append_protocol_name(
+      transcript,
+      Ligero::<G>::protocol_name(),
+    );
+    let (left_num_vars, right_num_vars) =
+      EqPolynomial::<G::ScalarField>::compute_factored_lens(r.len());
+    let (num_rows, row_len) = (left_num_vars.pow2(), right_num_vars.pow2());
+    if r.len() > gens.num_vars
+      || proof.row_proximity.len() != row_len
+      || proof.row_eval.len() != row_len
+      || proof.columns.len() != NUM_COLUMN_OPENINGS
+      || proof.paths.len() != NUM_COLUMN_OPENINGS
+      || proof
+        .columns
+        .iter()
+        .any(|c| c.len() != comms.len() || c.iter().any(|c| c.len() != num_rows))
+      || proof.paths.iter().any(|p| p.len() != comms.len())
+    {
+      return Err(ProofVerifyError::InternalError);
+    }
+
+    let challenges = <Transcript as ProofTranscript<G>>::challenge_vector(
+      transcript,
+      b"challenge_proximity",
+      comms.len() * num_rows,
+    );
+    <Transcript as ProofTranscript<G>>::append_scalars(
+      transcript,
+      b"row_proximity",
+      &proof.row_proximity,
+    );
+    <Transcript as ProofTranscript<G>>::append_scalars(transcript, b"row_eval", &proof.row_eval);
+    let indices = Ligero::<G>::column_indices(row_len * RATE_INV, transcript);
+
+    let (L, R) = EqPolynomial::new(r.to_vec()).compute_factored_evals();
+    let encoded_proximity = encode(&proof.row_proximity);
+    let encoded_eval = encode(&proof.row_eval);
+    for (q, j) in indices.iter().enumerate() {
+      let mut proximity = G::ScalarField::zero();
+      let mut eval = G::ScalarField::zero();
+      for (k, comm) in comms.iter().enumerate() {
+        let column = &proof.columns[q][k];
+        if !MerkleTree::verify_path(
+          &comm.root,
+          hash_column(column),
+          *j,
+          &proof.paths[q][k],
+          row_len * RATE_INV,
+        ) {
+          return Err(ProofVerifyError::InternalError);
+        }
+        for i in 0..num_rows {
+          proximity += challenges[k * num_rows + i] * column[i];
+          eval += powers[k] * L[i] * column[i];
+        }
+      }
+      if proximity != encoded_proximity[*j] || eval != encoded_eval[*j] {
+        return Err(ProofVerifyError::InternalError);
+      }
+    }
+
+    let eval_row = (0..row_len)
+      .map(|j| proof.row_eval[j] * R[j])
+      .sum::<G::ScalarField>();
+    if eval_row == *eval {
+      Ok(())
+    } else {
+      Err(ProofVerifyError::InternalError)
+    }
+  }
+}
+
+impl<G: ProjectiveCurve> MultilinearPCS<G> for Ligero<G> {
+  type Gens = LigeroGens<G>;
+  type Commitment = LigeroCommitment;
+  type Blinds = ();
+  type EvalProof = LigeroEvalProof<G::ScalarField>;
+  type BatchEvalProof = LigeroEvalProof<G::ScalarField>;
+
+  fn max_num_vars(gens: &Self::Gens) -> Option<usize> {
+    Some(gens.num_vars)
+  }
+
+  // Ligero proves no hidden evaluations, so the Pedersen generators are unrelated to it
+  fn gens_eval(_gens: &Self::Gens, label: &'static [u8]) -> MultiCommitGens<G> {
+    MultiCommitGens::new(1, label)
+  }
+
+  fn commit(
+    poly: &DensePolynomial<G::ScalarField>,
+    gens: &Self::Gens,
+    _random_tape: Option<&mut RandomTape<G>>,
+  ) -> (Self::Commitment, Self::Blinds) {
+    assert!(poly.get_num_vars() <= gens.num_vars);
+    let encoded = rows(poly).iter().map(|row| encode(row)).collect::<Vec<_>>();
+    let root = MerkleTree::new(&encoded).root();
+    (LigeroCommitment { root }, ())
+  }
+
+  fn prove(
+    poly: &DensePolynomial<G::ScalarField>,
+    _blinds: Option<&Self::Blinds>,
+    r: &[G::ScalarField],
+    _eval: &G::ScalarField,
+    gens: &Self::Gens,
+    transcript: &mut Transcript,
+    _random_tape: &mut RandomTape<G>,
+  ) -> Self::EvalProof {
+    Ligero::open(&[poly], &[G::ScalarField::one()], r, gens, transcript)
+  }
+
+  fn verify(
+    proof: &Self::EvalProof,
+    gens: &Self::Gens,
+    transcript: &mut Transcript,
+    r: &[G::ScalarField],
+    eval: &G::ScalarField,
+    comm: &Self::Commitment,
+    _acc: &mut MSMAccumulator<G>,
+  ) -> Result<(), ProofVerifyError> {
+    Ligero::check(
+      proof,
+      &[G::ScalarField::one()],
+      gens,
+      transcript,
+      r,
+      eval,
+      &[comm],
+    )
+  }
+
+  fn prove_batched(
+    polys: &[&DensePolynomial<G::ScalarField>],
+    _blinds: Option<&[&Self::Blinds]>,
+    r: &[G::ScalarField],
+    evals: &[G::ScalarField],
+    gens: &Self::Gens,
+    transcript: &mut Transcript,
+    _random_tape: &mut RandomTape<G>,
+  ) -> Self::BatchEvalProof {
+    assert!(!polys.is_empty());
+    assert_eq!(polys.len(), evals.len());
+    // Merkle roots cannot be folded, so every polynomial has its columns opened, and only the
+    // rows are combined
+    let powers = fold_challenge::<G>(Ligero::<G>::protocol_name(), evals, transcript);
+    Ligero::open(polys, &powers, r, gens, transcript)
+  }
+
+  fn verify_batched(
+    proof: &Self::BatchEvalProof,
+    gens: &Self::Gens,
+    transcript: &mut Transcript,
+    r: &[G::ScalarField],
+    evals: &[G::ScalarField],
+    comms: &[&Self::Commitment],
+    _acc: &mut MSMAccumulator<G>,
+  ) -> Result<(), ProofVerifyError> {
+    if comms.is_empty() || comms.len() != evals.len() {
+      return Err(ProofVerifyError::InternalError);
+    }
+    let powers = fold_challenge::<G>(Ligero::<G>::protocol_name(), evals, transcript);
+    let eval = (0..evals.len()).map(|i| powers[i] * evals[i]).sum();
+    Ligero::check(proof, &powers, gens, transcript, r, &eval, comms)
+  }
+}
+
+impl<G: ProjectiveCurve> SNARKGens<G, Ligero<G>> {
+  /// Constructs a new `SNARKGens` for `Ligero` given the size of the R1CS statement; returns an
+  /// error if the scalar field has too few roots of unity for the statement
+  pub fn try_new(
+    num_cons: usize,
+    num_vars: usize,
+    num_inputs: usize,
+    num_nz_entries: usize,
+  ) -> Result<Self, R1CSError> {
+    with_bounded_setup(
+      LigeroGens::<G>::max_supported_num_vars(),
+      |num_vars| LigeroGens {
+        num_vars,
+        _group: PhantomData,
+      },
+      |setup| SNARKGens::new_with_setup(num_cons, num_vars, num_inputs, num_nz_entries, setup),
+    )
+  }
+}
+
+impl<G: ProjectiveCurve> NIZKGens<G, Ligero<G>> {
+  /// Constructs a new `NIZKGens` for `Ligero` given the size of the R1CS statement; returns an
+  /// error if the scalar field has too few roots of unity for the statement
+  pub fn try_new(num_cons: usize, num_vars: usize, num_inputs: usize) -> Result<Self, R1CSError> {
+    with_bounded_setup(
+      LigeroGens::<G>::max_supported_num_vars(),
+      |num_vars| LigeroGens {
+        num_vars,
+        _group: PhantomData,
+      },
+      |setup| NIZKGens::new_with_setup(num_cons, num_vars, num_inputs, setup),
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::pcs::tests::check_batched_eval_proof_helper;
+  use crate::{Instance, NIZKGens, PlainNIZK, PlainSNARK, SNARKGens};
+  use ark_bls12_381::{Fr, G1Projective};
+
+  crate::curves::curve_tests!(
+    group: check_ligero_batched_eval_proof,
+    check_ligero_batched_eval_proof_helper
+  );
+
+  fn check_ligero_batched_eval_proof_helper<G: ProjectiveCurve>() {
+    let gens = LigeroGens::<G>::new(5).unwrap();
+    check_batched_eval_proof_helper::<G, Ligero<G>>(5, &gens)
+  }
+
+  #[test]
+  fn check_merkle_path() {
+    let rows = vec![(0..8u64).map(Fr::from).collect::<Vec<_>>(); 2];
+    let tree = MerkleTree::new(&rows);
+    let leaf = |j: usize| hash_column(&[rows[0][j], rows[1][j]]);
+    let path = tree.path(5);
+    assert!(MerkleTree::verify_path(&tree.root(), leaf(5), 5, &path, 8));
+    assert!(!MerkleTree::verify_path(&tree.root(), leaf(4), 5, &path, 8));
+
+    // a path that stops short of the leaves, at a node disguised as a leaf, is rejected
+    let node = tree.layers[1][2].clone();
+    assert!(MerkleTree::verify_path(
+      &tree.root(),
+      node.clone(),
+      2,
+      &tree.path(5)[1..],
+      4
+    ));
+    assert!(!MerkleTree::verify_path(
+      &tree.root(),
+      node,
+      2,
+      &path[1..],
+      8
+    ));
+  }
+
+  #[test]
+  fn check_ligero_gens_two_adicity() {
+    // the rows of polynomials with 64 variables need roots of unity of order 2^34, while the
+    // scalar field of BLS12-381 only has them up to order 2^32
+    assert!(LigeroGens::<G1Projective>::new(60).is_ok());
+    assert!(matches!(
+      LigeroGens::<G1Projective>::new(64),
+      Err(R1CSError::InvalidSetup)
+    ));
+  }
+
+  crate::curves::curve_tests!(group: check_ligero_snark, check_ligero_snark_helper);
+
+  fn check_ligero_snark_helper<G: ProjectiveCurve>() {
+    let num_vars = 16;
+    let num_cons = num_vars;
+    let num_inputs = 3;
+    let (inst, vars, inputs) = Instance::produce_synthetic_r1cs(num_cons, num_vars, num_inputs);
+
+    let gens =
+      SNARKGens::<G, Ligero<G>>::try_new(num_cons, num_vars, num_inputs, num_cons).unwrap();
+    let (comm, decomm) = PlainSNARK::encode(&inst, &gens);
+    let proof = PlainSNARK::prove(
+      &inst,
+      &comm,
+      &decomm,
+      vars.clone(),
+      &inputs,
+      &gens,
+      &mut Transcript::new(b"example"),
+    )
+    .unwrap();
+    assert!(proof
+      .verify(&comm, &inputs, &mut Transcript::new(b"example"), &gens)
+      .is_ok());
+
+    let mut wrong_inputs = inputs.clone();
+    wrong_inputs.assignment[0] += G::ScalarField::one();
+    assert!(proof
+      .verify(
+        &comm,
+        &wrong_inputs,
+        &mut Transcript::new(b"example"),
+        &gens
+      )
+      .is_err());
+
+    let gens = NIZKGens::<G, Ligero<G>>::try_new(num_cons, num_vars, num_inputs).unwrap();
+    let proof = PlainNIZK::prove(
+      &inst,
+      vars,
+      &inputs,
+      &gens,
+      &mut Transcript::new(b"example"),
+    )
+    .unwrap();
+    assert!(proof
+      .verify(&inst, &inputs, &mut Transcript::new(b"example"), &gens)
+      .is_ok());
+    // an instance larger than the generators is rejected before any commitment is made
+    let (inst, vars, inputs) =
+      Instance::produce_synthetic_r1cs(4 * num_cons, 4 * num_vars, num_inputs);
+    assert!(matches!(
+      PlainNIZK::prove(
+        &inst,
+        vars,
+        &inputs,
+        &gens,
+        &mut Transcript::new(b"example")
+      ),
+      Err(R1CSError::InvalidSetup)
+    ));
+  }
+}
@@ -0,0 +1,566 @@
+#![allow(clippy::too_many_arguments)]
+use super::commitments::MSMAccumulator;
+use super::dense_mlpoly::{DensePolynomial, EqPolynomial, IdentityPolynomial};
+use super::errors::{ProofVerifyError, R1CSError};
+use super::math::Math;
+use super::pcs::{Hyrax, MultilinearPCS};
+use super::product_tree::ProductCircuitEvalProofBatched;
+use super::random::RandomTape;
+use super::sparse_mlpoly::{AddrTimestamps, Layers};
+use super::transcript::{AppendToTranscript, ProofTranscript};
+use ark_ec::ProjectiveCurve;
+use ark_ff::PrimeField;
+use ark_serialize::*;
+use ark_std::{One, Zero};
+use core::cmp::max;
+use merlin::Transcript;
+use std::collections::HashMap;
+
+// the largest number of variables of a table, whose entries the prover materializes
+const MAX_TABLE_NUM_VARS: usize = 24;
+
+#[derive(Clone, Debug)]
+enum TableKind<F> {
+  Range(usize),
+  Explicit(Vec<F>),
+}
+
+/// `LookupTable` holds a fixed public table whose entries witness values can be looked up in
+#[derive(Clone, Debug)]
+pub struct LookupTable<F> {
+  kind: TableKind<F>,
+}
+
+impl<F: PrimeField> LookupTable<F> {
+  /// Constructs the table `[0, 1, ..., 2^num_bits - 1]`, used for range checks; `num_bits`
+  /// is at most 24
+  pub fn range(num_bits: usize) -> Result<Self, R1CSError> {
+    if num_bits == 0 || num_bits > MAX_TABLE_NUM_VARS {
+      return Err(R1CSError::InvalidLookup);
+    }
+    Ok(LookupTable {
+      kind: TableKind::Range(num_bits),
+    })
+  }
+
+  /// Constructs the table of all `num_bits`-bit XOR triples, where the triple `(a, b, a ^ b)`
+  /// is packed into the single entry `a * 2^(2 * num_bits) + b * 2^num_bits + (a ^ b)`;
+  /// `num_bits` is at most 12
+  pub fn xor(num_bits: usize) -> Result<Self, R1CSError> {
+    if num_bits == 0 || 2 * num_bits > MAX_TABLE_NUM_VARS {
+      return Err(R1CSError::InvalidLookup);
+    }
+    let size = num_bits.pow2();
+    let entries = (0..size * size)
+      .map(|i| {
+        let (a, b) = (i / size, i % size);
+        F::from(((a << (2 * num_bits)) + (b << num_bits) + (a ^ b)) as u64)
+      })
+      .collect::<Vec<F>>();
+    Ok(LookupTable {
+      kind: TableKind::Explicit(entries),
+    })
+  }
+
+  /// Constructs a table from an arbitrary list of entries; the table is padded
+  /// to a power of two by repeating its first entry, and may have at most `2^24` entries
+  pub fn from_entries(entries: &[F]) -> Result<Self, R1CSError> {
+    if entries.len() < 2 || entries.len() > MAX_TABLE_NUM_VARS.pow2() {
+      return Err(R1CSError::InvalidLookup);
+    }
+    let mut entries = entries.to_vec();
+    entries.resize(entries.len().next_power_of_two(), entries[0]);
+    Ok(LookupTable {
+      kind: TableKind::Explicit(entries),
+    })
+  }
+
+  /// Returns the number of entries in the (padded) table
+  pub fn len(&self) -> usize {
+    match &self.kind {
+      TableKind::Range(num_bits) => num_bits.pow2(),
+      TableKind::Explicit(entries) => entries.len(),
+    }
+  }
+
+  /// Returns true if the table has no entries; tables constructed through the public API never are
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  fn evals(&self) -> Vec<F> {
+    match &self.kind {
+      TableKind::Range(num_bits) => (0..num_bits.pow2()).map(|i| F::from(i as u64)).collect(),
+      TableKind::Explicit(entries) => entries.clone(),
+    }
+  }
+
+  // evaluates the multilinear extension of the table at r
+  fn evaluate(&self, r: &[F]) -> F {
+    assert_eq!(r.len().pow2(), self.len());
+    match &self.kind {
+      TableKind::Range(_) => IdentityPolynomial::new(r.len()).evaluate(r),
+      TableKind::Explicit(entries) => {
+        let chis = EqPolynomial::new(r.to_vec()).evals();
+        (0..entries.len()).map(|i| chis[i] * entries[i]).sum()
+      }
+    }
+  }
+
+  fn contains(&self, val: &F) -> bool {
+    match &self.kind {
+      TableKind::Range(num_bits) => {
+        let bits = val.into_repr();
+        (*num_bits..F::size_in_bits()).all(|i| !ark_ff::BigInteger::get_bit(&bits, i))
+      }
+      TableKind::Explicit(entries) => entries.contains(val),
+    }
+  }
+
+  fn append_to_transcript<G: ProjectiveCurve<ScalarField = F>>(&self, transcript: &mut Transcript) {
+    match &self.kind {
+      TableKind::Range(num_bits) => {
+        transcript.append_message(b"lookup_table", b"range");
+        transcript.append_u64(b"num_bits", *num_bits as u64);
+      }
+      TableKind::Explicit(entries) => {
+        transcript.append_message(b"lookup_table", b"explicit");
+        <Transcript as ProofTranscript<G>>::append_scalars(transcript, b"entries", entries);
+      }
+    }
+  }
+}
+
+/// `Lookup` asserts that the variables `offset..offset + num_lookups` take values in `table`
+#[derive(Clone, Debug)]
+pub struct Lookup<F> {
+  table: LookupTable<F>,
+  offset: usize,
+  num_lookups: usize,
+}
+
+impl<F: PrimeField> Lookup<F> {
+  pub fn new(
+    table: LookupTable<F>,
+    offset: usize,
+    num_lookups: usize,
+    num_vars: usize,
+  ) -> Result<Self, R1CSError> {
+    // the looked-up variables must form an aligned sub-cube of the witness polynomial
+    // so that their multilinear extension is a restriction of the witness polynomial
+    if num_lookups < 2
+      || num_lookups.next_power_of_two() != num_lookups
+      || offset % num_lookups != 0
+      || offset + num_lookups > num_vars
+    {
+      return Err(R1CSError::InvalidLookup);
+    }
+
+    Ok(Lookup {
+      table,
+      offset,
+      num_lookups,
+    })
+  }
+
+  pub fn get_num_lookups(&self) -> usize {
+    self.num_lookups
+  }
+
+  pub fn get_table_len(&self) -> usize {
+    self.table.len()
+  }
+
+  pub fn get_vars(&self) -> std::ops::Range<usize> {
+    self.offset..self.offset + self.num_lookups
+  }
+
+  pub fn is_sat(&self, vars: &[F]) -> bool {
+    vars[self.offset..self.offset + self.num_lookups]
+      .iter()
+      .all(|v| self.table.contains(v))
+  }
+
+  // the point at which the witness polynomial restricts to the looked-up values
+  fn witness_point_prefix(&self, num_vars: usize) -> Vec<F> {
+    let prefix_len = num_vars.log_2() - self.num_lookups.log_2();
+    (self.offset / self.num_lookups)
+      .get_bits(prefix_len)
+      .iter()
+      .map(|&b| if b { F::one() } else { F::zero() })
+      .collect::<Vec<F>>()
+  }
+
+  fn append_to_transcript<G: ProjectiveCurve<ScalarField = F>>(&self, transcript: &mut Transcript) {
+    transcript.append_u64(b"offset", self.offset as u64);
+    transcript.append_u64(b"num_lookups", self.num_lookups as u64);
+    self.table.append_to_transcript::<G>(transcript);
+  }
+}
+
+/// A proof that committed witness values appear in a public table, using offline memory checking:
+/// the table is a read-only memory, each lookup is a read, and the prover shows that
+/// `init * write = read * audit` as multisets with the product circuits used by Spark.
+/// The product circuits and the evaluation of the looked-up values at a random point are
+/// revealed to the verifier, so this component is not zero-knowledge with respect to the
+/// looked-up values; hiding that evaluation alone would not help, since it follows from the
+/// claims of the product circuits.
+#[derive(Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct LookupProof<G: ProjectiveCurve, PCS: MultilinearPCS<G> = Hyrax<G>> {
+  comm_ops: PCS::Commitment,
+  comm_mem: PCS::Commitment,
+  claims_prod: (
+    G::ScalarField,
+    G::ScalarField,
+    G::ScalarField,
+    G::ScalarField,
+  ),
+  proof_prod_ops: ProductCircuitEvalProofBatched<G::ScalarField>,
+  proof_prod_mem: ProductCircuitEvalProofBatched<G::ScalarField>,
+  eval_ops: (G::ScalarField, G::ScalarField, G::ScalarField),
+  eval_audit_ts: G::ScalarField,
+  proof_ops: PCS::EvalProof,
+  proof_mem: PCS::EvalProof,
+  proof_vars: PCS::EvalProof,
+}
+
+impl<G: ProjectiveCurve, PCS: MultilinearPCS<G>> LookupProof<G, PCS> {
+  fn protocol_name() -> &'static [u8] {
+    b"Lookup proof"
+  }
+
+  pub fn prove(
+    lookup: &Lookup<G::ScalarField>,
+    witness: &(DensePolynomial<G::ScalarField>, PCS::Blinds),
+    vars: &[G::ScalarField],
+    gens_pc: &PCS::Gens,
+    gens_aux: &PCS::Gens,
+    transcript: &mut Transcript,
+    random_tape: &mut RandomTape<G>,
+  ) -> Result<Self, R1CSError> {
+    <Transcript as ProofTranscript<G>>::append_protocol_name(
+      transcript,
+      LookupProof::<G, PCS>::protocol_name(),
+    );
+    lookup.append_to_transcript::<G>(transcript);
+
+    let (poly_vars, blinds_vars) = witness;
+    let (num_ops, num_cells) = (lookup.num_lookups, lookup.table.len());
+    if !fits_aux::<G, PCS>(num_ops, num_cells, gens_aux) {
+      return Err(R1CSError::InvalidLookup);
+    }
+
+    // the prover locates each looked-up value in the table
+    let table = lookup.table.evals();
+    let vals = vars[lookup.offset..lookup.offset + num_ops].to_vec();
+    let addrs = {
+      let index = (0..table.len())
+        .map(|i| (table[i], i))
+        .collect::<HashMap<G::ScalarField, usize>>();
+      vals
+        .iter()
+        .map(|v| index.get(v).copied().ok_or(R1CSError::InvalidLookup))
+        .collect::<Result<Vec<usize>, R1CSError>>()?
+    };
+    let addr_ts = AddrTimestamps::new(num_cells, num_ops, vec![addrs]);
+    let poly_vals = DensePolynomial::new(vals);
+
+    // commit to addresses and timestamps, which are non-deterministic choices of the prover;
+    // addr and read_ts are committed together
+    let comb_ops =
+      DensePolynomial::merge(&[addr_ts.ops_addr[0].clone(), addr_ts.read_ts[0].clone()]);
+    let (comm_ops, _blinds_ops) = PCS::commit(&comb_ops, gens_aux, None);
+    let (comm_mem, _blinds_mem) = PCS::commit(&addr_ts.audit_ts, gens_aux, None);
+    comm_ops.append_to_transcript(b"comm_ops", transcript);
+    comm_mem.append_to_transcript(b"comm_mem", transcript);
+
+    let r_mem_check =
+      <Transcript as ProofTranscript<G>>::challenge_vector(transcript, b"challenge_r_hash", 2);
+    let mut layers = Layers::new(
+      &table,
+      &addr_ts,
+      &[poly_vals.clone()],
+      &(r_mem_check[0], r_mem_check[1]),
+    );
+    let prod_layer = &mut layers.prod_layer;
+
+    let claims_prod = (
+      prod_layer.init.evaluate(),
+      prod_layer.read_vec[0].evaluate(),
+      prod_layer.write_vec[0].evaluate(),
+      prod_layer.audit.evaluate(),
+    );
+    assert_eq!(claims_prod.0 * claims_prod.2, claims_prod.1 * claims_prod.3);
+    <Transcript as ProofTranscript<G>>::append_scalars(
+      transcript,
+      b"claims_prod",
+      &[claims_prod.0, claims_prod.1, claims_prod.2, claims_prod.3],
+    );
+
+    let (proof_prod_ops, rand_ops) = {
+      let (read, write) = (&mut prod_layer.read_vec, &mut prod_layer.write_vec);
+      ProductCircuitEvalProofBatched::prove::<G>(
+        &mut vec![&mut read[0], &mut write[0]],
+        &mut Vec::new(),
+        transcript,
+      )
+    };
+    let (proof_prod_mem, rand_mem) = ProductCircuitEvalProofBatched::prove::<G>(
+      &mut vec![&mut prod_layer.init, &mut prod_layer.audit],
+      &mut Vec::new(),
+      transcript,
+    );
+
+    // decommit addr, read_ts, and the looked-up values at rand_ops, and audit_ts at rand_mem
+    let eval_ops = (
+      addr_ts.ops_addr[0].evaluate::<G>(&rand_ops),
+      addr_ts.read_ts[0].evaluate::<G>(&rand_ops),
+      poly_vals.evaluate::<G>(&rand_ops),
+    );
+    let eval_audit_ts = addr_ts.audit_ts.evaluate::<G>(&rand_mem);
+    <Transcript as ProofTranscript<G>>::append_scalars(
+      transcript,
+      b"claim_evals_lookup",
+      &[eval_ops.0, eval_ops.1, eval_ops.2, eval_audit_ts],
+    );
+
+    let proof_ops = {
+      let c = <Transcript as ProofTranscript<G>>::challenge_scalar(
+        transcript,
+        b"challenge_combine_two_to_one",
+      );
+      let eval_joint = (G::ScalarField::one() - c) * eval_ops.0 + c * eval_ops.1;
+      let mut r_joint = vec![c];
+      r_joint.extend(&rand_ops);
+      debug_assert_eq!(comb_ops.evaluate::<G>(&r_joint), eval_joint);
+
+      PCS::prove(
+        &comb_ops,
+        None,
+        &r_joint,
+        &eval_joint,
+        gens_aux,
+        transcript,
+        random_tape,
+      )
+    };
+
+    let proof_mem = PCS::prove(
+      &addr_ts.audit_ts,
+      None,
+      &rand_mem,
+      &eval_audit_ts,
+      gens_aux,
+      transcript,
+      random_tape,
+    );
+
+    // the looked-up values are a sub-cube of the committed witness
+    let proof_vars = {
+      let mut r_vars = lookup.witness_point_prefix(poly_vars.len());
+      r_vars.extend(&rand_ops);
+      debug_assert_eq!(poly_vars.evaluate::<G>(&r_vars), eval_ops.2);
+
+      PCS::prove(
+        poly_vars,
+        Some(blinds_vars),
+        &r_vars,
+        &eval_ops.2,
+        gens_pc,
+        transcript,
+        random_tape,
+      )
+    };
+
+    Ok(LookupProof {
+      comm_ops,
+      comm_mem,
+      claims_prod,
+      proof_prod_ops,
+      proof_prod_mem,
+      eval_ops,
+      eval_audit_ts,
+      proof_ops,
+      proof_mem,
+      proof_vars,
+    })
+  }
+
+  pub fn verify(
+    &self,
+    lookup: &Lookup<G::ScalarField>,
+    num_vars: usize,
+    comm_vars: &PCS::Commitment,
+    gens_pc: &PCS::Gens,
+    gens_aux: &PCS::Gens,
+    transcript: &mut Transcript,
+  ) -> Result<(), ProofVerifyError> {
+    <Transcript as ProofTranscript<G>>::append_protocol_name(
+      transcript,
+      LookupProof::<G, PCS>::protocol_name(),
+    );
+    lookup.append_to_transcript::<G>(transcript);
+
+    let (num_ops, num_cells) = (lookup.num_lookups, lookup.table.len());
+    if !fits_aux::<G, PCS>(num_ops, num_cells, gens_aux) {
+      return Err(ProofVerifyError::InternalError);
+    }
+
+    self.comm_ops.append_to_transcript(b"comm_ops", transcript);
+    self.comm_mem.append_to_transcript(b"comm_mem", transcript);
+
+    let r_mem_check =
+      <Transcript as ProofTranscript<G>>::challenge_vector(transcript, b"challenge_r_hash", 2);
+    let (r_hash, r_multiset_check) = (r_mem_check[0], r_mem_check[1]);
+
+    // the multiset of reads and audits must match the multiset of inits and writes
+    let (claim_init, claim_read, claim_write, claim_audit) = self.claims_prod;
+    if claim_init * claim_write != claim_read * claim_audit {
+      return Err(ProofVerifyError::InternalError);
+    }
+    <Transcript as ProofTranscript<G>>::append_scalars(
+      transcript,
+      b"claims_prod",
+      &[claim_init, claim_read, claim_write, claim_audit],
+    );
+
+    let (claims_ops, _claims_dotp, rand_ops) =
+      self
+        .proof_prod_ops
+        .verify::<G>(&[claim_read, claim_write], &[], num_ops, transcript)?;
+    let (claims_mem, _claims_dotp, rand_mem) =
+      self
+        .proof_prod_mem
+        .verify::<G>(&[claim_init, claim_audit], &[], num_cells, transcript)?;
+
+    let (eval_addr, eval_read_ts, eval_val) = self.eval_ops;
+    <Transcript as ProofTranscript<G>>::append_scalars(
+      transcript,
+      b"claim_evals_lookup",
+      &[eval_addr, eval_read_ts, eval_val, self.eval_audit_ts],
+    );
+
+    // check the leaves of the product circuits against the decommitted values
+    let r_hash_sqr = r_hash * r_hash;
+    let hash_func =
+      |addr: &G::ScalarField, val: &G::ScalarField, ts: &G::ScalarField| -> G::ScalarField {
+        *ts * r_hash_sqr + *val * r_hash + *addr - r_multiset_check
+      };
+
+    let eval_init_addr = IdentityPolynomial::new(rand_mem.len()).evaluate(&rand_mem);
+    let eval_init_val = lookup.table.evaluate(&rand_mem);
+    if hash_func(&eval_init_addr, &eval_init_val, &G::ScalarField::zero()) != claims_mem[0]
+      || hash_func(&eval_init_addr, &eval_init_val, &self.eval_audit_ts) != claims_mem[1]
+      || hash_func(&eval_addr, &eval_val, &eval_read_ts) != claims_ops[0]
+      || hash_func(
+        &eval_addr,
+        &eval_val,
+        &(eval_read_ts + G::ScalarField::one()),
+      ) != claims_ops[1]
+    {
+      return Err(ProofVerifyError::InternalError);
+    }
+
+    // verify the decommitments
+    let c = <Transcript as ProofTranscript<G>>::challenge_scalar(
+      transcript,
+      b"challenge_combine_two_to_one",
+    );
+    let eval_joint = (G::ScalarField::one() - c) * eval_addr + c * eval_read_ts;
+    let mut r_joint = vec![c];
+    r_joint.extend(&rand_ops);
+    let mut acc = MSMAccumulator::new();
+    PCS::verify(
+      &self.proof_ops,
+      gens_aux,
+      transcript,
+      &r_joint,
+      &eval_joint,
+      &self.comm_ops,
+      &mut acc,
+    )?;
+
+    PCS::verify(
+      &self.proof_mem,
+      gens_aux,
+      transcript,
+      &rand_mem,
+      &self.eval_audit_ts,
+      &self.comm_mem,
+      &mut acc,
+    )?;
+
+    let mut r_vars = lookup.witness_point_prefix(num_vars);
+    r_vars.extend(&rand_ops);
+    PCS::verify(
+      &self.proof_vars,
+      gens_pc,
+      transcript,
+      &r_vars,
+      &eval_val,
+      comm_vars,
+      &mut acc,
+    )?;
+    acc.check()
+  }
+}
+
+// whether the addresses and timestamps of a lookup can be committed with `gens_aux`
+fn fits_aux<G: ProjectiveCurve, PCS: MultilinearPCS<G>>(
+  num_ops: usize,
+  num_cells: usize,
+  gens_aux: &PCS::Gens,
+) -> bool {
+  let num_vars = max((2 * num_ops).log_2(), num_cells.log_2());
+  PCS::max_num_vars(gens_aux).map_or(true, |max_num_vars| num_vars <= max_num_vars)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ark_std::test_rng;
+
+  crate::curves::curve_tests!(field: check_table_evaluate, check_table_evaluate_helper);
+
+  fn check_table_evaluate_helper<F: PrimeField>() {
+    let mut prng = test_rng();
+
+    for table in [
+      LookupTable::<F>::range(6).unwrap(),
+      LookupTable::<F>::xor(3).unwrap(),
+    ] {
+      let r = (0..table.len().log_2())
+        .map(|_i| F::rand(&mut prng))
+        .collect::<Vec<F>>();
+      let chis = EqPolynomial::new(r.clone()).evals();
+      let evals = table.evals();
+      let expected: F = (0..evals.len()).map(|i| chis[i] * evals[i]).sum();
+      assert_eq!(table.evaluate(&r), expected);
+    }
+  }
+
+  crate::curves::curve_tests!(field: check_table_contains, check_table_contains_helper);
+
+  fn check_table_contains_helper<F: PrimeField>() {
+    let table = LookupTable::<F>::range(8).unwrap();
+    assert!(table.contains(&F::from(255u64)));
+    assert!(!table.contains(&F::from(256u64)));
+    assert!(!table.contains(&-F::one()));
+
+    let table = LookupTable::<F>::xor(2).unwrap();
+    // (a, b, a ^ b) = (3, 1, 2)
+    assert!(table.contains(&F::from((3u64 << 4) + (1 << 2) + 2)));
+    assert!(!table.contains(&F::from((3u64 << 4) + (1 << 2) + 3)));
+  }
+
+  crate::curves::curve_tests!(field: check_table_size, check_table_size_helper);
+
+  fn check_table_size_helper<F: PrimeField>() {
+    // tables must be small enough for the prover to materialize their entries
+    assert!(LookupTable::<F>::range(24).is_ok());
+    assert!(LookupTable::<F>::range(25).is_err());
+    assert!(LookupTable::<F>::range(63).is_err());
+    assert!(LookupTable::<F>::xor(13).is_err());
+    assert!(LookupTable::<F>::from_entries(&[F::one()]).is_err());
+  }
+}
@@ -0,0 +1,501 @@
+#![allow(clippy::too_many_arguments)]
+use super::commitments::MSMAccumulator;
+use super::dense_mlpoly::{DensePolynomial, EqPolynomial, IdentityPolynomial};
+use super::errors::{ProofVerifyError, R1CSError};
+use super::math::Math;
+use super::pcs::{Hyrax, MultilinearPCS};
+use super::product_tree::{ProductCircuit, ProductCircuitEvalProofBatched};
+use super::random::RandomTape;
+use super::sparse_mlpoly::ProductLayer;
+use super::transcript::{AppendToTranscript, ProofTranscript};
+use ark_ec::ProjectiveCurve;
+use ark_ff::PrimeField;
+use ark_serialize::*;
+use ark_std::{One, Zero};
+use merlin::Transcript;
+use std::collections::HashMap;
+
+/// `Memory` asserts that a sequence of accesses to a read-write memory is consistent.
+/// The accesses are held in the variables `offset..offset + 4 * num_ops`, as four consecutive
+/// blocks of `num_ops` variables with the address, the value read, the timestamp of the read,
+/// and the value written by each access. The `i`-th access writes with timestamp `i + 1`;
+/// a read must return the value written by the latest earlier access to the same address,
+/// or the initial value with timestamp zero.
+///
+/// The memory-checking argument only shows that reads and writes match up as multisets,
+/// so the instance must itself constrain each read timestamp to be at most `i`
+/// (e.g., by range-checking `i` minus the read timestamp with a lookup).
+#[derive(Clone, Debug)]
+pub struct Memory<F> {
+  init: Vec<F>,
+  offset: usize,
+  num_ops: usize,
+}
+
+impl<F: PrimeField> Memory<F> {
+  pub fn new(
+    init: &[F],
+    offset: usize,
+    num_ops: usize,
+    num_vars: usize,
+  ) -> Result<Self, R1CSError> {
+    // the accesses must form an aligned sub-cube of the witness polynomial
+    let block = 4 * num_ops;
+    if init.is_empty()
+      || num_ops < 2
+      || num_ops.next_power_of_two() != num_ops
+      || offset % block != 0
+      || offset + block > num_vars
+    {
+      return Err(R1CSError::InvalidMemory);
+    }
+
+    // pad the memory to a power of two (at least 2) with zero-initialized cells
+    let mut init = init.to_vec();
+    init.resize(init.len().next_power_of_two().max(2), F::zero());
+
+    Ok(Memory {
+      init,
+      offset,
+      num_ops,
+    })
+  }
+
+  pub fn get_num_ops(&self) -> usize {
+    self.num_ops
+  }
+
+  pub fn get_num_cells(&self) -> usize {
+    self.init.len()
+  }
+
+  pub fn get_vars(&self) -> std::ops::Range<usize> {
+    self.offset..self.offset + 4 * self.num_ops
+  }
+
+  // returns the address, read value, read timestamp, and written value of each access
+  fn accesses<'a>(&self, vars: &'a [F]) -> (&'a [F], &'a [F], &'a [F], &'a [F]) {
+    let n = self.num_ops;
+    let ops = &vars[self.offset..self.offset + 4 * n];
+    (&ops[..n], &ops[n..2 * n], &ops[2 * n..3 * n], &ops[3 * n..])
+  }
+
+  // replays the accesses, returning the final values and timestamps of the memory cells
+  // if every read returns the latest write to its address
+  fn replay(&self, vars: &[F]) -> Option<(Vec<F>, Vec<F>)> {
+    let index = (0..self.init.len())
+      .map(|i| (F::from(i as u64), i))
+      .collect::<HashMap<F, usize>>();
+    let (addr, read_val, read_ts, write_val) = self.accesses(vars);
+
+    let mut vals = self.init.clone();
+    let mut ts = vec![F::zero(); self.init.len()];
+    for i in 0..self.num_ops {
+      let cell = *index.get(&addr[i])?;
+      if vals[cell] != read_val[i] || ts[cell] != read_ts[i] {
+        return None;
+      }
+      vals[cell] = write_val[i];
+      ts[cell] = F::from((i + 1) as u64);
+    }
+    Some((vals, ts))
+  }
+
+  pub fn is_sat(&self, vars: &[F]) -> bool {
+    self.replay(vars).is_some()
+  }
+
+  // the point at which the witness polynomial restricts to the accesses
+  fn witness_point_prefix(&self, num_vars: usize) -> Vec<F> {
+    let block = 4 * self.num_ops;
+    let prefix_len = num_vars.log_2() - block.log_2();
+    (self.offset / block)
+      .get_bits(prefix_len)
+      .iter()
+      .map(|&b| if b { F::one() } else { F::zero() })
+      .collect::<Vec<F>>()
+  }
+
+  // evaluates the multilinear extension of the initial memory at r
+  fn evaluate_init(&self, r: &[F]) -> F {
+    let chis = EqPolynomial::new(r.to_vec()).evals();
+    (0..self.init.len()).map(|i| chis[i] * self.init[i]).sum()
+  }
+
+  fn append_to_transcript<G: ProjectiveCurve<ScalarField = F>>(&self, transcript: &mut Transcript) {
+    transcript.append_u64(b"offset", self.offset as u64);
+    transcript.append_u64(b"num_ops", self.num_ops as u64);
+    <Transcript as ProofTranscript<G>>::append_scalars(transcript, b"init", &self.init);
+  }
+}
+
+// the fingerprint of an (addr, val, ts) tuple
+fn hash<F: PrimeField>(r_hash: &F, r_multiset_check: &F, addr: &F, val: &F, ts: &F) -> F {
+  *ts * r_hash.square() + *val * r_hash + *addr - r_multiset_check
+}
+
+/// A proof that committed accesses to a read-write memory are consistent, using offline memory
+/// checking: the prover shows that `init * write = read * final` as multisets of
+/// `(addr, val, ts)` tuples with the product circuits used by Spark. As with lookups, the
+/// product circuits and the evaluations of the accesses at a random point are revealed to the
+/// verifier, so this component is not zero-knowledge with respect to the accesses.
+#[derive(Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct MemoryProof<G: ProjectiveCurve, PCS: MultilinearPCS<G> = Hyrax<G>> {
+  comm_final: PCS::Commitment,
+  claims_prod: (
+    G::ScalarField,
+    G::ScalarField,
+    G::ScalarField,
+    G::ScalarField,
+  ),
+  proof_prod_ops: ProductCircuitEvalProofBatched<G::ScalarField>,
+  proof_prod_mem: ProductCircuitEvalProofBatched<G::ScalarField>,
+  eval_ops: (
+    G::ScalarField,
+    G::ScalarField,
+    G::ScalarField,
+    G::ScalarField,
+  ),
+  eval_final: (G::ScalarField, G::ScalarField),
+  proof_final: PCS::EvalProof,
+  proof_vars: PCS::EvalProof,
+}
+
+impl<G: ProjectiveCurve, PCS: MultilinearPCS<G>> MemoryProof<G, PCS> {
+  fn protocol_name() -> &'static [u8] {
+    b"Memory proof"
+  }
+
+  pub fn prove(
+    memory: &Memory<G::ScalarField>,
+    witness: &(DensePolynomial<G::ScalarField>, PCS::Blinds),
+    vars: &[G::ScalarField],
+    gens_pc: &PCS::Gens,
+    gens_aux: &PCS::Gens,
+    transcript: &mut Transcript,
+    random_tape: &mut RandomTape<G>,
+  ) -> Result<Self, R1CSError> {
+    <Transcript as ProofTranscript<G>>::append_protocol_name(
+      transcript,
+      MemoryProof::<G, PCS>::protocol_name(),
+    );
+    memory.append_to_transcript::<G>(transcript);
+
+    let (poly_vars, blinds_vars) = witness;
+    let (num_ops, num_cells) = (memory.num_ops, memory.init.len());
+    if !fits_aux::<G, PCS>(num_cells, gens_aux) {
+      return Err(R1CSError::InvalidMemory);
+    }
+
+    // the final state of the memory is a non-deterministic choice of the prover
+    let (final_vals, final_ts) = memory.replay(vars).ok_or(R1CSError::InvalidMemory)?;
+    let poly_final_vals = DensePolynomial::new(final_vals);
+    let poly_final_ts = DensePolynomial::new(final_ts);
+    let comb_final = DensePolynomial::merge(&[poly_final_vals.clone(), poly_final_ts.clone()]);
+    let (comm_final, _blinds_final) = PCS::commit(&comb_final, gens_aux, None);
+    comm_final.append_to_transcript(b"comm_final", transcript);
+
+    let r_mem_check =
+      <Transcript as ProofTranscript<G>>::challenge_vector(transcript, b"challenge_r_hash", 2);
+    let h = |addr: &G::ScalarField, val: &G::ScalarField, ts: &G::ScalarField| {
+      hash(&r_mem_check[0], &r_mem_check[1], addr, val, ts)
+    };
+
+    let (addr, read_val, read_ts, write_val) = memory.accesses(vars);
+    let cells = (0..num_cells)
+      .map(|i| G::ScalarField::from(i as u64))
+      .collect::<Vec<G::ScalarField>>();
+    let leaves = |f: &dyn Fn(usize) -> G::ScalarField, len: usize| {
+      ProductCircuit::new(&DensePolynomial::new((0..len).map(f).collect()))
+    };
+    let mut prod_layer = ProductLayer {
+      init: leaves(
+        &|i| h(&cells[i], &memory.init[i], &G::ScalarField::zero()),
+        num_cells,
+      ),
+      read_vec: vec![leaves(&|i| h(&addr[i], &read_val[i], &read_ts[i]), num_ops)],
+      write_vec: vec![leaves(
+        &|i| {
+          h(
+            &addr[i],
+            &write_val[i],
+            &G::ScalarField::from((i + 1) as u64),
+          )
+        },
+        num_ops,
+      )],
+      audit: leaves(
+        &|i| h(&cells[i], &poly_final_vals[i], &poly_final_ts[i]),
+        num_cells,
+      ),
+    };
+
+    let claims_prod = (
+      prod_layer.init.evaluate(),
+      prod_layer.read_vec[0].evaluate(),
+      prod_layer.write_vec[0].evaluate(),
+      prod_layer.audit.evaluate(),
+    );
+    assert_eq!(claims_prod.0 * claims_prod.2, claims_prod.1 * claims_prod.3);
+    <Transcript as ProofTranscript<G>>::append_scalars(
+      transcript,
+      b"claims_prod",
+      &[claims_prod.0, claims_prod.1, claims_prod.2, claims_prod.3],
+    );
+
+    let (proof_prod_ops, rand_ops) = {
+      let (read, write) = (&mut prod_layer.read_vec, &mut prod_layer.write_vec);
+      ProductCircuitEvalProofBatched::prove::<G>(
+        &mut vec![&mut read[0], &mut write[0]],
+        &mut Vec::new(),
+        transcript,
+      )
+    };
+    let (proof_prod_mem, rand_mem) = ProductCircuitEvalProofBatched::prove::<G>(
+      &mut vec![&mut prod_layer.init, &mut prod_layer.audit],
+      &mut Vec::new(),
+      transcript,
+    );
+
+    // decommit the accesses at rand_ops and the final state at rand_mem
+    let eval =
+      |vals: &[G::ScalarField]| DensePolynomial::new(vals.to_vec()).evaluate::<G>(&rand_ops);
+    let eval_ops = (eval(addr), eval(read_val), eval(read_ts), eval(write_val));
+    let eval_final = (
+      poly_final_vals.evaluate::<G>(&rand_mem),
+      poly_final_ts.evaluate::<G>(&rand_mem),
+    );
+    <Transcript as ProofTranscript<G>>::append_scalars(
+      transcript,
+      b"claim_evals_memory",
+      &[
+        eval_ops.0,
+        eval_ops.1,
+        eval_ops.2,
+        eval_ops.3,
+        eval_final.0,
+        eval_final.1,
+      ],
+    );
+
+    let proof_final = {
+      let c = <Transcript as ProofTranscript<G>>::challenge_scalar(
+        transcript,
+        b"challenge_combine_two_to_one",
+      );
+      let eval_joint = (G::ScalarField::one() - c) * eval_final.0 + c * eval_final.1;
+      let mut r_joint = vec![c];
+      r_joint.extend(&rand_mem);
+      debug_assert_eq!(comb_final.evaluate::<G>(&r_joint), eval_joint);
+
+      PCS::prove(
+        &comb_final,
+        None,
+        &r_joint,
+        &eval_joint,
+        gens_aux,
+        transcript,
+        random_tape,
+      )
+    };
+
+    // the four blocks of accesses are a sub-cube of the committed witness
+    let proof_vars = {
+      let c = <Transcript as ProofTranscript<G>>::challenge_vector(
+        transcript,
+        b"challenge_combine_four_to_one",
+        2,
+      );
+      let chis = EqPolynomial::new(c.clone()).evals();
+      let eval_joint =
+        chis[0] * eval_ops.0 + chis[1] * eval_ops.1 + chis[2] * eval_ops.2 + chis[3] * eval_ops.3;
+      let mut r_vars = memory.witness_point_prefix(poly_vars.len());
+      r_vars.extend(&c);
+      r_vars.extend(&rand_ops);
+      debug_assert_eq!(poly_vars.evaluate::<G>(&r_vars), eval_joint);
+
+      PCS::prove(
+        poly_vars,
+        Some(blinds_vars),
+        &r_vars,
+        &eval_joint,
+        gens_pc,
+        transcript,
+        random_tape,
+      )
+    };
+
+    Ok(MemoryProof {
+      comm_final,
+      claims_prod,
+      proof_prod_ops,
+      proof_prod_mem,
+      eval_ops,
+      eval_final,
+      proof_final,
+      proof_vars,
+    })
+  }
+
+  pub fn verify(
+    &self,
+    memory: &Memory<G::ScalarField>,
+    num_vars: usize,
+    comm_vars: &PCS::Commitment,
+    gens_pc: &PCS::Gens,
+    gens_aux: &PCS::Gens,
+    transcript: &mut Transcript,
+  ) -> Result<(), ProofVerifyError> {
+    <Transcript as ProofTranscript<G>>::append_protocol_name(
+      transcript,
+      MemoryProof::<G, PCS>::protocol_name(),
+    );
+    memory.append_to_transcript::<G>(transcript);
+
+    let (num_ops, num_cells) = (memory.num_ops, memory.init.len());
+    if !fits_aux::<G, PCS>(num_cells, gens_aux) {
+      return Err(ProofVerifyError::InternalError);
+    }
+    self
+      .comm_final
+      .append_to_transcript(b"comm_final", transcript);
+
+    let r_mem_check =
+      <Transcript as ProofTranscript<G>>::challenge_vector(transcript, b"challenge_r_hash", 2);
+    let h = |addr: &G::ScalarField, val: &G::ScalarField, ts: &G::ScalarField| {
+      hash(&r_mem_check[0], &r_mem_check[1], addr, val, ts)
+    };
+
+    // the multiset of reads and final cells must match the multiset of inits and writes
+    let (claim_init, claim_read, claim_write, claim_final) = self.claims_prod;
+    if claim_init * claim_write != claim_read * claim_final {
+      return Err(ProofVerifyError::InternalError);
+    }
+    <Transcript as ProofTranscript<G>>::append_scalars(
+      transcript,
+      b"claims_prod",
+      &[claim_init, claim_read, claim_write, claim_final],
+    );
+
+    let (claims_ops, _claims_dotp, rand_ops) =
+      self
+        .proof_prod_ops
+        .verify::<G>(&[claim_read, claim_write], &[], num_ops, transcript)?;
+    let (claims_mem, _claims_dotp, rand_mem) =
+      self
+        .proof_prod_mem
+        .verify::<G>(&[claim_init, claim_final], &[], num_cells, transcript)?;
+
+    let (eval_addr, eval_read_val, eval_read_ts, eval_write_val) = self.eval_ops;
+    let (eval_final_val, eval_final_ts) = self.eval_final;
+    <Transcript as ProofTranscript<G>>::append_scalars(
+      transcript,
+      b"claim_evals_memory",
+      &[
+        eval_addr,
+        eval_read_val,
+        eval_read_ts,
+        eval_write_val,
+        eval_final_val,
+        eval_final_ts,
+      ],
+    );
+
+    // check the leaves of the product circuits against the decommitted values;
+    // write timestamps are the public sequence 1, 2, ..., num_ops
+    let eval_write_ts =
+      IdentityPolynomial::new(rand_ops.len()).evaluate(&rand_ops) + G::ScalarField::one();
+    let eval_cell = IdentityPolynomial::new(rand_mem.len()).evaluate(&rand_mem);
+    let eval_init = memory.evaluate_init(&rand_mem);
+    if h(&eval_cell, &eval_init, &G::ScalarField::zero()) != claims_mem[0]
+      || h(&eval_cell, &eval_final_val, &eval_final_ts) != claims_mem[1]
+      || h(&eval_addr, &eval_read_val, &eval_read_ts) != claims_ops[0]
+      || h(&eval_addr, &eval_write_val, &eval_write_ts) != claims_ops[1]
+    {
+      return Err(ProofVerifyError::InternalError);
+    }
+
+    // verify the decommitments
+    let c = <Transcript as ProofTranscript<G>>::challenge_scalar(
+      transcript,
+      b"challenge_combine_two_to_one",
+    );
+    let eval_joint = (G::ScalarField::one() - c) * eval_final_val + c * eval_final_ts;
+    let mut r_joint = vec![c];
+    r_joint.extend(&rand_mem);
+    let mut acc = MSMAccumulator::new();
+    PCS::verify(
+      &self.proof_final,
+      gens_aux,
+      transcript,
+      &r_joint,
+      &eval_joint,
+      &self.comm_final,
+      &mut acc,
+    )?;
+
+    let c = <Transcript as ProofTranscript<G>>::challenge_vector(
+      transcript,
+      b"challenge_combine_four_to_one",
+      2,
+    );
+    let chis = EqPolynomial::new(c.clone()).evals();
+    let eval_joint = chis[0] * eval_addr
+      + chis[1] * eval_read_val
+      + chis[2] * eval_read_ts
+      + chis[3] * eval_write_val;
+    let mut r_vars = memory.witness_point_prefix(num_vars);
+    r_vars.extend(&c);
+    r_vars.extend(&rand_ops);
+    PCS::verify(
+      &self.proof_vars,
+      gens_pc,
+      transcript,
+      &r_vars,
+      &eval_joint,
+      comm_vars,
+      &mut acc,
+    )?;
+    acc.check()
+  }
+}
+
+// whether the final values and timestamps of a memory can be committed with `gens_aux`
+fn fits_aux<G: ProjectiveCurve, PCS: MultilinearPCS<G>>(
+  num_cells: usize,
+  gens_aux: &PCS::Gens,
+) -> bool {
+  let num_vars = (2 * num_cells).log_2();
+  PCS::max_num_vars(gens_aux).map_or(true, |max_num_vars| num_vars <= max_num_vars)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  crate::curves::curve_tests!(field: check_replay, check_replay_helper);
+
+  fn check_replay_helper<F: PrimeField>() {
+    let v = |x: u64| F::from(x);
+    let memory = Memory::new(&[v(5), v(6), v(7)], 0, 2, 8).unwrap();
+    assert_eq!(memory.get_num_cells(), 4);
+
+    // write 9 to address 1, then read it back
+    let vars = [v(1), v(1), v(6), v(9), v(0), v(1), v(9), v(9)];
+    let (vals, ts) = memory.replay(&vars).unwrap();
+    assert_eq!(vals, vec![v(5), v(9), v(7), v(0)]);
+    assert_eq!(ts, vec![v(0), v(2), v(0), v(0)]);
+
+    // reads must return the latest write, with its timestamp
+    let stale = [v(1), v(1), v(6), v(6), v(0), v(1), v(9), v(9)];
+    assert!(!memory.is_sat(&stale));
+    let early = [v(1), v(1), v(6), v(9), v(0), v(0), v(9), v(9)];
+    assert!(!memory.is_sat(&early));
+    let out_of_range = [v(1), v(4), v(6), v(0), v(0), v(0), v(9), v(0)];
+    assert!(!memory.is_sat(&out_of_range));
+
+    // accesses must be an aligned block within the variables
+    assert!(Memory::new(&[v(0)], 4, 2, 16).is_err());
+    assert!(Memory::new(&[v(0)], 0, 4, 8).is_err());
+  }
+}
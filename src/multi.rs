@@ -0,0 +1,419 @@
+use super::errors::{ProofVerifyError, R1CSError};
+use super::r1csinstance::{R1CSCommitmentGens, R1CSEvalProof, R1CSInstance};
+use super::r1csproof::{R1CSGens, R1CSProof};
+use super::timer::Timer;
+use super::{
+  append_inst_evals, append_statement, start_proof, ComputationCommitment, ComputationDecommitment,
+  InputsAssignment, Instance, VarsAssignment, SNARK,
+};
+use ark_ec::ProjectiveCurve;
+use ark_ff::PrimeField;
+use ark_serialize::*;
+use core::cmp::max;
+use merlin::Transcript;
+
+/// `MultiSNARKGens` holds public parameters for producing and verifying a single proof
+/// of the satisfiability of several distinct R1CS instances with the same number of inputs
+pub struct MultiSNARKGens<G: ProjectiveCurve> {
+  gens_r1cs_sat: R1CSGens<G>,
+  gens_r1cs_eval: R1CSCommitmentGens<G>,
+  num_instances: usize,
+}
+
+impl<G: ProjectiveCurve> MultiSNARKGens<G> {
+  /// Constructs a new `MultiSNARKGens` given the size of the largest R1CS statement and the
+  /// number of instances proven together; `num_nz_entries` specifies the maximum number of
+  /// non-zero entries in any of the matrices of any instance
+  pub fn new(
+    num_cons: usize,
+    num_vars: usize,
+    num_inputs: usize,
+    num_nz_entries: usize,
+    num_instances: usize,
+  ) -> Self {
+    let num_vars_padded = max(num_vars, num_inputs + 1).next_power_of_two();
+
+    // the assignments are committed together, so the witness polynomial spans all of them
+    let gens_r1cs_sat = R1CSGens::<G>::new(
+      b"gens_r1cs_multi_sat",
+      num_cons,
+      num_instances.next_power_of_two() * num_vars_padded,
+    );
+    // the matrices of all instances are committed together
+    let gens_r1cs_eval = R1CSCommitmentGens::new_multi(
+      b"gens_r1cs_multi_eval",
+      num_cons,
+      num_vars_padded,
+      num_inputs,
+      num_nz_entries,
+      num_instances,
+    );
+    MultiSNARKGens {
+      gens_r1cs_sat,
+      gens_r1cs_eval,
+      num_instances,
+    }
+  }
+}
+
+/// `MultiSNARK` holds a single proof of the satisfiability of several distinct R1CS instances:
+/// the sum-checks run over all instances with shared challenges, and the evaluations of all
+/// of their matrices are proven with a single batched Spark proof
+#[derive(CanonicalSerialize, CanonicalDeserialize, Debug)]
+pub struct MultiSNARK<G: ProjectiveCurve> {
+  r1cs_sat_proof: R1CSProof<G>,
+  inst_evals: Vec<(G::ScalarField, G::ScalarField, G::ScalarField)>,
+  r1cs_eval_proof: R1CSEvalProof<G>,
+}
+
+impl<G: ProjectiveCurve> SNARK<G> {
+  fn protocol_name_multi() -> &'static [u8] {
+    b"Spartan multi-instance SNARK proof"
+  }
+
+  /// A public computation to create a single commitment to several R1CS instances, in the
+  /// order in which they are later proven; instances smaller than the largest one are padded
+  /// to its number of constraints and variables, and all must have the same number of inputs
+  pub fn encode_multi(
+    insts: &[&Instance<G::ScalarField>],
+    gens: &MultiSNARKGens<G>,
+  ) -> Result<
+    (
+      ComputationCommitment<G>,
+      ComputationDecommitment<G::ScalarField>,
+    ),
+    R1CSError,
+  > {
+    let timer_encode = Timer::new("SNARK::encode_multi");
+    if insts.len() != gens.num_instances {
+      return Err(R1CSError::InvalidNumberOfInstances);
+    }
+    let r1cs_insts = pad_insts(insts)?;
+    let (comm, decomm) =
+      R1CSInstance::multi_commit(&r1cs_insts.iter().collect::<Vec<_>>(), &gens.gens_r1cs_eval);
+    timer_encode.stop();
+    Ok((
+      ComputationCommitment {
+        comm,
+        lookups: Vec::new(),
+        memories: Vec::new(),
+        phases: None,
+      },
+      ComputationDecommitment { decomm },
+    ))
+  }
+
+  /// A method to produce a single proof of the satisfiability of each of several R1CS
+  /// instances by its assignment; instances with lookups, memories or a two-phase witness
+  /// are not supported
+  pub fn prove_multi(
+    insts: &[&Instance<G::ScalarField>],
+    comm: &ComputationCommitment<G>,
+    decomm: &ComputationDecommitment<G::ScalarField>,
+    vars: Vec<VarsAssignment<G::ScalarField>>,
+    inputs: &[InputsAssignment<G::ScalarField>],
+    gens: &MultiSNARKGens<G>,
+    transcript: &mut Transcript,
+  ) -> Result<MultiSNARK<G>, R1CSError> {
+    let timer_prove = Timer::new("SNARK::prove_multi");
+    if insts.len() != gens.num_instances {
+      return Err(R1CSError::InvalidNumberOfInstances);
+    }
+    if vars.len() != insts.len() {
+      return Err(R1CSError::InvalidNumberOfVars);
+    }
+    if inputs.len() != insts.len() {
+      return Err(R1CSError::InvalidNumberOfInputs);
+    }
+    for ((inst, vars), inputs) in insts.iter().zip(vars.iter()).zip(inputs.iter()) {
+      inst.check_plain()?;
+      inst.check_assignment(vars, inputs)?;
+    }
+    let r1cs_insts = pad_insts(insts)?;
+
+    let mut random_tape = start_proof::<G, _>(
+      SNARK::<G>::protocol_name_multi(),
+      b"comm",
+      &comm.comm,
+      transcript,
+    );
+
+    // we might need to pad variables
+    let num_padded_vars = r1cs_insts[0].get_num_vars();
+    let padded_vars = vars
+      .into_iter()
+      .map(|vars| vars.pad_to(num_padded_vars).assignment)
+      .collect::<Vec<_>>();
+    let inputs = inputs
+      .iter()
+      .map(|input| input.assignment.clone())
+      .collect::<Vec<_>>();
+
+    let (r1cs_sat_proof, rx, ry, _witness) = R1CSProof::prove_batched(
+      &r1cs_insts.iter().collect::<Vec<_>>(),
+      padded_vars,
+      &inputs,
+      &gens.gens_r1cs_sat,
+      transcript,
+      &mut random_tape,
+    );
+
+    // We send evaluations of A, B, C of every instance at r = (rx, ry) as claims
+    // to enable the verifier complete the second sum-check
+    let timer_eval = Timer::new("eval_sparse_polys");
+    let inst_evals = r1cs_insts
+      .iter()
+      .map(|inst| inst.evaluate(&rx, &ry))
+      .collect::<Vec<_>>();
+    for evals in inst_evals.iter() {
+      append_inst_evals::<G>(transcript, evals);
+    }
+    timer_eval.stop();
+
+    let r1cs_eval_proof = R1CSEvalProof::prove_multi(
+      &decomm.decomm,
+      &rx,
+      &ry,
+      &flatten_evals(&inst_evals),
+      &gens.gens_r1cs_eval,
+      transcript,
+      &mut random_tape,
+    );
+
+    timer_prove.stop();
+    Ok(MultiSNARK {
+      r1cs_sat_proof,
+      inst_evals,
+      r1cs_eval_proof,
+    })
+  }
+}
+
+impl<G: ProjectiveCurve> MultiSNARK<G> {
+  /// A method to verify a single proof of the satisfiability of several R1CS instances by
+  /// assignments with the given inputs, one for each instance
+  pub fn verify(
+    &self,
+    comm: &ComputationCommitment<G>,
+    inputs: &[InputsAssignment<G::ScalarField>],
+    transcript: &mut Transcript,
+    gens: &MultiSNARKGens<G>,
+  ) -> Result<(), ProofVerifyError> {
+    let timer_verify = Timer::new("MultiSNARK::verify");
+    if !comm.lookups.is_empty()
+      || !comm.memories.is_empty()
+      || comm.comm.get_num_instances() != gens.num_instances
+      || inputs.len() != gens.num_instances
+      || self.inst_evals.len() != gens.num_instances
+      || inputs
+        .iter()
+        .any(|input| input.assignment.len() != comm.comm.get_num_inputs())
+    {
+      return Err(ProofVerifyError::InternalError);
+    }
+
+    append_statement::<G, _>(
+      SNARK::<G>::protocol_name_multi(),
+      b"comm",
+      &comm.comm,
+      transcript,
+    );
+
+    let timer_sat_proof = Timer::new("verify_sat_proof");
+    let inputs = inputs
+      .iter()
+      .map(|input| input.assignment.clone())
+      .collect::<Vec<_>>();
+    let (rx, ry) = self.r1cs_sat_proof.verify_batched(
+      comm.comm.get_num_vars(),
+      comm.comm.get_num_cons(),
+      &inputs,
+      &self.inst_evals,
+      transcript,
+      &gens.gens_r1cs_sat,
+    )?;
+    timer_sat_proof.stop();
+
+    let timer_eval_proof = Timer::new("verify_eval_proof");
+    for evals in self.inst_evals.iter() {
+      append_inst_evals::<G>(transcript, evals);
+    }
+    self.r1cs_eval_proof.verify_multi(
+      &comm.comm,
+      &rx,
+      &ry,
+      &flatten_evals(&self.inst_evals),
+      &gens.gens_r1cs_eval,
+      transcript,
+    )?;
+    timer_eval_proof.stop();
+    timer_verify.stop();
+    Ok(())
+  }
+}
+
+// pads the instances to the number of constraints and the number of variables of the largest
+// one, so that they are proven and committed together; their numbers of inputs must agree
+fn pad_insts<F: PrimeField>(insts: &[&Instance<F>]) -> Result<Vec<R1CSInstance<F>>, R1CSError> {
+  let num_inputs = match insts.first() {
+    Some(inst) => inst.inst.get_num_inputs(),
+    None => return Err(R1CSError::InvalidNumberOfInstances),
+  };
+  if insts
+    .iter()
+    .any(|inst| inst.inst.get_num_inputs() != num_inputs)
+  {
+    return Err(R1CSError::InvalidNumberOfInputs);
+  }
+  let num_cons = insts.iter().map(|inst| inst.inst.get_num_cons()).max();
+  let num_vars = insts.iter().map(|inst| inst.inst.get_num_vars()).max();
+  Ok(
+    insts
+      .iter()
+      .map(|inst| inst.inst.pad(num_cons.unwrap(), num_vars.unwrap()))
+      .collect(),
+  )
+}
+
+// lists the evaluations in the order in which `multi_commit` commits to the matrices
+fn flatten_evals<F: Copy>(evals: &[(F, F, F)]) -> Vec<F> {
+  evals
+    .iter()
+    .flat_map(|(Ar, Br, Cr)| vec![*Ar, *Br, *Cr])
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ark_std::One;
+
+  // x^2 * z_e + c * z_k = y for a public y, over the variables z = (x, x^2, x^2 * z_e)
+  fn power_sum<F: PrimeField>(e: usize, k: usize, c: u64) -> Instance<F> {
+    let (num_cons, num_vars, num_inputs) = (3, 4, 1);
+    let one = F::one();
+    let A = vec![(0, 0, one), (1, 1, one), (2, 2, one), (2, k, F::from(c))];
+    let B = vec![(0, 0, one), (1, e, one), (2, num_vars, one)];
+    let C = vec![(0, 1, one), (1, 2, one), (2, num_vars + 1, one)];
+    Instance::new(num_cons, num_vars, num_inputs, &A, &B, &C).unwrap()
+  }
+
+  crate::curves::curve_tests!(group: check_multi_snark, check_multi_snark_helper);
+
+  fn check_multi_snark_helper<G: ProjectiveCurve>() {
+    // x^3 + 5x = y, x^4 + 2x^2 = y, and x^3 + 7x^2 = y, so that the batch is padded to four
+    let specs = [(0, 0, 5u64), (1, 1, 2u64), (0, 1, 7u64)];
+    let insts = specs
+      .iter()
+      .map(|(e, k, c)| power_sum::<G::ScalarField>(*e, *k, *c))
+      .collect::<Vec<_>>();
+    let (vars, inputs): (Vec<_>, Vec<_>) = specs
+      .iter()
+      .map(|(e, k, c)| {
+        let x = 3u64;
+        let pows = [x, x * x, x * x * [x, x * x][*e]];
+        let vars = [pows[0], pows[1], pows[2], 0]
+          .iter()
+          .map(|v| G::ScalarField::from(*v))
+          .collect::<Vec<_>>();
+        let y = G::ScalarField::from(pows[2] + c * pows[*k]);
+        (
+          VarsAssignment::new(&vars).unwrap(),
+          InputsAssignment::new(&[y]).unwrap(),
+        )
+      })
+      .unzip();
+    for ((inst, vars), inputs) in insts.iter().zip(vars.iter()).zip(inputs.iter()) {
+      assert!(inst.is_sat(vars, inputs).unwrap());
+    }
+
+    let gens = MultiSNARKGens::<G>::new(3, 4, 1, 4, insts.len());
+    let inst_refs = insts.iter().collect::<Vec<_>>();
+    let (comm, decomm) = SNARK::encode_multi(&inst_refs, &gens).unwrap();
+
+    let mut prover_transcript = Transcript::new(b"example");
+    let proof = SNARK::prove_multi(
+      &inst_refs,
+      &comm,
+      &decomm,
+      vars,
+      &inputs,
+      &gens,
+      &mut prover_transcript,
+    )
+    .unwrap();
+
+    let mut verifier_transcript = Transcript::new(b"example");
+    assert!(proof
+      .verify(&comm, &inputs, &mut verifier_transcript, &gens)
+      .is_ok());
+
+    // the proof does not verify if the inputs are assigned to the wrong instances
+    let mut wrong_inputs = inputs.clone();
+    wrong_inputs.swap(0, 2);
+    let mut verifier_transcript = Transcript::new(b"example");
+    assert!(proof
+      .verify(&comm, &wrong_inputs, &mut verifier_transcript, &gens)
+      .is_err());
+  }
+
+  crate::curves::curve_tests!(
+    group: check_multi_snark_sizes,
+    check_multi_snark_sizes_helper
+  );
+
+  fn check_multi_snark_sizes_helper<G: ProjectiveCurve>() {
+    // x^2 = y has fewer constraints and variables than x^3 + 5x = y, so it is padded to them
+    let one = G::ScalarField::one();
+    let square =
+      Instance::<G::ScalarField>::new(1, 1, 1, &[(0, 0, one)], &[(0, 0, one)], &[(0, 2, one)])
+        .unwrap();
+    let cubic = power_sum::<G::ScalarField>(0, 0, 5);
+    let x = 3u64;
+    let assignment = |vars: &[u64], y: u64| {
+      let vars = vars
+        .iter()
+        .map(|v| G::ScalarField::from(*v))
+        .collect::<Vec<_>>();
+      (
+        VarsAssignment::new(&vars).unwrap(),
+        InputsAssignment::new(&[G::ScalarField::from(y)]).unwrap(),
+      )
+    };
+    let (vars, inputs): (Vec<_>, Vec<_>) = vec![
+      assignment(&[x], x * x),
+      assignment(&[x, x * x, x * x * x, 0], x * x * x + 5 * x),
+    ]
+    .into_iter()
+    .unzip();
+    assert!(square.is_sat(&vars[0], &inputs[0]).unwrap());
+    assert!(cubic.is_sat(&vars[1], &inputs[1]).unwrap());
+
+    let gens = MultiSNARKGens::<G>::new(3, 4, 1, 4, 2);
+    let inst_refs = vec![&square, &cubic];
+    let (comm, decomm) = SNARK::encode_multi(&inst_refs, &gens).unwrap();
+
+    // the number of instances must be that of the generators
+    assert!(matches!(
+      SNARK::encode_multi(&inst_refs[..1], &gens),
+      Err(R1CSError::InvalidNumberOfInstances)
+    ));
+
+    let mut prover_transcript = Transcript::new(b"example");
+    let proof = SNARK::prove_multi(
+      &inst_refs,
+      &comm,
+      &decomm,
+      vars,
+      &inputs,
+      &gens,
+      &mut prover_transcript,
+    )
+    .unwrap();
+
+    let mut verifier_transcript = Transcript::new(b"example");
+    assert!(proof
+      .verify(&comm, &inputs, &mut verifier_transcript, &gens)
+      .is_ok());
+  }
+}
@@ -213,41 +213,38 @@ impl<G: ProjectiveCurve> BulletReductionProof<G> {
     Ok((challenges_sq, challenges_inv_sq, s))
   }
 
-  /// This method is for testing that proof generation work,
-  /// but for efficiency the actual protocols would use `verification_scalars`
-  /// method to combine inner product verification with other checks
-  /// in a single multiscalar multiplication.
+  /// Computes the terms of \\(\hat{G}\\) and \\(\hat{\Gamma}\\) as lists of (scalar, base) pairs,
+  /// along with \\(\hat{a}\\), so that the parent protocol can combine the inner product
+  /// verification with its other checks in a single multiscalar multiplication.
   pub fn verify(
     &self,
     n: usize,
     a: &[G::ScalarField],
     transcript: &mut Transcript,
-    Gamma: &G,
+    Gamma: &[G],
     G: &[G],
-  ) -> Result<(G, G, G::ScalarField), ProofVerifyError> {
+  ) -> Result<
+    (
+      Vec<(G::ScalarField, G)>,
+      Vec<(G::ScalarField, G)>,
+      G::ScalarField,
+    ),
+    ProofVerifyError,
+  > {
     let (u_sq, u_inv_sq, s) = self.verification_scalars(n, transcript)?;
 
-    let group_element = G::batch_normalization_into_affine(G);
-    let scalars = s.iter().map(|x| x.into_repr()).collect::<Vec<_>>();
-
-    let G_hat = VariableBaseMSM::multi_scalar_mul(group_element.as_ref(), scalars.as_ref());
+    let G_hat = s.iter().cloned().zip(G.iter().cloned()).collect::<Vec<_>>();
 
     let a_hat = inner_product(a, &s);
 
-    let bases = G::batch_normalization_into_affine(
-      [self.L_vec.as_slice(), self.R_vec.as_slice(), &[*Gamma]]
-        .concat()
-        .as_ref(),
-    );
-    let scalars = u_sq
+    let Gamma_hat = u_sq
       .iter()
       .chain(u_inv_sq.iter())
-      .chain(iter::once(&G::ScalarField::one()))
-      .map(|x| x.into_repr())
+      .cloned()
+      .zip(self.L_vec.iter().chain(self.R_vec.iter()).cloned())
+      .chain(Gamma.iter().map(|P| (G::ScalarField::one(), *P)))
       .collect::<Vec<_>>();
 
-    let Gamma_hat = VariableBaseMSM::multi_scalar_mul(bases.as_ref(), scalars.as_ref());
-
     Ok((G_hat, Gamma_hat, a_hat))
   }
 }
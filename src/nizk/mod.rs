@@ -1,5 +1,5 @@
 #![allow(clippy::too_many_arguments)]
-use super::commitments::{Commitments, MultiCommitGens};
+use super::commitments::{Commitments, MSMAccumulator, MultiCommitGens};
 use super::errors::ProofVerifyError;
 use super::math::Math;
 use super::random::RandomTape;
@@ -10,7 +10,7 @@ use bullet::BulletReductionProof;
 use merlin::Transcript;
 mod bullet;
 use ark_ff::PrimeField;
-use ark_std::Zero;
+use ark_std::{One, Zero};
 
 #[derive(CanonicalSerialize, CanonicalDeserialize, Debug)]
 pub struct KnowledgeProof<G: ProjectiveCurve> {
@@ -59,6 +59,19 @@ impl<G: ProjectiveCurve> KnowledgeProof<G> {
     gens_n: &MultiCommitGens<G>,
     transcript: &mut Transcript,
     C: &G,
+  ) -> Result<(), ProofVerifyError> {
+    let mut acc = MSMAccumulator::new();
+    self.verify_deferred(gens_n, transcript, C, &mut acc)?;
+    acc.check()
+  }
+
+  // adds the verification equation to `acc` instead of checking it
+  pub fn verify_deferred(
+    &self,
+    gens_n: &MultiCommitGens<G>,
+    transcript: &mut Transcript,
+    C: &G,
+    acc: &mut MSMAccumulator<G>,
   ) -> Result<(), ProofVerifyError> {
     <Transcript as ProofTranscript<G>>::append_protocol_name(
       transcript,
@@ -70,14 +83,13 @@ impl<G: ProjectiveCurve> KnowledgeProof<G> {
 
     let c = <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"c");
 
-    let lhs = self.z1.commit(&self.z2, gens_n);
-    let rhs = C.mul(c.into_repr()) + self.alpha;
-
-    if lhs == rhs {
-      Ok(())
-    } else {
-      Err(ProofVerifyError::InternalError)
-    }
+    // z1 * G + z2 * h = c * C + alpha
+    acc.append(&[
+      (self.z1, gens_n.G[0]),
+      (self.z2, gens_n.h),
+      (-c, *C),
+      (-G::ScalarField::one(), self.alpha),
+    ])
   }
 }
 
@@ -132,6 +144,20 @@ impl<G: ProjectiveCurve> EqualityProof<G> {
     transcript: &mut Transcript,
     C1: &G,
     C2: &G,
+  ) -> Result<(), ProofVerifyError> {
+    let mut acc = MSMAccumulator::new();
+    self.verify_deferred(gens_n, transcript, C1, C2, &mut acc)?;
+    acc.check()
+  }
+
+  // adds the verification equation to `acc` instead of checking it
+  pub fn verify_deferred(
+    &self,
+    gens_n: &MultiCommitGens<G>,
+    transcript: &mut Transcript,
+    C1: &G,
+    C2: &G,
+    acc: &mut MSMAccumulator<G>,
   ) -> Result<(), ProofVerifyError> {
     <Transcript as ProofTranscript<G>>::append_protocol_name(
       transcript,
@@ -144,18 +170,13 @@ impl<G: ProjectiveCurve> EqualityProof<G> {
 
     let c = <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"c");
 
-    let rhs = {
-      let C = *C1 - *C2;
-      C.mul(c.into_repr()) + self.alpha
-    };
-
-    let lhs = gens_n.h.mul(self.z.into_repr());
-
-    if lhs == rhs {
-      Ok(())
-    } else {
-      Err(ProofVerifyError::InternalError)
-    }
+    // z * h = c * (C1 - C2) + alpha
+    acc.append(&[
+      (self.z, gens_n.h),
+      (-c, *C1),
+      (c, *C2),
+      (-G::ScalarField::one(), self.alpha),
+    ])
   }
 }
 
@@ -282,18 +303,22 @@ impl<G: ProjectiveCurve> ProductProof<G> {
     )
   }
 
-  fn check_equality(
+  // the terms of P + c * X = z1 * g + z2 * h
+  fn equality_terms(
     P: &G,
     X: &G,
     c: &G::ScalarField,
-    gens_n: &MultiCommitGens<G>,
+    g: &G,
+    h: &G,
     z1: &G::ScalarField,
     z2: &G::ScalarField,
-  ) -> bool {
-    let lhs = *P + X.mul(c.into_repr());
-    let rhs = z1.commit(z2, gens_n);
-
-    lhs == rhs
+  ) -> [(G::ScalarField, G); 4] {
+    [
+      (G::ScalarField::one(), *P),
+      (*c, *X),
+      (-*z1, *g),
+      (-*z2, *h),
+    ]
   }
 
   pub fn verify(
@@ -303,6 +328,21 @@ impl<G: ProjectiveCurve> ProductProof<G> {
     X: &G,
     Y: &G,
     Z: &G,
+  ) -> Result<(), ProofVerifyError> {
+    let mut acc = MSMAccumulator::new();
+    self.verify_deferred(gens_n, transcript, X, Y, Z, &mut acc)?;
+    acc.check()
+  }
+
+  // adds the verification equations to `acc` instead of checking them
+  pub fn verify_deferred(
+    &self,
+    gens_n: &MultiCommitGens<G>,
+    transcript: &mut Transcript,
+    X: &G,
+    Y: &G,
+    Z: &G,
+    acc: &mut MSMAccumulator<G>,
   ) -> Result<(), ProofVerifyError> {
     <Transcript as ProofTranscript<G>>::append_protocol_name(
       transcript,
@@ -324,25 +364,28 @@ impl<G: ProjectiveCurve> ProductProof<G> {
 
     let c = <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"c");
 
-    if ProductProof::check_equality(&self.alpha, X, &c, gens_n, &z1, &z2)
-      && ProductProof::check_equality(&self.beta, Y, &c, gens_n, &z3, &z4)
-      && ProductProof::check_equality(
-        &self.delta,
-        Z,
-        &c,
-        &MultiCommitGens {
-          n: 1,
-          G: vec![*X],
-          h: gens_n.h,
-        },
-        &z3,
-        &z5,
-      )
-    {
-      Ok(())
-    } else {
-      Err(ProofVerifyError::InternalError)
-    }
+    let (g, h) = (&gens_n.G[0], &gens_n.h);
+    acc.append(&ProductProof::equality_terms(
+      &self.alpha,
+      X,
+      &c,
+      g,
+      h,
+      &z1,
+      &z2,
+    ))?;
+    acc.append(&ProductProof::equality_terms(
+      &self.beta, Y, &c, g, h, &z3, &z4,
+    ))?;
+    acc.append(&ProductProof::equality_terms(
+      &self.delta,
+      Z,
+      &c,
+      X,
+      h,
+      &z3,
+      &z5,
+    ))
   }
 }
 
@@ -429,6 +472,7 @@ impl<G: ProjectiveCurve> DotProductProof<G> {
     )
   }
 
+  // adds the verification equations to `acc`, which checks them
   pub fn verify(
     &self,
     gens_1: &MultiCommitGens<G>,
@@ -437,6 +481,7 @@ impl<G: ProjectiveCurve> DotProductProof<G> {
     a: &[G::ScalarField],
     Cx: &G,
     Cy: &G,
+    acc: &mut MSMAccumulator<G>,
   ) -> Result<(), ProofVerifyError> {
     assert_eq!(gens_n.n, a.len());
     assert_eq!(gens_1.n, 1);
@@ -455,17 +500,23 @@ impl<G: ProjectiveCurve> DotProductProof<G> {
 
     let c = <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"c");
 
-    let mut result = Cx.mul(c.into_repr()) + self.delta
-      == Commitments::batch_commit(self.z.as_ref(), &self.z_delta, gens_n);
+    // c * Cx + delta = Com(z; z_delta)
+    let mut terms = vec![
+      (c, *Cx),
+      (G::ScalarField::one(), self.delta),
+      (-self.z_delta, gens_n.h),
+    ];
+    terms.extend(self.z.iter().zip(gens_n.G.iter()).map(|(z, g)| (-*z, *g)));
+    acc.append(&terms)?;
 
+    // c * Cy + beta = Com(<z, a>; z_beta)
     let dotproduct_z_a = DotProductProof::<G>::compute_dotproduct(&self.z, a);
-    result &= Cy.mul(c.into_repr()) + self.beta == dotproduct_z_a.commit(&self.z_beta, gens_1);
-
-    if result {
-      Ok(())
-    } else {
-      Err(ProofVerifyError::InternalError)
-    }
+    acc.append(&[
+      (c, *Cy),
+      (G::ScalarField::one(), self.beta),
+      (-dotproduct_z_a, gens_1.G[0]),
+      (-self.z_beta, gens_1.h),
+    ])
   }
 }
 
@@ -480,6 +531,17 @@ impl<G: ProjectiveCurve> DotProductProofGens<G> {
     let (gens_n, gens_1) = MultiCommitGens::new(n + 1, label).split_at(n);
     DotProductProofGens { n, gens_n, gens_1 }
   }
+
+  // the generators for vectors of length `n`, which are the first of these generators
+  pub fn prefix(&self, n: usize) -> Self {
+    assert!(n <= self.n);
+    let (gens_n, _rest) = self.gens_n.split_at(n);
+    DotProductProofGens {
+      n,
+      gens_n,
+      gens_1: self.gens_1.clone(),
+    }
+  }
 }
 
 #[derive(Debug, CanonicalSerialize, CanonicalDeserialize)]
@@ -585,6 +647,8 @@ impl<G: ProjectiveCurve> DotProductProofLog<G> {
     )
   }
 
+  // adds the verification equation, including the inner product reduction, to `acc`, which
+  // checks it
   pub fn verify(
     &self,
     n: usize,
@@ -593,6 +657,7 @@ impl<G: ProjectiveCurve> DotProductProofLog<G> {
     a: &[G::ScalarField],
     Cx: &G,
     Cy: &G,
+    acc: &mut MSMAccumulator<G>,
   ) -> Result<(), ProofVerifyError> {
     assert_eq!(gens.n, n);
     assert_eq!(a.len(), n);
@@ -605,50 +670,39 @@ impl<G: ProjectiveCurve> DotProductProofLog<G> {
     <Transcript as ProofTranscript<G>>::append_point(transcript, b"Cy", Cy);
     <Transcript as ProofTranscript<G>>::append_scalars(transcript, b"a", a);
 
-    let Gamma = *Cx + *Cy;
-
+    // Gamma = Cx + Cy
     let (g_hat, Gamma_hat, a_hat) =
       self
         .bullet_reduction_proof
-        .verify(n, a, transcript, &Gamma, &gens.gens_n.G)?;
+        .verify(n, a, transcript, &[*Cx, *Cy], &gens.gens_n.G)?;
 
     <Transcript as ProofTranscript<G>>::append_point(transcript, b"delta", &self.delta);
     <Transcript as ProofTranscript<G>>::append_point(transcript, b"beta", &self.beta);
 
     let c = <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"c");
 
-    let c_s = &c;
-    let beta_s = self.beta;
-    let a_hat_s = &a_hat;
-    let delta_s = self.delta;
-    let z1_s = &self.z1;
-    let z2_s = &self.z2;
-
-    let lhs = (Gamma_hat.mul(c_s.into_repr()) + beta_s).mul(a_hat_s.into_repr()) + delta_s;
-    let rhs = (g_hat + gens.gens_1.G[0].mul(a_hat_s.into_repr())).mul(z1_s.into_repr())
-      + gens.gens_1.h.mul(z2_s.into_repr());
+    // (c * Gamma_hat + beta) * a_hat + delta = (g_hat + a_hat * g) * z1 + z2 * h
+    let mut terms = vec![
+      (a_hat, self.beta),
+      (G::ScalarField::one(), self.delta),
+      (-self.z1 * a_hat, gens.gens_1.G[0]),
+      (-self.z2, gens.gens_1.h),
+    ];
+    terms.extend(Gamma_hat.into_iter().map(|(s, P)| (c * a_hat * s, P)));
+    terms.extend(g_hat.into_iter().map(|(s, P)| (-self.z1 * s, P)));
+    acc.append(&terms)?;
 
-    assert_eq!(lhs, rhs);
-
-    if lhs == rhs {
-      Ok(())
-    } else {
-      Err(ProofVerifyError::InternalError)
-    }
+    Ok(())
   }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
-  use ark_bls12_381::G1Projective;
   use ark_std::test_rng;
   use ark_std::UniformRand;
 
-  #[test]
-  fn check_knowledgeproof() {
-    check_knowledgeproof_helper::<G1Projective>()
-  }
+  crate::curves::curve_tests!(group: check_knowledgeproof, check_knowledgeproof_helper);
 
   fn check_knowledgeproof_helper<G: ProjectiveCurve>() {
     let mut prng = test_rng();
@@ -669,10 +723,7 @@ mod tests {
       .is_ok());
   }
 
-  #[test]
-  fn check_equalityproof() {
-    check_equalityproof_helper::<G1Projective>()
-  }
+  crate::curves::curve_tests!(group: check_equalityproof, check_equalityproof_helper);
 
   fn check_equalityproof_helper<G: ProjectiveCurve>() {
     let mut prng = test_rng();
@@ -701,10 +752,7 @@ mod tests {
       .is_ok());
   }
 
-  #[test]
-  fn check_productproof() {
-    check_productproof_helper::<G1Projective>()
-  }
+  crate::curves::curve_tests!(group: check_productproof, check_productproof_helper);
 
   fn check_productproof_helper<G: ProjectiveCurve>() {
     let mut prng = test_rng();
@@ -737,10 +785,7 @@ mod tests {
       .is_ok());
   }
 
-  #[test]
-  fn check_dotproductproof() {
-    check_dotproductproof_helper::<G1Projective>()
-  }
+  crate::curves::curve_tests!(group: check_dotproductproof, check_dotproductproof_helper);
 
   fn check_dotproductproof_helper<G: ProjectiveCurve>() {
     let mut prng = test_rng();
@@ -775,15 +820,25 @@ mod tests {
     );
 
     let mut verifier_transcript = Transcript::new(b"example");
+    let mut acc = MSMAccumulator::new();
     assert!(proof
-      .verify(&gens_1, &gens_1024, &mut verifier_transcript, &a, &Cx, &Cy)
+      .verify(
+        &gens_1,
+        &gens_1024,
+        &mut verifier_transcript,
+        &a,
+        &Cx,
+        &Cy,
+        &mut acc,
+      )
       .is_ok());
+    assert!(acc.check().is_ok());
   }
 
-  #[test]
-  fn check_dotproductproof_log() {
-    check_dotproductproof_log_helper::<G1Projective>()
-  }
+  crate::curves::curve_tests!(
+    group: check_dotproductproof_log,
+    check_dotproductproof_log_helper
+  );
   fn check_dotproductproof_log_helper<G: ProjectiveCurve>() {
     let mut prng = test_rng();
 
@@ -812,8 +867,10 @@ mod tests {
     );
 
     let mut verifier_transcript = Transcript::new(b"example");
+    let mut acc = MSMAccumulator::new();
     assert!(proof
-      .verify(n, &gens, &mut verifier_transcript, &a, &Cx, &Cy)
+      .verify(n, &gens, &mut verifier_transcript, &a, &Cx, &Cy, &mut acc)
       .is_ok());
+    assert!(acc.check().is_ok());
   }
 }
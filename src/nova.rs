@@ -0,0 +1,817 @@
+#![allow(clippy::too_many_arguments)]
+use super::commitments::Commitments;
+use super::dense_mlpoly::{
+  DensePolynomial, EqPolynomial, PolyCommitment, PolyCommitmentBlinds, PolyEvalProof,
+};
+use super::errors::{ProofVerifyError, R1CSError};
+use super::math::Math;
+use super::nizk::{EqualityProof, KnowledgeProof, ProductProof};
+use super::r1csinstance::R1CSInstance;
+use super::r1csproof::{R1CSGens, R1CSProof};
+use super::random::RandomTape;
+use super::sparse_mlpoly::{SparsePolyEntry, SparsePolynomial};
+use super::sumcheck::ZKSumcheckInstanceProof;
+use super::timer::Timer;
+use super::transcript::{AppendToTranscript, ProofTranscript};
+use super::{InputsAssignment, Instance, VarsAssignment};
+use ark_ec::msm::VariableBaseMSM;
+use ark_ec::ProjectiveCurve;
+use ark_ff::{Field, PrimeField};
+use ark_serialize::*;
+use ark_std::{One, Zero};
+use core::cmp::max;
+use merlin::Transcript;
+
+/// `FoldingGens` holds public parameters for folding relaxed R1CS instances and for proving the
+/// satisfiability of the folded instance
+pub struct FoldingGens<G: ProjectiveCurve> {
+  gens_r1cs_sat: R1CSGens<G>,
+  num_entries: usize,
+}
+
+impl<G: ProjectiveCurve> FoldingGens<G> {
+  /// Constructs a new `FoldingGens` given the size of the R1CS statement
+  pub fn new(num_cons: usize, num_vars: usize, num_inputs: usize) -> Self {
+    let num_vars_padded = {
+      let mut num_vars_padded = max(num_vars, num_inputs + 1);
+      if num_vars_padded != num_vars_padded.next_power_of_two() {
+        num_vars_padded = num_vars_padded.next_power_of_two();
+      }
+      num_vars_padded
+    };
+    let num_cons_padded = max(num_cons, 2).next_power_of_two();
+
+    // W and E are committed as polynomials of the same size, so that openings of both are
+    // commitments under the generators of the sum-checks
+    let num_entries = max(num_vars_padded, num_cons_padded);
+    let gens_r1cs_sat = R1CSGens::<G>::new(b"gens_r1cs_sat", num_cons_padded, num_entries);
+    FoldingGens {
+      gens_r1cs_sat,
+      num_entries,
+    }
+  }
+
+  // pads `v` with zeros to a polynomial of the committed size
+  fn pad<F: PrimeField>(&self, v: &[F]) -> DensePolynomial<F> {
+    assert!(v.len() <= self.num_entries);
+    let mut Z = v.to_vec();
+    Z.resize(self.num_entries, F::zero());
+    DensePolynomial::new(Z)
+  }
+
+  // the point at which the padded polynomial of `num_entries` entries is evaluated to get the
+  // evaluation of the unpadded polynomial at `r`
+  fn pad_point<F: PrimeField>(&self, r: &[F]) -> Vec<F> {
+    let mut point = vec![F::zero(); self.num_entries.log_2() as usize - r.len()];
+    point.extend(r);
+    point
+  }
+}
+
+/// `RelaxedInstance` is the public part of a relaxed R1CS instance `Az * Bz = u * Cz + E` with
+/// `z = (W, u, x)`: commitments to the witness `W` and to the error vector `E`, the scalar `u`,
+/// and the public inputs `x`
+#[derive(CanonicalSerialize, CanonicalDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RelaxedInstance<G: ProjectiveCurve> {
+  comm_W: PolyCommitment<G>,
+  comm_E: PolyCommitment<G>,
+  u: G::ScalarField,
+  x: Vec<G::ScalarField>,
+}
+
+/// `RelaxedWitness` holds the witness `W` and the error vector `E` of a relaxed R1CS instance,
+/// along with the blinds of their commitments
+#[derive(Clone)]
+pub struct RelaxedWitness<F: PrimeField> {
+  W: Vec<F>,
+  E: Vec<F>,
+  blinds_W: PolyCommitmentBlinds<F>,
+  blinds_E: PolyCommitmentBlinds<F>,
+}
+
+impl<G: ProjectiveCurve> AppendToTranscript<G> for RelaxedInstance<G> {
+  fn append_to_transcript(&self, label: &'static [u8], transcript: &mut Transcript) {
+    transcript.append_message(label, b"relaxed_instance_begin");
+    self.comm_W.append_to_transcript(b"comm_W", transcript);
+    self.comm_E.append_to_transcript(b"comm_E", transcript);
+    <Transcript as ProofTranscript<G>>::append_scalar(transcript, b"u", &self.u);
+    <Transcript as ProofTranscript<G>>::append_scalars(transcript, b"x", &self.x);
+    transcript.append_message(label, b"relaxed_instance_end");
+  }
+}
+
+impl<G: ProjectiveCurve> RelaxedInstance<G> {
+  /// Turns an assignment to an `Instance` into a relaxed instance with `u = 1` and `E = 0`,
+  /// committing to the (padded) assignment to the variables. Only the R1CS constraints are
+  /// folded, so instances with lookups, memories or a two-phase witness are rejected.
+  pub fn new(
+    inst: &Instance<G::ScalarField>,
+    vars: &VarsAssignment<G::ScalarField>,
+    input: &InputsAssignment<G::ScalarField>,
+    gens: &FoldingGens<G>,
+  ) -> Result<(Self, RelaxedWitness<G::ScalarField>), R1CSError> {
+    if !inst.lookups.is_empty() {
+      return Err(R1CSError::InvalidLookup);
+    }
+    if !inst.memories.is_empty() {
+      return Err(R1CSError::InvalidMemory);
+    }
+    if inst.phases.is_some() {
+      return Err(R1CSError::InvalidPhases);
+    }
+    let (num_cons, num_vars) = (inst.inst.get_num_cons(), inst.inst.get_num_vars());
+    if vars.assignment.len() > num_vars {
+      return Err(R1CSError::InvalidNumberOfVars);
+    }
+    if input.assignment.len() != inst.inst.get_num_inputs() {
+      return Err(R1CSError::InvalidNumberOfInputs);
+    }
+
+    let mut random_tape = RandomTape::new(b"proof");
+    let mut W = vars.assignment.clone();
+    W.resize(num_vars, G::ScalarField::zero());
+    let E = vec![G::ScalarField::zero(); num_cons];
+
+    let gens_pc = &gens.gens_r1cs_sat.gens_pc;
+    let (comm_W, blinds_W) = gens.pad(&W).commit(gens_pc, Some(&mut random_tape));
+    let (comm_E, blinds_E) = gens.pad(&E).commit(gens_pc, None);
+
+    Ok((
+      RelaxedInstance {
+        comm_W,
+        comm_E,
+        u: G::ScalarField::one(),
+        x: input.assignment.clone(),
+      },
+      RelaxedWitness {
+        W,
+        E,
+        blinds_W,
+        blinds_E,
+      },
+    ))
+  }
+
+  /// Checks if `witness` opens the commitments of the relaxed instance and satisfies
+  /// `Az * Bz = u * Cz + E`
+  pub fn is_sat(
+    &self,
+    inst: &Instance<G::ScalarField>,
+    witness: &RelaxedWitness<G::ScalarField>,
+    gens: &FoldingGens<G>,
+  ) -> Result<bool, R1CSError> {
+    if witness.W.len() != inst.inst.get_num_vars() {
+      return Err(R1CSError::InvalidNumberOfVars);
+    }
+    if self.x.len() != inst.inst.get_num_inputs() {
+      return Err(R1CSError::InvalidNumberOfInputs);
+    }
+    if witness.E.len() != inst.inst.get_num_cons() {
+      return Err(R1CSError::InvalidNumberOfVars);
+    }
+
+    let gens_pc = &gens.gens_r1cs_sat.gens_pc;
+    let comm_W = gens
+      .pad(&witness.W)
+      .commit_with_blinds(&witness.blinds_W, gens_pc);
+    let comm_E = gens
+      .pad(&witness.E)
+      .commit_with_blinds(&witness.blinds_E, gens_pc);
+
+    Ok(
+      comm_W == self.comm_W
+        && comm_E == self.comm_E
+        && inst
+          .inst
+          .is_sat_relaxed(&witness.W, &self.u, &self.x, &witness.E),
+    )
+  }
+
+  // checks that a relaxed instance has the shape of `inst`
+  fn check_shape(
+    &self,
+    inst: &R1CSInstance<G::ScalarField>,
+    gens: &FoldingGens<G>,
+  ) -> Result<(), ProofVerifyError> {
+    let (left_num_vars, _right_num_vars) = gens
+      .gens_r1cs_sat
+      .gens_pc
+      .factored_lens(gens.num_entries.log_2() as usize);
+    if inst.get_num_vars() > gens.num_entries
+      || inst.get_num_cons() > gens.num_entries
+      || self.comm_W.num_rows() != left_num_vars.pow2()
+      || self.comm_E.num_rows() != left_num_vars.pow2()
+      || self.x.len() != inst.get_num_inputs()
+    {
+      return Err(ProofVerifyError::InternalError);
+    }
+    Ok(())
+  }
+
+  fn fold(
+    &self,
+    other: &RelaxedInstance<G>,
+    comm_T: &PolyCommitment<G>,
+    r: &G::ScalarField,
+  ) -> Self {
+    let r_sq = *r * r;
+    RelaxedInstance {
+      comm_W: self.comm_W.fold(&other.comm_W, r),
+      comm_E: self.comm_E.fold(comm_T, r).fold(&other.comm_E, &r_sq),
+      u: self.u + *r * other.u,
+      x: (0..self.x.len())
+        .map(|i| self.x[i] + *r * other.x[i])
+        .collect(),
+    }
+  }
+}
+
+/// `FoldingProof` holds a proof that a relaxed R1CS instance is the fold of two others; it
+/// consists of a commitment to the cross term of the two instances
+#[derive(CanonicalSerialize, CanonicalDeserialize, Debug)]
+pub struct FoldingProof<G: ProjectiveCurve> {
+  comm_T: PolyCommitment<G>,
+}
+
+impl<G: ProjectiveCurve> FoldingProof<G> {
+  fn protocol_name() -> &'static [u8] {
+    b"Nova folding proof"
+  }
+
+  /// Folds two relaxed instances of `inst` and their witnesses into a single relaxed instance
+  /// and witness, which is satisfying if both inputs are
+  #[allow(clippy::type_complexity)]
+  pub fn prove(
+    inst: &Instance<G::ScalarField>,
+    U1: &RelaxedInstance<G>,
+    W1: &RelaxedWitness<G::ScalarField>,
+    U2: &RelaxedInstance<G>,
+    W2: &RelaxedWitness<G::ScalarField>,
+    gens: &FoldingGens<G>,
+    transcript: &mut Transcript,
+  ) -> (
+    FoldingProof<G>,
+    RelaxedInstance<G>,
+    RelaxedWitness<G::ScalarField>,
+  ) {
+    let timer_prove = Timer::new("FoldingProof::prove");
+    let mut random_tape = RandomTape::new(b"proof");
+    let inst = &inst.inst;
+
+    <Transcript as ProofTranscript<G>>::append_protocol_name(
+      transcript,
+      FoldingProof::<G>::protocol_name(),
+    );
+    <R1CSInstance<G::ScalarField> as AppendToTranscript<G>>::append_to_transcript(
+      inst, b"inst", transcript,
+    );
+    U1.append_to_transcript(b"U1", transcript);
+    U2.append_to_transcript(b"U2", transcript);
+
+    // the cross term T = Az1 * Bz2 + Az2 * Bz1 - u1 * Cz2 - u2 * Cz1
+    let T = {
+      let num_cols = inst.get_num_vars() + inst.get_num_inputs() + 1;
+      let z = |U: &RelaxedInstance<G>, W: &RelaxedWitness<G::ScalarField>| {
+        let mut z = W.W.clone();
+        z.push(U.u);
+        z.extend(&U.x);
+        z
+      };
+      let (Az1, Bz1, Cz1) = inst.multiply_vec(inst.get_num_cons(), num_cols, &z(U1, W1));
+      let (Az2, Bz2, Cz2) = inst.multiply_vec(inst.get_num_cons(), num_cols, &z(U2, W2));
+      (0..inst.get_num_cons())
+        .map(|i| Az1[i] * Bz2[i] + Az2[i] * Bz1[i] - U1.u * Cz2[i] - U2.u * Cz1[i])
+        .collect::<Vec<G::ScalarField>>()
+    };
+    let (comm_T, blinds_T) = gens
+      .pad(&T)
+      .commit(&gens.gens_r1cs_sat.gens_pc, Some(&mut random_tape));
+    comm_T.append_to_transcript(b"comm_T", transcript);
+
+    let r = <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"challenge_r");
+    let r_sq = r * r;
+
+    let U = U1.fold(U2, &comm_T, &r);
+    let W = RelaxedWitness {
+      W: (0..W1.W.len()).map(|i| W1.W[i] + r * W2.W[i]).collect(),
+      E: (0..W1.E.len())
+        .map(|i| W1.E[i] + r * T[i] + r_sq * W2.E[i])
+        .collect(),
+      blinds_W: W1.blinds_W.fold(&W2.blinds_W, &r),
+      blinds_E: W1.blinds_E.fold(&blinds_T, &r).fold(&W2.blinds_E, &r_sq),
+    };
+
+    timer_prove.stop();
+    (FoldingProof { comm_T }, U, W)
+  }
+
+  /// Computes the fold of two relaxed instances of `inst` using the prover's cross term
+  pub fn verify(
+    &self,
+    inst: &Instance<G::ScalarField>,
+    U1: &RelaxedInstance<G>,
+    U2: &RelaxedInstance<G>,
+    transcript: &mut Transcript,
+    gens: &FoldingGens<G>,
+  ) -> Result<RelaxedInstance<G>, ProofVerifyError> {
+    let inst = &inst.inst;
+    U1.check_shape(inst, gens)?;
+    U2.check_shape(inst, gens)?;
+    if self.comm_T.num_rows() != U1.comm_E.num_rows() {
+      return Err(ProofVerifyError::InternalError);
+    }
+
+    <Transcript as ProofTranscript<G>>::append_protocol_name(
+      transcript,
+      FoldingProof::<G>::protocol_name(),
+    );
+    <R1CSInstance<G::ScalarField> as AppendToTranscript<G>>::append_to_transcript(
+      inst, b"inst", transcript,
+    );
+    U1.append_to_transcript(b"U1", transcript);
+    U2.append_to_transcript(b"U2", transcript);
+    self.comm_T.append_to_transcript(b"comm_T", transcript);
+
+    let r = <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"challenge_r");
+    Ok(U1.fold(U2, &self.comm_T, &r))
+  }
+}
+
+/// `RelaxedNIZK` holds a proof of the satisfiability of a relaxed R1CS instance, which is
+/// Spartan's R1CS proof with the first sum-check run over `u * Cz + E` and the constant
+/// entry of `z` replaced by `u`
+#[derive(CanonicalSerialize, CanonicalDeserialize, Debug)]
+pub struct RelaxedNIZK<G: ProjectiveCurve> {
+  sc_proof_phase1: ZKSumcheckInstanceProof<G>,
+  claims_phase2: (G, G, G, G),
+  pok_claims_phase2: (KnowledgeProof<G>, ProductProof<G>),
+  proof_eq_sc_phase1: EqualityProof<G>,
+  comm_E_at_rx: G,
+  proof_eval_E_at_rx: PolyEvalProof<G>,
+  sc_proof_phase2: ZKSumcheckInstanceProof<G>,
+  comm_W_at_ry: G,
+  proof_eval_W_at_ry: PolyEvalProof<G>,
+  proof_eq_sc_phase2: EqualityProof<G>,
+}
+
+impl<G: ProjectiveCurve> RelaxedNIZK<G> {
+  fn protocol_name() -> &'static [u8] {
+    b"Relaxed R1CS proof"
+  }
+
+  /// A method to produce a proof of the satisfiability of a relaxed instance of `inst`
+  pub fn prove(
+    inst: &Instance<G::ScalarField>,
+    U: &RelaxedInstance<G>,
+    W: &RelaxedWitness<G::ScalarField>,
+    gens: &FoldingGens<G>,
+    transcript: &mut Transcript,
+  ) -> Self {
+    let timer_prove = Timer::new("RelaxedNIZK::prove");
+    let mut random_tape = RandomTape::new(b"proof");
+    let random_tape = &mut random_tape;
+    let inst = &inst.inst;
+    let gens_sc = &gens.gens_r1cs_sat.gens_sc;
+    let gens_pc = &gens.gens_r1cs_sat.gens_pc;
+
+    // the prover cannot derive Cz from u * Cz + E otherwise
+    assert!(!U.u.is_zero());
+
+    <Transcript as ProofTranscript<G>>::append_protocol_name(
+      transcript,
+      RelaxedNIZK::<G>::protocol_name(),
+    );
+    <R1CSInstance<G::ScalarField> as AppendToTranscript<G>>::append_to_transcript(
+      inst, b"inst", transcript,
+    );
+    U.append_to_transcript(b"U", transcript);
+
+    // z = (W, u, x) padded with zeros
+    let z = {
+      let num_vars = W.W.len();
+      let mut z = W.W.clone();
+      z.push(U.u);
+      z.extend(&U.x);
+      z.extend(&vec![G::ScalarField::zero(); num_vars - U.x.len() - 1]);
+      z
+    };
+
+    let (num_rounds_x, num_rounds_y) = (
+      inst.get_num_cons().log_2() as usize,
+      z.len().log_2() as usize,
+    );
+    let tau = <Transcript as ProofTranscript<G>>::challenge_vector(
+      transcript,
+      b"challenge_tau",
+      num_rounds_x,
+    );
+
+    // the first sum-check runs over Az * Bz - (u * Cz + E)
+    let mut poly_tau = DensePolynomial::new(EqPolynomial::new(tau).evals());
+    let (mut poly_Az, mut poly_Bz, poly_Cz) = inst.multiply_vec(inst.get_num_cons(), z.len(), &z);
+    let mut poly_uCz_E = DensePolynomial::new(
+      (0..inst.get_num_cons())
+        .map(|i| U.u * poly_Cz[i] + W.E[i])
+        .collect(),
+    );
+
+    let (sc_proof_phase1, rx, _claims_phase1, blind_claim_postsc1) =
+      R1CSProof::<G>::prove_phase_one(
+        num_rounds_x,
+        &mut poly_tau,
+        &mut poly_Az,
+        &mut poly_Bz,
+        &mut poly_uCz_E,
+        gens_sc,
+        transcript,
+        random_tape,
+      );
+
+    let (tau_claim, Az_claim, Bz_claim, uCz_E_claim) =
+      (&poly_tau[0], &poly_Az[0], &poly_Bz[0], &poly_uCz_E[0]);
+    let (Az_blind, Bz_blind, uCz_E_blind, prod_Az_Bz_blind) = (
+      random_tape.random_scalar(b"Az_blind"),
+      random_tape.random_scalar(b"Bz_blind"),
+      random_tape.random_scalar(b"Cz_blind"),
+      random_tape.random_scalar(b"prod_Az_Bz_blind"),
+    );
+
+    let (pok_uCz_E_claim, comm_uCz_E_claim) = KnowledgeProof::prove(
+      &gens_sc.gens_1,
+      transcript,
+      random_tape,
+      uCz_E_claim,
+      &uCz_E_blind,
+    );
+
+    let (proof_prod, comm_Az_claim, comm_Bz_claim, comm_prod_Az_Bz_claims) = {
+      let prod = *Az_claim * *Bz_claim;
+      ProductProof::prove(
+        &gens_sc.gens_1,
+        transcript,
+        random_tape,
+        Az_claim,
+        &Az_blind,
+        Bz_claim,
+        &Bz_blind,
+        &prod,
+        &prod_Az_Bz_blind,
+      )
+    };
+
+    <Transcript as ProofTranscript<G>>::append_point(transcript, b"comm_Az_claim", &comm_Az_claim);
+    <Transcript as ProofTranscript<G>>::append_point(transcript, b"comm_Bz_claim", &comm_Bz_claim);
+    <Transcript as ProofTranscript<G>>::append_point(
+      transcript,
+      b"comm_Cz_claim",
+      &comm_uCz_E_claim,
+    );
+    <Transcript as ProofTranscript<G>>::append_point(
+      transcript,
+      b"comm_prod_Az_Bz_claims",
+      &comm_prod_Az_Bz_claims,
+    );
+
+    // prove the final step of sum-check #1
+    let blind_expected_claim_postsc1 = *tau_claim * (prod_Az_Bz_blind - uCz_E_blind);
+    let claim_post_phase1 = (*Az_claim * *Bz_claim - *uCz_E_claim) * *tau_claim;
+    let (proof_eq_sc_phase1, _C1, _C2) = EqualityProof::prove(
+      &gens_sc.gens_1,
+      transcript,
+      random_tape,
+      &claim_post_phase1,
+      &blind_expected_claim_postsc1,
+      &claim_post_phase1,
+      &blind_claim_postsc1,
+    );
+
+    // open E at rx, so that the verifier can derive a commitment to Cz(rx)
+    let poly_E = gens.pad(&W.E);
+    let rx_padded = gens.pad_point(&rx);
+    let eval_E_at_rx = poly_E.evaluate::<G>(&rx_padded);
+    let blind_E_at_rx = random_tape.random_scalar(b"blind_E_at_rx");
+    let (proof_eval_E_at_rx, comm_E_at_rx) = PolyEvalProof::prove(
+      &poly_E,
+      Some(&W.blinds_E),
+      &rx_padded,
+      &eval_E_at_rx,
+      Some(&blind_E_at_rx),
+      gens_pc,
+      transcript,
+      random_tape,
+    );
+    let u_inv = U.u.inverse().unwrap();
+    let Cz_claim = (*uCz_E_claim - eval_E_at_rx) * u_inv;
+    let Cz_blind = (uCz_E_blind - blind_E_at_rx) * u_inv;
+
+    // combine the three claims into a single claim
+    let r_A = <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"challenege_Az");
+    let r_B = <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"challenege_Bz");
+    let r_C = <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"challenege_Cz");
+    let claim_phase2 = r_A * Az_claim + r_B * Bz_claim + r_C * Cz_claim;
+    let blind_claim_phase2 = r_A * Az_blind + r_B * Bz_blind + r_C * Cz_blind;
+
+    let evals_ABC = {
+      let evals_rx = EqPolynomial::new(rx).evals();
+      let (evals_A, evals_B, evals_C) =
+        inst.compute_eval_table_sparse(inst.get_num_cons(), z.len(), &evals_rx);
+      (0..evals_A.len())
+        .map(|i| r_A * evals_A[i] + r_B * evals_B[i] + r_C * evals_C[i])
+        .collect::<Vec<G::ScalarField>>()
+    };
+
+    let (sc_proof_phase2, ry, claims_phase2, blind_claim_postsc2) = R1CSProof::<G>::prove_phase_two(
+      num_rounds_y,
+      &claim_phase2,
+      &blind_claim_phase2,
+      &mut DensePolynomial::new(z),
+      &mut DensePolynomial::new(evals_ABC),
+      gens_sc,
+      transcript,
+      random_tape,
+    );
+
+    let poly_W = gens.pad(&W.W);
+    let ry_padded = gens.pad_point(&ry[1..]);
+    let eval_W_at_ry = poly_W.evaluate::<G>(&ry_padded);
+    let blind_eval = random_tape.random_scalar(b"blind_eval");
+    let (proof_eval_W_at_ry, comm_W_at_ry) = PolyEvalProof::prove(
+      &poly_W,
+      Some(&W.blinds_W),
+      &ry_padded,
+      &eval_W_at_ry,
+      Some(&blind_eval),
+      gens_pc,
+      transcript,
+      random_tape,
+    );
+
+    // prove the final step of sum-check #2
+    let blind_eval_Z_at_ry = (G::ScalarField::one() - ry[0]) * blind_eval;
+    let blind_expected_claim_postsc2 = claims_phase2[1] * blind_eval_Z_at_ry;
+    let claim_post_phase2 = claims_phase2[0] * claims_phase2[1];
+    let (proof_eq_sc_phase2, _C1, _C2) = EqualityProof::prove(
+      &gens_pc.gens.gens_1,
+      transcript,
+      random_tape,
+      &claim_post_phase2,
+      &blind_expected_claim_postsc2,
+      &claim_post_phase2,
+      &blind_claim_postsc2,
+    );
+
+    timer_prove.stop();
+    RelaxedNIZK {
+      sc_proof_phase1,
+      claims_phase2: (
+        comm_Az_claim,
+        comm_Bz_claim,
+        comm_uCz_E_claim,
+        comm_prod_Az_Bz_claims,
+      ),
+      pok_claims_phase2: (pok_uCz_E_claim, proof_prod),
+      proof_eq_sc_phase1,
+      comm_E_at_rx,
+      proof_eval_E_at_rx,
+      sc_proof_phase2,
+      comm_W_at_ry,
+      proof_eval_W_at_ry,
+      proof_eq_sc_phase2,
+    }
+  }
+
+  /// A method to verify a proof of the satisfiability of a relaxed instance of `inst`
+  pub fn verify(
+    &self,
+    inst: &Instance<G::ScalarField>,
+    U: &RelaxedInstance<G>,
+    transcript: &mut Transcript,
+    gens: &FoldingGens<G>,
+  ) -> Result<(), ProofVerifyError> {
+    let timer_verify = Timer::new("RelaxedNIZK::verify");
+    let inst = &inst.inst;
+    let gens_sc = &gens.gens_r1cs_sat.gens_sc;
+    let gens_pc = &gens.gens_r1cs_sat.gens_pc;
+    U.check_shape(inst, gens)?;
+    let u_inv = U.u.inverse().ok_or(ProofVerifyError::InternalError)?;
+
+    <Transcript as ProofTranscript<G>>::append_protocol_name(
+      transcript,
+      RelaxedNIZK::<G>::protocol_name(),
+    );
+    <R1CSInstance<G::ScalarField> as AppendToTranscript<G>>::append_to_transcript(
+      inst, b"inst", transcript,
+    );
+    U.append_to_transcript(b"U", transcript);
+
+    let num_vars = inst.get_num_vars();
+    let (num_rounds_x, num_rounds_y) = (
+      inst.get_num_cons().log_2() as usize,
+      (2 * num_vars).log_2() as usize,
+    );
+    let tau = <Transcript as ProofTranscript<G>>::challenge_vector(
+      transcript,
+      b"challenge_tau",
+      num_rounds_x,
+    );
+
+    // verify the first sum-check instance
+    let claim_phase1 = G::ScalarField::zero().commit(&G::ScalarField::zero(), &gens_sc.gens_1);
+    let (comm_claim_post_phase1, rx) = self.sc_proof_phase1.verify(
+      &claim_phase1,
+      num_rounds_x,
+      3,
+      &gens_sc.gens_1,
+      &gens_sc.gens_d,
+      transcript,
+    )?;
+
+    let (comm_Az_claim, comm_Bz_claim, comm_uCz_E_claim, comm_prod_Az_Bz_claims) =
+      &self.claims_phase2;
+    let (pok_uCz_E_claim, proof_prod) = &self.pok_claims_phase2;
+    pok_uCz_E_claim.verify(&gens_sc.gens_1, transcript, comm_uCz_E_claim)?;
+    proof_prod.verify(
+      &gens_sc.gens_1,
+      transcript,
+      comm_Az_claim,
+      comm_Bz_claim,
+      comm_prod_Az_Bz_claims,
+    )?;
+
+    <Transcript as ProofTranscript<G>>::append_point(transcript, b"comm_Az_claim", comm_Az_claim);
+    <Transcript as ProofTranscript<G>>::append_point(transcript, b"comm_Bz_claim", comm_Bz_claim);
+    <Transcript as ProofTranscript<G>>::append_point(
+      transcript,
+      b"comm_Cz_claim",
+      comm_uCz_E_claim,
+    );
+    <Transcript as ProofTranscript<G>>::append_point(
+      transcript,
+      b"comm_prod_Az_Bz_claims",
+      comm_prod_Az_Bz_claims,
+    );
+
+    let taus_bound_rx = EqPolynomial::new(tau).evaluate(&rx);
+    let expected_claim_post_phase1 =
+      (*comm_prod_Az_Bz_claims - *comm_uCz_E_claim).mul(taus_bound_rx.into_repr());
+    self.proof_eq_sc_phase1.verify(
+      &gens_sc.gens_1,
+      transcript,
+      &expected_claim_post_phase1,
+      &comm_claim_post_phase1,
+    )?;
+
+    // derive a commitment to Cz(rx) = (u * Cz(rx) + E(rx)) / u
+    self.proof_eval_E_at_rx.verify(
+      gens_pc,
+      transcript,
+      &gens.pad_point(&rx),
+      &self.comm_E_at_rx,
+      &U.comm_E,
+    )?;
+    let comm_Cz_claim = (*comm_uCz_E_claim - self.comm_E_at_rx).mul(u_inv.into_repr());
+
+    let r_A = <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"challenege_Az");
+    let r_B = <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"challenege_Bz");
+    let r_C = <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"challenege_Cz");
+
+    let scalars = vec![r_A.into_repr(), r_B.into_repr(), r_C.into_repr()];
+    let bases = vec![*comm_Az_claim, *comm_Bz_claim, comm_Cz_claim];
+    let bases_affine = G::batch_normalization_into_affine(bases.as_ref());
+    let comm_claim_phase2 =
+      VariableBaseMSM::multi_scalar_mul(bases_affine.as_ref(), scalars.as_ref());
+
+    let (comm_claim_post_phase2, ry) = self.sc_proof_phase2.verify(
+      &comm_claim_phase2,
+      num_rounds_y,
+      2,
+      &gens_sc.gens_1,
+      &gens_sc.gens_3,
+      transcript,
+    )?;
+
+    self.proof_eval_W_at_ry.verify(
+      gens_pc,
+      transcript,
+      &gens.pad_point(&ry[1..]),
+      &self.comm_W_at_ry,
+      &U.comm_W,
+    )?;
+
+    // the constant entry of z is u rather than one
+    let poly_input_eval = {
+      let mut input_as_sparse_poly_entries = vec![SparsePolyEntry::new(0, U.u)];
+      input_as_sparse_poly_entries.extend(
+        (0..U.x.len())
+          .map(|i| SparsePolyEntry::new(i + 1, U.x[i]))
+          .collect::<Vec<SparsePolyEntry<G::ScalarField>>>(),
+      );
+      SparsePolynomial::new(num_vars.log_2() as usize, input_as_sparse_poly_entries)
+        .evaluate(&ry[1..])
+    };
+
+    let scalars = vec![
+      (G::ScalarField::one() - ry[0]).into_repr(),
+      ry[0].into_repr(),
+    ];
+    let bases = vec![
+      self.comm_W_at_ry.into_affine(),
+      poly_input_eval
+        .commit(&G::ScalarField::zero(), &gens_pc.gens.gens_1)
+        .into_affine(),
+    ];
+    let comm_eval_Z_at_ry = VariableBaseMSM::multi_scalar_mul(bases.as_ref(), scalars.as_ref());
+
+    let (eval_A_r, eval_B_r, eval_C_r) = inst.evaluate(&rx, &ry);
+    let expected_claim_post_phase2 =
+      comm_eval_Z_at_ry.mul((r_A * eval_A_r + r_B * eval_B_r + r_C * eval_C_r).into_repr());
+    self.proof_eq_sc_phase2.verify(
+      &gens_sc.gens_1,
+      transcript,
+      &expected_claim_post_phase2,
+      &comm_claim_post_phase2,
+    )?;
+
+    timer_verify.stop();
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  crate::curves::curve_tests!(group: check_folding, check_folding_helper);
+
+  fn check_folding_helper<G: ProjectiveCurve>() {
+    // z_0 = x * x, z_1 = z_0 * x, and z_2 = z_1 + x, with unused variables so that the
+    // witness is longer than the error vector
+    let (num_cons, num_vars, num_inputs) = (3, 5, 1);
+    let one = G::ScalarField::one();
+    let A = vec![(0, 6, one), (1, 0, one), (2, 1, one), (2, 6, one)];
+    let B = vec![(0, 6, one), (1, 6, one), (2, 5, one)];
+    let C = vec![(0, 0, one), (1, 1, one), (2, 2, one)];
+    let inst = Instance::new(num_cons, num_vars, num_inputs, &A, &B, &C).unwrap();
+    let gens = FoldingGens::<G>::new(num_cons, num_vars, num_inputs);
+
+    let assignment = |x: u64, valid: bool| {
+      let x = G::ScalarField::from(x);
+      let cube = if valid { x * x * x } else { x * x };
+      (
+        VarsAssignment::new(&[x * x, cube, cube + x]).unwrap(),
+        InputsAssignment::new(&[x]).unwrap(),
+      )
+    };
+    let relax = |x: u64, valid: bool| {
+      let (vars, input) = assignment(x, valid);
+      RelaxedInstance::new(&inst, &vars, &input, &gens).unwrap()
+    };
+
+    let (U1, W1) = relax(2, true);
+    let (U2, W2) = relax(3, true);
+    let (U3, W3) = relax(5, true);
+    assert!(U1.is_sat(&inst, &W1, &gens).unwrap());
+
+    // fold twice, checking that the verifier derives the same instance as the prover
+    let mut prover_transcript = Transcript::new(b"example");
+    let (proof_12, U12, W12) =
+      FoldingProof::prove(&inst, &U1, &W1, &U2, &W2, &gens, &mut prover_transcript);
+    assert!(U12.is_sat(&inst, &W12, &gens).unwrap());
+    let (proof_123, U123, W123) =
+      FoldingProof::prove(&inst, &U12, &W12, &U3, &W3, &gens, &mut prover_transcript);
+    assert!(U123.is_sat(&inst, &W123, &gens).unwrap());
+    let proof = RelaxedNIZK::prove(&inst, &U123, &W123, &gens, &mut prover_transcript);
+
+    let mut verifier_transcript = Transcript::new(b"example");
+    let V12 = proof_12
+      .verify(&inst, &U1, &U2, &mut verifier_transcript, &gens)
+      .unwrap();
+    assert_eq!(V12, U12);
+    let V123 = proof_123
+      .verify(&inst, &V12, &U3, &mut verifier_transcript, &gens)
+      .unwrap();
+    assert_eq!(V123, U123);
+    assert!(proof
+      .verify(&inst, &V123, &mut verifier_transcript, &gens)
+      .is_ok());
+
+    // the proof does not verify against another instance
+    let mut verifier_transcript = Transcript::new(b"example");
+    let V12 = proof_12
+      .verify(&inst, &U1, &U2, &mut verifier_transcript, &gens)
+      .unwrap();
+    let _ = proof_123
+      .verify(&inst, &V12, &U3, &mut verifier_transcript, &gens)
+      .unwrap();
+    assert!(proof
+      .verify(&inst, &V12, &mut verifier_transcript, &gens)
+      .is_err());
+
+    // folding in an unsatisfying witness gives an unsatisfying instance
+    let (U4, W4) = relax(7, false);
+    let mut prover_transcript = Transcript::new(b"example");
+    let (_proof, U14, W14) =
+      FoldingProof::prove(&inst, &U1, &W1, &U4, &W4, &gens, &mut prover_transcript);
+    assert!(!U14.is_sat(&inst, &W14, &gens).unwrap());
+  }
+}
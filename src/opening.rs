@@ -0,0 +1,263 @@
+use super::commitments::Commitments;
+use super::dense_mlpoly::{
+  DensePolynomial, PolyCommitment, PolyCommitmentBlinds, PolyCommitmentGens, PolyEvalProof,
+};
+use super::errors::{ProofVerifyError, R1CSError};
+use super::math::Math;
+use super::random::RandomTape;
+use super::transcript::ProofTranscript;
+use super::{
+  ComputationCommitment, ComputationDecommitment, InputsAssignment, Instance, NIZKGens, SNARKGens,
+  VarsAssignment, NIZK, SNARK,
+};
+use ark_ec::ProjectiveCurve;
+use ark_ff::PrimeField;
+use ark_serialize::*;
+use ark_std::Zero;
+use merlin::Transcript;
+
+/// `WitnessGens` is implemented by the public parameters of the proofs whose commitment to the
+/// witness can be opened at points other than the ones the proofs open it at
+pub trait WitnessGens<G: ProjectiveCurve> {
+  /// Returns the generators of the commitment to the witness
+  fn gens_witness(&self) -> &PolyCommitmentGens<G>;
+}
+
+impl<G: ProjectiveCurve> WitnessGens<G> for SNARKGens<G> {
+  fn gens_witness(&self) -> &PolyCommitmentGens<G> {
+    &self.gens_r1cs_sat.gens_pc
+  }
+}
+
+impl<G: ProjectiveCurve> WitnessGens<G> for NIZKGens<G> {
+  fn gens_witness(&self) -> &PolyCommitmentGens<G> {
+    &self.gens_r1cs_sat.gens_pc
+  }
+}
+
+/// `WitnessCommitment` holds the commitment to the multilinear extension of the (padded)
+/// assignment to the variables inside a proof
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct WitnessCommitment<G: ProjectiveCurve> {
+  comm: PolyCommitment<G>,
+}
+
+/// `WitnessOpening` holds the prover's opening of a `WitnessCommitment`: the (padded)
+/// assignment to the variables and the blinds of the commitment
+pub struct WitnessOpening<F> {
+  poly: DensePolynomial<F>,
+  blinds: PolyCommitmentBlinds<F>,
+}
+
+impl<F: PrimeField> WitnessOpening<F> {
+  pub(crate) fn new(witness: (DensePolynomial<F>, PolyCommitmentBlinds<F>)) -> Self {
+    let (poly, blinds) = witness;
+    WitnessOpening { poly, blinds }
+  }
+
+  /// Returns the number of variables of the multilinear extension of the witness, which is
+  /// the length of the points it can be opened at
+  pub fn get_num_vars(&self) -> usize {
+    self.poly.get_num_vars()
+  }
+
+  /// Evaluates the multilinear extension of the witness at `point`
+  pub fn evaluate<G: ProjectiveCurve<ScalarField = F>>(&self, point: &[F]) -> F {
+    self.poly.evaluate::<G>(point)
+  }
+
+  /// Proves the evaluation of the multilinear extension of the witness at `point`; the
+  /// evaluation is only given to the verifier as a Pedersen commitment with the given blind,
+  /// and a zero blind lets the verifier check the evaluation in the clear
+  pub fn prove<G, W>(
+    &self,
+    point: &[F],
+    blind: &F,
+    gens: &W,
+    transcript: &mut Transcript,
+  ) -> WitnessEvalProof<G>
+  where
+    G: ProjectiveCurve<ScalarField = F>,
+    W: WitnessGens<G>,
+  {
+    let mut random_tape = RandomTape::new(b"proof");
+    <Transcript as ProofTranscript<G>>::append_scalars(transcript, b"witness_point", point);
+    let eval = self.poly.evaluate::<G>(point);
+    let (proof, comm_eval) = PolyEvalProof::prove(
+      &self.poly,
+      Some(&self.blinds),
+      point,
+      &eval,
+      Some(blind),
+      gens.gens_witness(),
+      transcript,
+      &mut random_tape,
+    );
+    WitnessEvalProof { proof, comm_eval }
+  }
+}
+
+/// `WitnessEvalProof` holds a proof of the evaluation of the multilinear extension of the
+/// witness committed in a `WitnessCommitment` at a point
+#[derive(CanonicalSerialize, CanonicalDeserialize, Debug)]
+pub struct WitnessEvalProof<G: ProjectiveCurve> {
+  proof: PolyEvalProof<G>,
+  comm_eval: G,
+}
+
+impl<G: ProjectiveCurve> WitnessEvalProof<G> {
+  /// Returns the Pedersen commitment to the evaluation
+  pub fn get_comm_eval(&self) -> G {
+    self.comm_eval
+  }
+
+  /// Verifies the proof of the evaluation at `point`, whose commitment is `get_comm_eval()`
+  pub fn verify<W: WitnessGens<G>>(
+    &self,
+    comm: &WitnessCommitment<G>,
+    point: &[G::ScalarField],
+    gens: &W,
+    transcript: &mut Transcript,
+  ) -> Result<(), ProofVerifyError> {
+    let gens = gens.gens_witness();
+    if point.len() != (comm.comm.num_rows() * gens.gens.gens_n.n).log_2() {
+      return Err(ProofVerifyError::InternalError);
+    }
+    <Transcript as ProofTranscript<G>>::append_scalars(transcript, b"witness_point", point);
+    self
+      .proof
+      .verify(gens, transcript, point, &self.comm_eval, &comm.comm)
+  }
+
+  /// Verifies the proof of the evaluation at `point` for a proof produced with a zero blind
+  /// and the given evaluation
+  pub fn verify_plain<W: WitnessGens<G>>(
+    &self,
+    comm: &WitnessCommitment<G>,
+    point: &[G::ScalarField],
+    eval: &G::ScalarField,
+    gens: &W,
+    transcript: &mut Transcript,
+  ) -> Result<(), ProofVerifyError> {
+    if self.comm_eval != eval.commit(&G::ScalarField::zero(), &gens.gens_witness().gens.gens_1) {
+      return Err(ProofVerifyError::InternalError);
+    }
+    self.verify(comm, point, gens, transcript)
+  }
+}
+
+impl<G: ProjectiveCurve> SNARK<G> {
+  /// Like `prove`, but also returns the opening of the commitment to the witness in the proof,
+  /// with which it can be opened at further points with the same transcript; instances with a
+  /// two-phase witness are rejected with `R1CSError::InvalidPhases`
+  pub fn prove_with_witness(
+    inst: &Instance<G::ScalarField>,
+    comm: &ComputationCommitment<G>,
+    decomm: &ComputationDecommitment<G::ScalarField>,
+    vars: VarsAssignment<G::ScalarField>,
+    inputs: &InputsAssignment<G::ScalarField>,
+    gens: &SNARKGens<G>,
+    transcript: &mut Transcript,
+  ) -> Result<(Self, WitnessOpening<G::ScalarField>), R1CSError> {
+    if inst.phases.is_some() {
+      return Err(R1CSError::InvalidPhases);
+    }
+    SNARK::prove_with_fixed_blinds(inst, comm, decomm, vars, inputs, &[], gens, transcript)
+  }
+
+  /// Returns the commitment to the witness in the proof
+  pub fn get_witness_commitment(&self) -> WitnessCommitment<G> {
+    WitnessCommitment {
+      comm: self.r1cs_sat_proof.get_comm_vars().clone(),
+    }
+  }
+}
+
+impl<G: ProjectiveCurve> NIZK<G> {
+  /// Like `prove`, but also returns the opening of the commitment to the witness in the proof,
+  /// with which it can be opened at further points with the same transcript; instances with a
+  /// two-phase witness are rejected with `R1CSError::InvalidPhases`
+  pub fn prove_with_witness(
+    inst: &Instance<G::ScalarField>,
+    vars: VarsAssignment<G::ScalarField>,
+    input: &InputsAssignment<G::ScalarField>,
+    gens: &NIZKGens<G>,
+    transcript: &mut Transcript,
+  ) -> Result<(Self, WitnessOpening<G::ScalarField>), R1CSError> {
+    if inst.phases.is_some() {
+      return Err(R1CSError::InvalidPhases);
+    }
+    NIZK::prove_with_fixed_blinds(inst, vars, input, &[], &[], gens, transcript)
+  }
+
+  /// Returns the commitment to the witness in the proof
+  pub fn get_witness_commitment(&self) -> WitnessCommitment<G> {
+    WitnessCommitment {
+      comm: self.r1cs_sat_proof.get_comm_vars().clone(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ark_std::{test_rng, One, UniformRand};
+
+  crate::curves::curve_tests!(
+    group: check_witness_eval_proof,
+    check_witness_eval_proof_helper
+  );
+
+  fn check_witness_eval_proof_helper<G: ProjectiveCurve>() {
+    let (num_cons, num_vars, num_inputs) = (64, 64, 4);
+    let (inst, vars, inputs) =
+      Instance::<G::ScalarField>::produce_synthetic_r1cs(num_cons, num_vars, num_inputs);
+    let gens = SNARKGens::<G>::new(num_cons, num_vars, num_inputs, num_cons);
+    let (comm, decomm) = SNARK::encode(&inst, &gens);
+
+    let mut rng = test_rng();
+    let mut prover_transcript = Transcript::new(b"example");
+    let (proof, witness) = SNARK::prove_with_witness(
+      &inst,
+      &comm,
+      &decomm,
+      vars,
+      &inputs,
+      &gens,
+      &mut prover_transcript,
+    )
+    .unwrap();
+    let point = (0..witness.get_num_vars())
+      .map(|_| G::ScalarField::rand(&mut rng))
+      .collect::<Vec<_>>();
+    let eval = witness.evaluate::<G>(&point);
+    let eval_proof = witness.prove(
+      &point,
+      &G::ScalarField::zero(),
+      &gens,
+      &mut prover_transcript,
+    );
+
+    let mut verifier_transcript = Transcript::new(b"example");
+    assert!(proof
+      .verify(&comm, &inputs, &mut verifier_transcript, &gens)
+      .is_ok());
+    let comm_vars = proof.get_witness_commitment();
+    let mut transcript = verifier_transcript.clone();
+    assert!(eval_proof
+      .verify_plain(&comm_vars, &point, &eval, &gens, &mut transcript)
+      .is_ok());
+
+    // the proof does not verify for another evaluation
+    let mut transcript = verifier_transcript.clone();
+    assert!(eval_proof
+      .verify_plain(
+        &comm_vars,
+        &point,
+        &(eval + G::ScalarField::one()),
+        &gens,
+        &mut transcript
+      )
+      .is_err());
+  }
+}
@@ -0,0 +1,491 @@
+#![allow(clippy::too_many_arguments)]
+use super::commitments::{MSMAccumulator, MultiCommitGens};
+use super::dense_mlpoly::{
+  DensePolynomial, PolyCommitment, PolyCommitmentBlinds, PolyCommitmentGens, PolyEvalProof,
+};
+use super::errors::{ProofVerifyError, R1CSError};
+use super::random::RandomTape;
+use super::transcript::{AppendToTranscript, ProofTranscript};
+use ark_ec::ProjectiveCurve;
+use ark_ff::PrimeField;
+use ark_serialize::*;
+use ark_std::One;
+use core::cell::Cell;
+use core::fmt::Debug;
+use core::marker::PhantomData;
+use merlin::Transcript;
+
+/// `MultilinearPCS` is implemented by the commitment schemes for multilinear polynomials that
+/// proofs commit to witnesses and to the dense representation of sparse matrices with
+pub trait MultilinearPCS<G: ProjectiveCurve> {
+  /// Public parameters for committing to polynomials with up to a given number of variables
+  type Gens;
+  /// A commitment to a polynomial
+  type Commitment: Clone
+    + Debug
+    + PartialEq
+    + Eq
+    + CanonicalSerialize
+    + CanonicalDeserialize
+    + AppendToTranscript<G>;
+  /// The prover's randomness in a commitment
+  type Blinds: Clone;
+  /// A proof of the evaluation of a committed polynomial at a point
+  type EvalProof: Debug + CanonicalSerialize + CanonicalDeserialize;
+  /// A proof of the evaluations of several committed polynomials at the same point
+  type BatchEvalProof: Debug + CanonicalSerialize + CanonicalDeserialize;
+
+  /// Returns the largest number of variables of the polynomials `gens` can commit to, or `None`
+  /// if polynomials of any size can be committed
+  fn max_num_vars(_gens: &Self::Gens) -> Option<usize> {
+    None
+  }
+
+  /// Returns the generators of the Pedersen commitments to scalars that accompany proofs using
+  /// the scheme, such as the commitments to claims in the zero-knowledge sum-checks; schemes
+  /// whose public parameters do not fix them derive them from `label`
+  fn gens_eval(gens: &Self::Gens, label: &'static [u8]) -> MultiCommitGens<G>;
+
+  /// Commits to `poly`; the commitment is randomized with `random_tape` if one is given
+  fn commit(
+    poly: &DensePolynomial<G::ScalarField>,
+    gens: &Self::Gens,
+    random_tape: Option<&mut RandomTape<G>>,
+  ) -> (Self::Commitment, Self::Blinds);
+
+  /// Proves that `poly`, committed with `blinds`, evaluates to `eval` at `r`
+  fn prove(
+    poly: &DensePolynomial<G::ScalarField>,
+    blinds: Option<&Self::Blinds>,
+    r: &[G::ScalarField],
+    eval: &G::ScalarField,
+    gens: &Self::Gens,
+    transcript: &mut Transcript,
+    random_tape: &mut RandomTape<G>,
+  ) -> Self::EvalProof;
+
+  /// Verifies a proof that the polynomial committed in `comm` evaluates to `eval` at `r`;
+  /// schemes whose checks are multi-scalar multiplications over `G` may add them to `acc`
+  fn verify(
+    proof: &Self::EvalProof,
+    gens: &Self::Gens,
+    transcript: &mut Transcript,
+    r: &[G::ScalarField],
+    eval: &G::ScalarField,
+    comm: &Self::Commitment,
+    acc: &mut MSMAccumulator<G>,
+  ) -> Result<(), ProofVerifyError>;
+
+  /// Proves that each of `polys` evaluates to the corresponding entry of `evals` at `r`
+  fn prove_batched(
+    polys: &[&DensePolynomial<G::ScalarField>],
+    blinds: Option<&[&Self::Blinds]>,
+    r: &[G::ScalarField],
+    evals: &[G::ScalarField],
+    gens: &Self::Gens,
+    transcript: &mut Transcript,
+    random_tape: &mut RandomTape<G>,
+  ) -> Self::BatchEvalProof;
+
+  /// Verifies a proof that each polynomial committed in `comms` evaluates to the
+  /// corresponding entry of `evals` at `r`
+  fn verify_batched(
+    proof: &Self::BatchEvalProof,
+    gens: &Self::Gens,
+    transcript: &mut Transcript,
+    r: &[G::ScalarField],
+    evals: &[G::ScalarField],
+    comms: &[&Self::Commitment],
+    acc: &mut MSMAccumulator<G>,
+  ) -> Result<(), ProofVerifyError>;
+
+  /// Returns the commitments to the rows of the evaluations of the polynomial laid out as a
+  /// matrix, for schemes that commit to each row on its own; witnesses committed in phases
+  /// need them to bind the first phase
+  fn get_rows(_comm: &Self::Commitment) -> Option<&[G]> {
+    None
+  }
+}
+
+/// `TransparentMultilinearPCS` is implemented by the commitment schemes whose public parameters
+/// need no trusted setup and are derived from a label alone, which the constructors of
+/// generators that take no setup require
+pub trait TransparentMultilinearPCS<G: ProjectiveCurve>: MultilinearPCS<G> {
+  /// Produces the public parameters for polynomials with `num_vars` variables
+  fn setup(num_vars: usize, label: &'static [u8]) -> Self::Gens;
+}
+
+/// `HidingMultilinearPCS` is implemented by the commitment schemes that hide the committed
+/// polynomials and can prove an evaluation that is itself only given as a Pedersen commitment
+/// with the generators of `gens_eval`, as the zero-knowledge proofs require
+pub trait HidingMultilinearPCS<G: ProjectiveCurve>: MultilinearPCS<G> {
+  /// Proves that `poly`, committed with `blinds`, evaluates at `r` to `eval`, which the
+  /// verifier only gets as the returned Pedersen commitment with `blind_eval`
+  fn prove_hiding(
+    poly: &DensePolynomial<G::ScalarField>,
+    blinds: Option<&Self::Blinds>,
+    r: &[G::ScalarField],
+    eval: &G::ScalarField,
+    blind_eval: &G::ScalarField,
+    gens: &Self::Gens,
+    transcript: &mut Transcript,
+    random_tape: &mut RandomTape<G>,
+  ) -> (Self::EvalProof, G);
+
+  /// Verifies a proof that the polynomial committed in `comm` evaluates at `r` to the value
+  /// committed in `comm_eval`
+  fn verify_hiding(
+    proof: &Self::EvalProof,
+    gens: &Self::Gens,
+    transcript: &mut Transcript,
+    r: &[G::ScalarField],
+    comm_eval: &G,
+    comm: &Self::Commitment,
+    acc: &mut MSMAccumulator<G>,
+  ) -> Result<(), ProofVerifyError>;
+}
+
+// runs `f` with a setup that produces the public parameters with `gens` for at most
+// `max_num_vars` variables, and fails if any of them needs more
+pub(crate) fn with_bounded_setup<Gens, T, S, F>(
+  max_num_vars: usize,
+  gens: S,
+  f: F,
+) -> Result<T, R1CSError>
+where
+  S: Fn(usize) -> Gens,
+  F: FnOnce(&dyn Fn(usize, &'static [u8]) -> Gens) -> T,
+{
+  let is_too_large = Cell::new(false);
+  let setup = |num_vars: usize, _label: &'static [u8]| {
+    if num_vars > max_num_vars {
+      is_too_large.set(true);
+    }
+    gens(num_vars.min(max_num_vars))
+  };
+  let res = f(&setup);
+  if is_too_large.get() {
+    return Err(R1CSError::InvalidSetup);
+  }
+  Ok(res)
+}
+
+// checks that the public parameters of `PCS` can commit to polynomials with `num_vars`
+// variables, so that provers return an error instead of reaching the bounds of `commit`
+pub(crate) fn check_num_vars<G: ProjectiveCurve, PCS: MultilinearPCS<G>>(
+  gens: &PCS::Gens,
+  num_vars: usize,
+) -> Result<(), R1CSError> {
+  match PCS::max_num_vars(gens) {
+    Some(max_num_vars) if num_vars > max_num_vars => Err(R1CSError::InvalidSetup),
+    _ => Ok(()),
+  }
+}
+
+// folds the polynomials of a batched evaluation proof with the powers of a challenge that binds
+// their evaluations
+pub(crate) fn fold_challenge<G: ProjectiveCurve>(
+  protocol_name: &'static [u8],
+  evals: &[G::ScalarField],
+  transcript: &mut Transcript,
+) -> Vec<G::ScalarField> {
+  <Transcript as ProofTranscript<G>>::append_protocol_name(transcript, protocol_name);
+  <Transcript as ProofTranscript<G>>::append_scalars(transcript, b"evals", evals);
+  let rho = <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"challenge_fold");
+  let mut powers = vec![G::ScalarField::one(); evals.len()];
+  for i in 1..evals.len() {
+    powers[i] = powers[i - 1] * rho;
+  }
+  powers
+}
+
+// returns the sum of the polynomials weighted by the powers
+pub(crate) fn fold_polys<F: PrimeField>(
+  polys: &[&DensePolynomial<F>],
+  powers: &[F],
+) -> DensePolynomial<F> {
+  let mut poly = polys[0].clone();
+  for (p, power) in polys.iter().zip(powers.iter()).skip(1) {
+    poly = DensePolynomial::new(
+      (0..poly.len())
+        .map(|i| poly[i] + *power * p[i])
+        .collect::<Vec<_>>(),
+    );
+  }
+  poly
+}
+
+/// `Hyrax` is the default commitment scheme: a polynomial is laid out as a matrix whose rows
+/// are each committed with Pedersen, and evaluations are proven with a logarithmic-size inner
+/// product argument over the combined rows
+#[derive(Debug)]
+pub struct Hyrax<G> {
+  _group: PhantomData<G>,
+}
+
+impl<G: ProjectiveCurve> Hyrax<G> {
+  fn protocol_name() -> &'static [u8] {
+    b"Hyrax batched evaluation proof"
+  }
+}
+
+impl<G: ProjectiveCurve> TransparentMultilinearPCS<G> for Hyrax<G> {
+  fn setup(num_vars: usize, label: &'static [u8]) -> Self::Gens {
+    PolyCommitmentGens::new(num_vars, label)
+  }
+}
+
+impl<G: ProjectiveCurve> MultilinearPCS<G> for Hyrax<G> {
+  type Gens = PolyCommitmentGens<G>;
+  type Commitment = PolyCommitment<G>;
+  type Blinds = PolyCommitmentBlinds<G::ScalarField>;
+  type EvalProof = PolyEvalProof<G>;
+  type BatchEvalProof = PolyEvalProof<G>;
+
+  fn gens_eval(gens: &Self::Gens, _label: &'static [u8]) -> MultiCommitGens<G> {
+    gens.gens.gens_1.clone()
+  }
+
+  fn commit(
+    poly: &DensePolynomial<G::ScalarField>,
+    gens: &Self::Gens,
+    random_tape: Option<&mut RandomTape<G>>,
+  ) -> (Self::Commitment, Self::Blinds) {
+    poly.commit(gens, random_tape)
+  }
+
+  fn prove(
+    poly: &DensePolynomial<G::ScalarField>,
+    blinds: Option<&Self::Blinds>,
+    r: &[G::ScalarField],
+    eval: &G::ScalarField,
+    gens: &Self::Gens,
+    transcript: &mut Transcript,
+    random_tape: &mut RandomTape<G>,
+  ) -> Self::EvalProof {
+    let (proof, _comm_eval) =
+      PolyEvalProof::prove(poly, blinds, r, eval, None, gens, transcript, random_tape);
+    proof
+  }
+
+  fn verify(
+    proof: &Self::EvalProof,
+    gens: &Self::Gens,
+    transcript: &mut Transcript,
+    r: &[G::ScalarField],
+    eval: &G::ScalarField,
+    comm: &Self::Commitment,
+    acc: &mut MSMAccumulator<G>,
+  ) -> Result<(), ProofVerifyError> {
+    proof.verify_plain_deferred(gens, transcript, r, eval, comm, acc)
+  }
+
+  fn prove_batched(
+    polys: &[&DensePolynomial<G::ScalarField>],
+    blinds: Option<&[&Self::Blinds]>,
+    r: &[G::ScalarField],
+    evals: &[G::ScalarField],
+    gens: &Self::Gens,
+    transcript: &mut Transcript,
+    random_tape: &mut RandomTape<G>,
+  ) -> Self::BatchEvalProof {
+    assert!(!polys.is_empty());
+    assert_eq!(polys.len(), evals.len());
+    let powers = fold_challenge::<G>(Hyrax::<G>::protocol_name(), evals, transcript);
+
+    // the commitments are homomorphic, so the folded polynomial is opened against the
+    // commitments folded with the same powers
+    let poly = fold_polys(polys, &powers);
+    let blinds = blinds.map(|blinds| {
+      assert_eq!(blinds.len(), polys.len());
+      let mut folded = blinds[0].clone();
+      for (b, power) in blinds.iter().zip(powers.iter()).skip(1) {
+        folded = folded.fold(b, power);
+      }
+      folded
+    });
+    let eval = (0..evals.len()).map(|i| powers[i] * evals[i]).sum();
+    Hyrax::prove(
+      &poly,
+      blinds.as_ref(),
+      r,
+      &eval,
+      gens,
+      transcript,
+      random_tape,
+    )
+  }
+
+  fn verify_batched(
+    proof: &Self::BatchEvalProof,
+    gens: &Self::Gens,
+    transcript: &mut Transcript,
+    r: &[G::ScalarField],
+    evals: &[G::ScalarField],
+    comms: &[&Self::Commitment],
+    acc: &mut MSMAccumulator<G>,
+  ) -> Result<(), ProofVerifyError> {
+    if comms.is_empty()
+      || comms.len() != evals.len()
+      || comms.iter().any(|c| c.num_rows() != comms[0].num_rows())
+    {
+      return Err(ProofVerifyError::InternalError);
+    }
+    let powers = fold_challenge::<G>(Hyrax::<G>::protocol_name(), evals, transcript);
+    let mut comm = comms[0].clone();
+    for (c, power) in comms.iter().zip(powers.iter()).skip(1) {
+      comm = comm.fold(c, power);
+    }
+    let eval = (0..evals.len()).map(|i| powers[i] * evals[i]).sum();
+    Hyrax::verify(proof, gens, transcript, r, &eval, &comm, acc)
+  }
+
+  fn get_rows(comm: &Self::Commitment) -> Option<&[G]> {
+    Some(comm.get_rows())
+  }
+}
+
+impl<G: ProjectiveCurve> HidingMultilinearPCS<G> for Hyrax<G> {
+  fn prove_hiding(
+    poly: &DensePolynomial<G::ScalarField>,
+    blinds: Option<&Self::Blinds>,
+    r: &[G::ScalarField],
+    eval: &G::ScalarField,
+    blind_eval: &G::ScalarField,
+    gens: &Self::Gens,
+    transcript: &mut Transcript,
+    random_tape: &mut RandomTape<G>,
+  ) -> (Self::EvalProof, G) {
+    PolyEvalProof::prove(
+      poly,
+      blinds,
+      r,
+      eval,
+      Some(blind_eval),
+      gens,
+      transcript,
+      random_tape,
+    )
+  }
+
+  fn verify_hiding(
+    proof: &Self::EvalProof,
+    gens: &Self::Gens,
+    transcript: &mut Transcript,
+    r: &[G::ScalarField],
+    comm_eval: &G,
+    comm: &Self::Commitment,
+    acc: &mut MSMAccumulator<G>,
+  ) -> Result<(), ProofVerifyError> {
+    proof.verify_deferred(gens, transcript, r, comm_eval, comm, acc)
+  }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+  use super::*;
+  use ark_std::{test_rng, UniformRand};
+
+  crate::curves::curve_tests!(
+    group: check_hyrax_batched_eval_proof,
+    check_hyrax_batched_eval_proof_helper
+  );
+
+  fn check_hyrax_batched_eval_proof_helper<G: ProjectiveCurve>() {
+    let gens = PolyCommitmentGens::<G>::new(5, b"test_gens");
+    check_batched_eval_proof_helper::<G, Hyrax<G>>(5, &gens)
+  }
+
+  pub(crate) fn check_batched_eval_proof_helper<G, PCS>(num_vars: usize, gens: &PCS::Gens)
+  where
+    G: ProjectiveCurve,
+    PCS: MultilinearPCS<G>,
+  {
+    let mut rng = test_rng();
+    let polys = (0..3)
+      .map(|_| {
+        DensePolynomial::new(
+          (0..1 << num_vars)
+            .map(|_| G::ScalarField::rand(&mut rng))
+            .collect::<Vec<_>>(),
+        )
+      })
+      .collect::<Vec<_>>();
+    let mut random_tape = RandomTape::new(b"proof");
+    let (comms, blinds): (Vec<_>, Vec<_>) = polys
+      .iter()
+      .map(|poly| PCS::commit(poly, gens, Some(&mut random_tape)))
+      .unzip();
+    let r = (0..num_vars)
+      .map(|_| G::ScalarField::rand(&mut rng))
+      .collect::<Vec<_>>();
+    let evals = polys
+      .iter()
+      .map(|poly| poly.evaluate::<G>(&r))
+      .collect::<Vec<_>>();
+
+    // a single evaluation
+    let proof = PCS::prove(
+      &polys[0],
+      Some(&blinds[0]),
+      &r,
+      &evals[0],
+      gens,
+      &mut Transcript::new(b"example"),
+      &mut random_tape,
+    );
+    let mut acc = MSMAccumulator::new();
+    assert!(PCS::verify(
+      &proof,
+      gens,
+      &mut Transcript::new(b"example"),
+      &r,
+      &evals[0],
+      &comms[0],
+      &mut acc
+    )
+    .is_ok());
+    assert!(acc.check().is_ok());
+
+    // all evaluations at once
+    let proof = PCS::prove_batched(
+      &polys.iter().collect::<Vec<_>>(),
+      Some(&blinds.iter().collect::<Vec<_>>()),
+      &r,
+      &evals,
+      gens,
+      &mut Transcript::new(b"example"),
+      &mut random_tape,
+    );
+    let comms = comms.iter().collect::<Vec<_>>();
+    let mut acc = MSMAccumulator::new();
+    assert!(PCS::verify_batched(
+      &proof,
+      gens,
+      &mut Transcript::new(b"example"),
+      &r,
+      &evals,
+      &comms,
+      &mut acc
+    )
+    .is_ok());
+    assert!(acc.check().is_ok());
+
+    // the proof does not verify for other evaluations
+    let mut wrong_evals = evals.clone();
+    wrong_evals[1] += G::ScalarField::one();
+    let mut acc = MSMAccumulator::new();
+    let is_valid = PCS::verify_batched(
+      &proof,
+      gens,
+      &mut Transcript::new(b"example"),
+      &r,
+      &wrong_evals,
+      &comms,
+      &mut acc,
+    )
+    .is_ok()
+      && acc.check().is_ok();
+    assert!(!is_valid);
+  }
+}
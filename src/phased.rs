@@ -0,0 +1,464 @@
+use super::commitments::Commitments;
+use super::dense_mlpoly::{EqPolynomial, PolyCommitmentGens};
+use super::errors::{ProofVerifyError, R1CSError};
+use super::math::Math;
+use super::pcs::{HidingMultilinearPCS, MultilinearPCS};
+use super::r1csinstance::R1CSInstance;
+use super::random::RandomTape;
+use super::timer::Timer;
+use super::transcript::{AppendToTranscript, ProofTranscript};
+use super::{
+  ComputationCommitment, ComputationDecommitment, InputsAssignment, Instance, NIZKGens, SNARKGens,
+  VarsAssignment, NIZK, SNARK,
+};
+use ark_ec::ProjectiveCurve;
+use ark_ff::PrimeField;
+use merlin::Transcript;
+
+/// `Phases` splits the witness of an instance in two: the variables `0..num_vars_phase_one`
+/// are committed first, then `num_challenges` challenges are derived from the transcript and
+/// assigned to the last inputs, and only then are the remaining variables computed
+#[derive(Clone, Debug)]
+pub struct Phases {
+  num_vars_phase_one: usize,
+  num_vars_phase_two: usize,
+  num_challenges: usize,
+}
+
+impl Phases {
+  // the phase-one variables must fill whole rows of the commitment to the witness, so that
+  // they can be committed on their own; the phase-two variables are the rest of the `num_vars`
+  // variables of the instance before padding
+  pub fn new<F: PrimeField>(
+    num_vars_phase_one: usize,
+    num_challenges: usize,
+    num_vars: usize,
+    inst: &R1CSInstance<F>,
+  ) -> Result<Self, R1CSError> {
+    let (_left, right) = EqPolynomial::<F>::compute_factored_lens(inst.get_num_vars().log_2());
+    if num_vars_phase_one == 0
+      || num_vars_phase_one >= num_vars
+      || num_vars_phase_one % right.pow2() != 0
+      || num_challenges == 0
+      || num_challenges > inst.get_num_inputs()
+    {
+      return Err(R1CSError::InvalidPhases);
+    }
+    Ok(Phases {
+      num_vars_phase_one,
+      num_vars_phase_two: num_vars - num_vars_phase_one,
+      num_challenges,
+    })
+  }
+
+  // appends the variables computed in phase two, of which there must be as many as the
+  // instance declares, to those of phase one
+  fn assign<F: PrimeField>(
+    &self,
+    vars_phase_one: VarsAssignment<F>,
+    vars_phase_two: VarsAssignment<F>,
+  ) -> Result<VarsAssignment<F>, R1CSError> {
+    if vars_phase_two.assignment.len() != self.num_vars_phase_two {
+      return Err(R1CSError::InvalidPhases);
+    }
+    Ok(VarsAssignment {
+      assignment: [vars_phase_one.assignment, vars_phase_two.assignment].concat(),
+    })
+  }
+
+  fn protocol_name() -> &'static [u8] {
+    b"Spartan phased witness"
+  }
+
+  // commits to the phase-one variables and derives the challenges from the commitment and the
+  // inputs chosen before them; returns the blinds of the rows and the challenges
+  #[allow(clippy::type_complexity)]
+  fn prove_phase_one<G: ProjectiveCurve>(
+    &self,
+    vars: &[G::ScalarField],
+    input: &[G::ScalarField],
+    gens: &PolyCommitmentGens<G>,
+    transcript: &mut Transcript,
+  ) -> Result<(Vec<G::ScalarField>, Vec<G::ScalarField>), R1CSError> {
+    // generators with a layout other than the near-square one checked by `new` may have
+    // longer rows, which the phase-one variables must fill as well
+    let row_len = gens.gens.gens_n.n;
+    if vars.len() != self.num_vars_phase_one || vars.len() % row_len != 0 {
+      return Err(R1CSError::InvalidPhases);
+    }
+    let mut random_tape = RandomTape::<G>::new(b"phase_one");
+    let blinds = random_tape.random_vector(b"poly_blinds", vars.len() / row_len);
+    let rows = vars
+      .chunks(row_len)
+      .zip(blinds.iter())
+      .map(|(row, blind)| Commitments::batch_commit(row, blind, &gens.gens.gens_n))
+      .collect::<Vec<G>>();
+    let challenges = self.challenges(&rows, input, transcript);
+    Ok((blinds, challenges))
+  }
+
+  // re-derives the challenges from the first rows of the commitment to the witness in a proof,
+  // which are only available with commitment schemes that commit to each row on its own
+  fn verify_phase_one<G: ProjectiveCurve, PCS: MultilinearPCS<G>>(
+    &self,
+    comm_vars: &PCS::Commitment,
+    input: &[G::ScalarField],
+    num_inputs: usize,
+    num_vars: usize,
+    transcript: &mut Transcript,
+  ) -> Result<Vec<G::ScalarField>, ProofVerifyError> {
+    let rows = PCS::get_rows(comm_vars).ok_or(ProofVerifyError::InternalError)?;
+    if rows.is_empty() || num_vars % rows.len() != 0 {
+      return Err(ProofVerifyError::InternalError);
+    }
+    let row_len = num_vars / rows.len();
+    let num_rows = self.num_vars_phase_one / row_len;
+    if input.len() + self.num_challenges != num_inputs
+      || self.num_vars_phase_one % row_len != 0
+      || rows.len() < num_rows
+    {
+      return Err(ProofVerifyError::InternalError);
+    }
+    Ok(self.challenges(&rows[..num_rows], input, transcript))
+  }
+
+  fn challenges<G: ProjectiveCurve>(
+    &self,
+    rows: &[G],
+    input: &[G::ScalarField],
+    transcript: &mut Transcript,
+  ) -> Vec<G::ScalarField> {
+    <Transcript as ProofTranscript<G>>::append_protocol_name(transcript, Phases::protocol_name());
+    <Transcript as ProofTranscript<G>>::append_scalars(transcript, b"input", input);
+    for row in rows {
+      <Transcript as ProofTranscript<G>>::append_point(transcript, b"comm_phase_one", row);
+    }
+    <Transcript as ProofTranscript<G>>::challenge_vector(
+      transcript,
+      b"challenge_phase_two",
+      self.num_challenges,
+    )
+  }
+}
+
+// appends the challenges to the inputs chosen before them
+fn with_challenges<F: PrimeField>(input: &[F], challenges: &[F]) -> InputsAssignment<F> {
+  InputsAssignment {
+    assignment: [input, challenges].concat(),
+  }
+}
+
+impl<F: PrimeField> Instance<F> {
+  /// Splits the witness in two phases: the variables `0..num_vars_phase_one` are committed
+  /// first, after which `num_challenges` verifier challenges are derived and assigned to the
+  /// last inputs, so that the remaining variables can depend on them. The phase-one variables
+  /// must fill whole rows of the commitment to the witness, i.e., `num_vars_phase_one` must
+  /// be a multiple of `2^ceil(log2(n) / 2)` for the padded number of variables `n`, or of the
+  /// row length of generators constructed with another ratio, such as `SNARKGens::new_with_ratio`.
+  /// Such instances are proven with `SNARK::prove_phased` or `NIZK::prove_phased`, which fail
+  /// with `R1CSError::InvalidPhases` if the rows of the generators are longer.
+  pub fn add_challenges(
+    &mut self,
+    num_vars_phase_one: usize,
+    num_challenges: usize,
+  ) -> Result<(), R1CSError> {
+    if self.phases.is_some() {
+      return Err(R1CSError::InvalidPhases);
+    }
+    self.phases = Some(Phases::new(
+      num_vars_phase_one,
+      num_challenges,
+      self.num_vars,
+      &self.inst,
+    )?);
+    Ok(())
+  }
+}
+
+impl<G: ProjectiveCurve> SNARK<G> {
+  /// A method to produce a SNARK proof of the satisfiability of an R1CS instance with a
+  /// two-phase witness: `vars_phase_one` is committed before the challenges are derived,
+  /// and `phase_two` computes the remaining variables from the challenges. `inputs` holds
+  /// the inputs other than the challenges, which the verifier derives on its own. Returns
+  /// `R1CSError::InvalidPhases` if the instance has no phases or `phase_two` does not return
+  /// all of the remaining variables.
+  #[allow(clippy::too_many_arguments)]
+  pub fn prove_phased<P>(
+    inst: &Instance<G::ScalarField>,
+    comm: &ComputationCommitment<G>,
+    decomm: &ComputationDecommitment<G::ScalarField>,
+    vars_phase_one: VarsAssignment<G::ScalarField>,
+    inputs: &InputsAssignment<G::ScalarField>,
+    phase_two: P,
+    gens: &SNARKGens<G>,
+    transcript: &mut Transcript,
+  ) -> Result<Self, R1CSError>
+  where
+    P: FnOnce(&[G::ScalarField]) -> VarsAssignment<G::ScalarField>,
+  {
+    let timer_prove = Timer::new("SNARK::prove_phased");
+    let phases = inst.phases.as_ref().ok_or(R1CSError::InvalidPhases)?;
+    comm.comm.append_to_transcript(b"comm", transcript);
+    let (blinds, challenges) = phases.prove_phase_one(
+      &vars_phase_one.assignment,
+      &inputs.assignment,
+      &gens.gens_r1cs_sat.gens_pc,
+      transcript,
+    )?;
+    let vars = phases.assign(vars_phase_one, phase_two(&challenges))?;
+
+    let (proof, _witness) = SNARK::prove_with_fixed_blinds(
+      inst,
+      comm,
+      decomm,
+      vars,
+      &with_challenges(&inputs.assignment, &challenges),
+      &[(0, &blinds)],
+      gens,
+      transcript,
+    )?;
+    timer_prove.stop();
+    Ok(proof)
+  }
+}
+
+impl<G: ProjectiveCurve, PCS: HidingMultilinearPCS<G>> SNARK<G, PCS> {
+  // derives the challenges of an instance with a two-phase witness and appends them to the
+  // inputs; other instances keep their inputs
+  pub(crate) fn verify_phases(
+    &self,
+    comm: &ComputationCommitment<G, PCS>,
+    input: &InputsAssignment<G::ScalarField>,
+    transcript: &mut Transcript,
+  ) -> Result<InputsAssignment<G::ScalarField>, ProofVerifyError> {
+    match &comm.phases {
+      Some(phases) => {
+        comm.comm.append_to_transcript(b"comm", transcript);
+        let challenges = phases.verify_phase_one::<G, PCS>(
+          self.r1cs_sat_proof.get_comm_vars(),
+          &input.assignment,
+          comm.comm.get_num_inputs(),
+          comm.comm.get_num_vars(),
+          transcript,
+        )?;
+        Ok(with_challenges(&input.assignment, &challenges))
+      }
+      None => Ok(input.clone()),
+    }
+  }
+}
+
+impl<G: ProjectiveCurve> NIZK<G> {
+  /// A method to produce a NIZK proof of the satisfiability of an R1CS instance with a
+  /// two-phase witness: `vars_phase_one` is committed before the challenges are derived,
+  /// and `phase_two` computes the remaining variables from the challenges. `input` holds
+  /// the inputs other than the challenges, which the verifier derives on its own. Returns
+  /// `R1CSError::InvalidPhases` if the instance has no phases or `phase_two` does not return
+  /// all of the remaining variables.
+  pub fn prove_phased<P>(
+    inst: &Instance<G::ScalarField>,
+    vars_phase_one: VarsAssignment<G::ScalarField>,
+    input: &InputsAssignment<G::ScalarField>,
+    phase_two: P,
+    gens: &NIZKGens<G>,
+    transcript: &mut Transcript,
+  ) -> Result<Self, R1CSError>
+  where
+    P: FnOnce(&[G::ScalarField]) -> VarsAssignment<G::ScalarField>,
+  {
+    let timer_prove = Timer::new("NIZK::prove_phased");
+    let phases = inst.phases.as_ref().ok_or(R1CSError::InvalidPhases)?;
+    <R1CSInstance<G::ScalarField> as AppendToTranscript<G>>::append_to_transcript(
+      &inst.inst, b"inst", transcript,
+    );
+    let (blinds, challenges) = phases.prove_phase_one(
+      &vars_phase_one.assignment,
+      &input.assignment,
+      &gens.gens_r1cs_sat.gens_pc,
+      transcript,
+    )?;
+    let vars = phases.assign(vars_phase_one, phase_two(&challenges))?;
+
+    let (proof, _witness) = NIZK::prove_with_fixed_blinds(
+      inst,
+      vars,
+      &with_challenges(&input.assignment, &challenges),
+      &[(0, &blinds)],
+      &[],
+      gens,
+      transcript,
+    )?;
+    timer_prove.stop();
+    Ok(proof)
+  }
+}
+
+impl<G: ProjectiveCurve, PCS: HidingMultilinearPCS<G>> NIZK<G, PCS> {
+  // derives the challenges of an instance with a two-phase witness and appends them to the
+  // inputs; other instances keep their inputs
+  pub(crate) fn verify_phases(
+    &self,
+    inst: &Instance<G::ScalarField>,
+    input: &InputsAssignment<G::ScalarField>,
+    transcript: &mut Transcript,
+  ) -> Result<InputsAssignment<G::ScalarField>, ProofVerifyError> {
+    match &inst.phases {
+      Some(phases) => {
+        <R1CSInstance<G::ScalarField> as AppendToTranscript<G>>::append_to_transcript(
+          &inst.inst, b"inst", transcript,
+        );
+        let challenges = phases.verify_phase_one::<G, PCS>(
+          self.r1cs_sat_proof.get_comm_vars(),
+          &input.assignment,
+          inst.inst.get_num_inputs(),
+          inst.inst.get_num_vars(),
+          transcript,
+        )?;
+        Ok(with_challenges(&input.assignment, &challenges))
+      }
+      None => Ok(input.clone()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ark_std::One;
+
+  // the variables 0..4 are a permutation of the variables 4..8, checked by comparing the
+  // products of (a_i + gamma) and (b_i + gamma) for a challenge gamma assigned to the input
+  fn permutation<F: PrimeField>() -> Instance<F> {
+    let (num_cons, num_vars, num_inputs) = (7, 14, 1);
+    let one = F::one();
+    let gamma = num_vars + 1;
+    let (mut A, mut B, mut C) = (Vec::new(), Vec::new(), Vec::new());
+    for (offset, prod, row) in [(0, 8, 0), (4, 11, 3)] {
+      // (x_0 + gamma) * (x_1 + gamma) = p_1, and p_{i-1} * (x_i + gamma) = p_i
+      A.extend([(row, offset, one), (row, gamma, one)]);
+      B.extend([(row, offset + 1, one), (row, gamma, one)]);
+      C.push((row, prod, one));
+      for i in 1..3 {
+        A.push((row + i, prod + i - 1, one));
+        B.extend([(row + i, offset + i + 1, one), (row + i, gamma, one)]);
+        C.push((row + i, prod + i, one));
+      }
+    }
+    A.push((6, 10, one));
+    B.push((6, num_vars, one));
+    C.push((6, 13, one));
+    let mut inst = Instance::new(num_cons, num_vars, num_inputs, &A, &B, &C).unwrap();
+    assert!(inst.add_challenges(6, 1).is_err());
+    inst.add_challenges(8, 1).unwrap();
+    inst
+  }
+
+  // the running products of both halves of the phase-one variables
+  fn products<F: PrimeField>(vars: &[F], gamma: F) -> VarsAssignment<F> {
+    let mut products = Vec::new();
+    for half in vars.chunks(4) {
+      let mut prod = half[0] + gamma;
+      for x in &half[1..] {
+        prod *= *x + gamma;
+        products.push(prod);
+      }
+    }
+    VarsAssignment::new(&products).unwrap()
+  }
+
+  crate::curves::curve_tests!(group: check_phased_snark, check_phased_snark_helper);
+
+  fn check_phased_snark_helper<G: ProjectiveCurve>() {
+    let inst = permutation::<G::ScalarField>();
+    let gens = SNARKGens::<G>::new(7, 16, 1, 13);
+    let (comm, decomm) = SNARK::encode(&inst, &gens);
+    let inputs = InputsAssignment::new(&[]).unwrap();
+
+    let prove = |vars: &[u64]| {
+      let vars = vars
+        .iter()
+        .map(|v| G::ScalarField::from(*v))
+        .collect::<Vec<_>>();
+      let mut prover_transcript = Transcript::new(b"example");
+      SNARK::prove_phased(
+        &inst,
+        &comm,
+        &decomm,
+        VarsAssignment::new(&vars).unwrap(),
+        &inputs,
+        |challenges| products(&vars, challenges[0]),
+        &gens,
+        &mut prover_transcript,
+      )
+      .unwrap()
+    };
+
+    let proof = prove(&[3, 1, 4, 1, 1, 4, 1, 3]);
+    let mut verifier_transcript = Transcript::new(b"example");
+    assert!(proof
+      .verify(&comm, &inputs, &mut verifier_transcript, &gens)
+      .is_ok());
+
+    // the products differ for the challenge unless the halves are permutations of each other
+    let proof = prove(&[3, 1, 4, 1, 1, 5, 1, 3]);
+    let mut verifier_transcript = Transcript::new(b"example");
+    assert!(proof
+      .verify(&comm, &inputs, &mut verifier_transcript, &gens)
+      .is_err());
+
+    // the instance is only proven in two phases
+    let vars = vec![G::ScalarField::one(); 8];
+    let mut prover_transcript = Transcript::new(b"example");
+    assert!(matches!(
+      SNARK::prove(
+        &inst,
+        &comm,
+        &decomm,
+        VarsAssignment::new(&vars).unwrap(),
+        &inputs,
+        &gens,
+        &mut prover_transcript,
+      ),
+      Err(R1CSError::InvalidPhases)
+    ));
+
+    // phase two must assign all of the remaining variables
+    let mut prover_transcript = Transcript::new(b"example");
+    assert!(matches!(
+      SNARK::prove_phased(
+        &inst,
+        &comm,
+        &decomm,
+        VarsAssignment::new(&vars).unwrap(),
+        &inputs,
+        |challenges| VarsAssignment::new(&[challenges[0]]).unwrap(),
+        &gens,
+        &mut prover_transcript,
+      ),
+      Err(R1CSError::InvalidPhases)
+    ));
+  }
+
+  crate::curves::curve_tests!(group: check_phased_long_rows, check_phased_long_rows_helper);
+
+  fn check_phased_long_rows_helper<G: ProjectiveCurve>() {
+    // a single row of 16 variables, which the 8 phase-one variables do not fill
+    let inst = permutation::<G::ScalarField>();
+    let gens = NIZKGens::<G>::new_with_ratio(7, 16, 1, -4);
+    let vars = [3u64, 1, 4, 1, 1, 4, 1, 3]
+      .iter()
+      .map(|v| G::ScalarField::from(*v))
+      .collect::<Vec<_>>();
+    assert!(matches!(
+      NIZK::prove_phased(
+        &inst,
+        VarsAssignment::new(&vars).unwrap(),
+        &InputsAssignment::new(&[]).unwrap(),
+        |challenges| products(&vars, challenges[0]),
+        &gens,
+        &mut Transcript::new(b"example"),
+      ),
+      Err(R1CSError::InvalidPhases)
+    ));
+  }
+}
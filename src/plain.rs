@@ -0,0 +1,622 @@
+#![allow(clippy::too_many_arguments)]
+use super::commitments::MSMAccumulator;
+use super::dense_mlpoly::DensePolynomial;
+use super::errors::{ProofVerifyError, R1CSError};
+use super::math::Math;
+use super::pcs::{check_num_vars, Hyrax, MultilinearPCS};
+use super::r1csinstance::{R1CSEvalProof, R1CSInstance};
+use super::r1csproof::{assignment_z, combined_eval_table, input_eval, phase_one_tables, R1CSGens};
+use super::random::RandomTape;
+use super::sumcheck::SumcheckInstanceProof;
+use super::timer::Timer;
+use super::transcript::{AppendToTranscript, ProofTranscript};
+use super::{
+  append_inst_evals, append_statement, start_proof, ComputationCommitment, ComputationDecommitment,
+  InputsAssignment, Instance, NIZKGens, SNARKGens, VarsAssignment,
+};
+use ark_ec::ProjectiveCurve;
+use ark_serialize::*;
+use ark_std::{One, Zero};
+use core::slice;
+use merlin::Transcript;
+
+/// `R1CSPlainProof` is the counterpart of `R1CSProof` that does not hide the witness: both
+/// sum-checks send their round polynomials in the clear, the claimed evaluations of Az, Bz,
+/// Cz and of the witness are sent as scalars, and the witness is committed with zero blinds.
+/// It runs the sum-checks over the same evaluation tables and challenges as `R1CSProof`
+#[derive(CanonicalSerialize, CanonicalDeserialize, Debug)]
+pub struct R1CSPlainProof<G: ProjectiveCurve, PCS: MultilinearPCS<G> = Hyrax<G>> {
+  comm_vars: PCS::Commitment,
+  sc_proof_phase1: SumcheckInstanceProof<G::ScalarField>,
+  claims_phase2: (G::ScalarField, G::ScalarField, G::ScalarField),
+  sc_proof_phase2: SumcheckInstanceProof<G::ScalarField>,
+  eval_vars_at_ry: G::ScalarField,
+  proof_eval_vars_at_ry: PCS::EvalProof,
+}
+
+impl<G: ProjectiveCurve, PCS: MultilinearPCS<G>> R1CSPlainProof<G, PCS> {
+  fn protocol_name() -> &'static [u8] {
+    b"R1CS plain proof"
+  }
+
+  pub fn prove(
+    inst: &R1CSInstance<G::ScalarField>,
+    vars: Vec<G::ScalarField>,
+    input: &[G::ScalarField],
+    gens: &R1CSGens<G, PCS>,
+    transcript: &mut Transcript,
+    random_tape: &mut RandomTape<G>,
+  ) -> (Self, Vec<G::ScalarField>, Vec<G::ScalarField>) {
+    let timer_prove = Timer::new("R1CSPlainProof::prove");
+    <Transcript as ProofTranscript<G>>::append_protocol_name(
+      transcript,
+      R1CSPlainProof::<G, PCS>::protocol_name(),
+    );
+
+    // we currently require the number of |inputs| + 1 to be at most number of vars
+    let num_vars = vars.len();
+    assert!(input.len() < num_vars);
+    <Transcript as ProofTranscript<G>>::append_scalars(transcript, b"input", input);
+
+    let timer_commit = Timer::new("polycommit");
+    let (poly_vars, comm_vars) = {
+      // the commitment only needs to be binding, so it is made without blinds
+      let poly_vars = DensePolynomial::<G::ScalarField>::new(vars.clone());
+      let (comm_vars, _blinds_vars) = PCS::commit(&poly_vars, &gens.gens_pc, None);
+      comm_vars.append_to_transcript(b"poly_commitment", transcript);
+      (poly_vars, comm_vars)
+    };
+    timer_commit.stop();
+
+    let timer_sc_proof_phase1 = Timer::new("prove_sc_phase_one");
+
+    // append input to variables to create a single vector z
+    let z = assignment_z(vars, input);
+
+    // derive the verifier's challenge tau
+    let (num_rounds_x, num_rounds_y) = (inst.get_num_cons().log_2(), z.len().log_2());
+    let tau = <Transcript as ProofTranscript<G>>::challenge_vector(
+      transcript,
+      b"challenge_tau",
+      num_rounds_x,
+    );
+
+    // compute the initial evaluation tables for eq(\tau, x), Az, Bz, and Cz
+    let mut polys = phase_one_tables(&[inst], slice::from_ref(&z), tau);
+    let (poly_tau, poly_Az, poly_Bz, poly_Cz) = match &mut polys[..] {
+      [poly_tau, poly_Az, poly_Bz, poly_Cz] => (poly_tau, poly_Az, poly_Bz, poly_Cz),
+      _ => unreachable!("an R1CS instance has three matrices"),
+    };
+
+    let comb_func_phase1 =
+      |poly_A_comp: &G::ScalarField,
+       poly_B_comp: &G::ScalarField,
+       poly_C_comp: &G::ScalarField,
+       poly_D_comp: &G::ScalarField|
+       -> G::ScalarField { *poly_A_comp * (*poly_B_comp * *poly_C_comp - *poly_D_comp) };
+    let (sc_proof_phase1, rx, claims_phase1) =
+      SumcheckInstanceProof::prove_cubic_with_additive_term::<_, G>(
+        &G::ScalarField::zero(), // claim is zero
+        num_rounds_x,
+        poly_tau,
+        poly_Az,
+        poly_Bz,
+        poly_Cz,
+        comb_func_phase1,
+        transcript,
+      );
+    timer_sc_proof_phase1.stop();
+
+    // the claimed evaluations of Az, Bz, and Cz at rx are sent in the clear
+    let Mz_claims = &claims_phase1[1..];
+    <Transcript as ProofTranscript<G>>::append_scalars(transcript, b"Mz_claims", Mz_claims);
+
+    let timer_sc_proof_phase2 = Timer::new("prove_sc_phase_two");
+    // combine the three claims into a single claim
+    let r_M = <Transcript as ProofTranscript<G>>::challenge_vector(
+      transcript,
+      b"challenge_Mz",
+      Mz_claims.len(),
+    );
+    let claim_phase2 = (0..r_M.len()).map(|i| r_M[i] * Mz_claims[i]).sum();
+    let evals_M = combined_eval_table(inst, &rx, &r_M, num_vars);
+
+    // another instance of the sum-check protocol
+    let comb_func_phase2 = |poly_A_comp: &G::ScalarField,
+                            poly_B_comp: &G::ScalarField|
+     -> G::ScalarField { *poly_A_comp * *poly_B_comp };
+    let (sc_proof_phase2, ry, _claims_phase2) = SumcheckInstanceProof::prove_quad::<_, G>(
+      &claim_phase2,
+      num_rounds_y,
+      &mut DensePolynomial::new(z),
+      &mut DensePolynomial::new(evals_M),
+      comb_func_phase2,
+      transcript,
+    );
+    timer_sc_proof_phase2.stop();
+
+    let timer_polyeval = Timer::new("polyeval");
+    let eval_vars_at_ry = poly_vars.evaluate::<G>(&ry[1..]);
+    <Transcript as ProofTranscript<G>>::append_scalar(
+      transcript,
+      b"eval_vars_at_ry",
+      &eval_vars_at_ry,
+    );
+    let proof_eval_vars_at_ry = PCS::prove(
+      &poly_vars,
+      None,
+      &ry[1..],
+      &eval_vars_at_ry,
+      &gens.gens_pc,
+      transcript,
+      random_tape,
+    );
+    timer_polyeval.stop();
+
+    timer_prove.stop();
+    (
+      R1CSPlainProof {
+        comm_vars,
+        sc_proof_phase1,
+        claims_phase2: (Mz_claims[0], Mz_claims[1], Mz_claims[2]),
+        sc_proof_phase2,
+        eval_vars_at_ry,
+        proof_eval_vars_at_ry,
+      },
+      rx,
+      ry,
+    )
+  }
+
+  #[allow(clippy::type_complexity)]
+  pub fn verify(
+    &self,
+    num_vars: usize,
+    num_cons: usize,
+    input: &[G::ScalarField],
+    evals: &(G::ScalarField, G::ScalarField, G::ScalarField),
+    transcript: &mut Transcript,
+    gens: &R1CSGens<G, PCS>,
+  ) -> Result<(Vec<G::ScalarField>, Vec<G::ScalarField>), ProofVerifyError> {
+    <Transcript as ProofTranscript<G>>::append_protocol_name(
+      transcript,
+      R1CSPlainProof::<G, PCS>::protocol_name(),
+    );
+
+    if input.len() >= num_vars {
+      return Err(ProofVerifyError::InternalError);
+    }
+    <Transcript as ProofTranscript<G>>::append_scalars(transcript, b"input", input);
+
+    // add the commitment to the verifier's transcript
+    self
+      .comm_vars
+      .append_to_transcript(b"poly_commitment", transcript);
+
+    let (num_rounds_x, num_rounds_y) = (num_cons.log_2(), (2 * num_vars).log_2());
+
+    // derive the verifier's challenge tau
+    let tau = <Transcript as ProofTranscript<G>>::challenge_vector(
+      transcript,
+      b"challenge_tau",
+      num_rounds_x,
+    );
+
+    // verify the first sum-check instance
+    let (claim_post_phase1, rx) =
+      self
+        .sc_proof_phase1
+        .verify::<G>(G::ScalarField::zero(), num_rounds_x, 3, transcript)?;
+
+    // perform the intermediate sum-check test with claimed Az, Bz, and Cz
+    let (Az_claim, Bz_claim, Cz_claim) = &self.claims_phase2;
+    let taus_bound_rx: G::ScalarField = (0..rx.len())
+      .map(|i| rx[i] * tau[i] + (G::ScalarField::one() - rx[i]) * (G::ScalarField::one() - tau[i]))
+      .product();
+    if claim_post_phase1 != taus_bound_rx * (*Az_claim * *Bz_claim - *Cz_claim) {
+      return Err(ProofVerifyError::InternalError);
+    }
+    let Mz_claims = [*Az_claim, *Bz_claim, *Cz_claim];
+    <Transcript as ProofTranscript<G>>::append_scalars(transcript, b"Mz_claims", &Mz_claims);
+
+    // derive three public challenges and then derive a joint claim
+    let r_M = <Transcript as ProofTranscript<G>>::challenge_vector(
+      transcript,
+      b"challenge_Mz",
+      Mz_claims.len(),
+    );
+    let claim_phase2 = (0..r_M.len()).map(|i| r_M[i] * Mz_claims[i]).sum();
+
+    // verify the joint claim with a sum-check protocol
+    let (claim_post_phase2, ry) =
+      self
+        .sc_proof_phase2
+        .verify::<G>(claim_phase2, num_rounds_y, 2, transcript)?;
+
+    // verify Z(ry) proof against the initial commitment
+    <Transcript as ProofTranscript<G>>::append_scalar(
+      transcript,
+      b"eval_vars_at_ry",
+      &self.eval_vars_at_ry,
+    );
+    let mut acc = MSMAccumulator::new();
+    PCS::verify(
+      &self.proof_eval_vars_at_ry,
+      &gens.gens_pc,
+      transcript,
+      &ry[1..],
+      &self.eval_vars_at_ry,
+      &self.comm_vars,
+      &mut acc,
+    )?;
+    acc.check()?;
+
+    let poly_input_eval = input_eval(input, num_vars, &ry[1..]);
+
+    // perform the final check of the second sum-check
+    let eval_Z_at_ry =
+      (G::ScalarField::one() - ry[0]) * self.eval_vars_at_ry + ry[0] * poly_input_eval;
+    let (Ar, Br, Cr) = evals;
+    if claim_post_phase2 != (r_M[0] * Ar + r_M[1] * Br + r_M[2] * Cr) * eval_Z_at_ry {
+      return Err(ProofVerifyError::InternalError);
+    }
+
+    Ok((rx, ry))
+  }
+}
+
+/// `PlainSNARK` holds a proof produced by the Spartan SNARK without zero-knowledge: it shows
+/// that the prover knows a satisfying assignment, but does not hide it. Its proofs are smaller
+/// and faster to produce and to verify than those of `SNARK`, which makes it suited to
+/// verifiable computation in which nothing is secret. It uses the generators of `SNARK` and
+/// the same commitment to the instance; instances with lookups, memories, or phases are not
+/// supported. Since nothing is hidden, any commitment scheme `PCS` can be used, including `Ligero`,
+/// with which the proof relies only on hash functions
+#[derive(CanonicalSerialize, CanonicalDeserialize, Debug)]
+pub struct PlainSNARK<G: ProjectiveCurve, PCS: MultilinearPCS<G> = Hyrax<G>> {
+  r1cs_sat_proof: R1CSPlainProof<G, PCS>,
+  inst_evals: (G::ScalarField, G::ScalarField, G::ScalarField),
+  r1cs_eval_proof: R1CSEvalProof<G, PCS>,
+}
+
+impl<G: ProjectiveCurve, PCS: MultilinearPCS<G>> PlainSNARK<G, PCS> {
+  fn protocol_name() -> &'static [u8] {
+    b"Spartan plain SNARK proof"
+  }
+
+  /// A public computation to create a commitment to an R1CS instance, which equals the one
+  /// made by `SNARK::encode` with the same generators
+  pub fn encode(
+    inst: &Instance<G::ScalarField>,
+    gens: &SNARKGens<G, PCS>,
+  ) -> (
+    ComputationCommitment<G, PCS>,
+    ComputationDecommitment<G::ScalarField>,
+  ) {
+    let timer_encode = Timer::new("PlainSNARK::encode");
+    let (comm, decomm) = inst.inst.commit(&gens.gens_r1cs_eval);
+    timer_encode.stop();
+    (
+      ComputationCommitment {
+        comm,
+        lookups: inst.lookups.clone(),
+        memories: inst.memories.clone(),
+        phases: inst.phases.clone(),
+      },
+      ComputationDecommitment { decomm },
+    )
+  }
+
+  /// A method to produce a proof, which does not hide `vars`, of the satisfiability of an
+  /// R1CS instance; instances with lookups, memories, or phases are rejected with an error
+  pub fn prove(
+    inst: &Instance<G::ScalarField>,
+    comm: &ComputationCommitment<G, PCS>,
+    decomm: &ComputationDecommitment<G::ScalarField>,
+    vars: VarsAssignment<G::ScalarField>,
+    inputs: &InputsAssignment<G::ScalarField>,
+    gens: &SNARKGens<G, PCS>,
+    transcript: &mut Transcript,
+  ) -> Result<Self, R1CSError> {
+    let timer_prove = Timer::new("PlainSNARK::prove");
+    inst.check_plain()?;
+    inst.check_assignment(&vars, inputs)?;
+    check_num_vars::<G, PCS>(
+      &gens.gens_r1cs_sat.gens_pc,
+      inst.inst.get_num_vars().log_2(),
+    )?;
+
+    let mut random_tape = start_proof::<G, _>(
+      PlainSNARK::<G, PCS>::protocol_name(),
+      b"comm",
+      &comm.comm,
+      transcript,
+    );
+
+    // we might need to pad variables
+    let padded_vars = vars.pad_to(inst.inst.get_num_vars());
+
+    let (r1cs_sat_proof, rx, ry) = R1CSPlainProof::prove(
+      &inst.inst,
+      padded_vars.assignment,
+      &inputs.assignment,
+      &gens.gens_r1cs_sat,
+      transcript,
+      &mut random_tape,
+    );
+
+    // We send evaluations of A, B, C at r = (rx, ry) as claims
+    // to enable the verifier complete the first sum-check
+    let timer_eval = Timer::new("eval_sparse_polys");
+    let inst_evals = inst.inst.evaluate(&rx, &ry);
+    append_inst_evals::<G>(transcript, &inst_evals);
+    timer_eval.stop();
+
+    let r1cs_eval_proof = R1CSEvalProof::prove(
+      &decomm.decomm,
+      &rx,
+      &ry,
+      &inst_evals,
+      &gens.gens_r1cs_eval,
+      transcript,
+      &mut random_tape,
+    );
+
+    timer_prove.stop();
+    Ok(PlainSNARK {
+      r1cs_sat_proof,
+      inst_evals,
+      r1cs_eval_proof,
+    })
+  }
+
+  /// A method to verify a `PlainSNARK` proof of the satisfiability of an R1CS instance
+  pub fn verify(
+    &self,
+    comm: &ComputationCommitment<G, PCS>,
+    input: &InputsAssignment<G::ScalarField>,
+    transcript: &mut Transcript,
+    gens: &SNARKGens<G, PCS>,
+  ) -> Result<(), ProofVerifyError> {
+    let timer_verify = Timer::new("PlainSNARK::verify");
+    if !comm.lookups.is_empty()
+      || !comm.memories.is_empty()
+      || comm.phases.is_some()
+      || input.assignment.len() != comm.comm.get_num_inputs()
+    {
+      return Err(ProofVerifyError::InternalError);
+    }
+
+    append_statement::<G, _>(
+      PlainSNARK::<G, PCS>::protocol_name(),
+      b"comm",
+      &comm.comm,
+      transcript,
+    );
+
+    let timer_sat_proof = Timer::new("verify_sat_proof");
+    let (rx, ry) = self.r1cs_sat_proof.verify(
+      comm.comm.get_num_vars(),
+      comm.comm.get_num_cons(),
+      &input.assignment,
+      &self.inst_evals,
+      transcript,
+      &gens.gens_r1cs_sat,
+    )?;
+    timer_sat_proof.stop();
+
+    let timer_eval_proof = Timer::new("verify_eval_proof");
+    append_inst_evals::<G>(transcript, &self.inst_evals);
+    self.r1cs_eval_proof.verify(
+      &comm.comm,
+      &rx,
+      &ry,
+      &self.inst_evals,
+      &gens.gens_r1cs_eval,
+      transcript,
+    )?;
+    timer_eval_proof.stop();
+    timer_verify.stop();
+    Ok(())
+  }
+}
+
+/// `PlainNIZK` holds a proof produced by the Spartan NIZK without zero-knowledge: it shows
+/// that the prover knows a satisfying assignment, but does not hide it. Its proofs are smaller
+/// and faster to produce and to verify than those of `NIZK`. It uses the generators of `NIZK`
+/// with any commitment scheme `PCS`; instances with lookups, memories, or phases are not supported
+#[derive(CanonicalSerialize, CanonicalDeserialize, Debug)]
+pub struct PlainNIZK<G: ProjectiveCurve, PCS: MultilinearPCS<G> = Hyrax<G>> {
+  r1cs_sat_proof: R1CSPlainProof<G, PCS>,
+  r: (Vec<G::ScalarField>, Vec<G::ScalarField>),
+}
+
+impl<G: ProjectiveCurve, PCS: MultilinearPCS<G>> PlainNIZK<G, PCS> {
+  fn protocol_name() -> &'static [u8] {
+    b"Spartan plain NIZK proof"
+  }
+
+  /// A method to produce a proof, which does not hide `vars`, of the satisfiability of an
+  /// R1CS instance; instances with lookups, memories, or phases are rejected with an error
+  pub fn prove(
+    inst: &Instance<G::ScalarField>,
+    vars: VarsAssignment<G::ScalarField>,
+    input: &InputsAssignment<G::ScalarField>,
+    gens: &NIZKGens<G, PCS>,
+    transcript: &mut Transcript,
+  ) -> Result<Self, R1CSError> {
+    let timer_prove = Timer::new("PlainNIZK::prove");
+    inst.check_plain()?;
+    inst.check_assignment(&vars, input)?;
+    check_num_vars::<G, PCS>(
+      &gens.gens_r1cs_sat.gens_pc,
+      inst.inst.get_num_vars().log_2(),
+    )?;
+
+    let mut random_tape = start_proof::<G, _>(
+      PlainNIZK::<G, PCS>::protocol_name(),
+      b"inst",
+      &inst.inst,
+      transcript,
+    );
+
+    // we might need to pad variables
+    let padded_vars = vars.pad_to(inst.inst.get_num_vars());
+
+    let (r1cs_sat_proof, rx, ry) = R1CSPlainProof::prove(
+      &inst.inst,
+      padded_vars.assignment,
+      &input.assignment,
+      &gens.gens_r1cs_sat,
+      transcript,
+      &mut random_tape,
+    );
+
+    timer_prove.stop();
+    Ok(PlainNIZK {
+      r1cs_sat_proof,
+      r: (rx, ry),
+    })
+  }
+
+  /// A method to verify a `PlainNIZK` proof of the satisfiability of an R1CS instance
+  pub fn verify(
+    &self,
+    inst: &Instance<G::ScalarField>,
+    input: &InputsAssignment<G::ScalarField>,
+    transcript: &mut Transcript,
+    gens: &NIZKGens<G, PCS>,
+  ) -> Result<(), ProofVerifyError> {
+    let timer_verify = Timer::new("PlainNIZK::verify");
+    if !inst.lookups.is_empty()
+      || !inst.memories.is_empty()
+      || inst.phases.is_some()
+      || input.assignment.len() != inst.inst.get_num_inputs()
+    {
+      return Err(ProofVerifyError::InternalError);
+    }
+
+    append_statement::<G, _>(
+      PlainNIZK::<G, PCS>::protocol_name(),
+      b"inst",
+      &inst.inst,
+      transcript,
+    );
+
+    // evaluate the matrices at the claimed point r = (rx, ry)
+    let timer_eval = Timer::new("eval_sparse_polys");
+    let (claimed_rx, claimed_ry) = &self.r;
+    if claimed_rx.len() != inst.inst.get_num_cons().log_2()
+      || claimed_ry.len() != (2 * inst.inst.get_num_vars()).log_2()
+    {
+      return Err(ProofVerifyError::InternalError);
+    }
+    let inst_evals = inst.inst.evaluate(claimed_rx, claimed_ry);
+    timer_eval.stop();
+
+    let timer_sat_proof = Timer::new("verify_sat_proof");
+    let (rx, ry) = self.r1cs_sat_proof.verify(
+      inst.inst.get_num_vars(),
+      inst.inst.get_num_cons(),
+      &input.assignment,
+      &inst_evals,
+      transcript,
+      &gens.gens_r1cs_sat,
+    )?;
+
+    // verify if claimed rx and ry are correct
+    if rx != *claimed_rx || ry != *claimed_ry {
+      return Err(ProofVerifyError::InternalError);
+    }
+    timer_sat_proof.stop();
+    timer_verify.stop();
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{NIZK, SNARK};
+  use ark_std::One;
+
+  crate::curves::curve_tests!(group: check_plain_snark, check_plain_snark_helper);
+
+  fn check_plain_snark_helper<G: ProjectiveCurve>() {
+    let (num_cons, num_vars, num_inputs) = (256, 256, 10);
+    let (inst, vars, inputs) =
+      Instance::<G::ScalarField>::produce_synthetic_r1cs(num_cons, num_vars, num_inputs);
+    let gens = SNARKGens::<G>::new(num_cons, num_vars, num_inputs, num_cons);
+    let (comm, decomm) = SNARK::encode(&inst, &gens);
+
+    let mut prover_transcript = Transcript::new(b"example");
+    let proof = PlainSNARK::prove(
+      &inst,
+      &comm,
+      &decomm,
+      vars.clone(),
+      &inputs,
+      &gens,
+      &mut prover_transcript,
+    )
+    .unwrap();
+    let mut verifier_transcript = Transcript::new(b"example");
+    assert!(proof
+      .verify(&comm, &inputs, &mut verifier_transcript, &gens)
+      .is_ok());
+
+    // the proof is smaller than the zero-knowledge one
+    let zk_proof = SNARK::prove(
+      &inst,
+      &comm,
+      &decomm,
+      vars.clone(),
+      &inputs,
+      &gens,
+      &mut Transcript::new(b"example"),
+    )
+    .unwrap();
+    assert!(proof.serialized_size() < zk_proof.serialized_size());
+
+    // the proof does not verify for other inputs
+    let mut wrong_inputs = inputs.assignment.clone();
+    wrong_inputs[0] += G::ScalarField::one();
+    let wrong_inputs = InputsAssignment::new(&wrong_inputs).unwrap();
+    let mut verifier_transcript = Transcript::new(b"example");
+    assert!(proof
+      .verify(&comm, &wrong_inputs, &mut verifier_transcript, &gens)
+      .is_err());
+
+    // the same holds for the NIZK
+    let gens = NIZKGens::<G>::new(num_cons, num_vars, num_inputs);
+    let proof = PlainNIZK::prove(
+      &inst,
+      vars.clone(),
+      &inputs,
+      &gens,
+      &mut Transcript::new(b"example"),
+    )
+    .unwrap();
+    assert!(proof
+      .verify(&inst, &inputs, &mut Transcript::new(b"example"), &gens)
+      .is_ok());
+    assert!(proof
+      .verify(
+        &inst,
+        &wrong_inputs,
+        &mut Transcript::new(b"example"),
+        &gens
+      )
+      .is_err());
+
+    let zk_proof = NIZK::prove(
+      &inst,
+      vars,
+      &inputs,
+      &gens,
+      &mut Transcript::new(b"example"),
+    )
+    .unwrap();
+    assert!(proof.serialized_size() < zk_proof.serialized_size());
+  }
+}
@@ -0,0 +1,524 @@
+use super::ccsinstance::CCSInstance;
+use super::dense_mlpoly::{DensePolynomial, EqPolynomial, IdentityPolynomial, PolyEvalProof};
+use super::errors::{ProofVerifyError, R1CSError};
+use super::math::Math;
+use super::product_tree::{ProductCircuit, ProductCircuitEvalProofBatched};
+use super::r1csproof::{R1CSGens, R1CSProof};
+use super::timer::Timer;
+use super::transcript::{AppendToTranscript, ProofTranscript};
+use super::{append_statement, start_proof, InputsAssignment, VarsAssignment};
+use ark_ec::ProjectiveCurve;
+use ark_ff::PrimeField;
+use ark_serialize::*;
+use core::cmp::max;
+use merlin::Transcript;
+
+/// `PlonkishCircuit` holds a circuit of `num_gates` gates over `num_wires` wire columns.
+/// Every gate satisfies the same equation `sum_k q_{s_k} * prod_{w in W_k} w + PI = 0`, where
+/// each term `k` multiplies a selector column `q_{s_k}` with a multiset `W_k` of the gate's
+/// wires, and `PI` is the negated public input `i` at gate `i` and zero elsewhere. Copy
+/// constraints require wires of (possibly different) gates to carry equal values.
+///
+/// The gate equations are proven with the sum-check protocol over a customizable constraint
+/// system, and the copy constraints with a permutation argument on the committed wires using
+/// the product circuits of Spark. The evaluation of the wires at the random point of the
+/// permutation argument is revealed, so that argument is not zero-knowledge.
+pub struct PlonkishCircuit<F: PrimeField> {
+  num_gates: usize,
+  num_wires: usize,
+  inst: CCSInstance<F>,
+  sigma: Vec<usize>,
+}
+
+impl<F: PrimeField> PlonkishCircuit<F> {
+  /// Constructs a new `PlonkishCircuit` from selector columns with one value per gate and
+  /// terms given as a selector index and a multiset of wire indices
+  pub fn new(
+    num_gates: usize,
+    num_wires: usize,
+    num_inputs: usize,
+    selectors: &[Vec<F>],
+    terms: &[(usize, Vec<usize>)],
+  ) -> Result<Self, R1CSError> {
+    if num_wires == 0
+      || terms.is_empty()
+      || selectors.iter().any(|q| q.len() != num_gates)
+      || terms
+        .iter()
+        .any(|(s, wires)| *s >= selectors.len() || wires.iter().any(|&w| w >= num_wires))
+    {
+      return Err(R1CSError::InvalidGate);
+    }
+
+    // the wires are laid out column by column, with the column of each wire padded to a
+    // power of two so that it is a sub-cube of the witness polynomial
+    let num_gates_padded = max(num_gates, 2).next_power_of_two();
+    let num_vars = num_gates_padded * num_wires.next_power_of_two();
+    if num_inputs > num_gates || num_inputs >= num_vars {
+      return Err(R1CSError::InvalidNumberOfInputs);
+    }
+
+    let mut mats = (0..num_wires)
+      .map(|w| {
+        (0..num_gates)
+          .map(|g| (g, w * num_gates_padded + g, F::one()))
+          .collect::<Vec<(usize, usize, F)>>()
+      })
+      .collect::<Vec<Vec<(usize, usize, F)>>>();
+    // the selectors multiply the constant column of z
+    mats.extend(selectors.iter().map(|q| {
+      (0..num_gates)
+        .filter(|&g| !q[g].is_zero())
+        .map(|g| (g, num_vars, q[g]))
+        .collect::<Vec<(usize, usize, F)>>()
+    }));
+
+    let mut coeffs = vec![F::one(); terms.len()];
+    let mut multisets = terms
+      .iter()
+      .map(|(s, wires)| {
+        let mut multiset = vec![num_wires + s];
+        multiset.extend(wires);
+        multiset
+      })
+      .collect::<Vec<Vec<usize>>>();
+    if num_inputs > 0 {
+      mats.push(
+        (0..num_inputs)
+          .map(|i| (i, num_vars + 1 + i, -F::one()))
+          .collect(),
+      );
+      coeffs.push(F::one());
+      multisets.push(vec![mats.len() - 1]);
+    }
+
+    let inst = CCSInstance::new(
+      num_gates_padded,
+      num_vars,
+      num_inputs,
+      &mats,
+      &coeffs,
+      &multisets,
+    );
+
+    Ok(PlonkishCircuit {
+      num_gates,
+      num_wires,
+      inst,
+      sigma: (0..num_vars).collect(),
+    })
+  }
+
+  fn cell(&self, gate: usize, wire: usize) -> usize {
+    wire * self.inst.get_num_cons() + gate
+  }
+
+  /// Adds a copy constraint requiring the wires `left` and `right`, each given as a pair
+  /// of a gate and a wire index, to carry equal values
+  pub fn add_copy(&mut self, left: (usize, usize), right: (usize, usize)) -> Result<(), R1CSError> {
+    if left.0 >= self.num_gates
+      || right.0 >= self.num_gates
+      || left.1 >= self.num_wires
+      || right.1 >= self.num_wires
+    {
+      return Err(R1CSError::InvalidIndex);
+    }
+
+    // the permutation is a product of cycles, one for each class of wires with equal values;
+    // swapping the successors of two cells merges their cycles, unless they already share one
+    let (x, y) = (self.cell(left.0, left.1), self.cell(right.0, right.1));
+    let mut cur = self.sigma[x];
+    while cur != x {
+      if cur == y {
+        return Ok(());
+      }
+      cur = self.sigma[cur];
+    }
+    if x != y {
+      self.sigma.swap(x, y);
+    }
+    Ok(())
+  }
+
+  /// Returns the largest degree of a term in the gate equation, counting its selector
+  pub fn get_degree(&self) -> usize {
+    self.inst.get_degree()
+  }
+
+  /// Lays out the wire values given as `num_gates` rows of `num_wires` values into a
+  /// `VarsAssignment`
+  pub fn assign_wires(&self, wires: &[Vec<F>]) -> Result<VarsAssignment<F>, R1CSError> {
+    if wires.len() != self.num_gates || wires.iter().any(|row| row.len() != self.num_wires) {
+      return Err(R1CSError::InvalidNumberOfVars);
+    }
+    let mut vars = vec![F::zero(); self.inst.get_num_vars()];
+    for (g, row) in wires.iter().enumerate() {
+      for (w, val) in row.iter().enumerate() {
+        vars[self.cell(g, w)] = *val;
+      }
+    }
+    VarsAssignment::new(&vars)
+  }
+
+  /// Checks if the wire values laid out in `vars` satisfy the gate equations and copy constraints
+  pub fn is_sat(
+    &self,
+    vars: &VarsAssignment<F>,
+    inputs: &InputsAssignment<F>,
+  ) -> Result<bool, R1CSError> {
+    if vars.assignment.len() != self.inst.get_num_vars() {
+      return Err(R1CSError::InvalidNumberOfVars);
+    }
+    if inputs.assignment.len() != self.inst.get_num_inputs() {
+      return Err(R1CSError::InvalidNumberOfInputs);
+    }
+    let copies_hold =
+      (0..self.sigma.len()).all(|i| vars.assignment[i] == vars.assignment[self.sigma[i]]);
+    Ok(copies_hold && self.inst.is_sat(&vars.assignment, &inputs.assignment))
+  }
+
+  // evaluates the multilinear extension of the permutation at r
+  fn evaluate_sigma(&self, r: &[F]) -> F {
+    let chis = EqPolynomial::new(r.to_vec()).evals();
+    (0..self.sigma.len())
+      .map(|i| chis[i] * F::from(self.sigma[i] as u64))
+      .sum()
+  }
+}
+
+impl<G: ProjectiveCurve> AppendToTranscript<G> for PlonkishCircuit<G::ScalarField> {
+  fn append_to_transcript(&self, _label: &'static [u8], transcript: &mut Transcript) {
+    <CCSInstance<G::ScalarField> as AppendToTranscript<G>>::append_to_transcript(
+      &self.inst, b"inst", transcript,
+    );
+    let sigma = self
+      .sigma
+      .iter()
+      .map(|s| G::ScalarField::from(*s as u64))
+      .collect::<Vec<G::ScalarField>>();
+    <Transcript as ProofTranscript<G>>::append_scalars(transcript, b"sigma", &sigma);
+  }
+}
+
+/// `PlonkishNIZKGens` holds public parameters for producing and verifying proofs of the
+/// satisfiability of a `PlonkishCircuit`
+pub struct PlonkishNIZKGens<G: ProjectiveCurve> {
+  gens_ccs_sat: R1CSGens<G>,
+}
+
+impl<G: ProjectiveCurve> PlonkishNIZKGens<G> {
+  /// Constructs a new `PlonkishNIZKGens` given the size of the circuit and the degree of its
+  /// gate equation, as returned by `PlonkishCircuit::get_degree`
+  pub fn new(num_gates: usize, num_wires: usize, degree: usize) -> Self {
+    let num_gates_padded = max(num_gates, 2).next_power_of_two();
+    let num_vars = num_gates_padded * num_wires.next_power_of_two();
+    let gens_ccs_sat =
+      R1CSGens::new_with_degree(b"gens_plonkish_sat", num_gates_padded, num_vars, degree);
+    PlonkishNIZKGens { gens_ccs_sat }
+  }
+}
+
+/// `PlonkishNIZK` holds a proof of the satisfiability of a `PlonkishCircuit`
+#[derive(CanonicalSerialize, CanonicalDeserialize, Debug)]
+pub struct PlonkishNIZK<G: ProjectiveCurve> {
+  ccs_sat_proof: R1CSProof<G>,
+  r: (Vec<G::ScalarField>, Vec<G::ScalarField>),
+  claims_prod: (G::ScalarField, G::ScalarField),
+  proof_prod: ProductCircuitEvalProofBatched<G::ScalarField>,
+  eval_wires: G::ScalarField,
+  proof_wires: PolyEvalProof<G>,
+}
+
+impl<G: ProjectiveCurve> PlonkishNIZK<G> {
+  fn protocol_name() -> &'static [u8] {
+    b"Spartan Plonkish NIZK proof"
+  }
+
+  /// A method to produce a proof of the satisfiability of a `PlonkishCircuit`
+  pub fn prove(
+    circuit: &PlonkishCircuit<G::ScalarField>,
+    vars: VarsAssignment<G::ScalarField>,
+    inputs: &InputsAssignment<G::ScalarField>,
+    gens: &PlonkishNIZKGens<G>,
+    transcript: &mut Transcript,
+  ) -> Self {
+    let timer_prove = Timer::new("PlonkishNIZK::prove");
+    let mut random_tape = start_proof::<G, _>(
+      PlonkishNIZK::<G>::protocol_name(),
+      b"circuit",
+      circuit,
+      transcript,
+    );
+
+    let (ccs_sat_proof, rx, ry, (poly_vars, blinds_vars)) = R1CSProof::prove(
+      &circuit.inst,
+      vars.assignment.clone(),
+      &inputs.assignment,
+      &gens.gens_ccs_sat,
+      transcript,
+      &mut random_tape,
+    );
+
+    // the wires are a permutation of themselves that respects copy constraints if the
+    // fingerprints of (w_i, i) and (w_i, sigma(i)) have equal products
+    let timer_perm = Timer::new("prove_permutation");
+    let r_perm =
+      <Transcript as ProofTranscript<G>>::challenge_vector(transcript, b"challenge_perm", 2);
+    let (beta, gamma) = (r_perm[0], r_perm[1]);
+    let hash = |w: &G::ScalarField, i: usize| -> G::ScalarField {
+      *w + beta * G::ScalarField::from(i as u64) + gamma
+    };
+    let vars = vars.assignment;
+    let mut circuit_id = ProductCircuit::new(&DensePolynomial::new(
+      (0..vars.len()).map(|i| hash(&vars[i], i)).collect(),
+    ));
+    let mut circuit_sigma = ProductCircuit::new(&DensePolynomial::new(
+      (0..vars.len())
+        .map(|i| hash(&vars[i], circuit.sigma[i]))
+        .collect(),
+    ));
+
+    let claims_prod = (circuit_id.evaluate(), circuit_sigma.evaluate());
+    <Transcript as ProofTranscript<G>>::append_scalars(
+      transcript,
+      b"claims_prod",
+      &[claims_prod.0, claims_prod.1],
+    );
+    let (proof_prod, rand) = ProductCircuitEvalProofBatched::prove::<G>(
+      &mut vec![&mut circuit_id, &mut circuit_sigma],
+      &mut Vec::new(),
+      transcript,
+    );
+
+    let eval_wires = poly_vars.evaluate::<G>(&rand);
+    <Transcript as ProofTranscript<G>>::append_scalar(transcript, b"claim_eval_wires", &eval_wires);
+    let (proof_wires, _comm_eval) = PolyEvalProof::prove(
+      &poly_vars,
+      Some(&blinds_vars),
+      &rand,
+      &eval_wires,
+      None,
+      &gens.gens_ccs_sat.gens_pc,
+      transcript,
+      &mut random_tape,
+    );
+    timer_perm.stop();
+
+    timer_prove.stop();
+    PlonkishNIZK {
+      ccs_sat_proof,
+      r: (rx, ry),
+      claims_prod,
+      proof_prod,
+      eval_wires,
+      proof_wires,
+    }
+  }
+
+  /// A method to verify a proof of the satisfiability of a `PlonkishCircuit`
+  pub fn verify(
+    &self,
+    circuit: &PlonkishCircuit<G::ScalarField>,
+    inputs: &InputsAssignment<G::ScalarField>,
+    transcript: &mut Transcript,
+    gens: &PlonkishNIZKGens<G>,
+  ) -> Result<(), ProofVerifyError> {
+    let timer_verify = Timer::new("PlonkishNIZK::verify");
+    let inst = &circuit.inst;
+    if inputs.assignment.len() != inst.get_num_inputs() {
+      return Err(ProofVerifyError::InternalError);
+    }
+
+    append_statement::<G, _>(
+      PlonkishNIZK::<G>::protocol_name(),
+      b"circuit",
+      circuit,
+      transcript,
+    );
+
+    let (claimed_rx, claimed_ry) = &self.r;
+    if claimed_rx.len() != inst.get_num_cons().log_2()
+      || claimed_ry.len() != (2 * inst.get_num_vars()).log_2()
+    {
+      return Err(ProofVerifyError::InternalError);
+    }
+    let timer_eval = Timer::new("eval_sparse_polys");
+    let inst_evals = inst.evaluate(claimed_rx, claimed_ry);
+    timer_eval.stop();
+
+    let (rx, ry) = self.ccs_sat_proof.verify_ccs(
+      inst.get_num_vars(),
+      inst.get_num_cons(),
+      &inputs.assignment,
+      inst.get_terms(),
+      &inst_evals,
+      transcript,
+      &gens.gens_ccs_sat,
+    )?;
+    if rx != *claimed_rx || ry != *claimed_ry {
+      return Err(ProofVerifyError::InternalError);
+    }
+
+    let timer_perm = Timer::new("verify_permutation");
+    let r_perm =
+      <Transcript as ProofTranscript<G>>::challenge_vector(transcript, b"challenge_perm", 2);
+    let (beta, gamma) = (r_perm[0], r_perm[1]);
+
+    let (claim_id, claim_sigma) = self.claims_prod;
+    if claim_id != claim_sigma {
+      return Err(ProofVerifyError::InternalError);
+    }
+    <Transcript as ProofTranscript<G>>::append_scalars(
+      transcript,
+      b"claims_prod",
+      &[claim_id, claim_sigma],
+    );
+    let (claims_leaves, _claims_dotp, rand) = self.proof_prod.verify::<G>(
+      &[claim_id, claim_sigma],
+      &[],
+      inst.get_num_vars(),
+      transcript,
+    )?;
+
+    // check the leaves of the product circuits against the decommitted wires
+    <Transcript as ProofTranscript<G>>::append_scalar(
+      transcript,
+      b"claim_eval_wires",
+      &self.eval_wires,
+    );
+    let eval_id = IdentityPolynomial::new(rand.len()).evaluate(&rand);
+    let eval_sigma = circuit.evaluate_sigma(&rand);
+    if self.eval_wires + beta * eval_id + gamma != claims_leaves[0]
+      || self.eval_wires + beta * eval_sigma + gamma != claims_leaves[1]
+    {
+      return Err(ProofVerifyError::InternalError);
+    }
+    self.proof_wires.verify_plain(
+      &gens.gens_ccs_sat.gens_pc,
+      transcript,
+      &rand,
+      &self.eval_wires,
+      self.ccs_sat_proof.get_comm_vars(),
+    )?;
+    timer_perm.stop();
+
+    timer_verify.stop();
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  crate::curves::curve_tests!(group: check_plonkish_nizk, check_plonkish_nizk_helper);
+
+  fn check_plonkish_nizk_helper<G: ProjectiveCurve>() {
+    // proves knowledge of x with x^3 + x + 5 = out for a public out, with wires (a, b, c) and
+    // selectors (q_L, q_R, q_O, q_M, q_C, q_P), where q_P is a custom gate for a^3
+    let (a, b, c) = (0, 1, 2);
+    let (q_L, q_R, q_O, q_M, q_C, q_P) = (0, 1, 2, 3, 4, 5);
+    let terms = vec![
+      (q_L, vec![a]),
+      (q_R, vec![b]),
+      (q_O, vec![c]),
+      (q_M, vec![a, b]),
+      (q_C, vec![]),
+      (q_P, vec![a, a, a]),
+    ];
+    let scalar = |v: i64| {
+      if v < 0 {
+        -G::ScalarField::from((-v) as u64)
+      } else {
+        G::ScalarField::from(v as u64)
+      }
+    };
+    // gate 0 binds the output, gates 1 and 2 compute x^3 by multiplication, gate 3 adds x,
+    // gate 4 adds 5, and gate 5 recomputes x^3 with the custom gate
+    let gates: [[i64; 6]; 6] = [
+      [1, 0, 0, 0, 0, 0],
+      [0, 0, -1, 1, 0, 0],
+      [0, 0, -1, 1, 0, 0],
+      [1, 1, -1, 0, 0, 0],
+      [1, 0, -1, 0, 5, 0],
+      [0, 0, -1, 0, 0, 1],
+    ];
+    let selectors = (0..6)
+      .map(|s| gates.iter().map(|gate| scalar(gate[s])).collect())
+      .collect::<Vec<Vec<G::ScalarField>>>();
+    let mut circuit = PlonkishCircuit::new(6, 3, 1, &selectors, &terms).unwrap();
+    assert_eq!(circuit.get_degree(), 4);
+
+    let copies = [
+      ((1, a), (1, b)),
+      ((1, b), (2, b)),
+      ((2, b), (3, b)),
+      ((3, b), (5, a)),
+      ((1, c), (2, a)),
+      ((2, c), (3, a)),
+      ((3, a), (5, c)),
+      ((3, c), (4, a)),
+      ((4, c), (0, a)),
+    ];
+    for (left, right) in copies.iter() {
+      circuit.add_copy(*left, *right).unwrap();
+    }
+    // adding a copy constraint between wires that are already equal is a no-op
+    circuit.add_copy((5, a), (1, a)).unwrap();
+
+    let wires = |x: i64| {
+      let (x2, x3) = (x * x, x * x * x);
+      [
+        [x3 + x + 5, 0, 0],
+        [x, x, x2],
+        [x2, x, x3],
+        [x3, x, x3 + x],
+        [x3 + x, 0, x3 + x + 5],
+        [x, 0, x3],
+      ]
+      .iter()
+      .map(|row| row.iter().map(|v| scalar(*v)).collect())
+      .collect::<Vec<Vec<G::ScalarField>>>()
+    };
+    let vars = circuit.assign_wires(&wires(3)).unwrap();
+    let inputs = InputsAssignment::new(&[scalar(35)]).unwrap();
+    assert!(circuit.is_sat(&vars, &inputs).unwrap());
+
+    let gens = PlonkishNIZKGens::<G>::new(6, 3, circuit.get_degree());
+    let mut prover_transcript = Transcript::new(b"example");
+    let proof = PlonkishNIZK::prove(&circuit, vars, &inputs, &gens, &mut prover_transcript);
+
+    let mut verifier_transcript = Transcript::new(b"example");
+    assert!(proof
+      .verify(&circuit, &inputs, &mut verifier_transcript, &gens)
+      .is_ok());
+
+    let wrong_inputs = InputsAssignment::new(&[scalar(36)]).unwrap();
+    let mut verifier_transcript = Transcript::new(b"example");
+    assert!(proof
+      .verify(&circuit, &wrong_inputs, &mut verifier_transcript, &gens)
+      .is_err());
+
+    // wires that satisfy every gate but use a different x in gate 3 break a copy constraint
+    let mut bad_wires = wires(3);
+    bad_wires[3] = vec![scalar(27), scalar(4), scalar(31)];
+    bad_wires[4] = vec![scalar(31), scalar(0), scalar(36)];
+    bad_wires[0] = vec![scalar(36), scalar(0), scalar(0)];
+    let bad_vars = circuit.assign_wires(&bad_wires).unwrap();
+    let bad_inputs = InputsAssignment::new(&[scalar(36)]).unwrap();
+    assert!(!circuit.is_sat(&bad_vars, &bad_inputs).unwrap());
+
+    let mut prover_transcript = Transcript::new(b"example");
+    let proof = PlonkishNIZK::prove(
+      &circuit,
+      bad_vars,
+      &bad_inputs,
+      &gens,
+      &mut prover_transcript,
+    );
+    let mut verifier_transcript = Transcript::new(b"example");
+    assert!(proof
+      .verify(&circuit, &bad_inputs, &mut verifier_transcript, &gens)
+      .is_err());
+  }
+}
@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 use super::dense_mlpoly::DensePolynomial;
 use super::dense_mlpoly::EqPolynomial;
+use super::errors::ProofVerifyError;
 use super::math::Math;
 use super::sumcheck::SumcheckInstanceProof;
 use super::transcript::ProofTranscript;
@@ -154,14 +155,13 @@ impl<F: PrimeField> LayerProofBatched<F> {
     num_rounds: usize,
     degree_bound: usize,
     transcript: &mut Transcript,
-  ) -> (F, Vec<F>)
+  ) -> Result<(F, Vec<F>), ProofVerifyError>
   where
     G: ProjectiveCurve<ScalarField = F>,
   {
     self
       .proof
       .verify::<G>(claim, num_rounds, degree_bound, transcript)
-      .unwrap()
   }
 }
 
@@ -438,13 +438,14 @@ impl<F: PrimeField> ProductCircuitEvalProofBatched<F> {
     )
   }
 
+  #[allow(clippy::type_complexity)]
   pub fn verify<G>(
     &self,
     claims_prod_vec: &[F],
     claims_dotp_vec: &[F],
     len: usize,
     transcript: &mut Transcript,
-  ) -> (Vec<F>, Vec<F>, Vec<F>)
+  ) -> Result<(Vec<F>, Vec<F>, Vec<F>), ProofVerifyError>
   where
     G: ProjectiveCurve<ScalarField = F>,
   {
@@ -472,7 +473,7 @@ impl<F: PrimeField> ProductCircuitEvalProofBatched<F> {
         .map(|i| claims_to_verify[i] * coeff_vec[i])
         .sum();
 
-      let (claim_last, rand_prod) = self.proof[i].verify::<G>(claim, num_rounds, 3, transcript);
+      let (claim_last, rand_prod) = self.proof[i].verify::<G>(claim, num_rounds, 3, transcript)?;
 
       let claims_prod_left = &self.proof[i].claims_prod_left;
       let claims_prod_right = &self.proof[i].claims_prod_right;
@@ -531,7 +532,9 @@ impl<F: PrimeField> ProductCircuitEvalProofBatched<F> {
         }
       }
 
-      assert_eq!(claim_expected, claim_last);
+      if claim_expected != claim_last {
+        return Err(ProofVerifyError::InternalError);
+      }
 
       // produce a random challenge
       let r_layer =
@@ -565,6 +568,6 @@ impl<F: PrimeField> ProductCircuitEvalProofBatched<F> {
       ext.extend(rand_prod);
       rand = ext;
     }
-    (claims_to_verify, claims_to_verify_dotp, rand)
+    Ok((claims_to_verify, claims_to_verify_dotp, rand))
   }
 }
@@ -0,0 +1,259 @@
+use super::r1csinstance::R1CSInstance;
+use ark_ff::PrimeField;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// `Column` identifies an entry of the vector `z = (vars, 1, inputs)` that R1CS matrices are applied to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Column {
+  /// the variable with the given index
+  Var(usize),
+  /// the constant one
+  One,
+  /// the public input with the given index
+  Input(usize),
+}
+
+/// `Labels` holds optional human-readable names for the variables, inputs, and constraints of an `Instance`
+#[derive(Clone, Debug, Default)]
+pub struct Labels {
+  vars: HashMap<usize, String>,
+  inputs: HashMap<usize, String>,
+  cons: HashMap<usize, String>,
+}
+
+impl Labels {
+  /// Constructs an empty set of labels
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Names the variable with the given index
+  pub fn name_var(&mut self, var: usize, name: &str) {
+    self.vars.insert(var, name.to_string());
+  }
+
+  /// Names the public input with the given index
+  pub fn name_input(&mut self, input: usize, name: &str) {
+    self.inputs.insert(input, name.to_string());
+  }
+
+  /// Names the constraint with the given index
+  pub fn name_constraint(&mut self, cons: usize, name: &str) {
+    self.cons.insert(cons, name.to_string());
+  }
+
+  fn get_column(&self, col: &Column) -> Option<String> {
+    match col {
+      Column::Var(i) => self.vars.get(i).cloned(),
+      Column::One => None,
+      Column::Input(i) => self.inputs.get(i).cloned(),
+    }
+  }
+}
+
+/// `ColumnValue` holds the value assigned to a column referenced by an unsatisfied constraint
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColumnValue<F> {
+  /// the column
+  pub column: Column,
+  /// the name of the column, if one was supplied
+  pub name: Option<String>,
+  /// the value assigned to the column
+  pub value: F,
+}
+
+/// `UnsatisfiedConstraint` describes a constraint for which `Az * Bz != Cz`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnsatisfiedConstraint<F> {
+  /// the index of the constraint
+  pub index: usize,
+  /// the name of the constraint, if one was supplied
+  pub name: Option<String>,
+  /// the value of `Az` at the constraint
+  pub Az: F,
+  /// the value of `Bz` at the constraint
+  pub Bz: F,
+  /// the value of `Cz` at the constraint
+  pub Cz: F,
+  /// the columns with a non-zero entry in the constraint, in any of A, B, or C
+  pub columns: Vec<ColumnValue<F>>,
+}
+
+/// `SatReport` holds the result of checking an assignment against an `Instance`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SatReport<F> {
+  /// the number of constraints checked
+  pub num_cons: usize,
+  /// the constraints that the assignment does not satisfy, in increasing order of index
+  pub unsatisfied: Vec<UnsatisfiedConstraint<F>>,
+}
+
+impl<F> SatReport<F> {
+  /// Returns true if the assignment satisfies every constraint
+  pub fn is_sat(&self) -> bool {
+    self.unsatisfied.is_empty()
+  }
+}
+
+impl fmt::Display for Column {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Column::Var(i) => write!(f, "var[{}]", i),
+      Column::One => write!(f, "one"),
+      Column::Input(i) => write!(f, "input[{}]", i),
+    }
+  }
+}
+
+impl<F: fmt::Display> fmt::Display for SatReport<F> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(
+      f,
+      "{} of {} constraints unsatisfied",
+      self.unsatisfied.len(),
+      self.num_cons
+    )?;
+    for cons in self.unsatisfied.iter() {
+      write!(f, "constraint {}", cons.index)?;
+      if let Some(name) = &cons.name {
+        write!(f, " ({})", name)?;
+      }
+      writeln!(f, ": Az = {}, Bz = {}, Cz = {}", cons.Az, cons.Bz, cons.Cz)?;
+      for col in cons.columns.iter() {
+        write!(f, "  {}", col.column)?;
+        if let Some(name) = &col.name {
+          write!(f, " ({})", name)?;
+        }
+        writeln!(f, " = {}", col.value)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+// checks the padded assignment `vars` and `input` against `inst`, reporting every unsatisfied constraint
+pub fn check<F: PrimeField>(
+  inst: &R1CSInstance<F>,
+  vars: &[F],
+  input: &[F],
+  labels: Option<&Labels>,
+) -> SatReport<F> {
+  let num_vars = inst.get_num_vars();
+  let z = {
+    let mut z = vars.to_vec();
+    z.extend(&vec![F::one()]);
+    z.extend(input);
+    z
+  };
+
+  let (Az, Bz, Cz) = inst.multiply_vec(inst.get_num_cons(), z.len(), &z);
+  let mut unsatisfied = (0..inst.get_num_cons())
+    .filter(|&i| Az[i] * Bz[i] != Cz[i])
+    .map(|i| (i, BTreeMap::new()))
+    .collect::<BTreeMap<usize, BTreeMap<Column, F>>>();
+
+  // collect the columns that contribute to each unsatisfied constraint
+  let (A, B, C) = inst.get_matrices();
+  for &(row, col, val) in A.iter().chain(B.iter()).chain(C.iter()) {
+    if val.is_zero() {
+      continue;
+    }
+    if let Some(cols) = unsatisfied.get_mut(&row) {
+      let column = match col.cmp(&num_vars) {
+        Ordering::Less => Column::Var(col),
+        Ordering::Equal => Column::One,
+        Ordering::Greater => Column::Input(col - num_vars - 1),
+      };
+      cols.insert(column, z[col]);
+    }
+  }
+
+  let unsatisfied = unsatisfied
+    .into_iter()
+    .map(|(i, cols)| UnsatisfiedConstraint {
+      index: i,
+      name: labels.and_then(|l| l.cons.get(&i).cloned()),
+      Az: Az[i],
+      Bz: Bz[i],
+      Cz: Cz[i],
+      columns: cols
+        .into_iter()
+        .map(|(column, value)| ColumnValue {
+          column,
+          name: labels.and_then(|l| l.get_column(&column)),
+          value,
+        })
+        .collect(),
+    })
+    .collect();
+
+  SatReport {
+    num_cons: inst.get_num_cons(),
+    unsatisfied,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::{InputsAssignment, Instance, VarsAssignment};
+  use super::*;
+
+  crate::curves::curve_tests!(field: check_sat_report, check_sat_report_helper);
+
+  fn check_sat_report_helper<F: PrimeField>() {
+    let num_cons = 2;
+    let num_vars = 3;
+    let num_inputs = 1;
+    let one = F::one();
+
+    // z_1 = z_0 * z_0 and z_2 = z_1 * x + 1
+    let A = vec![(0, 0, one), (1, 1, one)];
+    let B = vec![(0, 0, one), (1, num_vars + 1, one)];
+    let C = vec![(0, 1, one), (1, 2, one), (1, num_vars, -one)];
+
+    let mut labels = Labels::new();
+    labels.name_var(2, "out");
+    labels.name_input(0, "x");
+    labels.name_constraint(1, "mul");
+
+    let inst = Instance::new(num_cons, num_vars, num_inputs, &A, &B, &C).unwrap();
+    let inputs = InputsAssignment::new(&[F::from(5u64)]).unwrap();
+    let vars = VarsAssignment::new(&[F::from(2u64), F::from(4u64), F::from(21u64)]).unwrap();
+    let report = inst.check(&vars, &inputs, Some(&labels)).unwrap();
+    assert!(report.is_sat());
+
+    // a wrong output only violates the second constraint
+    let vars = VarsAssignment::new(&[F::from(2u64), F::from(4u64), F::from(20u64)]).unwrap();
+    let report = inst.check(&vars, &inputs, Some(&labels)).unwrap();
+    assert!(!report.is_sat());
+    assert_eq!(report.unsatisfied.len(), 1);
+
+    let cons = &report.unsatisfied[0];
+    assert_eq!(cons.index, 1);
+    assert_eq!(cons.name, Some("mul".to_string()));
+    assert_eq!(
+      (cons.Az, cons.Bz, cons.Cz),
+      (F::from(4u64), F::from(5u64), F::from(19u64))
+    );
+    let columns = cons
+      .columns
+      .iter()
+      .map(|c| (c.column, c.name.clone(), c.value))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      columns,
+      vec![
+        (Column::Var(1), None, F::from(4u64)),
+        (Column::Var(2), Some("out".to_string()), F::from(20u64)),
+        (Column::One, None, one),
+        (Column::Input(0), Some("x".to_string()), F::from(5u64)),
+      ]
+    );
+
+    // assignments of the wrong size are reported as errors
+    let vars = VarsAssignment::new(&[F::zero(); 5]).unwrap();
+    assert!(inst.check(&vars, &inputs, None).is_err());
+  }
+}
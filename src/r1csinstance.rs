@@ -1,6 +1,8 @@
+use super::commitments::MSMAccumulator;
 use super::dense_mlpoly::DensePolynomial;
 use super::errors::ProofVerifyError;
 use super::math::Math;
+use super::pcs::{Hyrax, MultilinearPCS, TransparentMultilinearPCS};
 use super::random::RandomTape;
 use super::sparse_mlpoly::{
   MultiSparseMatPolynomialAsDense, SparseMatEntry, SparseMatPolyCommitment,
@@ -33,36 +35,102 @@ impl<G: ProjectiveCurve> AppendToTranscript<G> for R1CSInstance<G::ScalarField>
   }
 }
 
-pub struct R1CSCommitmentGens<G> {
-  gens: SparseMatPolyCommitmentGens<G>,
+pub struct R1CSCommitmentGens<G: ProjectiveCurve, PCS: MultilinearPCS<G> = Hyrax<G>> {
+  gens: SparseMatPolyCommitmentGens<G, PCS>,
 }
 
-impl<G: ProjectiveCurve> R1CSCommitmentGens<G> {
+impl<G: ProjectiveCurve, PCS: TransparentMultilinearPCS<G>> R1CSCommitmentGens<G, PCS> {
   pub fn new(
     label: &'static [u8],
     num_cons: usize,
     num_vars: usize,
     num_inputs: usize,
     num_nz_entries: usize,
-  ) -> R1CSCommitmentGens<G> {
+  ) -> R1CSCommitmentGens<G, PCS> {
+    R1CSCommitmentGens::new_multi(label, num_cons, num_vars, num_inputs, num_nz_entries, 1)
+  }
+
+  // generators for committing to the matrices of several instances of the same size together
+  pub fn new_multi(
+    label: &'static [u8],
+    num_cons: usize,
+    num_vars: usize,
+    num_inputs: usize,
+    num_nz_entries: usize,
+    num_instances: usize,
+  ) -> R1CSCommitmentGens<G, PCS> {
+    R1CSCommitmentGens::new_multi_with_setup(
+      label,
+      num_cons,
+      num_vars,
+      num_inputs,
+      num_nz_entries,
+      num_instances,
+      PCS::setup,
+    )
+  }
+}
+
+impl<G: ProjectiveCurve, PCS: MultilinearPCS<G>> R1CSCommitmentGens<G, PCS> {
+  // generators whose public parameters for the commitment scheme come from `setup`
+  pub fn new_with_setup<S>(
+    label: &'static [u8],
+    num_cons: usize,
+    num_vars: usize,
+    num_inputs: usize,
+    num_nz_entries: usize,
+    setup: S,
+  ) -> R1CSCommitmentGens<G, PCS>
+  where
+    S: Fn(usize, &'static [u8]) -> PCS::Gens,
+  {
+    R1CSCommitmentGens::new_multi_with_setup(
+      label,
+      num_cons,
+      num_vars,
+      num_inputs,
+      num_nz_entries,
+      1,
+      setup,
+    )
+  }
+
+  fn new_multi_with_setup<S>(
+    label: &'static [u8],
+    num_cons: usize,
+    num_vars: usize,
+    num_inputs: usize,
+    num_nz_entries: usize,
+    num_instances: usize,
+    setup: S,
+  ) -> R1CSCommitmentGens<G, PCS>
+  where
+    S: Fn(usize, &'static [u8]) -> PCS::Gens,
+  {
     assert!(num_inputs < num_vars);
     let num_poly_vars_x = num_cons.log_2() as usize;
     let num_poly_vars_y = (2 * num_vars).log_2() as usize;
-    let gens =
-      SparseMatPolyCommitmentGens::new(label, num_poly_vars_x, num_poly_vars_y, num_nz_entries, 3);
+    let gens = SparseMatPolyCommitmentGens::new_with_setup(
+      label,
+      num_poly_vars_x,
+      num_poly_vars_y,
+      num_nz_entries,
+      3 * num_instances,
+      setup,
+    );
     R1CSCommitmentGens { gens }
   }
 }
 
 #[derive(Debug, CanonicalSerialize, CanonicalDeserialize)]
-pub struct R1CSCommitment<G: ProjectiveCurve> {
+pub struct R1CSCommitment<G: ProjectiveCurve, PCS: MultilinearPCS<G> = Hyrax<G>> {
   num_cons: usize,
   num_vars: usize,
   num_inputs: usize,
-  comm: SparseMatPolyCommitment<G>,
+  comm: SparseMatPolyCommitment<G, PCS>,
 }
 
-impl<G: ProjectiveCurve> AppendToTranscript<G> for R1CSCommitment<G> {
+impl<G: ProjectiveCurve, PCS: MultilinearPCS<G>> AppendToTranscript<G> for R1CSCommitment<G, PCS> {
   fn append_to_transcript(&self, _label: &'static [u8], transcript: &mut Transcript) {
     transcript.append_u64(b"num_cons", self.num_cons as u64);
     transcript.append_u64(b"num_vars", self.num_vars as u64);
@@ -75,7 +143,7 @@ pub struct R1CSDecommitment<F> {
   dense: MultiSparseMatPolynomialAsDense<F>,
 }
 
-impl<G: ProjectiveCurve> R1CSCommitment<G> {
+impl<G: ProjectiveCurve, PCS: MultilinearPCS<G>> R1CSCommitment<G, PCS> {
   pub fn get_num_cons(&self) -> usize {
     self.num_cons
   }
@@ -87,6 +155,10 @@ impl<G: ProjectiveCurve> R1CSCommitment<G> {
   pub fn get_num_inputs(&self) -> usize {
     self.num_inputs
   }
+
+  pub fn get_num_instances(&self) -> usize {
+    self.comm.get_batch_size() / 3
+  }
 }
 
 impl<F: PrimeField> R1CSInstance<F> {
@@ -154,6 +226,48 @@ impl<F: PrimeField> R1CSInstance<F> {
     self.num_inputs
   }
 
+  #[allow(clippy::type_complexity)]
+  pub fn get_matrices(
+    &self,
+  ) -> (
+    Vec<(usize, usize, F)>,
+    Vec<(usize, usize, F)>,
+    Vec<(usize, usize, F)>,
+  ) {
+    (
+      self.A.get_entries(),
+      self.B.get_entries(),
+      self.C.get_entries(),
+    )
+  }
+
+  // the same instance with `num_cons` constraints and `num_vars` variables, at least as many
+  // as it has; the columns of the constant and the inputs move past the added variables
+  pub fn pad(&self, num_cons: usize, num_vars: usize) -> R1CSInstance<F> {
+    assert!(num_cons >= self.num_cons && num_vars >= self.num_vars);
+    let shift = |M: Vec<(usize, usize, F)>| {
+      M.into_iter()
+        .map(|(row, col, val)| {
+          let col = if col < self.num_vars {
+            col
+          } else {
+            col + num_vars - self.num_vars
+          };
+          (row, col, val)
+        })
+        .collect::<Vec<_>>()
+    };
+    let (A, B, C) = self.get_matrices();
+    R1CSInstance::new(
+      num_cons,
+      num_vars,
+      self.num_inputs,
+      &shift(A),
+      &shift(B),
+      &shift(C),
+    )
+  }
+
   pub fn produce_synthetic_r1cs(
     num_cons: usize,
     num_vars: usize,
@@ -264,6 +378,27 @@ impl<F: PrimeField> R1CSInstance<F> {
     res == 0
   }
 
+  // checks the relaxed relation Az * Bz = u * Cz + E, where z = (vars, u, input)
+  pub fn is_sat_relaxed(&self, vars: &[F], u: &F, input: &[F], E: &[F]) -> bool {
+    assert_eq!(vars.len(), self.num_vars);
+    assert_eq!(input.len(), self.num_inputs);
+    assert_eq!(E.len(), self.num_cons);
+
+    let z = {
+      let mut z = vars.to_vec();
+      z.push(*u);
+      z.extend(input);
+      z
+    };
+
+    let num_cols = self.num_vars + self.num_inputs + 1;
+    let Az = self.A.multiply_vec(self.num_cons, num_cols, &z);
+    let Bz = self.B.multiply_vec(self.num_cons, num_cols, &z);
+    let Cz = self.C.multiply_vec(self.num_cons, num_cols, &z);
+
+    (0..self.num_cons).all(|i| Az[i] * Bz[i] == *u * Cz[i] + E[i])
+  }
+
   pub fn multiply_vec(
     &self,
     num_rows: usize,
@@ -301,10 +436,14 @@ impl<F: PrimeField> R1CSInstance<F> {
     (evals[0], evals[1], evals[2])
   }
 
-  pub fn commit<G: ProjectiveCurve<ScalarField = F>>(
+  pub fn commit<G, PCS>(
     &self,
-    gens: &R1CSCommitmentGens<G>,
-  ) -> (R1CSCommitment<G>, R1CSDecommitment<F>) {
+    gens: &R1CSCommitmentGens<G, PCS>,
+  ) -> (R1CSCommitment<G, PCS>, R1CSDecommitment<F>)
+  where
+    G: ProjectiveCurve<ScalarField = F>,
+    PCS: MultilinearPCS<G>,
+  {
     let (comm, dense) = SparseMatPolynomial::multi_commit(&[&self.A, &self.B, &self.C], &gens.gens);
     let r1cs_comm = R1CSCommitment {
       num_cons: self.num_cons,
@@ -317,23 +456,55 @@ impl<F: PrimeField> R1CSInstance<F> {
 
     (r1cs_comm, r1cs_decomm)
   }
+
+  // commits to the matrices of several instances of the same size together, so that their
+  // evaluations at a shared point can be proven with a single Spark proof
+  pub fn multi_commit<G, PCS>(
+    insts: &[&R1CSInstance<F>],
+    gens: &R1CSCommitmentGens<G, PCS>,
+  ) -> (R1CSCommitment<G, PCS>, R1CSDecommitment<F>)
+  where
+    G: ProjectiveCurve<ScalarField = F>,
+    PCS: MultilinearPCS<G>,
+  {
+    assert!(!insts.is_empty());
+    let (num_cons, num_vars, num_inputs) =
+      (insts[0].num_cons, insts[0].num_vars, insts[0].num_inputs);
+    assert!(insts.iter().all(|inst| inst.num_cons == num_cons
+      && inst.num_vars == num_vars
+      && inst.num_inputs == num_inputs));
+
+    let polys = insts
+      .iter()
+      .flat_map(|inst| vec![&inst.A, &inst.B, &inst.C])
+      .collect::<Vec<&SparseMatPolynomial<F>>>();
+    let (comm, dense) = SparseMatPolynomial::multi_commit(&polys, &gens.gens);
+    let r1cs_comm = R1CSCommitment {
+      num_cons,
+      num_vars,
+      num_inputs,
+      comm,
+    };
+
+    (r1cs_comm, R1CSDecommitment { dense })
+  }
 }
 
 #[derive(Debug, CanonicalSerialize, CanonicalDeserialize)]
-pub struct R1CSEvalProof<G: ProjectiveCurve> {
-  proof: SparseMatPolyEvalProof<G>,
+pub struct R1CSEvalProof<G: ProjectiveCurve, PCS: MultilinearPCS<G> = Hyrax<G>> {
+  proof: SparseMatPolyEvalProof<G, PCS>,
 }
 
-impl<G: ProjectiveCurve> R1CSEvalProof<G> {
+impl<G: ProjectiveCurve, PCS: MultilinearPCS<G>> R1CSEvalProof<G, PCS> {
   pub fn prove(
     decomm: &R1CSDecommitment<G::ScalarField>,
     rx: &[G::ScalarField], // point at which the polynomial is evaluated
     ry: &[G::ScalarField],
     evals: &(G::ScalarField, G::ScalarField, G::ScalarField),
-    gens: &R1CSCommitmentGens<G>,
+    gens: &R1CSCommitmentGens<G, PCS>,
     transcript: &mut Transcript,
     random_tape: &mut RandomTape<G>,
-  ) -> R1CSEvalProof<G> {
+  ) -> R1CSEvalProof<G, PCS> {
     let timer = Timer::new("R1CSEvalProof::prove");
     let proof = SparseMatPolyEvalProof::prove(
       &decomm.dense,
@@ -349,13 +520,39 @@ impl<G: ProjectiveCurve> R1CSEvalProof<G> {
     R1CSEvalProof { proof }
   }
 
+  // proves the evaluations of the matrices of all instances committed with `multi_commit`,
+  // given in the order A, B, C of each instance
+  pub fn prove_multi(
+    decomm: &R1CSDecommitment<G::ScalarField>,
+    rx: &[G::ScalarField],
+    ry: &[G::ScalarField],
+    evals: &[G::ScalarField],
+    gens: &R1CSCommitmentGens<G, PCS>,
+    transcript: &mut Transcript,
+    random_tape: &mut RandomTape<G>,
+  ) -> R1CSEvalProof<G, PCS> {
+    let timer = Timer::new("R1CSEvalProof::prove_multi");
+    let proof = SparseMatPolyEvalProof::prove(
+      &decomm.dense,
+      rx,
+      ry,
+      evals,
+      &gens.gens,
+      transcript,
+      random_tape,
+    );
+    timer.stop();
+
+    R1CSEvalProof { proof }
+  }
+
   pub fn verify(
     &self,
-    comm: &R1CSCommitment<G>,
+    comm: &R1CSCommitment<G, PCS>,
     rx: &[G::ScalarField], // point at which the R1CS matrix polynomials are evaluated
     ry: &[G::ScalarField],
     evals: &(G::ScalarField, G::ScalarField, G::ScalarField),
-    gens: &R1CSCommitmentGens<G>,
+    gens: &R1CSCommitmentGens<G, PCS>,
     transcript: &mut Transcript,
   ) -> Result<(), ProofVerifyError> {
     self.proof.verify(
@@ -367,4 +564,40 @@ impl<G: ProjectiveCurve> R1CSEvalProof<G> {
       transcript,
     )
   }
+
+  #[allow(clippy::too_many_arguments)]
+  pub fn verify_deferred(
+    &self,
+    comm: &R1CSCommitment<G, PCS>,
+    rx: &[G::ScalarField],
+    ry: &[G::ScalarField],
+    evals: &(G::ScalarField, G::ScalarField, G::ScalarField),
+    gens: &R1CSCommitmentGens<G, PCS>,
+    transcript: &mut Transcript,
+    acc: &mut MSMAccumulator<G>,
+  ) -> Result<(), ProofVerifyError> {
+    self.proof.verify_deferred(
+      &comm.comm,
+      rx,
+      ry,
+      &[evals.0, evals.1, evals.2],
+      &gens.gens,
+      transcript,
+      acc,
+    )
+  }
+
+  pub fn verify_multi(
+    &self,
+    comm: &R1CSCommitment<G, PCS>,
+    rx: &[G::ScalarField],
+    ry: &[G::ScalarField],
+    evals: &[G::ScalarField],
+    gens: &R1CSCommitmentGens<G, PCS>,
+    transcript: &mut Transcript,
+  ) -> Result<(), ProofVerifyError> {
+    self
+      .proof
+      .verify(&comm.comm, rx, ry, evals, &gens.gens, transcript)
+  }
 }
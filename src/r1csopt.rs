@@ -0,0 +1,442 @@
+use super::errors::R1CSError;
+use super::r1csinstance::R1CSInstance;
+use super::VarsAssignment;
+use ark_ff::PrimeField;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+type Row<F> = BTreeMap<usize, F>;
+type Matrix<F> = Vec<(usize, usize, F)>;
+
+/// `OptimizationReport` summarizes the changes made to an `Instance` by `Instance::optimize`;
+/// constraint and variable indices refer to the original instance
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OptimizationReport {
+  /// number of entries merged into another entry with the same `(row, col)`
+  pub num_merged_entries: usize,
+  /// number of entries dropped because their coefficient is zero
+  pub num_zero_entries: usize,
+  /// constraints dropped because they are satisfied by every assignment
+  pub removed_trivial_cons: Vec<usize>,
+  /// constraints dropped because they repeat an earlier constraint
+  pub removed_duplicate_cons: Vec<usize>,
+  /// linear constraints dropped after substituting out a variable, as `(constraint, variable)`
+  pub eliminated_vars: Vec<(usize, usize)>,
+  /// number of constraints before and after the optimization
+  pub num_cons: (usize, usize),
+  /// number of variables before and after the optimization
+  pub num_vars: (usize, usize),
+  /// total number of non-zero entries in A, B, and C before and after the optimization
+  pub num_nz_entries: (usize, usize),
+}
+
+/// `WitnessMap` maps an assignment to the variables of an `Instance`
+/// to an assignment to the variables of the optimized `Instance`
+#[derive(Clone, Debug)]
+pub struct WitnessMap {
+  num_vars: usize,
+  var_map: Vec<usize>,
+}
+
+impl WitnessMap {
+  /// Maps a satisfying assignment of the original instance to one of the optimized instance
+  pub fn map<F: PrimeField>(
+    &self,
+    vars: &VarsAssignment<F>,
+  ) -> Result<VarsAssignment<F>, R1CSError> {
+    if vars.assignment.len() > self.num_vars {
+      return Err(R1CSError::InvalidNumberOfVars);
+    }
+
+    let value = |i: usize| -> F {
+      if i < vars.assignment.len() {
+        vars.assignment[i]
+      } else {
+        F::zero()
+      }
+    };
+
+    Ok(VarsAssignment {
+      assignment: self.var_map.iter().map(|&i| value(i)).collect(),
+    })
+  }
+
+  /// Returns the index in the optimized instance of the variable `var` of the original instance,
+  /// or `None` if it was eliminated
+  pub fn get_index(&self, var: usize) -> Option<usize> {
+    self.var_map.iter().position(|&i| i == var)
+  }
+}
+
+pub struct R1CSOptimizer<F> {
+  num_vars: usize,
+  num_inputs: usize,
+  mats: [Vec<Row<F>>; 3],
+  // for every variable, the (matrix, row) pairs in which it has a non-zero entry
+  occurrences: Vec<BTreeSet<(usize, usize)>>,
+  pinned: Vec<bool>,
+  removed: Vec<bool>,
+  // constraints without any entries, such as those added by padding
+  empty: Vec<bool>,
+  report: OptimizationReport,
+}
+
+impl<F: PrimeField> R1CSOptimizer<F> {
+  /// variables in `pinned` are kept in place and never eliminated
+  pub fn new(inst: &R1CSInstance<F>, pinned: &[usize]) -> Self {
+    let (num_cons, num_vars, num_inputs) = (
+      inst.get_num_cons(),
+      inst.get_num_vars(),
+      inst.get_num_inputs(),
+    );
+    let (A, B, C) = inst.get_matrices();
+
+    let mut report = OptimizationReport {
+      num_cons: (num_cons, 0),
+      num_vars: (num_vars, 0),
+      num_nz_entries: (A.len() + B.len() + C.len(), 0),
+      ..Default::default()
+    };
+
+    // merge entries with the same (row, col)
+    let mut mats = [
+      vec![Row::new(); num_cons],
+      vec![Row::new(); num_cons],
+      vec![Row::new(); num_cons],
+    ];
+    for (m, entries) in [A, B, C].iter().enumerate() {
+      for &(row, col, val) in entries {
+        let entry = mats[m][row].entry(col).or_insert_with(F::zero);
+        *entry += val;
+      }
+    }
+    let empty = (0..num_cons)
+      .map(|r| mats.iter().all(|mat| mat[r].is_empty()))
+      .collect::<Vec<bool>>();
+    report.num_merged_entries = report.num_nz_entries.0
+      - mats
+        .iter()
+        .map(|mat| mat.iter().map(|row| row.len()).sum::<usize>())
+        .sum::<usize>();
+
+    // drop zero coefficients, including those produced by merging
+    for mat in mats.iter_mut() {
+      for row in mat.iter_mut() {
+        let len = row.len();
+        row.retain(|_col, val| !val.is_zero());
+        report.num_zero_entries += len - row.len();
+      }
+    }
+
+    let mut occurrences = vec![BTreeSet::new(); num_vars];
+    for (m, mat) in mats.iter().enumerate() {
+      for (r, row) in mat.iter().enumerate() {
+        for &col in row.keys().filter(|&&col| col < num_vars) {
+          occurrences[col].insert((m, r));
+        }
+      }
+    }
+
+    let mut pinned_vec = vec![false; num_vars];
+    for &var in pinned {
+      pinned_vec[var] = true;
+    }
+
+    R1CSOptimizer {
+      num_vars,
+      num_inputs,
+      mats,
+      occurrences,
+      pinned: pinned_vec,
+      removed: vec![false; num_cons],
+      empty,
+      report,
+    }
+  }
+
+  // returns `L` such that the constraint is equivalent to `L * z = 0`, if it is linear
+  fn linear_form(&self, r: usize) -> Option<Row<F>> {
+    let const_col = self.num_vars;
+    let as_constant = |row: &Row<F>| -> Option<F> {
+      match row.len() {
+        0 => Some(F::zero()),
+        1 => row.get(&const_col).copied(),
+        _ => None,
+      }
+    };
+
+    let (A, B, C) = (&self.mats[0][r], &self.mats[1][r], &self.mats[2][r]);
+    let (scale, other) = if let Some(c) = as_constant(B) {
+      (c, A)
+    } else if let Some(c) = as_constant(A) {
+      (c, B)
+    } else {
+      return None;
+    };
+
+    let mut form = Row::new();
+    if !scale.is_zero() {
+      for (&col, &val) in other.iter() {
+        form.insert(col, scale * val);
+      }
+    }
+    for (&col, &val) in C.iter() {
+      let entry = form.entry(col).or_insert_with(F::zero);
+      *entry -= val;
+    }
+    form.retain(|_col, val| !val.is_zero());
+    Some(form)
+  }
+
+  fn remove_row(&mut self, r: usize) {
+    for m in 0..3 {
+      let row = std::mem::take(&mut self.mats[m][r]);
+      for &col in row.keys().filter(|&&col| col < self.num_vars) {
+        self.occurrences[col].remove(&(m, r));
+      }
+    }
+    self.removed[r] = true;
+  }
+
+  // tries to use the linear constraint `r` to substitute out one of its variables;
+  // a variable is only eliminated if doing so does not increase the number of entries
+  fn eliminate(&mut self, r: usize) -> bool {
+    let form = match self.linear_form(r) {
+      Some(form) => form,
+      None => return false,
+    };
+
+    let row_len = (0..3).map(|m| self.mats[m][r].len()).sum::<usize>();
+    let candidate = form
+      .keys()
+      .filter(|&&col| col < self.num_vars && !self.pinned[col])
+      .map(|&col| {
+        let occ = self.occurrences[col]
+          .iter()
+          .filter(|(_m, row)| *row != r)
+          .count();
+        (occ, col)
+      })
+      .filter(|&(occ, _col)| occ * (form.len() - 1) <= occ + row_len)
+      .min();
+
+    let var = match candidate {
+      Some((_occ, var)) => var,
+      None => return false,
+    };
+
+    self.remove_row(r);
+
+    // z_var = sum_{j != var} -form[j] / form[var] * z_j
+    let inv = form[&var].inverse().unwrap();
+    let subst = form
+      .iter()
+      .filter(|(&col, _val)| col != var)
+      .map(|(&col, &val)| (col, -val * inv))
+      .collect::<Vec<(usize, F)>>();
+
+    let occurrences = std::mem::take(&mut self.occurrences[var]);
+    for &(m, row) in occurrences.iter() {
+      let coeff = self.mats[m][row].remove(&var).unwrap();
+      for &(col, val) in subst.iter() {
+        let entry = self.mats[m][row].entry(col).or_insert_with(F::zero);
+        *entry += coeff * val;
+        if entry.is_zero() {
+          self.mats[m][row].remove(&col);
+          if col < self.num_vars {
+            self.occurrences[col].remove(&(m, row));
+          }
+        } else if col < self.num_vars {
+          self.occurrences[col].insert((m, row));
+        }
+      }
+    }
+
+    self.report.eliminated_vars.push((r, var));
+    true
+  }
+
+  /// substitutes out variables defined by linear constraints until no more can be eliminated
+  pub fn eliminate_linear_constraints(&mut self) {
+    let mut progress = true;
+    while progress {
+      progress = false;
+      for r in 0..self.removed.len() {
+        if !self.removed[r] && self.eliminate(r) {
+          progress = true;
+        }
+      }
+    }
+  }
+
+  /// drops constraints that hold for every assignment and constraints that repeat an earlier one
+  pub fn remove_redundant_constraints(&mut self) {
+    let mut seen = HashSet::new();
+    for r in 0..self.removed.len() {
+      if self.removed[r] {
+        continue;
+      }
+
+      let (A, B, C) = (&self.mats[0][r], &self.mats[1][r], &self.mats[2][r]);
+      if (A.is_empty() || B.is_empty()) && C.is_empty() {
+        self.remove_row(r);
+        if !self.empty[r] {
+          self.report.removed_trivial_cons.push(r);
+        }
+        continue;
+      }
+
+      // A * B = C is the same constraint as B * A = C
+      let to_vec = |row: &Row<F>| row.iter().map(|(&c, &v)| (c, v)).collect::<Vec<_>>();
+      let (a, b) = (to_vec(A), to_vec(B));
+      let key = if a <= b {
+        (a, b, to_vec(C))
+      } else {
+        (b, a, to_vec(C))
+      };
+      if !seen.insert(key) {
+        self.remove_row(r);
+        self.report.removed_duplicate_cons.push(r);
+      }
+    }
+  }
+
+  /// returns the remaining constraints as matrices over renumbered constraints and variables;
+  /// if `renumber_vars` is false, variables keep their original indices
+  pub fn finalize(
+    mut self,
+    renumber_vars: bool,
+  ) -> (
+    usize,
+    usize,
+    Matrix<F>,
+    Matrix<F>,
+    Matrix<F>,
+    WitnessMap,
+    OptimizationReport,
+  ) {
+    let var_map = if renumber_vars {
+      (0..self.num_vars)
+        .filter(|&i| self.pinned[i] || !self.occurrences[i].is_empty())
+        .collect::<Vec<usize>>()
+    } else {
+      (0..self.num_vars).collect::<Vec<usize>>()
+    };
+    let num_vars = var_map.len();
+
+    let mut col_map = vec![None; self.num_vars + 1 + self.num_inputs];
+    for (i, &var) in var_map.iter().enumerate() {
+      col_map[var] = Some(i);
+    }
+    for i in 0..self.num_inputs + 1 {
+      col_map[self.num_vars + i] = Some(num_vars + i);
+    }
+
+    let rows = (0..self.removed.len())
+      .filter(|&r| !self.removed[r])
+      .collect::<Vec<usize>>();
+    let mut mats = [Vec::new(), Vec::new(), Vec::new()];
+    for (m, mat) in mats.iter_mut().enumerate() {
+      for (new_r, &r) in rows.iter().enumerate() {
+        for (&col, &val) in self.mats[m][r].iter() {
+          mat.push((new_r, col_map[col].unwrap(), val));
+        }
+      }
+    }
+    let [A, B, C] = mats;
+
+    self.report.num_cons.1 = rows.len();
+    self.report.num_vars.1 = num_vars;
+    self.report.num_nz_entries.1 = A.len() + B.len() + C.len();
+
+    let witness_map = WitnessMap {
+      num_vars: self.num_vars,
+      var_map,
+    };
+    (rows.len(), num_vars, A, B, C, witness_map, self.report)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::{InputsAssignment, Instance};
+  use super::*;
+
+  crate::curves::curve_tests!(field: check_optimize, check_optimize_helper);
+
+  fn check_optimize_helper<F: PrimeField>() {
+    let num_cons = 6;
+    let num_vars = 4;
+    let num_inputs = 1;
+    let (one, two) = (F::one(), F::from(2u64));
+    let const_col = num_vars;
+    let input_col = num_vars + 1;
+
+    // z_0 = 2 * x, expressed as (z_0 - x) * 1 = x with duplicate entries in A
+    // z_1 = z_0 * z_0
+    // z_1 = z_0 * z_0 (a duplicate)
+    // z_2 = z_1 * x
+    // 0 * z_3 = 0 (trivial)
+    // z_3 = z_2 + z_1, which is linear
+    let A = vec![
+      (0, 0, one),
+      (0, input_col, -two),
+      (0, input_col, one),
+      (1, 0, one),
+      (2, 0, one),
+      (3, 1, one),
+      (4, 3, F::zero()),
+      (5, 2, one),
+      (5, 1, one),
+    ];
+    let B = vec![
+      (0, const_col, one),
+      (1, 0, one),
+      (2, 0, one),
+      (3, input_col, one),
+      (4, 3, one),
+      (5, const_col, one),
+    ];
+    let C = vec![
+      (0, input_col, one),
+      (1, 1, one),
+      (2, 1, one),
+      (3, 2, one),
+      (5, 3, one),
+    ];
+
+    let x = F::from(3u64);
+    let vars = VarsAssignment::new(&[
+      two * x,
+      (two * x).square(),
+      (two * x).square() * x,
+      (two * x).square() * x + (two * x).square(),
+    ])
+    .unwrap();
+    let inputs = InputsAssignment::new(&[x]).unwrap();
+
+    let inst = Instance::new(num_cons, num_vars, num_inputs, &A, &B, &C).unwrap();
+    assert!(inst.is_sat(&vars, &inputs).unwrap());
+
+    let (opt_inst, witness_map, report) = inst.optimize().unwrap();
+    assert_eq!(report.num_merged_entries, 1);
+    assert_eq!(report.num_zero_entries, 1);
+    assert_eq!(report.removed_trivial_cons, vec![4]);
+    assert_eq!(report.removed_duplicate_cons, vec![2]);
+    assert_eq!(report.eliminated_vars, vec![(0, 0), (5, 2)]);
+    assert_eq!(report.num_cons, (8, 2));
+    assert_eq!(report.num_vars, (4, 2));
+    assert!(report.num_nz_entries.1 < report.num_nz_entries.0);
+
+    assert_eq!(witness_map.get_index(0), None);
+    assert_eq!(witness_map.get_index(3), Some(1));
+    let opt_vars = witness_map.map(&vars).unwrap();
+    assert_eq!(opt_vars.assignment.len(), 2);
+    assert!(opt_inst.is_sat(&opt_vars, &inputs).unwrap());
+
+    // a wrong witness for the original instance does not satisfy the optimized instance
+    let mut bad_vars = vars;
+    bad_vars.assignment[1] += one;
+    let bad_opt_vars = witness_map.map(&bad_vars).unwrap();
+    assert!(!opt_inst.is_sat(&bad_opt_vars, &inputs).unwrap());
+  }
+}
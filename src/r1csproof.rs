@@ -1,12 +1,11 @@
 #![allow(clippy::too_many_arguments)]
-use super::commitments::{Commitments, MultiCommitGens};
-use super::dense_mlpoly::{
-  DensePolynomial, EqPolynomial, PolyCommitment, PolyCommitmentGens, PolyEvalProof,
-};
+use super::ccsinstance::{r1cs_terms, CCSInstance, CCSMatrices};
+use super::commitments::{Commitments, MSMAccumulator, MultiCommitGens};
+use super::dense_mlpoly::{DensePolynomial, EqPolynomial};
 use super::errors::ProofVerifyError;
 use super::math::Math;
 use super::nizk::{EqualityProof, KnowledgeProof, ProductProof};
-use super::r1csinstance::R1CSInstance;
+use super::pcs::{HidingMultilinearPCS, Hyrax, MultilinearPCS, TransparentMultilinearPCS};
 use super::random::RandomTape;
 use super::sparse_mlpoly::{SparsePolyEntry, SparsePolynomial};
 use super::sumcheck::ZKSumcheckInstanceProof;
@@ -19,57 +18,92 @@ use ark_serialize::*;
 use ark_std::{One, Zero};
 use merlin::Transcript;
 
+// a proof of the satisfiability of a customizable constraint system, of which R1CS is the
+// special case with terms Az * Bz and -Cz; the claimed M_i z are proven with a chain of product
+// proofs per term, and with a proof of knowledge if they are not a factor in any product
 #[derive(CanonicalSerialize, CanonicalDeserialize, Debug)]
-pub struct R1CSProof<G: ProjectiveCurve> {
-  comm_vars: PolyCommitment<G>,
+pub struct R1CSProof<G: ProjectiveCurve, PCS: HidingMultilinearPCS<G> = Hyrax<G>> {
+  comm_vars: PCS::Commitment,
   sc_proof_phase1: ZKSumcheckInstanceProof<G>,
-  claims_phase2: (G, G, G, G),
-  pok_claims_phase2: (KnowledgeProof<G>, ProductProof<G>),
+  comm_Mz_claims: Vec<G>,
+  pok_Mz_claims: Vec<KnowledgeProof<G>>,
+  // for each term, commitments to the running products of its factors and proofs of each step
+  comm_prods: Vec<Vec<G>>,
+  proof_prods: Vec<Vec<ProductProof<G>>>,
   proof_eq_sc_phase1: EqualityProof<G>,
   sc_proof_phase2: ZKSumcheckInstanceProof<G>,
   comm_vars_at_ry: G,
-  proof_eval_vars_at_ry: PolyEvalProof<G>,
+  proof_eval_vars_at_ry: PCS::EvalProof,
   proof_eq_sc_phase2: EqualityProof<G>,
 }
 
 pub struct R1CSSumcheckGens<G> {
-  gens_1: MultiCommitGens<G>,
-  gens_3: MultiCommitGens<G>,
-  gens_4: MultiCommitGens<G>,
+  pub gens_1: MultiCommitGens<G>,
+  pub gens_3: MultiCommitGens<G>,
+  pub gens_d: MultiCommitGens<G>,
 }
 
-// TODO: fix passing gens_1_ref
 impl<G: ProjectiveCurve> R1CSSumcheckGens<G> {
-  pub fn new(label: &'static [u8], gens_1_ref: &MultiCommitGens<G>) -> Self {
-    let gens_1 = gens_1_ref.clone();
+  // the first sum-check is over eq(tau, x) times a degree-`degree` combination of the M_i z
+  pub fn new(label: &'static [u8], gens_1: MultiCommitGens<G>, degree: usize) -> Self {
     let gens_3 = MultiCommitGens::new(3, label);
-    let gens_4 = MultiCommitGens::new(4, label);
+    let gens_d = MultiCommitGens::new(degree + 2, label);
 
     R1CSSumcheckGens {
       gens_1,
       gens_3,
-      gens_4,
+      gens_d,
     }
   }
 }
 
-pub struct R1CSGens<G> {
-  gens_sc: R1CSSumcheckGens<G>,
-  gens_pc: PolyCommitmentGens<G>,
+pub struct R1CSGens<G: ProjectiveCurve, PCS: MultilinearPCS<G> = Hyrax<G>> {
+  pub gens_sc: R1CSSumcheckGens<G>,
+  pub gens_pc: PCS::Gens,
 }
 
-impl<G: ProjectiveCurve> R1CSGens<G> {
-  pub fn new(label: &'static [u8], _num_cons: usize, num_vars: usize) -> Self {
+impl<G: ProjectiveCurve, PCS: TransparentMultilinearPCS<G>> R1CSGens<G, PCS> {
+  pub fn new(label: &'static [u8], num_cons: usize, num_vars: usize) -> Self {
+    R1CSGens::new_with_degree(label, num_cons, num_vars, 2)
+  }
+
+  // generators for instances whose terms multiply up to `degree` matrices together
+  pub fn new_with_degree(
+    label: &'static [u8],
+    num_cons: usize,
+    num_vars: usize,
+    degree: usize,
+  ) -> Self {
+    R1CSGens::new_with_setup(label, num_cons, num_vars, degree, PCS::setup)
+  }
+}
+
+impl<G: ProjectiveCurve, PCS: MultilinearPCS<G>> R1CSGens<G, PCS> {
+  // generators whose public parameters for the commitment scheme come from `setup`
+  pub fn new_with_setup<S>(
+    label: &'static [u8],
+    _num_cons: usize,
+    num_vars: usize,
+    degree: usize,
+    setup: S,
+  ) -> Self
+  where
+    S: Fn(usize, &'static [u8]) -> PCS::Gens,
+  {
     let num_poly_vars = num_vars.log_2() as usize;
-    let gens_pc = PolyCommitmentGens::new(num_poly_vars, label);
-    let gens_sc = R1CSSumcheckGens::new(label, &gens_pc.gens.gens_1);
+    let gens_pc = setup(num_poly_vars, label);
+    let gens_sc = R1CSSumcheckGens::new(label, PCS::gens_eval(&gens_pc, label), degree);
     R1CSGens { gens_sc, gens_pc }
   }
+
+  pub fn get_degree(&self) -> usize {
+    self.gens_sc.gens_d.n - 2
+  }
 }
 
-impl<G: ProjectiveCurve> R1CSProof<G> {
+impl<G: ProjectiveCurve, PCS: HidingMultilinearPCS<G>> R1CSProof<G, PCS> {
   #[allow(clippy::type_complexity)]
-  fn prove_phase_one(
+  pub fn prove_phase_one(
     num_rounds: usize,
     evals_tau: &mut DensePolynomial<G::ScalarField>,
     evals_Az: &mut DensePolynomial<G::ScalarField>,
@@ -102,7 +136,7 @@ impl<G: ProjectiveCurve> R1CSProof<G> {
         evals_Cz,
         comb_func,
         &gens.gens_1,
-        &gens.gens_4,
+        &gens.gens_d,
         transcript,
         random_tape,
       );
@@ -111,7 +145,7 @@ impl<G: ProjectiveCurve> R1CSProof<G> {
   }
 
   #[allow(clippy::type_complexity)]
-  fn prove_phase_two(
+  pub fn prove_phase_two(
     num_rounds: usize,
     claim: &G::ScalarField,
     blind_claim: &G::ScalarField,
@@ -149,30 +183,181 @@ impl<G: ProjectiveCurve> R1CSProof<G> {
     b"R1CS proof"
   }
 
-  pub fn prove(
-    inst: &R1CSInstance<G::ScalarField>,
+  // besides the proof and the point (rx, ry), returns the witness polynomial and the blinds
+  // used to commit to it, so that callers can open `comm_vars` at additional points; `inst` is
+  // an R1CS instance or any other customizable constraint system whose degree is that of `gens`
+  #[allow(clippy::type_complexity)]
+  pub fn prove<I: CCSMatrices<G::ScalarField>>(
+    inst: &I,
+    vars: Vec<G::ScalarField>,
+    input: &[G::ScalarField],
+    gens: &R1CSGens<G, PCS>,
+    transcript: &mut Transcript,
+    random_tape: &mut RandomTape<G>,
+  ) -> (
+    R1CSProof<G, PCS>,
+    Vec<G::ScalarField>,
+    Vec<G::ScalarField>,
+    (DensePolynomial<G::ScalarField>, PCS::Blinds),
+  ) {
+    R1CSProof::prove_committed(
+      inst,
+      vars,
+      input,
+      |poly, gens, random_tape| PCS::commit(poly, gens, Some(random_tape)),
+      &[],
+      gens,
+      transcript,
+      random_tape,
+    )
+  }
+
+  // like `prove`, except that the witness is committed with `commit`, so that callers can tie
+  // the commitment to commitments made elsewhere, and that the inputs at the indices in
+  // `hidden_blinds` are only given to the verifier as Pedersen commitments with the given blinds
+  #[allow(clippy::type_complexity)]
+  pub fn prove_committed<I, C>(
+    inst: &I,
     vars: Vec<G::ScalarField>,
     input: &[G::ScalarField],
-    gens: &R1CSGens<G>,
+    commit: C,
+    hidden_blinds: &[(usize, G::ScalarField)],
+    gens: &R1CSGens<G, PCS>,
     transcript: &mut Transcript,
     random_tape: &mut RandomTape<G>,
-  ) -> (R1CSProof<G>, Vec<G::ScalarField>, Vec<G::ScalarField>) {
+  ) -> (
+    R1CSProof<G, PCS>,
+    Vec<G::ScalarField>,
+    Vec<G::ScalarField>,
+    (DensePolynomial<G::ScalarField>, PCS::Blinds),
+  )
+  where
+    I: CCSMatrices<G::ScalarField>,
+    C: FnOnce(
+      &DensePolynomial<G::ScalarField>,
+      &PCS::Gens,
+      &mut RandomTape<G>,
+    ) -> (PCS::Commitment, PCS::Blinds),
+  {
+    R1CSProof::prove_batched_committed(
+      &[inst],
+      vec![vars],
+      &[input.to_vec()],
+      commit,
+      hidden_blinds,
+      gens,
+      transcript,
+      random_tape,
+    )
+  }
+
+  // proves that each of the assignments satisfies its instance with data-parallel Spartan:
+  // the assignments are committed together as one polynomial over (instance, variable), and the
+  // first sum-check runs over (instance, constraint). If all assignments share one instance, the
+  // second sum-check runs over the assignments folded at the instance part r_b of the point of
+  // the first, so the matrices only need to be evaluated at (rx, ry). Otherwise, `insts` holds an
+  // instance of the same size and terms for each assignment, and the second sum-check runs over
+  // (instance, variable), which leaves claims about every instance's matrices at (rx, ry).
+  // The number of assignments is padded to a power of two with all-zero vectors z, which
+  // trivially satisfy the constraints, and `gens` must be for the padded size of the witness
+  #[allow(clippy::type_complexity)]
+  pub fn prove_batched<I: CCSMatrices<G::ScalarField>>(
+    insts: &[&I],
+    vars: Vec<Vec<G::ScalarField>>,
+    inputs: &[Vec<G::ScalarField>],
+    gens: &R1CSGens<G, PCS>,
+    transcript: &mut Transcript,
+    random_tape: &mut RandomTape<G>,
+  ) -> (
+    R1CSProof<G, PCS>,
+    Vec<G::ScalarField>,
+    Vec<G::ScalarField>,
+    (DensePolynomial<G::ScalarField>, PCS::Blinds),
+  ) {
+    R1CSProof::prove_batched_committed(
+      insts,
+      vars,
+      inputs,
+      |poly, gens, random_tape| PCS::commit(poly, gens, Some(random_tape)),
+      &[],
+      gens,
+      transcript,
+      random_tape,
+    )
+  }
+
+  #[allow(clippy::type_complexity)]
+  fn prove_batched_committed<I, C>(
+    insts: &[&I],
+    vars: Vec<Vec<G::ScalarField>>,
+    inputs: &[Vec<G::ScalarField>],
+    commit: C,
+    hidden_blinds: &[(usize, G::ScalarField)],
+    gens: &R1CSGens<G, PCS>,
+    transcript: &mut Transcript,
+    random_tape: &mut RandomTape<G>,
+  ) -> (
+    R1CSProof<G, PCS>,
+    Vec<G::ScalarField>,
+    Vec<G::ScalarField>,
+    (DensePolynomial<G::ScalarField>, PCS::Blinds),
+  )
+  where
+    I: CCSMatrices<G::ScalarField>,
+    C: FnOnce(
+      &DensePolynomial<G::ScalarField>,
+      &PCS::Gens,
+      &mut RandomTape<G>,
+    ) -> (PCS::Commitment, PCS::Blinds),
+  {
     let timer_prove = Timer::new("R1CSProof::prove");
     <Transcript as ProofTranscript<G>>::append_protocol_name(
       transcript,
-      R1CSProof::<G>::protocol_name(),
+      R1CSProof::<G, PCS>::protocol_name(),
     );
 
     // we currently require the number of |inputs| + 1 to be at most number of vars
-    assert!(input.len() < vars.len());
-    <Transcript as ProofTranscript<G>>::append_scalars(transcript, b"input", input);
+    assert!(!vars.is_empty());
+    assert_eq!(vars.len(), inputs.len());
+    let num_vars = vars[0].len();
+    let hidden = hidden_blinds
+      .iter()
+      .map(|(i, _blind)| *i)
+      .collect::<Vec<_>>();
+    for (vars, input) in vars.iter().zip(inputs.iter()) {
+      assert_eq!(vars.len(), num_vars);
+      assert!(input.len() < num_vars);
+      let input = visible_inputs(input, &hidden);
+      <Transcript as ProofTranscript<G>>::append_scalars(transcript, b"input", &input);
+    }
+    // hidden inputs are only supported for a single assignment
+    assert!(hidden.is_empty() || inputs.len() == 1);
+    assert!(are_valid_hidden(&hidden, inputs[0].len()));
+    for (i, blind) in hidden_blinds {
+      let comm_input = inputs[0][*i].commit(blind, &gens.gens_sc.gens_1);
+      transcript.append_u64(b"hidden_index", *i as u64);
+      <Transcript as ProofTranscript<G>>::append_point(transcript, b"comm_input", &comm_input);
+    }
+    assert!(insts.len() == 1 || insts.len() == vars.len());
+    let inst = insts[0];
+    let (coeffs, multisets) = inst.get_terms();
+    assert!(insts.iter().all(|i| i.get_num_cons() == inst.get_num_cons()
+      && i.get_num_vars() == num_vars
+      && i.get_terms() == (coeffs.clone(), multisets.clone())));
+    let degree = multisets.iter().map(|m| m.len()).max().unwrap_or(1);
+    assert_eq!(gens.get_degree(), degree);
+    let num_instances = vars.len().next_power_of_two();
+    let num_rounds_b = num_instances.log_2() as usize;
+
     let timer_commit = Timer::new("polycommit");
     let (poly_vars, comm_vars, blinds_vars) = {
-      // create a multilinear polynomial using the supplied assignment for variables
-      let poly_vars = DensePolynomial::<G::ScalarField>::new(vars.clone());
+      // create a multilinear polynomial using the supplied assignments for variables
+      let mut stacked_vars = vars.concat();
+      stacked_vars.resize(num_instances * num_vars, G::ScalarField::zero());
+      let poly_vars = DensePolynomial::<G::ScalarField>::new(stacked_vars);
 
       // produce a commitment to the satisfying assignment
-      let (comm_vars, blinds_vars) = poly_vars.commit(&gens.gens_pc, Some(random_tape));
+      let (comm_vars, blinds_vars) = commit(&poly_vars, &gens.gens_pc, random_tape);
 
       // add the commitment to the prover's transcript
       comm_vars.append_to_transcript(b"poly_commitment", transcript);
@@ -182,96 +367,143 @@ impl<G: ProjectiveCurve> R1CSProof<G> {
 
     let timer_sc_proof_phase1 = Timer::new("prove_sc_phase_one");
 
-    // append input to variables to create a single vector z
-    let z = {
-      let num_inputs = input.len();
-      let num_vars = vars.len();
-      let mut z = vars;
-      z.extend(&vec![G::ScalarField::one()]); // add constant term in z
-      z.extend(input);
-      z.extend(&vec![G::ScalarField::zero(); num_vars - num_inputs - 1]); // we will pad with zeros
-      z
+    // append input to variables to create a single vector z for each assignment
+    let zs = {
+      let mut zs = vars
+        .into_iter()
+        .zip(inputs.iter())
+        .map(|(vars, input)| assignment_z(vars, input))
+        .collect::<Vec<Vec<G::ScalarField>>>();
+      zs.resize(num_instances, vec![G::ScalarField::zero(); 2 * num_vars]);
+      zs
     };
 
     // derive the verifier's challenge tau
     let (num_rounds_x, num_rounds_y) = (
       inst.get_num_cons().log_2() as usize,
-      z.len().log_2() as usize,
+      (2 * num_vars).log_2() as usize,
     );
     let tau = <Transcript as ProofTranscript<G>>::challenge_vector(
       transcript,
       b"challenge_tau",
-      num_rounds_x,
+      num_rounds_b + num_rounds_x,
     );
 
-    // compute the initial evaluation table for R(\tau, x)
-    let mut poly_tau = DensePolynomial::new(EqPolynomial::new(tau).evals());
-    let (mut poly_Az, mut poly_Bz, mut poly_Cz) =
-      inst.multiply_vec(inst.get_num_cons(), z.len(), &z);
-
-    let (sc_proof_phase1, rx, _claims_phase1, blind_claim_postsc1) = R1CSProof::prove_phase_one(
-      num_rounds_x,
-      &mut poly_tau,
-      &mut poly_Az,
-      &mut poly_Bz,
-      &mut poly_Cz,
-      &gens.gens_sc,
-      transcript,
-      random_tape,
-    );
-    assert_eq!(poly_tau.len(), 1);
-    assert_eq!(poly_Az.len(), 1);
-    assert_eq!(poly_Bz.len(), 1);
-    assert_eq!(poly_Cz.len(), 1);
-    timer_sc_proof_phase1.stop();
-
-    let (tau_claim, Az_claim, Bz_claim, Cz_claim) =
-      (&poly_tau[0], &poly_Az[0], &poly_Bz[0], &poly_Cz[0]);
-    let (Az_blind, Bz_blind, Cz_blind, prod_Az_Bz_blind) = (
-      random_tape.random_scalar(b"Az_blind"),
-      random_tape.random_scalar(b"Bz_blind"),
-      random_tape.random_scalar(b"Cz_blind"),
-      random_tape.random_scalar(b"prod_Az_Bz_blind"),
-    );
-
-    let (pok_Cz_claim, comm_Cz_claim) = {
-      KnowledgeProof::prove(
-        &gens.gens_sc.gens_1,
-        transcript,
-        random_tape,
-        Cz_claim,
-        &Cz_blind,
-      )
+    // compute the initial evaluation tables for eq(\tau, x) and each M_i z
+    let mut polys = phase_one_tables(insts, &zs, tau);
+
+    // the first sum-check is over eq(tau, x) * sum_j c_j * prod_{i in S_j} (M_i z)(x), for
+    // which R1CS has a specialized prover of eq(tau, x) * (Az(x) * Bz(x) - Cz(x))
+    let num_rounds = num_rounds_b + num_rounds_x;
+    let is_r1cs = (coeffs.clone(), multisets.clone()) == r1cs_terms::<G::ScalarField>();
+    let (sc_proof_phase1, rx, claims_phase1, blind_claim_postsc1) = match &mut polys[..] {
+      [poly_tau, poly_Az, poly_Bz, poly_Cz] if is_r1cs => {
+        let (sc_proof_phase1, rx, _claims_phase1, blind_claim_postsc1) =
+          R1CSProof::<G, PCS>::prove_phase_one(
+            num_rounds,
+            poly_tau,
+            poly_Az,
+            poly_Bz,
+            poly_Cz,
+            &gens.gens_sc,
+            transcript,
+            random_tape,
+          );
+        let claims_phase1 = vec![poly_tau[0], poly_Az[0], poly_Bz[0], poly_Cz[0]];
+        (sc_proof_phase1, rx, claims_phase1, blind_claim_postsc1)
+      }
+      polys => {
+        let comb_func = |vals: &[G::ScalarField]| -> G::ScalarField {
+          vals[0] * CCSInstance::combine(&coeffs, &multisets, &vals[1..])
+        };
+        ZKSumcheckInstanceProof::prove_arbitrary(
+          &G::ScalarField::zero(), // claim is zero
+          &G::ScalarField::zero(), // blind for claim is also zero
+          num_rounds,
+          polys,
+          degree + 1,
+          comb_func,
+          &gens.gens_sc.gens_1,
+          &gens.gens_sc.gens_d,
+          transcript,
+          random_tape,
+        )
+      }
     };
+    timer_sc_proof_phase1.stop();
 
-    let (proof_prod, comm_Az_claim, comm_Bz_claim, comm_prod_Az_Bz_claims) = {
-      let prod = *Az_claim * *Bz_claim;
-      ProductProof::prove(
-        &gens.gens_sc.gens_1,
-        transcript,
-        random_tape,
-        Az_claim,
-        &Az_blind,
-        Bz_claim,
-        &Bz_blind,
-        &prod,
-        &prod_Az_Bz_blind,
-      )
-    };
+    let (tau_claim, Mz_claims) = (&claims_phase1[0], &claims_phase1[1..]);
+    let Mz_blinds = random_tape.random_vector(b"Mz_blinds", Mz_claims.len());
+    let comm_Mz_claims = (0..Mz_claims.len())
+      .map(|i| Mz_claims[i].commit(&Mz_blinds[i], &gens.gens_sc.gens_1))
+      .collect::<Vec<G>>();
+
+    // the product proofs below also prove knowledge of their factors
+    let pok_Mz_claims = (0..Mz_claims.len())
+      .filter(|&i| !is_factor(&multisets, i))
+      .map(|i| {
+        let (pok, _comm) = KnowledgeProof::prove(
+          &gens.gens_sc.gens_1,
+          transcript,
+          random_tape,
+          &Mz_claims[i],
+          &Mz_blinds[i],
+        );
+        pok
+      })
+      .collect::<Vec<KnowledgeProof<G>>>();
+
+    // prove each term's product one factor at a time
+    let mut comm_prods = Vec::new();
+    let mut proof_prods = Vec::new();
+    let mut term_claims = Vec::new();
+    let mut term_blinds = Vec::new();
+    for multiset in multisets.iter() {
+      let (mut prod, mut prod_blind) = (Mz_claims[multiset[0]], Mz_blinds[multiset[0]]);
+      let mut comms = Vec::new();
+      let mut proofs = Vec::new();
+      for &i in multiset[1..].iter() {
+        let next = prod * Mz_claims[i];
+        let next_blind = random_tape.random_scalar(b"prod_blind");
+        let (proof, _comm_prod, _comm_Mz_claim, comm_next) = ProductProof::prove(
+          &gens.gens_sc.gens_1,
+          transcript,
+          random_tape,
+          &prod,
+          &prod_blind,
+          &Mz_claims[i],
+          &Mz_blinds[i],
+          &next,
+          &next_blind,
+        );
+        comms.push(comm_next);
+        proofs.push(proof);
+        prod = next;
+        prod_blind = next_blind;
+      }
+      comm_prods.push(comms);
+      proof_prods.push(proofs);
+      term_claims.push(prod);
+      term_blinds.push(prod_blind);
+    }
 
-    <Transcript as ProofTranscript<G>>::append_point(transcript, b"comm_Az_claim", &comm_Az_claim);
-    <Transcript as ProofTranscript<G>>::append_point(transcript, b"comm_Bz_claim", &comm_Bz_claim);
-    <Transcript as ProofTranscript<G>>::append_point(transcript, b"comm_Cz_claim", &comm_Cz_claim);
-    <Transcript as ProofTranscript<G>>::append_point(
-      transcript,
-      b"comm_prod_Az_Bz_claims",
-      &comm_prod_Az_Bz_claims,
-    );
+    for comm in comm_Mz_claims.iter() {
+      <Transcript as ProofTranscript<G>>::append_point(transcript, b"comm_Mz_claim", comm);
+    }
+    for comm in comm_prods.iter().flatten() {
+      <Transcript as ProofTranscript<G>>::append_point(transcript, b"comm_prod_claim", comm);
+    }
 
     // prove the final step of sum-check #1
     let taus_bound_rx = tau_claim;
-    let blind_expected_claim_postsc1 = *taus_bound_rx * (prod_Az_Bz_blind - Cz_blind);
-    let claim_post_phase1 = (*Az_claim * *Bz_claim - *Cz_claim) * *taus_bound_rx;
+    let blind_expected_claim_postsc1 = *taus_bound_rx
+      * (0..coeffs.len())
+        .map(|j| coeffs[j] * term_blinds[j])
+        .sum::<G::ScalarField>();
+    let claim_post_phase1 = *taus_bound_rx
+      * (0..coeffs.len())
+        .map(|j| coeffs[j] * term_claims[j])
+        .sum::<G::ScalarField>();
     let (proof_eq_sc_phase1, _C1, _C2) = EqualityProof::prove(
       &gens.gens_sc.gens_1,
       transcript,
@@ -283,60 +515,92 @@ impl<G: ProjectiveCurve> R1CSProof<G> {
     );
 
     let timer_sc_proof_phase2 = Timer::new("prove_sc_phase_two");
-    // combine the three claims into a single claim
-    let r_A = <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"challenege_Az");
-    let r_B = <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"challenege_Bz");
-    let r_C = <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"challenege_Cz");
-    let claim_phase2 = r_A * Az_claim + r_B * Bz_claim + r_C * Cz_claim;
-    let blind_claim_phase2 = r_A * Az_blind + r_B * Bz_blind + r_C * Cz_blind;
-
-    let evals_ABC = {
-      // compute the initial evaluation table for R(\tau, x)
-      let evals_rx = EqPolynomial::new(rx.clone()).evals();
-      let (evals_A, evals_B, evals_C) =
-        inst.compute_eval_table_sparse(inst.get_num_cons(), z.len(), &evals_rx);
-
-      assert_eq!(evals_A.len(), evals_B.len());
-      assert_eq!(evals_A.len(), evals_C.len());
-      (0..evals_A.len())
-        .map(|i| r_A * evals_A[i] + r_B * evals_B[i] + r_C * evals_C[i])
-        .collect::<Vec<G::ScalarField>>()
+    // combine the claims about the matrices into a single claim
+    let r_M = <Transcript as ProofTranscript<G>>::challenge_vector(
+      transcript,
+      b"challenge_Mz",
+      Mz_claims.len(),
+    );
+    let claim_phase2 = (0..r_M.len()).map(|i| r_M[i] * Mz_claims[i]).sum();
+    let blind_claim_phase2 = (0..r_M.len()).map(|i| r_M[i] * Mz_blinds[i]).sum();
+
+    let (r_b, rx) = (rx[..num_rounds_b].to_vec(), rx[num_rounds_b..].to_vec());
+    let evals_rb = EqPolynomial::new(r_b.clone()).evals();
+    let evals_M = |inst: &I| combined_eval_table(inst, &rx, &r_M, num_vars);
+
+    let (poly_z, poly_M, num_rounds_z) = if insts.len() == 1 {
+      // fold the assignments at the instance part of the point of the first sum-check
+      let z = (0..2 * num_vars)
+        .map(|j| (0..num_instances).map(|b| evals_rb[b] * zs[b][j]).sum())
+        .collect::<Vec<G::ScalarField>>();
+      (z, evals_M(inst), 0)
+    } else {
+      // weigh the matrices of each instance by its share of the claims
+      let mut evals = Vec::new();
+      for b in 0..num_instances {
+        if b < insts.len() {
+          evals.extend(evals_M(insts[b]).iter().map(|e| evals_rb[b] * e));
+        } else {
+          evals.extend(vec![G::ScalarField::zero(); 2 * num_vars]);
+        }
+      }
+      (zs.concat(), evals, num_rounds_b)
     };
 
     // another instance of the sum-check protocol
-    let (sc_proof_phase2, ry, claims_phase2, blind_claim_postsc2) = R1CSProof::prove_phase_two(
-      num_rounds_y,
-      &claim_phase2,
-      &blind_claim_phase2,
-      &mut DensePolynomial::new(z),
-      &mut DensePolynomial::new(evals_ABC),
-      &gens.gens_sc,
-      transcript,
-      random_tape,
-    );
+    let (sc_proof_phase2, ry, claims_phase2, blind_claim_postsc2) =
+      R1CSProof::<G, PCS>::prove_phase_two(
+        num_rounds_z + num_rounds_y,
+        &claim_phase2,
+        &blind_claim_phase2,
+        &mut DensePolynomial::new(poly_z),
+        &mut DensePolynomial::new(poly_M),
+        &gens.gens_sc,
+        transcript,
+        random_tape,
+      );
     timer_sc_proof_phase2.stop();
 
+    // the assignments are evaluated at r_b if they were folded, and at the instance part of the
+    // point of the second sum-check otherwise
+    let (r_z, ry) = if insts.len() == 1 {
+      (r_b, ry)
+    } else {
+      (ry[..num_rounds_z].to_vec(), ry[num_rounds_z..].to_vec())
+    };
+
     let timer_polyeval = Timer::new("polyeval");
-    let eval_vars_at_ry = poly_vars.evaluate::<G>(&ry[1..]);
+    let r_vars = [&r_z, &ry[1..]].concat();
+    let eval_vars_at_ry = poly_vars.evaluate::<G>(&r_vars);
     let blind_eval = random_tape.random_scalar(b"blind_eval");
-    let (proof_eval_vars_at_ry, comm_vars_at_ry) = PolyEvalProof::prove(
+    let (proof_eval_vars_at_ry, comm_vars_at_ry) = PCS::prove_hiding(
       &poly_vars,
       Some(&blinds_vars),
-      &ry[1..],
+      &r_vars,
       &eval_vars_at_ry,
-      Some(&blind_eval),
+      &blind_eval,
       &gens.gens_pc,
       transcript,
       random_tape,
     );
     timer_polyeval.stop();
 
-    // prove the final step of sum-check #2
-    let blind_eval_Z_at_ry = (G::ScalarField::one() - ry[0]) * blind_eval;
+    // prove the final step of sum-check #2, where the evaluation of the inputs is blinded by
+    // the blinds of the hidden inputs
+    let blind_inputs_at_ry = SparsePolynomial::new(
+      num_vars.log_2() as usize,
+      hidden_blinds
+        .iter()
+        .map(|(i, blind)| SparsePolyEntry::new(i + 1, *blind))
+        .collect(),
+    )
+    .evaluate(&ry[1..]);
+    let blind_eval_Z_at_ry =
+      (G::ScalarField::one() - ry[0]) * blind_eval + ry[0] * blind_inputs_at_ry;
     let blind_expected_claim_postsc2 = claims_phase2[1] * blind_eval_Z_at_ry;
     let claim_post_phase2 = claims_phase2[0] * claims_phase2[1];
     let (proof_eq_sc_phase2, _C1, _C2) = EqualityProof::prove(
-      &gens.gens_pc.gens.gens_1,
+      &gens.gens_sc.gens_1,
       transcript,
       random_tape,
       &claim_post_phase2,
@@ -351,13 +615,10 @@ impl<G: ProjectiveCurve> R1CSProof<G> {
       R1CSProof {
         comm_vars,
         sc_proof_phase1,
-        claims_phase2: (
-          comm_Az_claim,
-          comm_Bz_claim,
-          comm_Cz_claim,
-          comm_prod_Az_Bz_claims,
-        ),
-        pok_claims_phase2: (pok_Cz_claim, proof_prod),
+        comm_Mz_claims,
+        pok_Mz_claims,
+        comm_prods,
+        proof_prods,
         proof_eq_sc_phase1,
         sc_proof_phase2,
         comm_vars_at_ry,
@@ -366,9 +627,14 @@ impl<G: ProjectiveCurve> R1CSProof<G> {
       },
       rx,
       ry,
+      (poly_vars, blinds_vars),
     )
   }
 
+  pub fn get_comm_vars(&self) -> &PCS::Commitment {
+    &self.comm_vars
+  }
+
   #[allow(clippy::type_complexity)]
   pub fn verify(
     &self,
@@ -377,14 +643,168 @@ impl<G: ProjectiveCurve> R1CSProof<G> {
     input: &[G::ScalarField],
     evals: &(G::ScalarField, G::ScalarField, G::ScalarField),
     transcript: &mut Transcript,
-    gens: &R1CSGens<G>,
+    gens: &R1CSGens<G, PCS>,
   ) -> Result<(Vec<G::ScalarField>, Vec<G::ScalarField>), ProofVerifyError> {
+    self.verify_batched(
+      num_vars,
+      num_cons,
+      &[input.to_vec()],
+      &[*evals],
+      transcript,
+      gens,
+    )
+  }
+
+  // verifies a proof produced by `prove` for a customizable constraint system with the given
+  // terms, where `evals` holds the claimed evaluations of its matrices at (rx, ry)
+  #[allow(clippy::type_complexity)]
+  pub fn verify_ccs(
+    &self,
+    num_vars: usize,
+    num_cons: usize,
+    input: &[G::ScalarField],
+    terms: (&[G::ScalarField], &[Vec<usize>]),
+    evals: &[G::ScalarField],
+    transcript: &mut Transcript,
+    gens: &R1CSGens<G, PCS>,
+  ) -> Result<(Vec<G::ScalarField>, Vec<G::ScalarField>), ProofVerifyError> {
+    let mut acc = MSMAccumulator::new();
+    let (rx, ry) = self.verify_ccs_deferred(
+      num_vars,
+      num_cons,
+      &[input.to_vec()],
+      &[],
+      terms,
+      &[evals.to_vec()],
+      transcript,
+      gens,
+      &mut acc,
+    )?;
+    acc.check()?;
+    Ok((rx, ry))
+  }
+
+  // verifies a proof produced by `prove_batched`; `evals` holds the claimed evaluations of the
+  // matrices at (rx, ry) for the shared instance, or for each instance if there are several
+  #[allow(clippy::type_complexity)]
+  pub fn verify_batched(
+    &self,
+    num_vars: usize,
+    num_cons: usize,
+    inputs: &[Vec<G::ScalarField>],
+    evals: &[(G::ScalarField, G::ScalarField, G::ScalarField)],
+    transcript: &mut Transcript,
+    gens: &R1CSGens<G, PCS>,
+  ) -> Result<(Vec<G::ScalarField>, Vec<G::ScalarField>), ProofVerifyError> {
+    let mut acc = MSMAccumulator::new();
+    let (rx, ry) = self.verify_deferred(
+      num_vars,
+      num_cons,
+      inputs,
+      &[],
+      evals,
+      transcript,
+      gens,
+      &mut acc,
+    )?;
+    acc.check()?;
+    Ok((rx, ry))
+  }
+
+  // like `verify_batched`, but adds the verification equations of the group elements in the
+  // proof to `acc` instead of checking them; the inputs at the indices in `hidden` are replaced
+  // by the given Pedersen commitments, and their values in `inputs` are ignored
+  #[allow(clippy::type_complexity)]
+  pub fn verify_deferred(
+    &self,
+    num_vars: usize,
+    num_cons: usize,
+    inputs: &[Vec<G::ScalarField>],
+    hidden: &[(usize, G)],
+    evals: &[(G::ScalarField, G::ScalarField, G::ScalarField)],
+    transcript: &mut Transcript,
+    gens: &R1CSGens<G, PCS>,
+    acc: &mut MSMAccumulator<G>,
+  ) -> Result<(Vec<G::ScalarField>, Vec<G::ScalarField>), ProofVerifyError> {
+    let (coeffs, multisets) = r1cs_terms();
+    let evals = evals
+      .iter()
+      .map(|(eval_A_r, eval_B_r, eval_C_r)| vec![*eval_A_r, *eval_B_r, *eval_C_r])
+      .collect::<Vec<_>>();
+    self.verify_ccs_deferred(
+      num_vars,
+      num_cons,
+      inputs,
+      hidden,
+      (&coeffs, &multisets),
+      &evals,
+      transcript,
+      gens,
+      acc,
+    )
+  }
+
+  #[allow(clippy::type_complexity)]
+  fn verify_ccs_deferred(
+    &self,
+    num_vars: usize,
+    num_cons: usize,
+    inputs: &[Vec<G::ScalarField>],
+    hidden: &[(usize, G)],
+    terms: (&[G::ScalarField], &[Vec<usize>]),
+    evals: &[Vec<G::ScalarField>],
+    transcript: &mut Transcript,
+    gens: &R1CSGens<G, PCS>,
+    acc: &mut MSMAccumulator<G>,
+  ) -> Result<(Vec<G::ScalarField>, Vec<G::ScalarField>), ProofVerifyError> {
+    let (coeffs, multisets) = terms;
+    let num_mats = self.comm_Mz_claims.len();
+    let degree = multisets.iter().map(|m| m.len()).max().unwrap_or(1);
+    if gens.get_degree() != degree
+      || coeffs.len() != multisets.len()
+      || evals.iter().any(|evals| evals.len() != num_mats)
+      || self.pok_Mz_claims.len() != (0..num_mats).filter(|&i| !is_factor(multisets, i)).count()
+      || self.comm_prods.len() != multisets.len()
+      || self.proof_prods.len() != multisets.len()
+      || multisets.iter().flatten().any(|&i| i >= num_mats)
+    {
+      return Err(ProofVerifyError::InternalError);
+    }
+    for (j, multiset) in multisets.iter().enumerate() {
+      if multiset.is_empty()
+        || self.comm_prods[j].len() != multiset.len() - 1
+        || self.proof_prods[j].len() != multiset.len() - 1
+      {
+        return Err(ProofVerifyError::InternalError);
+      }
+    }
+
     <Transcript as ProofTranscript<G>>::append_protocol_name(
       transcript,
-      R1CSProof::<G>::protocol_name(),
+      R1CSProof::<G, PCS>::protocol_name(),
     );
 
-    <Transcript as ProofTranscript<G>>::append_scalars(transcript, b"input", input);
+    if inputs.is_empty() || (evals.len() != 1 && evals.len() != inputs.len()) {
+      return Err(ProofVerifyError::InternalError);
+    }
+    let hidden_indices = hidden.iter().map(|(i, _comm)| *i).collect::<Vec<_>>();
+    if !hidden.is_empty()
+      && (inputs.len() != 1 || !are_valid_hidden(&hidden_indices, inputs[0].len()))
+    {
+      return Err(ProofVerifyError::InternalError);
+    }
+    let inputs = inputs
+      .iter()
+      .map(|input| visible_inputs(input, &hidden_indices))
+      .collect::<Vec<_>>();
+    for input in inputs.iter() {
+      <Transcript as ProofTranscript<G>>::append_scalars(transcript, b"input", input);
+    }
+    for (i, comm_input) in hidden {
+      transcript.append_u64(b"hidden_index", *i as u64);
+      <Transcript as ProofTranscript<G>>::append_point(transcript, b"comm_input", comm_input);
+    }
+    let num_rounds_b = inputs.len().next_power_of_two().log_2() as usize;
 
     let n = num_vars;
     // add the commitment to the verifier's transcript
@@ -398,138 +818,266 @@ impl<G: ProjectiveCurve> R1CSProof<G> {
     let tau = <Transcript as ProofTranscript<G>>::challenge_vector(
       transcript,
       b"challenge_tau",
-      num_rounds_x,
+      num_rounds_b + num_rounds_x,
     );
 
     // verify the first sum-check instance
     let claim_phase1 = G::ScalarField::zero().commit(&G::ScalarField::zero(), &gens.gens_sc.gens_1);
 
-    let (comm_claim_post_phase1, rx) = self.sc_proof_phase1.verify(
+    let (comm_claim_post_phase1, rx) = self.sc_proof_phase1.verify_deferred(
       &claim_phase1,
-      num_rounds_x,
-      3,
+      num_rounds_b + num_rounds_x,
+      degree + 1,
       &gens.gens_sc.gens_1,
-      &gens.gens_sc.gens_4,
+      &gens.gens_sc.gens_d,
       transcript,
+      acc,
     )?;
-    // perform the intermediate sum-check test with claimed Az, Bz, and Cz
-    let (comm_Az_claim, comm_Bz_claim, comm_Cz_claim, comm_prod_Az_Bz_claims) = &self.claims_phase2;
-    let (pok_Cz_claim, proof_prod) = &self.pok_claims_phase2;
 
-    pok_Cz_claim.verify(&gens.gens_sc.gens_1, transcript, comm_Cz_claim)?;
-    proof_prod.verify(
-      &gens.gens_sc.gens_1,
-      transcript,
-      comm_Az_claim,
-      comm_Bz_claim,
-      comm_prod_Az_Bz_claims,
-    )?;
+    // verify the claimed M_i z and the products in each term
+    let comms_pok = (0..num_mats)
+      .filter(|&i| !is_factor(multisets, i))
+      .map(|i| &self.comm_Mz_claims[i]);
+    for (pok, comm) in self.pok_Mz_claims.iter().zip(comms_pok) {
+      pok.verify_deferred(&gens.gens_sc.gens_1, transcript, comm, acc)?;
+    }
+    let mut comm_terms = Vec::new();
+    for (j, multiset) in multisets.iter().enumerate() {
+      let mut comm_prod = &self.comm_Mz_claims[multiset[0]];
+      for (k, &i) in multiset[1..].iter().enumerate() {
+        self.proof_prods[j][k].verify_deferred(
+          &gens.gens_sc.gens_1,
+          transcript,
+          comm_prod,
+          &self.comm_Mz_claims[i],
+          &self.comm_prods[j][k],
+          acc,
+        )?;
+        comm_prod = &self.comm_prods[j][k];
+      }
+      comm_terms.push(*comm_prod);
+    }
 
-    <Transcript as ProofTranscript<G>>::append_point(transcript, b"comm_Az_claim", comm_Az_claim);
-    <Transcript as ProofTranscript<G>>::append_point(transcript, b"comm_Bz_claim", comm_Bz_claim);
-    <Transcript as ProofTranscript<G>>::append_point(transcript, b"comm_Cz_claim", comm_Cz_claim);
-    <Transcript as ProofTranscript<G>>::append_point(
-      transcript,
-      b"comm_prod_Az_Bz_claims",
-      comm_prod_Az_Bz_claims,
-    );
+    for comm in self.comm_Mz_claims.iter() {
+      <Transcript as ProofTranscript<G>>::append_point(transcript, b"comm_Mz_claim", comm);
+    }
+    for comm in self.comm_prods.iter().flatten() {
+      <Transcript as ProofTranscript<G>>::append_point(transcript, b"comm_prod_claim", comm);
+    }
 
     let taus_bound_rx: G::ScalarField = (0..rx.len())
       .map(|i| rx[i] * tau[i] + (G::ScalarField::one() - rx[i]) * (G::ScalarField::one() - tau[i]))
       .product();
-    let expected_claim_post_phase1 =
-      (*comm_prod_Az_Bz_claims - *comm_Cz_claim).mul(taus_bound_rx.into_repr());
+    let expected_claim_post_phase1 = {
+      // taus_bound_rx * sum_j c_j * comm_terms[j]
+      let scalars = coeffs
+        .iter()
+        .map(|c| (*c * taus_bound_rx).into_repr())
+        .collect::<Vec<_>>();
+      let bases_affine = G::batch_normalization_into_affine(comm_terms.as_ref());
+      VariableBaseMSM::multi_scalar_mul(bases_affine.as_ref(), scalars.as_ref())
+    };
 
     // verify proof that expected_claim_post_phase1 == claim_post_phase1
-    self.proof_eq_sc_phase1.verify(
+    self.proof_eq_sc_phase1.verify_deferred(
       &gens.gens_sc.gens_1,
       transcript,
       &expected_claim_post_phase1,
       &comm_claim_post_phase1,
+      acc,
     )?;
 
-    // derive three public challenges and then derive a joint claim
-    let r_A = <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"challenege_Az");
-    let r_B = <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"challenege_Bz");
-    let r_C = <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"challenege_Cz");
-
-    // r_A * comm_Az_claim + r_B * comm_Bz_claim + r_C * comm_Cz_claim;
-    let scalars = vec![r_A.into_repr(), r_B.into_repr(), r_C.into_repr()];
-    let bases = vec![*comm_Az_claim, *comm_Bz_claim, *comm_Cz_claim];
+    let (r_b, rx) = (rx[..num_rounds_b].to_vec(), rx[num_rounds_b..].to_vec());
 
-    let bases_affine = G::batch_normalization_into_affine(bases.as_ref());
+    // derive public challenges and then derive a joint claim
+    let r_M =
+      <Transcript as ProofTranscript<G>>::challenge_vector(transcript, b"challenge_Mz", num_mats);
+    let comm_claim_phase2 = {
+      let scalars = r_M.iter().map(|r| r.into_repr()).collect::<Vec<_>>();
+      let bases_affine = G::batch_normalization_into_affine(self.comm_Mz_claims.as_ref());
+      VariableBaseMSM::multi_scalar_mul(bases_affine.as_ref(), scalars.as_ref())
+    };
 
-    let comm_claim_phase2 =
-      VariableBaseMSM::multi_scalar_mul(bases_affine.as_ref(), scalars.as_ref());
+    // the second sum-check also runs over the assignments if the instances differ
+    let num_rounds_z = if evals.len() == 1 { 0 } else { num_rounds_b };
 
     // verify the joint claim with a sum-check protocol
-    let (comm_claim_post_phase2, ry) = self.sc_proof_phase2.verify(
+    let (comm_claim_post_phase2, ry) = self.sc_proof_phase2.verify_deferred(
       &comm_claim_phase2,
-      num_rounds_y,
+      num_rounds_z + num_rounds_y,
       2,
       &gens.gens_sc.gens_1,
       &gens.gens_sc.gens_3,
       transcript,
+      acc,
     )?;
 
+    let (r_z, ry) = if evals.len() == 1 {
+      (r_b.clone(), ry)
+    } else {
+      (ry[..num_rounds_z].to_vec(), ry[num_rounds_z..].to_vec())
+    };
+
     // verify Z(ry) proof against the initial commitment
-    self.proof_eval_vars_at_ry.verify(
+    PCS::verify_hiding(
+      &self.proof_eval_vars_at_ry,
       &gens.gens_pc,
       transcript,
-      &ry[1..],
+      &[&r_z, &ry[1..]].concat(),
       &self.comm_vars_at_ry,
       &self.comm_vars,
+      acc,
     )?;
 
-    let poly_input_eval = {
-      // constant term
-      let mut input_as_sparse_poly_entries = vec![SparsePolyEntry::new(0, G::ScalarField::one())];
-      //remaining inputs
-      input_as_sparse_poly_entries.extend(
-        (0..input.len())
-          .map(|i| SparsePolyEntry::new(i + 1, input[i]))
-          .collect::<Vec<SparsePolyEntry<G::ScalarField>>>(),
-      );
-      SparsePolynomial::new(n.log_2() as usize, input_as_sparse_poly_entries).evaluate(&ry[1..])
-    };
+    // the inputs are evaluated at r_z, like the assignments
+    let evals_rz = EqPolynomial::new(r_z).evals();
+    let poly_input_eval = (0..inputs.len())
+      .map(|b| evals_rz[b] * input_eval(&inputs[b], n, &ry[1..]))
+      .sum::<G::ScalarField>();
 
-    // compute commitment to eval_Z_at_ry = (F::one() - ry[0]) * self.eval_vars_at_ry + ry[0] * poly_input_eval
-    let scalars = vec![
+    // compute commitment to eval_Z_at_ry = (F::one() - ry[0]) * self.eval_vars_at_ry + ry[0] * poly_input_eval,
+    // where the hidden inputs are added to poly_input_eval homomorphically
+    let mut scalars = vec![
       (G::ScalarField::one() - ry[0]).into_repr(),
       ry[0].into_repr(),
     ];
 
-    let bases = vec![
+    let mut bases = vec![
       self.comm_vars_at_ry.into_affine(),
       poly_input_eval
-        .commit(&G::ScalarField::zero(), &gens.gens_pc.gens.gens_1)
+        .commit(&G::ScalarField::zero(), &gens.gens_sc.gens_1)
         .into_affine(),
     ];
+    for (i, comm_input) in hidden {
+      let eq_i = SparsePolynomial::new(
+        n.log_2() as usize,
+        vec![SparsePolyEntry::new(i + 1, G::ScalarField::one())],
+      )
+      .evaluate(&ry[1..]);
+      scalars.push((ry[0] * eq_i).into_repr());
+      bases.push(comm_input.into_affine());
+    }
 
     let comm_eval_Z_at_ry = VariableBaseMSM::multi_scalar_mul(bases.as_ref(), scalars.as_ref());
 
     // perform the final check in the second sum-check protocol
-    let (eval_A_r, eval_B_r, eval_C_r) = evals;
-    let expected_claim_post_phase2 =
-      comm_eval_Z_at_ry.mul((r_A * eval_A_r + r_B * eval_B_r + r_C * eval_C_r).into_repr());
+    let eval_M_r = |evals: &[G::ScalarField]| -> G::ScalarField {
+      (0..num_mats).map(|i| r_M[i] * evals[i]).sum()
+    };
+    let eval_M_r = if evals.len() == 1 {
+      eval_M_r(&evals[0])
+    } else {
+      // each instance's matrices were weighed by eq(r_b, b), and are bound at r_z
+      let evals_rb = EqPolynomial::new(r_b).evals();
+      evals
+        .iter()
+        .enumerate()
+        .map(|(b, evals)| evals_rb[b] * evals_rz[b] * eval_M_r(evals))
+        .sum()
+    };
+    let expected_claim_post_phase2 = comm_eval_Z_at_ry.mul(eval_M_r.into_repr());
 
-    // verify proof that expected_claim_post_phase1 == claim_post_phase1
-    self.proof_eq_sc_phase2.verify(
+    // verify proof that expected_claim_post_phase2 == claim_post_phase2
+    self.proof_eq_sc_phase2.verify_deferred(
       &gens.gens_sc.gens_1,
       transcript,
       &expected_claim_post_phase2,
       &comm_claim_post_phase2,
+      acc,
     )?;
 
     Ok((rx, ry))
   }
 }
 
+// appends the constant term and the inputs to the variables of an assignment, and pads the
+// result with zeros to twice the number of variables
+pub(crate) fn assignment_z<F: PrimeField>(vars: Vec<F>, input: &[F]) -> Vec<F> {
+  let num_vars = vars.len();
+  let mut z = vars;
+  z.push(F::one()); // add constant term in z
+  z.extend(input);
+  z.resize(2 * num_vars, F::zero()); // we will pad with zeros
+  z
+}
+
+// the initial evaluation tables of the first sum-check: eq(tau, (b, x)) and, for each matrix,
+// M_i z_b over the assignments b and the constraints x, where `insts` holds a shared instance
+// or one instance per assignment
+pub(crate) fn phase_one_tables<F: PrimeField, I: CCSMatrices<F>>(
+  insts: &[&I],
+  zs: &[Vec<F>],
+  tau: Vec<F>,
+) -> Vec<DensePolynomial<F>> {
+  let mut polys = vec![DensePolynomial::new(EqPolynomial::new(tau).evals())];
+  let mut Mzs: Vec<Vec<DensePolynomial<F>>> = Vec::new();
+  for (b, z) in zs.iter().enumerate() {
+    // padded assignments are zero, so any instance maps them to zero
+    let inst = insts[if b < insts.len() { b } else { 0 }];
+    let Mz = inst.multiply_vec_all(inst.get_num_cons(), z.len(), z);
+    Mzs.resize(Mz.len(), Vec::new());
+    for (Mzs, Mz) in Mzs.iter_mut().zip(Mz.into_iter()) {
+      Mzs.push(Mz);
+    }
+  }
+  polys.extend(Mzs.iter().map(|Mz| DensePolynomial::merge(Mz)));
+  polys
+}
+
+// the evaluation table of sum_i r_M[i] * M_i(rx, y) over the columns y, with which the second
+// sum-check reduces the claims about the M_i z to a single one
+pub(crate) fn combined_eval_table<F: PrimeField, I: CCSMatrices<F>>(
+  inst: &I,
+  rx: &[F],
+  r_M: &[F],
+  num_vars: usize,
+) -> Vec<F> {
+  // compute the initial evaluation table for R(\tau, x)
+  let evals_rx = EqPolynomial::new(rx.to_vec()).evals();
+  let evals = inst.compute_eval_tables(inst.get_num_cons(), 2 * num_vars, &evals_rx);
+  (0..2 * num_vars)
+    .map(|j| (0..r_M.len()).map(|i| r_M[i] * evals[i][j]).sum())
+    .collect::<Vec<F>>()
+}
+
+// evaluates the part of z after the variables, i.e., the constant term and the inputs, at ry
+pub(crate) fn input_eval<F: PrimeField>(input: &[F], num_vars: usize, ry: &[F]) -> F {
+  // constant term
+  let mut input_as_sparse_poly_entries = vec![SparsePolyEntry::new(0, F::one())];
+  //remaining inputs
+  input_as_sparse_poly_entries.extend(
+    (0..input.len())
+      .map(|i| SparsePolyEntry::new(i + 1, input[i]))
+      .collect::<Vec<SparsePolyEntry<F>>>(),
+  );
+  SparsePolynomial::new(num_vars.log_2() as usize, input_as_sparse_poly_entries).evaluate(ry)
+}
+
+// whether the claim about M_i z is a factor in a product, whose proof also proves knowledge of it
+fn is_factor(multisets: &[Vec<usize>], i: usize) -> bool {
+  multisets.iter().any(|m| m.len() > 1 && m.contains(&i))
+}
+
+// checks that the indices of hidden inputs are distinct and below `num_inputs`
+pub(crate) fn are_valid_hidden(hidden: &[usize], num_inputs: usize) -> bool {
+  hidden.iter().all(|i| *i < num_inputs)
+    && (1..hidden.len()).all(|j| !hidden[..j].contains(&hidden[j]))
+}
+
+// returns the inputs with the ones at the given indices set to zero, as they appear in the
+// transcript and in the verifier's evaluation of the inputs
+fn visible_inputs<F: PrimeField>(input: &[F], hidden: &[usize]) -> Vec<F> {
+  let mut input = input.to_vec();
+  for i in hidden {
+    input[*i] = F::zero();
+  }
+  input
+}
+
 #[cfg(test)]
 mod tests {
+  use super::super::r1csinstance::R1CSInstance;
   use super::*;
-  use ark_bls12_381::Fr;
-  use ark_bls12_381::G1Projective;
   use ark_std::test_rng;
 
   fn produce_tiny_r1cs<F: PrimeField>() -> (R1CSInstance<F>, Vec<F>, Vec<F>) {
@@ -589,10 +1137,7 @@ mod tests {
     (inst, vars, input)
   }
 
-  #[test]
-  fn test_tiny_r1cs() {
-    test_tiny_r1cs_helper::<Fr>()
-  }
+  crate::curves::curve_tests!(field: test_tiny_r1cs, test_tiny_r1cs_helper);
 
   fn test_tiny_r1cs_helper<F: PrimeField>() {
     let (inst, vars, input) = tests::produce_tiny_r1cs::<F>();
@@ -600,10 +1145,7 @@ mod tests {
     assert!(is_sat);
   }
 
-  #[test]
-  fn test_synthetic_r1cs() {
-    test_synthetic_r1cs_helper::<Fr>()
-  }
+  crate::curves::curve_tests!(field: test_synthetic_r1cs, test_synthetic_r1cs_helper);
 
   fn test_synthetic_r1cs_helper<F: PrimeField>() {
     let (inst, vars, input) = R1CSInstance::<F>::produce_synthetic_r1cs(1024, 1024, 10);
@@ -611,10 +1153,7 @@ mod tests {
     assert!(is_sat);
   }
 
-  #[test]
-  pub fn check_r1cs_proof() {
-    check_r1cs_proof_helper::<G1Projective>()
-  }
+  crate::curves::curve_tests!(group: check_r1cs_proof, check_r1cs_proof_helper);
 
   fn check_r1cs_proof_helper<G: ProjectiveCurve>() {
     let num_vars = 1024;
@@ -627,7 +1166,7 @@ mod tests {
 
     let mut random_tape = RandomTape::new(b"proof");
     let mut prover_transcript = Transcript::new(b"example");
-    let (proof, rx, ry) = R1CSProof::prove(
+    let (proof, rx, ry, _witness) = R1CSProof::prove(
       &inst,
       vars,
       &input,
@@ -0,0 +1,341 @@
+use super::errors::R1CSError;
+use super::r1csinstance::R1CSInstance;
+use super::{Column, InputsAssignment, Instance, VarsAssignment};
+use ark_ff::PrimeField;
+use std::collections::{BTreeMap, BTreeSet};
+
+type HintFn<F> = Box<dyn Fn(&[F]) -> Vec<F>>;
+
+struct Hint<F> {
+  inputs: Vec<Column>,
+  outputs: Vec<usize>,
+  func: HintFn<F>,
+}
+
+/// `WitnessSolver` completes a partial assignment to the variables of an `Instance`.
+/// It repeatedly solves constraints that are linear in their only unknown variable, and runs
+/// user-registered hints to compute values that the constraints do not determine on their own
+/// (e.g., inverses or bit decompositions) once the values the hints depend on are known.
+pub struct WitnessSolver<'a, F: PrimeField> {
+  inst: &'a Instance<F>,
+  hints: Vec<Hint<F>>,
+}
+
+// the value of the constraint `(a * v + A0) * (b * v + B0) = c * v + C0` in the only unknown `v`,
+// as coefficients `(a, A0)`, `(b, B0)`, `(c, C0)`
+type Partial<F> = [(F, F); 3];
+
+impl<'a, F: PrimeField> WitnessSolver<'a, F> {
+  /// Constructs a new solver for the given instance without any hints
+  pub fn new(inst: &'a Instance<F>) -> Self {
+    WitnessSolver {
+      inst,
+      hints: Vec::new(),
+    }
+  }
+
+  /// Registers a hint that computes the variables `outputs` by applying `func` to the values of
+  /// `inputs`; `func` must return one value per output. A hint runs once all of its inputs are
+  /// known and only assigns outputs that are still unknown. Returns `R1CSError::InvalidHint` if
+  /// a column or output is out of range.
+  pub fn add_hint<Func>(
+    &mut self,
+    inputs: &[Column],
+    outputs: &[usize],
+    func: Func,
+  ) -> Result<(), R1CSError>
+  where
+    Func: Fn(&[F]) -> Vec<F> + 'static,
+  {
+    let inst = &self.inst.inst;
+    let (num_vars, num_inputs) = (inst.get_num_vars(), inst.get_num_inputs());
+    let is_valid = |c: &Column| match c {
+      Column::Var(i) => *i < num_vars,
+      Column::One => true,
+      Column::Input(i) => *i < num_inputs,
+    };
+    if !inputs.iter().all(is_valid) || outputs.iter().any(|&v| v >= num_vars) {
+      return Err(R1CSError::InvalidHint);
+    }
+
+    self.hints.push(Hint {
+      inputs: inputs.to_vec(),
+      outputs: outputs.to_vec(),
+      func: Box::new(func),
+    });
+    Ok(())
+  }
+
+  /// Completes `vars`, in which `None` marks an unknown variable, using the constraints of the
+  /// instance and the registered hints. Variables that do not appear in any constraint or hint
+  /// are set to zero. Returns `R1CSError::UnsolvableVars` with the remaining unknown variables
+  /// if the assignment cannot be completed, and `R1CSError::InvalidHint` if a hint returns the
+  /// wrong number of values.
+  pub fn solve(
+    &self,
+    vars: &[Option<F>],
+    inputs: &InputsAssignment<F>,
+  ) -> Result<VarsAssignment<F>, R1CSError> {
+    let inst = &self.inst.inst;
+    let num_vars = inst.get_num_vars();
+    if vars.len() > num_vars {
+      return Err(R1CSError::InvalidNumberOfVars);
+    }
+    if inputs.assignment.len() != inst.get_num_inputs() {
+      return Err(R1CSError::InvalidNumberOfInputs);
+    }
+
+    let mut z = vars.to_vec();
+    z.resize(num_vars, None);
+    z.push(Some(F::one()));
+    z.extend(inputs.assignment.iter().map(|x| Some(*x)));
+
+    let rows = Rows::new(inst);
+    let mut num_unknowns = rows
+      .vars
+      .iter()
+      .map(|vars| vars.iter().filter(|&&v| z[v].is_none()).count())
+      .collect::<Vec<usize>>();
+    let mut queue = (0..num_unknowns.len())
+      .filter(|&r| num_unknowns[r] == 1)
+      .collect::<Vec<usize>>();
+    let mut pending_hints = (0..self.hints.len()).collect::<Vec<usize>>();
+
+    loop {
+      // propagate values through constraints with a single unknown
+      while let Some(r) = queue.pop() {
+        if num_unknowns[r] != 1 {
+          continue;
+        }
+        let v = *rows.vars[r].iter().find(|&&v| z[v].is_none()).unwrap();
+        if let Some(val) = rows.solve(r, v, &z) {
+          z[v] = Some(val);
+          for &row in rows.occurrences[&v].iter() {
+            num_unknowns[row] -= 1;
+            if num_unknowns[row] == 1 {
+              queue.push(row);
+            }
+          }
+        }
+      }
+
+      // run a hint whose inputs are all known
+      let col = |c: &Column| match c {
+        Column::Var(i) => *i,
+        Column::One => num_vars,
+        Column::Input(i) => num_vars + 1 + i,
+      };
+      let ready = pending_hints
+        .iter()
+        .position(|&h| self.hints[h].inputs.iter().all(|c| z[col(c)].is_some()));
+      let h = match ready {
+        Some(pos) => pending_hints.remove(pos),
+        None => break,
+      };
+
+      let hint = &self.hints[h];
+      let args = hint
+        .inputs
+        .iter()
+        .map(|c| z[col(c)].unwrap())
+        .collect::<Vec<F>>();
+      let vals = (hint.func)(&args);
+      if vals.len() != hint.outputs.len() {
+        return Err(R1CSError::InvalidHint);
+      }
+      for (&v, &val) in hint.outputs.iter().zip(vals.iter()) {
+        if z[v].is_some() {
+          continue;
+        }
+        z[v] = Some(val);
+        for &row in rows.occurrences.get(&v).into_iter().flatten() {
+          num_unknowns[row] -= 1;
+          if num_unknowns[row] == 1 {
+            queue.push(row);
+          }
+        }
+      }
+    }
+
+    // variables that nothing refers to are unconstrained
+    let hint_outputs = self
+      .hints
+      .iter()
+      .flat_map(|hint| hint.outputs.iter().copied())
+      .collect::<BTreeSet<usize>>();
+    let unsolved = (0..num_vars)
+      .filter(|&v| z[v].is_none())
+      .filter(|v| rows.occurrences.contains_key(v) || hint_outputs.contains(v))
+      .collect::<Vec<usize>>();
+    if !unsolved.is_empty() {
+      return Err(R1CSError::UnsolvableVars(unsolved));
+    }
+
+    Ok(VarsAssignment {
+      assignment: z[..num_vars]
+        .iter()
+        .map(|v| v.unwrap_or_else(F::zero))
+        .collect(),
+    })
+  }
+}
+
+// the constraints of an instance, indexed by the variables they refer to
+struct Rows<F> {
+  mats: [Vec<BTreeMap<usize, F>>; 3],
+  vars: Vec<Vec<usize>>,
+  occurrences: BTreeMap<usize, Vec<usize>>,
+}
+
+impl<F: PrimeField> Rows<F> {
+  fn new(inst: &R1CSInstance<F>) -> Self {
+    let (num_cons, num_vars) = (inst.get_num_cons(), inst.get_num_vars());
+    let (A, B, C) = inst.get_matrices();
+
+    let mut mats = [
+      vec![BTreeMap::new(); num_cons],
+      vec![BTreeMap::new(); num_cons],
+      vec![BTreeMap::new(); num_cons],
+    ];
+    for (m, entries) in [A, B, C].iter().enumerate() {
+      for &(row, col, val) in entries {
+        *mats[m][row].entry(col).or_insert_with(F::zero) += val;
+      }
+    }
+
+    let mut vars = vec![Vec::new(); num_cons];
+    let mut occurrences: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for (r, row_vars) in vars.iter_mut().enumerate() {
+      let cols = mats
+        .iter()
+        .flat_map(|mat| mat[r].iter())
+        .filter(|(&col, val)| col < num_vars && !val.is_zero())
+        .map(|(&col, _val)| col)
+        .collect::<BTreeSet<usize>>();
+      for &col in cols.iter() {
+        occurrences.entry(col).or_default().push(r);
+      }
+      *row_vars = cols.into_iter().collect();
+    }
+
+    Rows {
+      mats,
+      vars,
+      occurrences,
+    }
+  }
+
+  // solves the constraint `r` for its only unknown `v`, if it is linear in `v`
+  fn solve(&self, r: usize, v: usize, z: &[Option<F>]) -> Option<F> {
+    let mut partial: Partial<F> = [(F::zero(), F::zero()); 3];
+    for (m, (coeff, rest)) in partial.iter_mut().enumerate() {
+      for (&col, &val) in self.mats[m][r].iter() {
+        if col == v {
+          *coeff = val;
+        } else {
+          *rest += val * z[col].unwrap();
+        }
+      }
+    }
+
+    let [(a, A0), (b, B0), (c, C0)] = partial;
+    if !(a * b).is_zero() {
+      return None;
+    }
+    // (a * v + A0) * (b * v + B0) = c * v + C0 with a * b = 0
+    let k = a * B0 + b * A0 - c;
+    k.inverse().map(|k_inv| (C0 - A0 * B0) * k_inv)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ark_ff::BigInteger;
+
+  crate::curves::curve_tests!(field: check_solver, check_solver_helper);
+
+  fn check_solver_helper<F: PrimeField>() {
+    let num_cons = 9;
+    let num_vars = 8;
+    let num_inputs = 1;
+    let one = F::one();
+    let (const_col, input_col) = (num_vars, num_vars + 1);
+
+    // z_0 = x * x, z_1 = z_0 * x, z_2 = z_1 + x + 5, z_3 * x = 1, and
+    // z_4..z_7 are the bits of z_2, i.e., z_i * (z_i - 1) = 0 and sum_i 2^(i - 4) * z_i = z_2
+    let mut A = vec![
+      (0, input_col, one),
+      (1, 0, one),
+      (2, 1, one),
+      (2, input_col, one),
+      (2, const_col, F::from(5u64)),
+      (3, 3, one),
+    ];
+    let mut B = vec![
+      (0, input_col, one),
+      (1, input_col, one),
+      (2, const_col, one),
+      (3, input_col, one),
+    ];
+    let mut C = vec![(0, 0, one), (1, 1, one), (2, 2, one), (3, const_col, one)];
+    for i in 0..4 {
+      A.push((4 + i, 4 + i, one));
+      B.push((4 + i, 4 + i, one));
+      B.push((4 + i, const_col, -one));
+    }
+    for i in 0..4 {
+      A.push((8, 4 + i, F::from(1u64 << i)));
+    }
+    B.push((8, const_col, one));
+    C.push((8, 2, one));
+
+    let inst = Instance::new(num_cons, num_vars, num_inputs, &A, &B, &C).unwrap();
+    let inputs = InputsAssignment::new(&[F::from(2u64)]).unwrap();
+
+    // the bits are not determined by linear constraints
+    let solver = WitnessSolver::new(&inst);
+    match solver.solve(&[], &inputs) {
+      Err(R1CSError::UnsolvableVars(vars)) => assert_eq!(vars, vec![4, 5, 6, 7]),
+      _ => panic!("expected unsolvable variables"),
+    }
+
+    // with a hint for the bit decomposition, the assignment can be completed
+    let mut solver = WitnessSolver::new(&inst);
+    solver
+      .add_hint(&[Column::Var(2)], &[4, 5, 6, 7], |args: &[F]| {
+        let bits = args[0].into_repr();
+        (0..4)
+          .map(|i| F::from(bits.get_bit(i) as u64))
+          .collect::<Vec<F>>()
+      })
+      .unwrap();
+    let vars = solver.solve(&[], &inputs).unwrap();
+    assert_eq!(vars.assignment[2], F::from(15u64));
+    assert_eq!(vars.assignment[3], F::from(2u64).inverse().unwrap());
+    assert!(inst.is_sat(&vars, &inputs).unwrap());
+
+    // known values are kept and used
+    let vars = solver
+      .solve(&[Some(F::from(4u64)), None, None, None], &inputs)
+      .unwrap();
+    assert_eq!(vars.assignment[1], F::from(8u64));
+
+    // hints must refer to existing columns and return one value per output
+    let mut solver = WitnessSolver::new(&inst);
+    assert!(solver
+      .add_hint(&[Column::Var(2)], &[num_vars], |args: &[F]| args.to_vec())
+      .is_err());
+    assert!(solver
+      .add_hint(&[Column::Input(num_inputs)], &[4], |args: &[F]| args
+        .to_vec())
+      .is_err());
+    solver
+      .add_hint(&[Column::Var(2)], &[4, 5, 6, 7], |args: &[F]| args.to_vec())
+      .unwrap();
+    assert!(matches!(
+      solver.solve(&[], &inputs),
+      Err(R1CSError::InvalidHint)
+    ));
+  }
+}
@@ -1,12 +1,12 @@
 #![allow(clippy::type_complexity)]
 #![allow(clippy::too_many_arguments)]
 #![allow(clippy::needless_range_loop)]
+use super::commitments::MSMAccumulator;
 use super::dense_mlpoly::DensePolynomial;
-use super::dense_mlpoly::{
-  EqPolynomial, IdentityPolynomial, PolyCommitment, PolyCommitmentGens, PolyEvalProof,
-};
+use super::dense_mlpoly::{EqPolynomial, IdentityPolynomial};
 use super::errors::ProofVerifyError;
 use super::math::Math;
+use super::pcs::{Hyrax, MultilinearPCS, TransparentMultilinearPCS};
 use super::product_tree::{DotProductCircuit, ProductCircuit, ProductCircuitEvalProofBatched};
 use super::random::RandomTape;
 use super::timer::Timer;
@@ -45,8 +45,8 @@ pub struct Derefs<F> {
 }
 
 #[derive(Debug, CanonicalSerialize, CanonicalDeserialize)]
-pub struct DerefsCommitment<G: ProjectiveCurve> {
-  comm_ops_val: PolyCommitment<G>,
+pub struct DerefsCommitment<G: ProjectiveCurve, PCS: MultilinearPCS<G> = Hyrax<G>> {
+  comm_ops_val: PCS::Commitment,
 }
 
 impl<F: PrimeField> Derefs<F> {
@@ -71,21 +71,22 @@ impl<F: PrimeField> Derefs<F> {
     derefs
   }
 
-  pub fn commit<G: ProjectiveCurve<ScalarField = F>>(
-    &self,
-    gens: &PolyCommitmentGens<G>,
-  ) -> DerefsCommitment<G> {
-    let (comm_ops_val, _blinds) = self.comb.commit(gens, None);
+  pub fn commit<G, PCS>(&self, gens: &PCS::Gens) -> DerefsCommitment<G, PCS>
+  where
+    G: ProjectiveCurve<ScalarField = F>,
+    PCS: MultilinearPCS<G>,
+  {
+    let (comm_ops_val, _blinds) = PCS::commit(&self.comb, gens, None);
     DerefsCommitment { comm_ops_val }
   }
 }
 
 #[derive(Debug, CanonicalSerialize, CanonicalDeserialize)]
-pub struct DerefsEvalProof<G: ProjectiveCurve> {
-  proof_derefs: PolyEvalProof<G>,
+pub struct DerefsEvalProof<G: ProjectiveCurve, PCS: MultilinearPCS<G> = Hyrax<G>> {
+  proof_derefs: PCS::EvalProof,
 }
 
-impl<G: ProjectiveCurve> DerefsEvalProof<G> {
+impl<G: ProjectiveCurve, PCS: MultilinearPCS<G>> DerefsEvalProof<G, PCS> {
   fn protocol_name() -> &'static [u8] {
     b"Derefs evaluation proof"
   }
@@ -94,10 +95,10 @@ impl<G: ProjectiveCurve> DerefsEvalProof<G> {
     joint_poly: &DensePolynomial<G::ScalarField>,
     r: &[G::ScalarField],
     evals: Vec<G::ScalarField>,
-    gens: &PolyCommitmentGens<G>,
+    gens: &PCS::Gens,
     transcript: &mut Transcript,
     random_tape: &mut RandomTape<G>,
-  ) -> PolyEvalProof<G> {
+  ) -> PCS::EvalProof {
     assert_eq!(
       joint_poly.get_num_vars(),
       r.len() + evals.len().log_2() as usize
@@ -129,18 +130,15 @@ impl<G: ProjectiveCurve> DerefsEvalProof<G> {
     // decommit the joint polynomial at r_joint
     <Transcript as ProofTranscript<G>>::append_scalar(transcript, b"joint_claim_eval", &eval_joint);
 
-    let (proof_derefs, _comm_derefs_eval) = PolyEvalProof::prove(
+    PCS::prove(
       joint_poly,
       None,
       &r_joint,
       &eval_joint,
-      None,
       gens,
       transcript,
       random_tape,
-    );
-
-    proof_derefs
+    )
   }
 
   // evalues both polynomials at r and produces a joint proof of opening
@@ -149,13 +147,13 @@ impl<G: ProjectiveCurve> DerefsEvalProof<G> {
     eval_row_ops_val_vec: &[G::ScalarField],
     eval_col_ops_val_vec: &[G::ScalarField],
     r: &[G::ScalarField],
-    gens: &PolyCommitmentGens<G>,
+    gens: &PCS::Gens,
     transcript: &mut Transcript,
     random_tape: &mut RandomTape<G>,
   ) -> Self {
     <Transcript as ProofTranscript<G>>::append_protocol_name(
       transcript,
-      DerefsEvalProof::<G>::protocol_name(),
+      DerefsEvalProof::<G, PCS>::protocol_name(),
     );
 
     let evals = {
@@ -164,19 +162,26 @@ impl<G: ProjectiveCurve> DerefsEvalProof<G> {
       evals.resize(evals.len().next_power_of_two(), G::ScalarField::zero());
       evals
     };
-    let proof_derefs =
-      DerefsEvalProof::prove_single(&derefs.comb, r, evals, gens, transcript, random_tape);
+    let proof_derefs = DerefsEvalProof::<G, PCS>::prove_single(
+      &derefs.comb,
+      r,
+      evals,
+      gens,
+      transcript,
+      random_tape,
+    );
 
     DerefsEvalProof { proof_derefs }
   }
 
   fn verify_single(
-    proof: &PolyEvalProof<G>,
-    comm: &PolyCommitment<G>,
+    proof: &PCS::EvalProof,
+    comm: &PCS::Commitment,
     r: &[G::ScalarField],
     evals: Vec<G::ScalarField>,
-    gens: &PolyCommitmentGens<G>,
+    gens: &PCS::Gens,
     transcript: &mut Transcript,
+    acc: &mut MSMAccumulator<G>,
   ) -> Result<(), ProofVerifyError> {
     // append the claimed evaluations to transcript
     <Transcript as ProofTranscript<G>>::append_scalars(transcript, b"evals_ops_val", &evals);
@@ -203,7 +208,15 @@ impl<G: ProjectiveCurve> DerefsEvalProof<G> {
       &joint_claim_eval,
     );
 
-    proof.verify_plain(gens, transcript, &r_joint, &joint_claim_eval, comm)
+    PCS::verify(
+      proof,
+      gens,
+      transcript,
+      &r_joint,
+      &joint_claim_eval,
+      comm,
+      acc,
+    )
   }
 
   // verify evaluations of both polynomials at r
@@ -212,30 +225,34 @@ impl<G: ProjectiveCurve> DerefsEvalProof<G> {
     r: &[G::ScalarField],
     eval_row_ops_val_vec: &[G::ScalarField],
     eval_col_ops_val_vec: &[G::ScalarField],
-    gens: &PolyCommitmentGens<G>,
-    comm: &DerefsCommitment<G>,
+    gens: &PCS::Gens,
+    comm: &DerefsCommitment<G, PCS>,
     transcript: &mut Transcript,
+    acc: &mut MSMAccumulator<G>,
   ) -> Result<(), ProofVerifyError> {
     <Transcript as ProofTranscript<G>>::append_protocol_name(
       transcript,
-      DerefsEvalProof::<G>::protocol_name(),
+      DerefsEvalProof::<G, PCS>::protocol_name(),
     );
     let mut evals = eval_row_ops_val_vec.to_owned();
     evals.extend(eval_col_ops_val_vec);
     evals.resize(evals.len().next_power_of_two(), G::ScalarField::zero());
 
-    DerefsEvalProof::verify_single(
+    DerefsEvalProof::<G, PCS>::verify_single(
       &self.proof_derefs,
       &comm.comm_ops_val,
       r,
       evals,
       gens,
       transcript,
+      acc,
     )
   }
 }
 
-impl<G: ProjectiveCurve> AppendToTranscript<G> for DerefsCommitment<G> {
+impl<G: ProjectiveCurve, PCS: MultilinearPCS<G>> AppendToTranscript<G>
+  for DerefsCommitment<G, PCS>
+{
   fn append_to_transcript(&self, label: &'static [u8], transcript: &mut Transcript) {
     transcript.append_message(b"derefs_commitment", b"begin_derefs_commitment");
     self.comm_ops_val.append_to_transcript(label, transcript);
@@ -243,11 +260,11 @@ impl<G: ProjectiveCurve> AppendToTranscript<G> for DerefsCommitment<G> {
   }
 }
 
-struct AddrTimestamps<F> {
+pub struct AddrTimestamps<F> {
   ops_addr_usize: Vec<Vec<usize>>,
-  ops_addr: Vec<DensePolynomial<F>>,
-  read_ts: Vec<DensePolynomial<F>>,
-  audit_ts: DensePolynomial<F>,
+  pub ops_addr: Vec<DensePolynomial<F>>,
+  pub read_ts: Vec<DensePolynomial<F>>,
+  pub audit_ts: DensePolynomial<F>,
 }
 
 impl<F: PrimeField> AddrTimestamps<F> {
@@ -313,20 +330,44 @@ pub struct MultiSparseMatPolynomialAsDense<F> {
   comb_mem: DensePolynomial<F>,
 }
 
-pub struct SparseMatPolyCommitmentGens<G> {
-  gens_ops: PolyCommitmentGens<G>,
-  gens_mem: PolyCommitmentGens<G>,
-  gens_derefs: PolyCommitmentGens<G>,
+pub struct SparseMatPolyCommitmentGens<G: ProjectiveCurve, PCS: MultilinearPCS<G> = Hyrax<G>> {
+  gens_ops: PCS::Gens,
+  gens_mem: PCS::Gens,
+  gens_derefs: PCS::Gens,
 }
 
-impl<G: ProjectiveCurve> SparseMatPolyCommitmentGens<G> {
+impl<G: ProjectiveCurve, PCS: TransparentMultilinearPCS<G>> SparseMatPolyCommitmentGens<G, PCS> {
   pub fn new(
     label: &'static [u8],
     num_vars_x: usize,
     num_vars_y: usize,
     num_nz_entries: usize,
     batch_size: usize,
-  ) -> SparseMatPolyCommitmentGens<G> {
+  ) -> SparseMatPolyCommitmentGens<G, PCS> {
+    SparseMatPolyCommitmentGens::new_with_setup(
+      label,
+      num_vars_x,
+      num_vars_y,
+      num_nz_entries,
+      batch_size,
+      PCS::setup,
+    )
+  }
+}
+
+impl<G: ProjectiveCurve, PCS: MultilinearPCS<G>> SparseMatPolyCommitmentGens<G, PCS> {
+  // generators whose public parameters for the commitment scheme come from `setup`
+  pub fn new_with_setup<S>(
+    label: &'static [u8],
+    num_vars_x: usize,
+    num_vars_y: usize,
+    num_nz_entries: usize,
+    batch_size: usize,
+    setup: S,
+  ) -> SparseMatPolyCommitmentGens<G, PCS>
+  where
+    S: Fn(usize, &'static [u8]) -> PCS::Gens,
+  {
     let num_vars_ops = num_nz_entries.next_power_of_two().log_2() as usize
       + (batch_size * 5).next_power_of_two().log_2() as usize;
     let num_vars_mem = if num_vars_x > num_vars_y {
@@ -337,9 +378,9 @@ impl<G: ProjectiveCurve> SparseMatPolyCommitmentGens<G> {
     let num_vars_derefs = num_nz_entries.next_power_of_two().log_2() as usize
       + (batch_size * 2).next_power_of_two().log_2() as usize;
 
-    let gens_ops = PolyCommitmentGens::new(num_vars_ops, label);
-    let gens_mem = PolyCommitmentGens::new(num_vars_mem, label);
-    let gens_derefs = PolyCommitmentGens::new(num_vars_derefs, label);
+    let gens_ops = setup(num_vars_ops, label);
+    let gens_mem = setup(num_vars_mem, label);
+    let gens_derefs = setup(num_vars_derefs, label);
     SparseMatPolyCommitmentGens {
       gens_ops,
       gens_mem,
@@ -349,15 +390,23 @@ impl<G: ProjectiveCurve> SparseMatPolyCommitmentGens<G> {
 }
 
 #[derive(Debug, CanonicalSerialize, CanonicalDeserialize)]
-pub struct SparseMatPolyCommitment<G: ProjectiveCurve> {
+pub struct SparseMatPolyCommitment<G: ProjectiveCurve, PCS: MultilinearPCS<G> = Hyrax<G>> {
   batch_size: usize,
   num_ops: usize,
   num_mem_cells: usize,
-  comm_comb_ops: PolyCommitment<G>,
-  comm_comb_mem: PolyCommitment<G>,
+  comm_comb_ops: PCS::Commitment,
+  comm_comb_mem: PCS::Commitment,
+}
+
+impl<G: ProjectiveCurve, PCS: MultilinearPCS<G>> SparseMatPolyCommitment<G, PCS> {
+  pub fn get_batch_size(&self) -> usize {
+    self.batch_size
+  }
 }
 
-impl<G: ProjectiveCurve> AppendToTranscript<G> for SparseMatPolyCommitment<G> {
+impl<G: ProjectiveCurve, PCS: MultilinearPCS<G>> AppendToTranscript<G>
+  for SparseMatPolyCommitment<G, PCS>
+{
   fn append_to_transcript(&self, _label: &'static [u8], transcript: &mut Transcript) {
     transcript.append_u64(b"batch_size", self.batch_size as u64);
     transcript.append_u64(b"num_ops", self.num_ops as u64);
@@ -384,6 +433,14 @@ impl<F: PrimeField> SparseMatPolynomial<F> {
     self.M.len().next_power_of_two()
   }
 
+  pub fn get_entries(&self) -> Vec<(usize, usize, F)> {
+    self
+      .M
+      .iter()
+      .map(|entry| (entry.row, entry.col, entry.val))
+      .collect()
+  }
+
   fn sparse_to_dense_vecs(&self, N: usize) -> (Vec<usize>, Vec<usize>, Vec<F>) {
     assert!(N >= self.get_num_nz_entries());
     let mut ops_row: Vec<usize> = vec![0; N];
@@ -510,18 +567,22 @@ impl<F: PrimeField> SparseMatPolynomial<F> {
     M_evals
   }
 
-  pub fn multi_commit<G: ProjectiveCurve<ScalarField = F>>(
+  pub fn multi_commit<G, PCS>(
     sparse_polys: &[&SparseMatPolynomial<F>],
-    gens: &SparseMatPolyCommitmentGens<G>,
+    gens: &SparseMatPolyCommitmentGens<G, PCS>,
   ) -> (
-    SparseMatPolyCommitment<G>,
+    SparseMatPolyCommitment<G, PCS>,
     MultiSparseMatPolynomialAsDense<F>,
-  ) {
+  )
+  where
+    G: ProjectiveCurve<ScalarField = F>,
+    PCS: MultilinearPCS<G>,
+  {
     let batch_size = sparse_polys.len();
     let dense = SparseMatPolynomial::multi_sparse_to_dense_rep(sparse_polys);
 
-    let (comm_comb_ops, _blinds_comb_ops) = dense.comb_ops.commit(&gens.gens_ops, None);
-    let (comm_comb_mem, _blinds_comb_mem) = dense.comb_mem.commit(&gens.gens_mem, None);
+    let (comm_comb_ops, _blinds_comb_ops) = PCS::commit(&dense.comb_ops, &gens.gens_ops, None);
+    let (comm_comb_mem, _blinds_comb_mem) = PCS::commit(&dense.comb_mem, &gens.gens_mem, None);
 
     (
       SparseMatPolyCommitment {
@@ -546,16 +607,16 @@ impl<F: PrimeField> MultiSparseMatPolynomialAsDense<F> {
 }
 
 #[derive(Debug)]
-struct ProductLayer<F> {
-  init: ProductCircuit<F>,
-  read_vec: Vec<ProductCircuit<F>>,
-  write_vec: Vec<ProductCircuit<F>>,
-  audit: ProductCircuit<F>,
+pub struct ProductLayer<F> {
+  pub init: ProductCircuit<F>,
+  pub read_vec: Vec<ProductCircuit<F>>,
+  pub write_vec: Vec<ProductCircuit<F>>,
+  pub audit: ProductCircuit<F>,
 }
 
 #[derive(Debug)]
-struct Layers<F> {
-  prod_layer: ProductLayer<F>,
+pub struct Layers<F> {
+  pub prod_layer: ProductLayer<F>,
 }
 
 impl<F: PrimeField> Layers<F> {
@@ -709,17 +770,17 @@ impl<F: PrimeField> PolyEvalNetwork<F> {
 }
 
 #[derive(Debug, CanonicalSerialize, CanonicalDeserialize)]
-struct HashLayerProof<G: ProjectiveCurve> {
+struct HashLayerProof<G: ProjectiveCurve, PCS: MultilinearPCS<G>> {
   eval_row: (Vec<G::ScalarField>, Vec<G::ScalarField>, G::ScalarField),
   eval_col: (Vec<G::ScalarField>, Vec<G::ScalarField>, G::ScalarField),
   eval_val: Vec<G::ScalarField>,
   eval_derefs: (Vec<G::ScalarField>, Vec<G::ScalarField>),
-  proof_ops: PolyEvalProof<G>,
-  proof_mem: PolyEvalProof<G>,
-  proof_derefs: DerefsEvalProof<G>,
+  proof_ops: PCS::EvalProof,
+  proof_mem: PCS::EvalProof,
+  proof_derefs: DerefsEvalProof<G, PCS>,
 }
 
-impl<G: ProjectiveCurve> HashLayerProof<G> {
+impl<G: ProjectiveCurve, PCS: MultilinearPCS<G>> HashLayerProof<G, PCS> {
   fn protocol_name() -> &'static [u8] {
     b"Sparse polynomial hash layer proof"
   }
@@ -754,13 +815,13 @@ impl<G: ProjectiveCurve> HashLayerProof<G> {
     rand: (&Vec<G::ScalarField>, &Vec<G::ScalarField>),
     dense: &MultiSparseMatPolynomialAsDense<G::ScalarField>,
     derefs: &Derefs<G::ScalarField>,
-    gens: &SparseMatPolyCommitmentGens<G>,
+    gens: &SparseMatPolyCommitmentGens<G, PCS>,
     transcript: &mut Transcript,
     random_tape: &mut RandomTape<G>,
   ) -> Self {
     <Transcript as ProofTranscript<G>>::append_protocol_name(
       transcript,
-      HashLayerProof::<G>::protocol_name(),
+      HashLayerProof::<G, PCS>::protocol_name(),
     );
 
     let (rand_mem, rand_ops) = rand;
@@ -786,9 +847,9 @@ impl<G: ProjectiveCurve> HashLayerProof<G> {
     // evaluate row_addr, row_read-ts, col_addr, col_read-ts, val at rand_ops
     // evaluate row_audit_ts and col_audit_ts at rand_mem
     let (eval_row_addr_vec, eval_row_read_ts_vec, eval_row_audit_ts) =
-      HashLayerProof::<G>::prove_helper((rand_mem, rand_ops), &dense.row);
+      HashLayerProof::<G, PCS>::prove_helper((rand_mem, rand_ops), &dense.row);
     let (eval_col_addr_vec, eval_col_read_ts_vec, eval_col_audit_ts) =
-      HashLayerProof::<G>::prove_helper((rand_mem, rand_ops), &dense.col);
+      HashLayerProof::<G, PCS>::prove_helper((rand_mem, rand_ops), &dense.col);
     let eval_val_vec = (0..dense.val.len())
       .map(|i| dense.val[i].evaluate::<G>(rand_ops))
       .collect::<Vec<G::ScalarField>>();
@@ -828,12 +889,11 @@ impl<G: ProjectiveCurve> HashLayerProof<G> {
       &joint_claim_eval_ops,
     );
 
-    let (proof_ops, _comm_ops_eval) = PolyEvalProof::prove(
+    let proof_ops = PCS::prove(
       &dense.comb_ops,
       None,
       &r_joint_ops,
       &joint_claim_eval_ops,
-      None,
       &gens.gens_ops,
       transcript,
       random_tape,
@@ -867,12 +927,11 @@ impl<G: ProjectiveCurve> HashLayerProof<G> {
       &joint_claim_eval_mem,
     );
 
-    let (proof_mem, _comm_mem_eval) = PolyEvalProof::prove(
+    let proof_mem = PCS::prove(
       &dense.comb_mem,
       None,
       &r_joint_mem,
       &joint_claim_eval_mem,
-      None,
       &gens.gens_mem,
       transcript,
       random_tape,
@@ -919,13 +978,17 @@ impl<G: ProjectiveCurve> HashLayerProof<G> {
     let eval_init_val = EqPolynomial::new(r.to_vec()).evaluate(rand_mem);
     let hash_init_at_rand_mem =
       hash_func(&eval_init_addr, &eval_init_val, &G::ScalarField::zero()) - r_multiset_check; // verify the claim_last of init chunk
-    assert_eq!(&hash_init_at_rand_mem, claim_init);
+    if &hash_init_at_rand_mem != claim_init {
+      return Err(ProofVerifyError::InternalError);
+    }
 
     // read
     for i in 0..eval_ops_addr.len() {
       let hash_read_at_rand_ops =
         hash_func(&eval_ops_addr[i], &eval_ops_val[i], &eval_read_ts[i]) - r_multiset_check; // verify the claim_last of init chunk
-      assert_eq!(&hash_read_at_rand_ops, &claim_read[i]);
+      if hash_read_at_rand_ops != claim_read[i] {
+        return Err(ProofVerifyError::InternalError);
+      }
     }
 
     // write: shares addr, val component; only decommit write_ts
@@ -933,7 +996,9 @@ impl<G: ProjectiveCurve> HashLayerProof<G> {
       let eval_write_ts = eval_read_ts[i] + G::ScalarField::one();
       let hash_write_at_rand_ops =
         hash_func(&eval_ops_addr[i], &eval_ops_val[i], &eval_write_ts) - r_multiset_check; // verify the claim_last of init chunk
-      assert_eq!(&hash_write_at_rand_ops, &claim_write[i]);
+      if hash_write_at_rand_ops != claim_write[i] {
+        return Err(ProofVerifyError::InternalError);
+      }
     }
 
     // audit: shares addr and val with init
@@ -941,7 +1006,10 @@ impl<G: ProjectiveCurve> HashLayerProof<G> {
     let eval_audit_val = eval_init_val;
     let hash_audit_at_rand_mem =
       hash_func(&eval_audit_addr, &eval_audit_val, eval_audit_ts) - r_multiset_check;
-    assert_eq!(&hash_audit_at_rand_mem, claim_audit); // verify the last step of the sum-check for audit
+    // verify the last step of the sum-check for audit
+    if &hash_audit_at_rand_mem != claim_audit {
+      return Err(ProofVerifyError::InternalError);
+    }
 
     Ok(())
   }
@@ -962,19 +1030,20 @@ impl<G: ProjectiveCurve> HashLayerProof<G> {
       G::ScalarField,
     ),
     claims_dotp: &[G::ScalarField],
-    comm: &SparseMatPolyCommitment<G>,
-    gens: &SparseMatPolyCommitmentGens<G>,
-    comm_derefs: &DerefsCommitment<G>,
+    comm: &SparseMatPolyCommitment<G, PCS>,
+    gens: &SparseMatPolyCommitmentGens<G, PCS>,
+    comm_derefs: &DerefsCommitment<G, PCS>,
     rx: &[G::ScalarField],
     ry: &[G::ScalarField],
     r_hash: &G::ScalarField,
     r_multiset_check: &G::ScalarField,
     transcript: &mut Transcript,
+    acc: &mut MSMAccumulator<G>,
   ) -> Result<(), ProofVerifyError> {
     let timer = Timer::new("verify_hash_proof");
     <Transcript as ProofTranscript<G>>::append_protocol_name(
       transcript,
-      HashLayerProof::<G>::protocol_name(),
+      HashLayerProof::<G, PCS>::protocol_name(),
     );
 
     let (rand_mem, rand_ops) = rand;
@@ -989,6 +1058,7 @@ impl<G: ProjectiveCurve> HashLayerProof<G> {
       &gens.gens_derefs,
       comm_derefs,
       transcript,
+      acc,
     )?;
 
     // verify the decommitments used in evaluation sum-check
@@ -999,9 +1069,12 @@ impl<G: ProjectiveCurve> HashLayerProof<G> {
       let claim_col_ops_val = claims_dotp[3 * i + 1];
       let claim_val = claims_dotp[3 * i + 2];
 
-      assert_eq!(claim_row_ops_val, eval_row_ops_val[i]);
-      assert_eq!(claim_col_ops_val, eval_col_ops_val[i]);
-      assert_eq!(claim_val, eval_val_vec[i]);
+      if claim_row_ops_val != eval_row_ops_val[i]
+        || claim_col_ops_val != eval_col_ops_val[i]
+        || claim_val != eval_val_vec[i]
+      {
+        return Err(ProofVerifyError::InternalError);
+      }
     }
 
     // verify addr-timestamps using comm_comb_ops at rand_ops
@@ -1037,12 +1110,14 @@ impl<G: ProjectiveCurve> HashLayerProof<G> {
       b"joint_claim_eval_ops",
       &joint_claim_eval_ops,
     );
-    self.proof_ops.verify_plain(
+    PCS::verify(
+      &self.proof_ops,
       &gens.gens_ops,
       transcript,
       &r_joint_ops,
       &joint_claim_eval_ops,
       &comm.comm_comb_ops,
+      acc,
     )?;
 
     // verify proof-mem using comm_comb_mem at rand_mem
@@ -1068,17 +1143,19 @@ impl<G: ProjectiveCurve> HashLayerProof<G> {
       b"joint_claim_eval_mem",
       &joint_claim_eval_mem,
     );
-    self.proof_mem.verify_plain(
+    PCS::verify(
+      &self.proof_mem,
       &gens.gens_mem,
       transcript,
       &r_joint_mem,
       &joint_claim_eval_mem,
       &comm.comm_comb_mem,
+      acc,
     )?;
 
     // verify the claims from the product layer
     let (eval_ops_addr, eval_read_ts, eval_audit_ts) = &self.eval_row;
-    HashLayerProof::<G>::verify_helper(
+    HashLayerProof::<G, PCS>::verify_helper(
       &(rand_mem, rand_ops),
       claims_row,
       eval_row_ops_val,
@@ -1091,7 +1168,7 @@ impl<G: ProjectiveCurve> HashLayerProof<G> {
     )?;
 
     let (eval_ops_addr, eval_read_ts, eval_audit_ts) = &self.eval_col;
-    HashLayerProof::<G>::verify_helper(
+    HashLayerProof::<G, PCS>::verify_helper(
       &(rand_mem, rand_ops),
       claims_col,
       eval_col_ops_val,
@@ -1256,67 +1333,19 @@ impl<F: PrimeField> ProductLayerProof<F> {
     // The number of operations into the memory encoded by rx and ry are always the same (by design)
     // So we can produce a batched product proof for all of them at the same time.
     // prove the correctness of claim_row_eval_read, claim_row_eval_write, claim_col_eval_read, and claim_col_eval_write
-    // TODO: we currently only produce proofs for 3 batched sparse polynomial evaluations
-    assert_eq!(row_prod_layer.read_vec.len(), 3);
-    let (row_read_A, row_read_B, row_read_C) = {
-      let (vec_A, vec_BC) = row_prod_layer.read_vec.split_at_mut(1);
-      let (vec_B, vec_C) = vec_BC.split_at_mut(1);
-      (vec_A, vec_B, vec_C)
-    };
-
-    let (row_write_A, row_write_B, row_write_C) = {
-      let (vec_A, vec_BC) = row_prod_layer.write_vec.split_at_mut(1);
-      let (vec_B, vec_C) = vec_BC.split_at_mut(1);
-      (vec_A, vec_B, vec_C)
-    };
-
-    let (col_read_A, col_read_B, col_read_C) = {
-      let (vec_A, vec_BC) = col_prod_layer.read_vec.split_at_mut(1);
-      let (vec_B, vec_C) = vec_BC.split_at_mut(1);
-      (vec_A, vec_B, vec_C)
-    };
-
-    let (col_write_A, col_write_B, col_write_C) = {
-      let (vec_A, vec_BC) = col_prod_layer.write_vec.split_at_mut(1);
-      let (vec_B, vec_C) = vec_BC.split_at_mut(1);
-      (vec_A, vec_B, vec_C)
-    };
-
-    let (dotp_left_A, dotp_left_B, dotp_left_C) = {
-      let (vec_A, vec_BC) = dotp_circuit_left_vec.split_at_mut(1);
-      let (vec_B, vec_C) = vec_BC.split_at_mut(1);
-      (vec_A, vec_B, vec_C)
-    };
-
-    let (dotp_right_A, dotp_right_B, dotp_right_C) = {
-      let (vec_A, vec_BC) = dotp_circuit_right_vec.split_at_mut(1);
-      let (vec_B, vec_C) = vec_BC.split_at_mut(1);
-      (vec_A, vec_B, vec_C)
-    };
-
     let (proof_ops, rand_ops) = ProductCircuitEvalProofBatched::<F>::prove::<G>(
-      &mut vec![
-        &mut row_read_A[0],
-        &mut row_read_B[0],
-        &mut row_read_C[0],
-        &mut row_write_A[0],
-        &mut row_write_B[0],
-        &mut row_write_C[0],
-        &mut col_read_A[0],
-        &mut col_read_B[0],
-        &mut col_read_C[0],
-        &mut col_write_A[0],
-        &mut col_write_B[0],
-        &mut col_write_C[0],
-      ],
-      &mut vec![
-        &mut dotp_left_A[0],
-        &mut dotp_right_A[0],
-        &mut dotp_left_B[0],
-        &mut dotp_right_B[0],
-        &mut dotp_left_C[0],
-        &mut dotp_right_C[0],
-      ],
+      &mut row_prod_layer
+        .read_vec
+        .iter_mut()
+        .chain(row_prod_layer.write_vec.iter_mut())
+        .chain(col_prod_layer.read_vec.iter_mut())
+        .chain(col_prod_layer.write_vec.iter_mut())
+        .collect(),
+      &mut dotp_circuit_left_vec
+        .iter_mut()
+        .zip(dotp_circuit_right_vec.iter_mut())
+        .flat_map(|(left, right)| [left, right])
+        .collect(),
       transcript,
     );
 
@@ -1380,7 +1409,9 @@ impl<F: PrimeField> ProductLayerProof<F> {
       .map(|i| row_eval_write[i])
       .product();
     let rs: F = (0..row_eval_read.len()).map(|i| row_eval_read[i]).product();
-    assert_eq!(*row_eval_init * ws, rs * row_eval_audit);
+    if *row_eval_init * ws != rs * row_eval_audit {
+      return Err(ProofVerifyError::InternalError);
+    }
 
     <Transcript as ProofTranscript<G>>::append_scalar(
       transcript,
@@ -1411,7 +1442,9 @@ impl<F: PrimeField> ProductLayerProof<F> {
       .map(|i| col_eval_write[i])
       .product();
     let rs: F = (0..col_eval_read.len()).map(|i| col_eval_read[i]).product();
-    assert_eq!(*col_eval_init * ws, rs * col_eval_audit);
+    if *col_eval_init * ws != rs * col_eval_audit {
+      return Err(ProofVerifyError::InternalError);
+    }
 
     <Transcript as ProofTranscript<G>>::append_scalar(
       transcript,
@@ -1440,7 +1473,9 @@ impl<F: PrimeField> ProductLayerProof<F> {
     assert_eq!(eval_dotp_left.len(), num_instances);
     let mut claims_dotp_circuit: Vec<F> = Vec::new();
     for i in 0..num_instances {
-      assert_eq!(eval_dotp_left[i] + eval_dotp_right[i], eval[i]);
+      if eval_dotp_left[i] + eval_dotp_right[i] != eval[i] {
+        return Err(ProofVerifyError::InternalError);
+      }
 
       <Transcript as ProofTranscript<G>>::append_scalar(
         transcript,
@@ -1470,7 +1505,7 @@ impl<F: PrimeField> ProductLayerProof<F> {
       &claims_dotp_circuit,
       num_ops,
       transcript,
-    );
+    )?;
     // verify the correctness of claim_row_eval_init and claim_row_eval_audit
     let (claims_mem, _claims_mem_dotp, rand_mem) = self.proof_mem.verify::<G>(
       &[
@@ -1482,7 +1517,7 @@ impl<F: PrimeField> ProductLayerProof<F> {
       &Vec::new(),
       num_cells,
       transcript,
-    );
+    )?;
     timer.stop();
 
     Ok((claims_mem, rand_mem, claims_ops, claims_dotp, rand_ops))
@@ -1490,12 +1525,12 @@ impl<F: PrimeField> ProductLayerProof<F> {
 }
 
 #[derive(Debug, CanonicalSerialize, CanonicalDeserialize)]
-struct PolyEvalNetworkProof<G: ProjectiveCurve> {
+struct PolyEvalNetworkProof<G: ProjectiveCurve, PCS: MultilinearPCS<G>> {
   proof_prod_layer: ProductLayerProof<G::ScalarField>,
-  proof_hash_layer: HashLayerProof<G>,
+  proof_hash_layer: HashLayerProof<G, PCS>,
 }
 
-impl<G: ProjectiveCurve> PolyEvalNetworkProof<G> {
+impl<G: ProjectiveCurve, PCS: MultilinearPCS<G>> PolyEvalNetworkProof<G, PCS> {
   fn protocol_name() -> &'static [u8] {
     b"Sparse polynomial evaluation proof"
   }
@@ -1505,13 +1540,13 @@ impl<G: ProjectiveCurve> PolyEvalNetworkProof<G> {
     dense: &MultiSparseMatPolynomialAsDense<G::ScalarField>,
     derefs: &Derefs<G::ScalarField>,
     evals: &[G::ScalarField],
-    gens: &SparseMatPolyCommitmentGens<G>,
+    gens: &SparseMatPolyCommitmentGens<G, PCS>,
     transcript: &mut Transcript,
     random_tape: &mut RandomTape<G>,
   ) -> Self {
     <Transcript as ProofTranscript<G>>::append_protocol_name(
       transcript,
-      PolyEvalNetworkProof::<G>::protocol_name(),
+      PolyEvalNetworkProof::<G, PCS>::protocol_name(),
     );
 
     let (proof_prod_layer, rand_mem, rand_ops) = ProductLayerProof::<G::ScalarField>::prove::<G>(
@@ -1541,20 +1576,21 @@ impl<G: ProjectiveCurve> PolyEvalNetworkProof<G> {
 
   pub fn verify(
     &self,
-    comm: &SparseMatPolyCommitment<G>,
-    comm_derefs: &DerefsCommitment<G>,
+    comm: &SparseMatPolyCommitment<G, PCS>,
+    comm_derefs: &DerefsCommitment<G, PCS>,
     evals: &[G::ScalarField],
-    gens: &SparseMatPolyCommitmentGens<G>,
+    gens: &SparseMatPolyCommitmentGens<G, PCS>,
     rx: &[G::ScalarField],
     ry: &[G::ScalarField],
     r_mem_check: &(G::ScalarField, G::ScalarField),
     nz: usize,
     transcript: &mut Transcript,
+    acc: &mut MSMAccumulator<G>,
   ) -> Result<(), ProofVerifyError> {
     let timer = Timer::new("verify_polyeval_proof");
     <Transcript as ProofTranscript<G>>::append_protocol_name(
       transcript,
-      PolyEvalNetworkProof::<G>::protocol_name(),
+      PolyEvalNetworkProof::<G, PCS>::protocol_name(),
     );
 
     let num_instances = evals.len();
@@ -1599,6 +1635,7 @@ impl<G: ProjectiveCurve> PolyEvalNetworkProof<G> {
       r_hash,
       r_multiset_check,
       transcript,
+      acc,
     )?;
     timer.stop();
 
@@ -1607,12 +1644,12 @@ impl<G: ProjectiveCurve> PolyEvalNetworkProof<G> {
 }
 
 #[derive(Debug, CanonicalSerialize, CanonicalDeserialize)]
-pub struct SparseMatPolyEvalProof<G: ProjectiveCurve> {
-  comm_derefs: DerefsCommitment<G>,
-  poly_eval_network_proof: PolyEvalNetworkProof<G>,
+pub struct SparseMatPolyEvalProof<G: ProjectiveCurve, PCS: MultilinearPCS<G> = Hyrax<G>> {
+  comm_derefs: DerefsCommitment<G, PCS>,
+  poly_eval_network_proof: PolyEvalNetworkProof<G, PCS>,
 }
 
-impl<G: ProjectiveCurve> SparseMatPolyEvalProof<G> {
+impl<G: ProjectiveCurve, PCS: MultilinearPCS<G>> SparseMatPolyEvalProof<G, PCS> {
   fn protocol_name() -> &'static [u8] {
     b"Sparse polynomial evaluation proof"
   }
@@ -1643,13 +1680,13 @@ impl<G: ProjectiveCurve> SparseMatPolyEvalProof<G> {
     rx: &[G::ScalarField], // point at which the polynomial is evaluated
     ry: &[G::ScalarField],
     evals: &[G::ScalarField], // a vector evaluation of \widetilde{M}(r = (rx,ry)) for each M
-    gens: &SparseMatPolyCommitmentGens<G>,
+    gens: &SparseMatPolyCommitmentGens<G, PCS>,
     transcript: &mut Transcript,
     random_tape: &mut RandomTape<G>,
-  ) -> SparseMatPolyEvalProof<G> {
+  ) -> SparseMatPolyEvalProof<G, PCS> {
     <Transcript as ProofTranscript<G>>::append_protocol_name(
       transcript,
-      SparseMatPolyEvalProof::<G>::protocol_name(),
+      SparseMatPolyEvalProof::<G, PCS>::protocol_name(),
     );
 
     // ensure there is one eval for each polynomial in dense
@@ -1657,7 +1694,7 @@ impl<G: ProjectiveCurve> SparseMatPolyEvalProof<G> {
 
     let (mem_rx, mem_ry) = {
       // equalize the lengths of rx and ry
-      let (rx_ext, ry_ext) = SparseMatPolyEvalProof::<G>::equalize(rx, ry);
+      let (rx_ext, ry_ext) = SparseMatPolyEvalProof::<G, PCS>::equalize(rx, ry);
       let poly_rx = EqPolynomial::new(rx_ext).evals();
       let poly_ry = EqPolynomial::new(ry_ext).evals();
       (poly_rx, poly_ry)
@@ -1668,7 +1705,7 @@ impl<G: ProjectiveCurve> SparseMatPolyEvalProof<G> {
     // commit to non-deterministic choices of the prover
     let timer_commit = Timer::new("commit_nondet_witness");
     let comm_derefs = {
-      let comm = derefs.commit(&gens.gens_derefs);
+      let comm = derefs.commit::<G, PCS>(&gens.gens_derefs);
       comm.append_to_transcript(b"comm_poly_row_col_ops_val", transcript);
       comm
     };
@@ -1713,20 +1750,37 @@ impl<G: ProjectiveCurve> SparseMatPolyEvalProof<G> {
 
   pub fn verify(
     &self,
-    comm: &SparseMatPolyCommitment<G>,
+    comm: &SparseMatPolyCommitment<G, PCS>,
     rx: &[G::ScalarField], // point at which the polynomial is evaluated
     ry: &[G::ScalarField],
     evals: &[G::ScalarField], // evaluation of \widetilde{M}(r = (rx,ry))
-    gens: &SparseMatPolyCommitmentGens<G>,
+    gens: &SparseMatPolyCommitmentGens<G, PCS>,
+    transcript: &mut Transcript,
+  ) -> Result<(), ProofVerifyError> {
+    let mut acc = MSMAccumulator::new();
+    self.verify_deferred(comm, rx, ry, evals, gens, transcript, &mut acc)?;
+    acc.check()
+  }
+
+  // like `verify`, but adds the verification equations of the dense polynomial evaluation
+  // proofs to `acc` instead of checking them
+  pub fn verify_deferred(
+    &self,
+    comm: &SparseMatPolyCommitment<G, PCS>,
+    rx: &[G::ScalarField],
+    ry: &[G::ScalarField],
+    evals: &[G::ScalarField],
+    gens: &SparseMatPolyCommitmentGens<G, PCS>,
     transcript: &mut Transcript,
+    acc: &mut MSMAccumulator<G>,
   ) -> Result<(), ProofVerifyError> {
     <Transcript as ProofTranscript<G>>::append_protocol_name(
       transcript,
-      SparseMatPolyEvalProof::<G>::protocol_name(),
+      SparseMatPolyEvalProof::<G, PCS>::protocol_name(),
     );
 
     // equalize the lengths of rx and ry
-    let (rx_ext, ry_ext) = SparseMatPolyEvalProof::<G>::equalize(rx, ry);
+    let (rx_ext, ry_ext) = SparseMatPolyEvalProof::<G, PCS>::equalize(rx, ry);
 
     let (nz, num_mem_cells) = (comm.num_ops, comm.num_mem_cells);
     assert_eq!(rx_ext.len().pow2(), num_mem_cells);
@@ -1750,6 +1804,7 @@ impl<G: ProjectiveCurve> SparseMatPolyEvalProof<G> {
       &(r_mem_check[0], r_mem_check[1]),
       nz,
       transcript,
+      acc,
     )
   }
 }
@@ -1804,15 +1859,14 @@ impl<F: PrimeField> SparsePolynomial<F> {
 #[cfg(test)]
 mod tests {
   use super::*;
-  use ark_bls12_381::G1Projective;
   use ark_std::rand::RngCore;
   use ark_std::test_rng;
   use ark_std::UniformRand;
 
-  #[test]
-  fn check_sparse_polyeval_proof() {
-    check_sparse_polyeval_proof_helper::<G1Projective>()
-  }
+  crate::curves::curve_tests!(
+    group: check_sparse_polyeval_proof,
+    check_sparse_polyeval_proof_helper
+  );
   fn check_sparse_polyeval_proof_helper<G: ProjectiveCurve>() {
     let mut prng = test_rng();
 
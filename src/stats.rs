@@ -0,0 +1,665 @@
+use super::dense_mlpoly::EqPolynomial;
+use super::math::Math;
+use ark_ec::ProjectiveCurve;
+use ark_ff::Zero;
+use ark_serialize::CanonicalSerialize;
+use core::cmp::{max, min};
+
+/// `PhaseCost` holds an estimate of the work done in one phase of proving or verifying.
+/// Multi-scalar multiplications (MSMs) are counted together with their total number of bases;
+/// single-base commitments and generator folding are counted as MSMs as well.
+/// Field operations count multiplications, which dominate the cost of field arithmetic.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PhaseCost {
+  /// the name of the phase
+  pub phase: &'static str,
+  /// the number of MSMs
+  pub num_msms: usize,
+  /// the total number of bases over all MSMs
+  pub num_msm_bases: usize,
+  /// the number of field multiplications
+  pub num_field_muls: usize,
+}
+
+impl PhaseCost {
+  fn new(phase: &'static str) -> Self {
+    PhaseCost {
+      phase,
+      ..Default::default()
+    }
+  }
+
+  fn msm(mut self, count: usize, num_bases: usize) -> Self {
+    self.num_msms += count;
+    self.num_msm_bases += count * num_bases;
+    self
+  }
+
+  fn muls(mut self, count: usize) -> Self {
+    self.num_field_muls += count;
+    self
+  }
+
+  fn add(mut self, other: PhaseCost) -> Self {
+    self.num_msms += other.num_msms;
+    self.num_msm_bases += other.num_msm_bases;
+    self.num_field_muls += other.num_field_muls;
+    self
+  }
+}
+
+/// `InstanceStats` reports the sizes of public parameters and proofs for an R1CS instance,
+/// along with estimates of prover and verifier costs, without producing a proof
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstanceStats {
+  /// the number of constraints after padding
+  pub num_cons: usize,
+  /// the number of variables after padding
+  pub num_vars: usize,
+  /// the number of public inputs
+  pub num_inputs: usize,
+  /// the number of non-zero entries in A, B, and C, including those added by padding
+  pub num_nz_entries: (usize, usize, usize),
+  /// the number of memory operations in Spark, the padded maximum number of non-zero entries
+  pub spark_num_ops: usize,
+  /// the number of memory cells in Spark
+  pub spark_num_mem_cells: usize,
+  /// the number of group elements sampled for `SNARKGens`
+  pub num_snark_gens: usize,
+  /// the number of group elements sampled for `NIZKGens`
+  pub num_nizk_gens: usize,
+  /// the size in bytes of a serialized `SNARK`
+  pub snark_proof_size: usize,
+  /// the size in bytes of a serialized `NIZK`
+  pub nizk_proof_size: usize,
+  /// estimated costs of producing a `SNARK`, by phase
+  pub snark_prover_cost: Vec<PhaseCost>,
+  /// estimated costs of verifying a `SNARK`, by phase
+  pub snark_verifier_cost: Vec<PhaseCost>,
+  /// estimated costs of producing a `NIZK`, by phase
+  pub nizk_prover_cost: Vec<PhaseCost>,
+  /// estimated costs of verifying a `NIZK`, by phase
+  pub nizk_verifier_cost: Vec<PhaseCost>,
+}
+
+// sizes of the serialized components of proofs, mirroring their `CanonicalSerialize` encodings,
+// for polynomials committed with generators that have about `2^log_ratio` times as many rows
+// as columns, where lookups and memories share generators for `aux_num_vars` variables
+struct ProofSizes {
+  point: usize,
+  scalar: usize,
+  len: usize,
+  log_ratio: isize,
+  aux_num_vars: usize,
+  split: fn(usize, isize) -> (usize, usize),
+}
+
+impl ProofSizes {
+  fn new<G: ProjectiveCurve>(log_ratio: isize, aux_num_vars: usize) -> Self {
+    ProofSizes {
+      point: G::zero().serialized_size(),
+      scalar: G::ScalarField::zero().serialized_size(),
+      len: 0usize.serialized_size(),
+      log_ratio,
+      aux_num_vars,
+      split: EqPolynomial::<G::ScalarField>::compute_factored_lens_with_ratio,
+    }
+  }
+
+  // splits the variables of a committed polynomial into rows and columns, as the generators
+  // of `PolyCommitmentGens::new_with_ratio` for as many variables do
+  fn factored_lens(&self, num_vars: usize) -> (usize, usize) {
+    (self.split)(num_vars, self.log_ratio)
+  }
+
+  // splits the variables of a polynomial committed with the generators of lookups and
+  // memories, whose rows are shortened for polynomials with fewer variables
+  fn aux_lens(&self, num_vars: usize) -> (usize, usize) {
+    let right = min(self.factored_lens(self.aux_num_vars).1, num_vars);
+    (num_vars - right, right)
+  }
+
+  fn scalars(&self, n: usize) -> usize {
+    self.len + n * self.scalar
+  }
+
+  fn poly_commitment(&self, (left, _right): (usize, usize)) -> usize {
+    self.len + left.pow2() * self.point
+  }
+
+  fn poly_eval_proof(&self, (_left, right): (usize, usize)) -> usize {
+    // a bullet reduction proof followed by delta, beta, z1, and z2
+    2 * (self.len + right * self.point) + 2 * self.point + 2 * self.scalar
+  }
+
+  fn zk_sumcheck(&self, num_rounds: usize, degree: usize) -> usize {
+    // each round has a commitment to the polynomial and to its evaluation, and a dot-product proof
+    let dot_product_proof = 2 * self.point + self.scalars(degree + 1) + 2 * self.scalar;
+    3 * self.len + num_rounds * (2 * self.point + dot_product_proof)
+  }
+
+  fn sumcheck(&self, num_rounds: usize, degree: usize) -> usize {
+    self.len + num_rounds * self.scalars(degree)
+  }
+
+  fn product_circuit_batched(&self, num_leaves: usize, num_prod: usize, num_dotp: usize) -> usize {
+    let layers = (0..num_leaves.log_2())
+      .map(|num_rounds| self.sumcheck(num_rounds, 3) + 2 * self.scalars(num_prod))
+      .sum::<usize>();
+    self.len + layers + 3 * self.scalars(num_dotp)
+  }
+
+  fn r1cs_proof(&self, num_cons: usize, num_vars: usize) -> usize {
+    let (knowledge, product, equality) = (
+      self.point + 2 * self.scalar,
+      3 * self.point + 5 * self.scalar,
+      self.point + self.scalar,
+    );
+    // the claims Az, Bz and Cz, a proof of knowledge of Cz, and the product Az * Bz with its
+    // proof, each in a vector with one entry per matrix or term
+    self.poly_commitment(self.factored_lens(num_vars.log_2()))
+      + self.zk_sumcheck(num_cons.log_2(), 3)
+      + self.len
+      + 3 * self.point
+      + self.len
+      + knowledge
+      + 3 * self.len
+      + self.point
+      + 3 * self.len
+      + product
+      + equality
+      + self.zk_sumcheck((2 * num_vars).log_2(), 2)
+      + self.point
+      + self.poly_eval_proof(self.factored_lens(num_vars.log_2()))
+      + equality
+  }
+
+  fn r1cs_eval_proof(&self, num_ops: usize, num_mem_cells: usize) -> usize {
+    let log_ops = num_ops.log_2();
+    let log_mem = num_mem_cells.log_2();
+
+    // evaluations of init, read, write, and audit for rows and columns, and of the dot products
+    let product_layer = 2 * (2 * self.scalar + 2 * self.scalars(3))
+      + 2 * self.scalars(3)
+      + self.product_circuit_batched(num_mem_cells, 4, 0)
+      + self.product_circuit_batched(num_ops, 12, 6);
+
+    // evaluations of addresses, timestamps, values, and derefs, and three openings
+    let hash_layer = 2 * (2 * self.scalars(3) + self.scalar)
+      + self.scalars(3)
+      + 2 * self.scalars(3)
+      + self.poly_eval_proof(self.factored_lens(log_ops + 4))
+      + self.poly_eval_proof(self.factored_lens(log_mem + 1))
+      + self.poly_eval_proof(self.factored_lens(log_ops + 3));
+
+    self.poly_commitment(self.factored_lens(log_ops + 3)) + product_layer + hash_layer
+  }
+
+  fn lookup_proof(&self, num_lookups: usize, table_len: usize, num_vars: usize) -> usize {
+    self.poly_commitment(self.aux_lens((2 * num_lookups).log_2()))
+      + self.poly_commitment(self.aux_lens(table_len.log_2()))
+      + 4 * self.scalar
+      + self.product_circuit_batched(num_lookups, 2, 0)
+      + self.product_circuit_batched(table_len, 2, 0)
+      + 4 * self.scalar
+      + self.poly_eval_proof(self.aux_lens((2 * num_lookups).log_2()))
+      + self.poly_eval_proof(self.aux_lens(table_len.log_2()))
+      + self.poly_eval_proof(self.factored_lens(num_vars.log_2()))
+  }
+
+  fn memory_proof(&self, num_ops: usize, num_cells: usize, num_vars: usize) -> usize {
+    self.poly_commitment(self.aux_lens((2 * num_cells).log_2()))
+      + 4 * self.scalar
+      + self.product_circuit_batched(num_ops, 2, 0)
+      + self.product_circuit_batched(num_cells, 2, 0)
+      + 6 * self.scalar
+      + self.poly_eval_proof(self.aux_lens((2 * num_cells).log_2()))
+      + self.poly_eval_proof(self.factored_lens(num_vars.log_2()))
+  }
+}
+
+// the number of group elements sampled for `DotProductProofGens` used to commit to a polynomial
+// whose variables are split into `(left, right)` for rows and columns
+fn num_poly_commitment_gens((_left, right): (usize, usize)) -> usize {
+  right.pow2() + 2
+}
+
+fn commit_cost(phase: &'static str, (left, right): (usize, usize)) -> PhaseCost {
+  PhaseCost::new(phase).msm(left.pow2(), right.pow2() + 1)
+}
+
+fn poly_eval_prover_cost(phase: &'static str, (left, right): (usize, usize)) -> PhaseCost {
+  let mut cost = PhaseCost::new(phase)
+    .muls((left + right).pow2() + left.pow2() + right.pow2())
+    .msm(3, 2);
+  // each round of the bullet reduction commits to two cross terms and folds vectors and generators
+  for round in 0..right {
+    let n = right.pow2() >> round;
+    cost = cost.msm(2, n / 2 + 2).msm(1, n).muls(3 * n);
+  }
+  cost
+}
+
+fn poly_eval_verifier_cost(phase: &'static str, (left, right): (usize, usize)) -> PhaseCost {
+  let n = right.pow2();
+  PhaseCost::new(phase)
+    .muls(left.pow2() + n + n * right)
+    .msm(1, left.pow2())
+    .msm(1, n + 2 * right + 3)
+}
+
+fn zk_sumcheck_prover_cost(
+  phase: &'static str,
+  num_rounds: usize,
+  degree: usize,
+  num_polys: usize,
+) -> PhaseCost {
+  let mut cost = PhaseCost::new(phase);
+  for round in 0..num_rounds {
+    let n = (num_rounds - round).pow2();
+    cost = cost
+      .muls(n / 2 * (degree + 1) * num_polys + n / 2 * num_polys)
+      .msm(2, degree + 2)
+      .msm(2, 2);
+  }
+  cost
+}
+
+fn zk_sumcheck_verifier_cost(phase: &'static str, num_rounds: usize, degree: usize) -> PhaseCost {
+  PhaseCost::new(phase)
+    .msm(3 * num_rounds, degree + 2)
+    .muls(num_rounds * 2 * (degree + 1))
+}
+
+fn product_circuit_prover_cost(num_leaves: usize, num_circuits: usize) -> PhaseCost {
+  // building the circuit, followed by a cubic sum-check per layer
+  PhaseCost::new("").muls(num_circuits * 13 * num_leaves)
+}
+
+fn product_circuit_verifier_cost(num_leaves: usize, num_circuits: usize) -> PhaseCost {
+  let num_rounds = (0..num_leaves.log_2()).sum::<usize>();
+  PhaseCost::new("").muls(num_rounds * (4 + 2 * num_circuits))
+}
+
+impl InstanceStats {
+  /// Computes statistics for an R1CS instance with `num_cons` constraints, `num_vars` variables,
+  /// `num_inputs` public inputs, and `num_nz_entries` non-zero entries in A, B, and C,
+  /// as they would be supplied to `Instance::new`
+  pub fn new<G: ProjectiveCurve>(
+    num_cons: usize,
+    num_vars: usize,
+    num_inputs: usize,
+    num_nz_entries: (usize, usize, usize),
+  ) -> Self {
+    InstanceStats::new_with_ratio::<G>(num_cons, num_vars, num_inputs, num_nz_entries, 0)
+  }
+
+  /// Computes statistics like `new`, for proofs whose generators are constructed with
+  /// `SNARKGens::new_with_ratio` and `NIZKGens::new_with_ratio` with the given `log_ratio`
+  pub fn new_with_ratio<G: ProjectiveCurve>(
+    num_cons: usize,
+    num_vars: usize,
+    num_inputs: usize,
+    num_nz_entries: (usize, usize, usize),
+    log_ratio: isize,
+  ) -> Self {
+    let num_vars_padded = max(num_vars, num_inputs + 1).next_power_of_two();
+    let num_cons_padded = max(num_cons, 2).next_power_of_two();
+
+    // `Instance::new` pads matrices with zero entries when there are fewer than two constraints
+    let pad = |nz: usize| {
+      if num_cons < 2 {
+        max(nz, num_cons_padded)
+      } else {
+        nz
+      }
+    };
+    let num_nz_entries = (
+      pad(num_nz_entries.0),
+      pad(num_nz_entries.1),
+      pad(num_nz_entries.2),
+    );
+
+    Self::from_padded::<G>(
+      num_cons_padded,
+      num_vars_padded,
+      num_inputs,
+      num_nz_entries,
+      &[],
+      &[],
+      log_ratio,
+    )
+  }
+
+  // computes statistics for an instance whose dimensions are already padded, along with
+  // lookups given as (num_lookups, table_len) and memories given as (num_ops, num_cells)
+  pub(crate) fn from_padded<G: ProjectiveCurve>(
+    num_cons: usize,
+    num_vars: usize,
+    num_inputs: usize,
+    num_nz_entries: (usize, usize, usize),
+    lookups: &[(usize, usize)],
+    memories: &[(usize, usize)],
+    log_ratio: isize,
+  ) -> Self {
+    let sizes = ProofSizes::new::<G>(log_ratio, (2 * num_vars).log_2());
+    let (nz_A, nz_B, nz_C) = num_nz_entries;
+    let num_nz = nz_A + nz_B + nz_C;
+    let spark_num_ops = max(
+      max(nz_A.next_power_of_two(), nz_B.next_power_of_two()),
+      nz_C.next_power_of_two(),
+    );
+    let spark_num_mem_cells = max(num_cons, 2 * num_vars);
+    let (num_rounds_x, num_rounds_y) = (num_cons.log_2(), (2 * num_vars).log_2());
+    let (log_ops, log_mem) = (spark_num_ops.log_2(), spark_num_mem_cells.log_2());
+    let witness_lens = sizes.factored_lens(num_vars.log_2());
+
+    // generators for the sum-checks, the witness commitment, lookups and memories, and Spark
+    let num_nizk_gens = 4
+      + 5
+      + num_poly_commitment_gens(witness_lens)
+      + num_poly_commitment_gens(sizes.factored_lens(sizes.aux_num_vars));
+    let num_snark_gens = num_nizk_gens
+      + num_poly_commitment_gens(sizes.factored_lens(log_ops + 4))
+      + num_poly_commitment_gens(sizes.factored_lens(log_mem + 1))
+      + num_poly_commitment_gens(sizes.factored_lens(log_ops + 3));
+
+    let r1cs_proof_size = sizes.r1cs_proof(num_cons, num_vars);
+    // lookups and memories are both proved with offline memory checking after the R1CS proof
+    let memory_checking_size = sizes.len
+      + lookups
+        .iter()
+        .map(|&(num_lookups, table_len)| sizes.lookup_proof(num_lookups, table_len, num_vars))
+        .sum::<usize>()
+      + sizes.len
+      + memories
+        .iter()
+        .map(|&(num_ops, num_cells)| sizes.memory_proof(num_ops, num_cells, num_vars))
+        .sum::<usize>();
+    let snark_proof_size = r1cs_proof_size
+      + 3 * sizes.scalar
+      + sizes.r1cs_eval_proof(spark_num_ops, spark_num_mem_cells)
+      + memory_checking_size;
+    let nizk_proof_size = r1cs_proof_size
+      + sizes.scalars(num_rounds_x)
+      + sizes.scalars(num_rounds_y)
+      + memory_checking_size;
+
+    // costs shared by the SNARK and the NIZK
+    let r1cs_prover_cost = vec![
+      commit_cost("commit_witness", witness_lens),
+      zk_sumcheck_prover_cost("sumcheck_phase_one", num_rounds_x, 3, 4)
+        .muls(num_nz + num_cons)
+        .msm(8, 2),
+      zk_sumcheck_prover_cost("sumcheck_phase_two", num_rounds_y, 2, 2)
+        .muls(num_nz + num_cons + 3 * 2 * num_vars),
+      poly_eval_prover_cost("eval_witness", witness_lens).msm(1, 2),
+    ];
+    let r1cs_verifier_cost = vec![
+      zk_sumcheck_verifier_cost("sumcheck_phase_one", num_rounds_x, 3).msm(6, 2),
+      zk_sumcheck_verifier_cost("sumcheck_phase_two", num_rounds_y, 2)
+        .muls(num_inputs + num_rounds_y),
+      poly_eval_verifier_cost("eval_witness", witness_lens).msm(2, 2),
+    ];
+
+    let lookups_prover_cost = lookups.iter().fold(
+      PhaseCost::new("lookups"),
+      |cost, &(num_lookups, table_len)| {
+        let log_lookups = (2 * num_lookups).log_2();
+        cost
+          .add(commit_cost("", sizes.aux_lens(log_lookups)))
+          .add(commit_cost("", sizes.aux_lens(table_len.log_2())))
+          .add(product_circuit_prover_cost(num_lookups, 2))
+          .add(product_circuit_prover_cost(table_len, 2))
+          .add(poly_eval_prover_cost("", sizes.aux_lens(log_lookups)))
+          .add(poly_eval_prover_cost("", sizes.aux_lens(table_len.log_2())))
+          .add(poly_eval_prover_cost("", witness_lens))
+      },
+    );
+    let lookups_verifier_cost = lookups.iter().fold(
+      PhaseCost::new("lookups"),
+      |cost, &(num_lookups, table_len)| {
+        cost
+          .muls(table_len)
+          .add(product_circuit_verifier_cost(num_lookups, 2))
+          .add(product_circuit_verifier_cost(table_len, 2))
+          .add(poly_eval_verifier_cost(
+            "",
+            sizes.aux_lens((2 * num_lookups).log_2()),
+          ))
+          .add(poly_eval_verifier_cost(
+            "",
+            sizes.aux_lens(table_len.log_2()),
+          ))
+          .add(poly_eval_verifier_cost("", witness_lens))
+      },
+    );
+
+    let memories_prover_cost =
+      memories
+        .iter()
+        .fold(PhaseCost::new("memories"), |cost, &(num_ops, num_cells)| {
+          let log_final = (2 * num_cells).log_2();
+          cost
+            .muls(4 * (num_ops + num_cells))
+            .add(commit_cost("", sizes.aux_lens(log_final)))
+            .add(product_circuit_prover_cost(num_ops, 2))
+            .add(product_circuit_prover_cost(num_cells, 2))
+            .add(poly_eval_prover_cost("", sizes.aux_lens(log_final)))
+            .add(poly_eval_prover_cost("", witness_lens))
+        });
+    let memories_verifier_cost =
+      memories
+        .iter()
+        .fold(PhaseCost::new("memories"), |cost, &(num_ops, num_cells)| {
+          cost
+            .muls(num_cells)
+            .add(product_circuit_verifier_cost(num_ops, 2))
+            .add(product_circuit_verifier_cost(num_cells, 2))
+            .add(poly_eval_verifier_cost(
+              "",
+              sizes.aux_lens((2 * num_cells).log_2()),
+            ))
+            .add(poly_eval_verifier_cost("", witness_lens))
+        });
+
+    // Spark proves the evaluations of A, B, and C with memory-checking product circuits
+    let spark_prover_cost = PhaseCost::new("eval_instance")
+      .muls(num_nz + spark_num_mem_cells + 2 * 3 * spark_num_ops)
+      .add(commit_cost("", sizes.factored_lens(log_ops + 3)))
+      .add(product_circuit_prover_cost(spark_num_ops, 12 + 6))
+      .add(product_circuit_prover_cost(spark_num_mem_cells, 4))
+      .add(poly_eval_prover_cost("", sizes.factored_lens(log_ops + 4)))
+      .add(poly_eval_prover_cost("", sizes.factored_lens(log_mem + 1)))
+      .add(poly_eval_prover_cost("", sizes.factored_lens(log_ops + 3)));
+    let spark_verifier_cost = PhaseCost::new("eval_instance")
+      .muls(2 * spark_num_mem_cells)
+      .add(product_circuit_verifier_cost(spark_num_ops, 12 + 6))
+      .add(product_circuit_verifier_cost(spark_num_mem_cells, 4))
+      .add(poly_eval_verifier_cost(
+        "",
+        sizes.factored_lens(log_ops + 4),
+      ))
+      .add(poly_eval_verifier_cost(
+        "",
+        sizes.factored_lens(log_mem + 1),
+      ))
+      .add(poly_eval_verifier_cost(
+        "",
+        sizes.factored_lens(log_ops + 3),
+      ));
+
+    // the NIZK verifier evaluates A, B, and C itself
+    let nizk_eval_cost = PhaseCost::new("eval_instance").muls(2 * num_nz + num_cons + 2 * num_vars);
+
+    let with = |costs: &[PhaseCost], extra: &[&PhaseCost]| {
+      let mut costs = costs.to_vec();
+      costs.extend(extra.iter().map(|&c| c.clone()));
+      costs
+    };
+    InstanceStats {
+      num_cons,
+      num_vars,
+      num_inputs,
+      num_nz_entries,
+      spark_num_ops,
+      spark_num_mem_cells,
+      num_snark_gens,
+      num_nizk_gens,
+      snark_proof_size,
+      nizk_proof_size,
+      snark_prover_cost: with(
+        &r1cs_prover_cost,
+        &[
+          &lookups_prover_cost,
+          &memories_prover_cost,
+          &spark_prover_cost,
+        ],
+      ),
+      snark_verifier_cost: with(
+        &r1cs_verifier_cost,
+        &[
+          &lookups_verifier_cost,
+          &memories_verifier_cost,
+          &spark_verifier_cost,
+        ],
+      ),
+      nizk_prover_cost: with(
+        &r1cs_prover_cost,
+        &[&lookups_prover_cost, &memories_prover_cost],
+      ),
+      nizk_verifier_cost: with(
+        &[nizk_eval_cost],
+        &[
+          &r1cs_verifier_cost[0],
+          &r1cs_verifier_cost[1],
+          &r1cs_verifier_cost[2],
+          &lookups_verifier_cost,
+          &memories_verifier_cost,
+        ],
+      ),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::{
+    InputsAssignment, Instance, LookupTable, NIZKGens, SNARKGens, VarsAssignment, NIZK, SNARK,
+  };
+  use super::*;
+  use ark_std::One;
+  use merlin::Transcript;
+
+  fn proof_sizes<G: ProjectiveCurve>(
+    inst: &Instance<G::ScalarField>,
+    vars: VarsAssignment<G::ScalarField>,
+    inputs: &InputsAssignment<G::ScalarField>,
+    num_nz_entries: usize,
+    log_ratio: isize,
+  ) -> (usize, usize) {
+    let (num_cons, num_vars, num_inputs) = (
+      inst.inst.get_num_cons(),
+      inst.inst.get_num_vars(),
+      inst.inst.get_num_inputs(),
+    );
+
+    let gens =
+      SNARKGens::<G>::new_with_ratio(num_cons, num_vars, num_inputs, num_nz_entries, log_ratio);
+    let (comm, decomm) = SNARK::encode(inst, &gens);
+    let mut prover_transcript = Transcript::new(b"example");
+    let snark = SNARK::prove(
+      inst,
+      &comm,
+      &decomm,
+      vars.clone(),
+      inputs,
+      &gens,
+      &mut prover_transcript,
+    )
+    .unwrap();
+
+    let gens = NIZKGens::<G>::new_with_ratio(num_cons, num_vars, num_inputs, log_ratio);
+    let mut prover_transcript = Transcript::new(b"example");
+    let nizk = NIZK::prove(inst, vars, inputs, &gens, &mut prover_transcript).unwrap();
+
+    (snark.serialized_size(), nizk.serialized_size())
+  }
+
+  crate::curves::curve_tests!(group: check_proof_sizes, check_proof_sizes_helper);
+
+  fn check_proof_sizes_helper<G: ProjectiveCurve>() {
+    for (num_cons, num_vars, num_inputs) in [(64, 32, 3), (16, 64, 10)] {
+      let (inst, vars, inputs) =
+        Instance::<G::ScalarField>::produce_synthetic_r1cs(num_cons, num_vars, num_inputs);
+      let stats = inst.stats::<G>();
+      let (snark_size, nizk_size) = proof_sizes::<G>(&inst, vars, &inputs, num_cons, 0);
+      assert_eq!(stats.snark_proof_size, snark_size);
+      assert_eq!(stats.nizk_proof_size, nizk_size);
+    }
+
+    // an instance with lookups, which contribute to the size of both proofs
+    let one = G::ScalarField::one();
+    let A = vec![(0, 0, one), (1, 1, one)];
+    let B = vec![(0, 8, one), (1, 8, one)];
+    let C = vec![(0, 0, one), (1, 1, one)];
+    let mut inst = Instance::new(2, 8, 1, &A, &B, &C).unwrap();
+    inst
+      .add_lookup(LookupTable::range(4).unwrap(), 0, 4)
+      .unwrap();
+    inst
+      .add_lookup(LookupTable::range(2).unwrap(), 4, 2)
+      .unwrap();
+    let vars = VarsAssignment::new(&[one; 8]).unwrap();
+    let inputs = InputsAssignment::new(&[one]).unwrap();
+    let stats = inst.stats::<G>();
+    let (snark_size, nizk_size) = proof_sizes::<G>(&inst, vars, &inputs, 2, 0);
+    assert_eq!(stats.snark_proof_size, snark_size);
+    assert_eq!(stats.nizk_proof_size, nizk_size);
+
+    // an instance with a memory, accessed twice at address zero
+    let mut inst = Instance::new(2, 8, 1, &A, &B, &C).unwrap();
+    inst.add_memory(&[one, one, one], 0, 2).unwrap();
+    let zero = G::ScalarField::zero();
+    let vars = VarsAssignment::new(&[zero, zero, one, one, zero, one, one, one]).unwrap();
+    assert!(inst.is_sat(&vars, &inputs).unwrap());
+    let stats = inst.stats::<G>();
+    let (snark_size, nizk_size) = proof_sizes::<G>(&inst, vars, &inputs, 2, 0);
+    assert_eq!(stats.snark_proof_size, snark_size);
+    assert_eq!(stats.nizk_proof_size, nizk_size);
+  }
+
+  crate::curves::curve_tests!(
+    group: check_proof_sizes_with_ratio,
+    check_proof_sizes_with_ratio_helper
+  );
+
+  fn check_proof_sizes_with_ratio_helper<G: ProjectiveCurve>() {
+    let (num_cons, num_vars, num_inputs) = (64, 32, 3);
+    for log_ratio in [-3, 2] {
+      let (inst, vars, inputs) =
+        Instance::<G::ScalarField>::produce_synthetic_r1cs(num_cons, num_vars, num_inputs);
+      let stats = inst.stats_with_ratio::<G>(log_ratio);
+      let (snark_size, nizk_size) = proof_sizes::<G>(&inst, vars, &inputs, num_cons, log_ratio);
+      assert_eq!(stats.snark_proof_size, snark_size);
+      assert_eq!(stats.nizk_proof_size, nizk_size);
+    }
+  }
+
+  crate::curves::curve_tests!(group: check_stats_padding, check_stats_padding_helper);
+
+  fn check_stats_padding_helper<G: ProjectiveCurve>() {
+    // a single constraint over three inputs, as in the padded-constraints example
+    let one = G::ScalarField::one();
+    let A = vec![(0, 2, one)];
+    let B = vec![(0, 2, one)];
+    let C = vec![(0, 1, one), (0, 0, one), (0, 3, one)];
+    let inst = Instance::new(1, 0, 3, &A, &B, &C).unwrap();
+
+    let stats = InstanceStats::new::<G>(1, 0, 3, (1, 1, 3));
+    assert_eq!(stats, inst.stats::<G>());
+    assert_eq!((stats.num_cons, stats.num_vars), (2, 4));
+    assert_eq!(stats.num_nz_entries, (2, 2, 3));
+    assert_eq!((stats.spark_num_ops, stats.spark_num_mem_cells), (4, 8));
+  }
+}
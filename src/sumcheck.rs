@@ -1,6 +1,6 @@
 #![allow(clippy::too_many_arguments)]
 #![allow(clippy::type_complexity)]
-use super::commitments::{Commitments, MultiCommitGens};
+use super::commitments::{Commitments, MSMAccumulator, MultiCommitGens};
 use super::dense_mlpoly::DensePolynomial;
 use super::errors::ProofVerifyError;
 use super::nizk::DotProductProof;
@@ -40,15 +40,21 @@ impl<F: PrimeField> SumcheckInstanceProof<F> {
     let mut r: Vec<F> = Vec::new();
 
     // verify that there is a univariate polynomial for each round
-    assert_eq!(self.compressed_polys.len(), num_rounds);
+    if self.compressed_polys.len() != num_rounds {
+      return Err(ProofVerifyError::InternalError);
+    }
     for i in 0..self.compressed_polys.len() {
       let poly = self.compressed_polys[i].decompress(&e);
 
       // verify degree bound
-      assert_eq!(poly.degree(), degree_bound);
+      if poly.degree() != degree_bound {
+        return Err(ProofVerifyError::InternalError);
+      }
 
       // check if G_k(0) + G_k(1) = e
-      assert_eq!(poly.eval_at_zero() + poly.eval_at_one(), e);
+      if poly.eval_at_zero() + poly.eval_at_one() != e {
+        return Err(ProofVerifyError::InternalError);
+      }
 
       // append the prover's message to the transcript
       <UniPoly<F> as AppendToTranscript<G>>::append_to_transcript(&poly, b"poly", transcript);
@@ -91,6 +97,32 @@ impl<G: ProjectiveCurve> ZKSumcheckInstanceProof<G> {
     gens_1: &MultiCommitGens<G>,
     gens_n: &MultiCommitGens<G>,
     transcript: &mut Transcript,
+  ) -> Result<(G, Vec<G::ScalarField>), ProofVerifyError> {
+    let mut acc = MSMAccumulator::new();
+    let (comm_claim_final, r) = self.verify_deferred(
+      comm_claim,
+      num_rounds,
+      degree_bound,
+      gens_1,
+      gens_n,
+      transcript,
+      &mut acc,
+    )?;
+    acc.check()?;
+    Ok((comm_claim_final, r))
+  }
+
+  // adds the verification equations of the proofs of each round to `acc` instead of checking
+  // them
+  pub fn verify_deferred(
+    &self,
+    comm_claim: &G,
+    num_rounds: usize,
+    degree_bound: usize,
+    gens_1: &MultiCommitGens<G>,
+    gens_n: &MultiCommitGens<G>,
+    transcript: &mut Transcript,
+    acc: &mut MSMAccumulator<G>,
   ) -> Result<(G, Vec<G::ScalarField>), ProofVerifyError> {
     // verify degree bound
     assert_eq!(gens_n.n, degree_bound + 1);
@@ -111,7 +143,7 @@ impl<G: ProjectiveCurve> ZKSumcheckInstanceProof<G> {
         <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"challenge_nextround");
 
       // verify the proof of sum-check and evals
-      let res = {
+      {
         let comm_claim_per_round = if i == 0 {
           comm_claim
         } else {
@@ -164,19 +196,15 @@ impl<G: ProjectiveCurve> ZKSumcheckInstanceProof<G> {
             .collect::<Vec<G::ScalarField>>()
         };
 
-        self.proofs[i]
-          .verify(
-            gens_1,
-            gens_n,
-            transcript,
-            &a,
-            &self.comm_polys[i],
-            &comm_target,
-          )
-          .is_ok()
-      };
-      if !res {
-        return Err(ProofVerifyError::InternalError);
+        self.proofs[i].verify(
+          gens_1,
+          gens_n,
+          transcript,
+          &a,
+          &self.comm_polys[i],
+          &comm_target,
+          acc,
+        )?;
       }
 
       r.push(r_i);
@@ -261,6 +289,140 @@ impl<F: PrimeField> SumcheckInstanceProof<F> {
     )
   }
 
+  pub fn prove_quad<Func, G>(
+    claim: &F,
+    num_rounds: usize,
+    poly_A: &mut DensePolynomial<F>,
+    poly_B: &mut DensePolynomial<F>,
+    comb_func: Func,
+    transcript: &mut Transcript,
+  ) -> (Self, Vec<F>, Vec<F>)
+  where
+    Func: Fn(&F, &F) -> F,
+    G: ProjectiveCurve<ScalarField = F>,
+  {
+    let mut e = *claim;
+    let mut r: Vec<F> = Vec::new();
+    let mut quad_polys: Vec<CompressedUniPoly<F>> = Vec::new();
+    for _j in 0..num_rounds {
+      let mut eval_point_0 = F::zero();
+      let mut eval_point_2 = F::zero();
+
+      let len = poly_A.len() / 2;
+      for i in 0..len {
+        // eval 0: bound_func is A(low)
+        eval_point_0 += comb_func(&poly_A[i], &poly_B[i]);
+
+        // eval 2: bound_func is -A(low) + 2*A(high)
+        let poly_A_bound_point = poly_A[len + i] + poly_A[len + i] - poly_A[i];
+        let poly_B_bound_point = poly_B[len + i] + poly_B[len + i] - poly_B[i];
+        eval_point_2 += comb_func(&poly_A_bound_point, &poly_B_bound_point);
+      }
+
+      let evals = vec![eval_point_0, e - eval_point_0, eval_point_2];
+      let poly = UniPoly::from_evals(&evals);
+
+      // append the prover's message to the transcript
+      <UniPoly<F> as AppendToTranscript<G>>::append_to_transcript(&poly, b"poly", transcript);
+
+      //derive the verifier's challenge for the next round
+      let r_j =
+        <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"challenge_nextround");
+
+      r.push(r_j);
+      // bound all tables to the verifier's challenege
+      poly_A.bound_poly_var_top(&r_j);
+      poly_B.bound_poly_var_top(&r_j);
+      e = poly.evaluate(&r_j);
+      quad_polys.push(poly.compress());
+    }
+
+    (
+      SumcheckInstanceProof::new(quad_polys),
+      r,
+      vec![poly_A[0], poly_B[0]],
+    )
+  }
+
+  pub fn prove_cubic_with_additive_term<Func, G>(
+    claim: &F,
+    num_rounds: usize,
+    poly_A: &mut DensePolynomial<F>,
+    poly_B: &mut DensePolynomial<F>,
+    poly_C: &mut DensePolynomial<F>,
+    poly_D: &mut DensePolynomial<F>,
+    comb_func: Func,
+    transcript: &mut Transcript,
+  ) -> (Self, Vec<F>, Vec<F>)
+  where
+    Func: Fn(&F, &F, &F, &F) -> F,
+    G: ProjectiveCurve<ScalarField = F>,
+  {
+    let mut e = *claim;
+    let mut r: Vec<F> = Vec::new();
+    let mut cubic_polys: Vec<CompressedUniPoly<F>> = Vec::new();
+    for _j in 0..num_rounds {
+      let mut eval_point_0 = F::zero();
+      let mut eval_point_2 = F::zero();
+      let mut eval_point_3 = F::zero();
+
+      let len = poly_A.len() / 2;
+      for i in 0..len {
+        // eval 0: bound_func is A(low)
+        eval_point_0 += comb_func(&poly_A[i], &poly_B[i], &poly_C[i], &poly_D[i]);
+
+        // eval 2: bound_func is -A(low) + 2*A(high)
+        let poly_A_bound_point = poly_A[len + i] + poly_A[len + i] - poly_A[i];
+        let poly_B_bound_point = poly_B[len + i] + poly_B[len + i] - poly_B[i];
+        let poly_C_bound_point = poly_C[len + i] + poly_C[len + i] - poly_C[i];
+        let poly_D_bound_point = poly_D[len + i] + poly_D[len + i] - poly_D[i];
+        eval_point_2 += comb_func(
+          &poly_A_bound_point,
+          &poly_B_bound_point,
+          &poly_C_bound_point,
+          &poly_D_bound_point,
+        );
+
+        // eval 3: bound_func is -2A(low) + 3A(high); computed incrementally with bound_func applied to eval(2)
+        let poly_A_bound_point = poly_A_bound_point + poly_A[len + i] - poly_A[i];
+        let poly_B_bound_point = poly_B_bound_point + poly_B[len + i] - poly_B[i];
+        let poly_C_bound_point = poly_C_bound_point + poly_C[len + i] - poly_C[i];
+        let poly_D_bound_point = poly_D_bound_point + poly_D[len + i] - poly_D[i];
+        eval_point_3 += comb_func(
+          &poly_A_bound_point,
+          &poly_B_bound_point,
+          &poly_C_bound_point,
+          &poly_D_bound_point,
+        );
+      }
+
+      let evals = vec![eval_point_0, e - eval_point_0, eval_point_2, eval_point_3];
+      let poly = UniPoly::from_evals(&evals);
+
+      // append the prover's message to the transcript
+      <UniPoly<F> as AppendToTranscript<G>>::append_to_transcript(&poly, b"poly", transcript);
+
+      //derive the verifier's challenge for the next round
+      let r_j =
+        <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"challenge_nextround");
+
+      r.push(r_j);
+      // bound all tables to the verifier's challenege
+      poly_A.bound_poly_var_top(&r_j);
+      poly_B.bound_poly_var_top(&r_j);
+      poly_C.bound_poly_var_top(&r_j);
+      poly_D.bound_poly_var_top(&r_j);
+      e = poly.evaluate(&r_j);
+      cubic_polys.push(poly.compress());
+    }
+
+    (
+      SumcheckInstanceProof::new(cubic_polys),
+      r,
+      vec![poly_A[0], poly_B[0], poly_C[0], poly_D[0]],
+    )
+  }
+
   pub fn prove_cubic_batched<Func, G>(
     claim: &F,
     num_rounds: usize,
@@ -802,4 +964,178 @@ impl<G: ProjectiveCurve> ZKSumcheckInstanceProof<G> {
       blinds_evals[num_rounds - 1],
     )
   }
+
+  // a sum-check over an arbitrary combination of the given polynomials, where `degree` bounds the
+  // degree of `comb_func` in each variable
+  pub fn prove_arbitrary<Func>(
+    claim: &G::ScalarField,
+    blind_claim: &G::ScalarField,
+    num_rounds: usize,
+    polys: &mut [DensePolynomial<G::ScalarField>],
+    degree: usize,
+    comb_func: Func,
+    gens_1: &MultiCommitGens<G>,
+    gens_n: &MultiCommitGens<G>,
+    transcript: &mut Transcript,
+    random_tape: &mut RandomTape<G>,
+  ) -> (
+    Self,
+    Vec<G::ScalarField>,
+    Vec<G::ScalarField>,
+    G::ScalarField,
+  )
+  where
+    Func: Fn(&[G::ScalarField]) -> G::ScalarField,
+  {
+    assert!(degree >= 2);
+    assert_eq!(gens_n.n, degree + 1);
+
+    let (blinds_poly, blinds_evals) = (
+      random_tape.random_vector(b"blinds_poly", num_rounds),
+      random_tape.random_vector(b"blinds_evals", num_rounds),
+    );
+
+    let mut claim_per_round = *claim;
+    let mut comm_claim_per_round = claim_per_round.commit(blind_claim, gens_1);
+
+    let mut r: Vec<G::ScalarField> = Vec::new();
+    let mut comm_polys: Vec<G> = Vec::new();
+    let mut comm_evals: Vec<G> = Vec::new();
+    let mut proofs: Vec<DotProductProof<G>> = Vec::new();
+
+    for j in 0..num_rounds {
+      let (poly, comm_poly) = {
+        // evaluations at 0, 2, 3, ..., degree; the evaluation at 1 follows from the claim
+        let mut evals_points = vec![G::ScalarField::zero(); degree];
+
+        let len = polys[0].len() / 2;
+        for i in 0..len {
+          let mut bound_points = polys.iter().map(|poly| poly[i]).collect::<Vec<_>>();
+          evals_points[0] += comb_func(&bound_points);
+
+          // the bound point for t is computed incrementally as A(low) + t * (A(high) - A(low))
+          let steps = polys
+            .iter()
+            .map(|poly| poly[len + i] - poly[i])
+            .collect::<Vec<_>>();
+          for (k, point) in bound_points.iter_mut().enumerate() {
+            *point = polys[k][len + i] + steps[k];
+          }
+          evals_points[1] += comb_func(&bound_points);
+          for eval_point in evals_points.iter_mut().skip(2) {
+            for (point, step) in bound_points.iter_mut().zip(steps.iter()) {
+              *point += step;
+            }
+            *eval_point += comb_func(&bound_points);
+          }
+        }
+
+        let mut evals = vec![evals_points[0], claim_per_round - evals_points[0]];
+        evals.extend(&evals_points[1..]);
+        let poly = UniPoly::from_evals(&evals);
+        let comm_poly = poly.commit(gens_n, &blinds_poly[j]);
+        (poly, comm_poly)
+      };
+
+      // append the prover's message to the transcript
+      <Transcript as ProofTranscript<G>>::append_point(transcript, b"comm_poly", &comm_poly);
+      comm_polys.push(comm_poly);
+
+      //derive the verifier's challenge for the next round
+      let r_j =
+        <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"challenge_nextround");
+
+      // bound all tables to the verifier's challenege
+      for poly in polys.iter_mut() {
+        poly.bound_poly_var_top(&r_j);
+      }
+
+      // produce a proof of sum-check and of evaluation, as in the sum-checks above
+      let (proof, claim_next_round, comm_claim_next_round) = {
+        let eval = poly.evaluate(&r_j);
+        let comm_eval = eval.commit(&blinds_evals[j], gens_1);
+
+        // add two claims to transcript
+        <Transcript as ProofTranscript<G>>::append_point(
+          transcript,
+          b"comm_claim_per_round",
+          &comm_claim_per_round,
+        );
+        <Transcript as ProofTranscript<G>>::append_point(transcript, b"comm_eval", &comm_eval);
+
+        // produce two weights
+        let w = <Transcript as ProofTranscript<G>>::challenge_vector(
+          transcript,
+          b"combine_two_claims_to_one",
+          2,
+        );
+
+        // compute a weighted sum of the RHS
+        let target = w[0] * claim_per_round + w[1] * eval;
+
+        let blind = {
+          let blind_sc = if j == 0 {
+            blind_claim
+          } else {
+            &blinds_evals[j - 1]
+          };
+
+          let blind_eval = &blinds_evals[j];
+
+          w[0] * blind_sc + w[1] * blind_eval
+        };
+
+        let a = {
+          // the vector to use to decommit for sum-check test
+          let a_sc = {
+            let mut a = vec![G::ScalarField::one(); poly.degree() + 1];
+            a[0] += G::ScalarField::one();
+            a
+          };
+
+          // the vector to use to decommit for evaluation
+          let a_eval = {
+            let mut a = vec![G::ScalarField::one(); poly.degree() + 1];
+            for j in 1..a.len() {
+              a[j] = a[j - 1] * r_j;
+            }
+            a
+          };
+
+          // take weighted sum of the two vectors using w
+          assert_eq!(a_sc.len(), a_eval.len());
+          (0..a_sc.len())
+            .map(|i| w[0] * a_sc[i] + w[1] * a_eval[i])
+            .collect::<Vec<G::ScalarField>>()
+        };
+
+        let (proof, _comm_poly, _comm_sc_eval) = DotProductProof::prove(
+          gens_1,
+          gens_n,
+          transcript,
+          random_tape,
+          &poly.as_vec(),
+          &blinds_poly[j],
+          &a,
+          &target,
+          &blind,
+        );
+
+        (proof, eval, comm_eval)
+      };
+
+      proofs.push(proof);
+      claim_per_round = claim_next_round;
+      comm_claim_per_round = comm_claim_next_round;
+      r.push(r_j);
+      comm_evals.push(comm_claim_per_round);
+    }
+
+    (
+      ZKSumcheckInstanceProof::new(comm_polys, comm_evals, proofs),
+      r,
+      polys.iter().map(|poly| poly[0]).collect(),
+      blinds_evals[num_rounds - 1],
+    )
+  }
 }
@@ -1,5 +1,5 @@
 use ark_ec::ProjectiveCurve;
-use ark_ff::PrimeField;
+use ark_ff::Zero;
 use ark_serialize::CanonicalSerialize;
 use merlin::Transcript;
 
@@ -49,7 +49,13 @@ impl<G: ProjectiveCurve> ProofTranscript<G> for Transcript {
   fn challenge_scalar(&mut self, label: &'static [u8]) -> G::ScalarField {
     let mut buf = [0u8; 64];
     self.challenge_bytes(label, &mut buf);
-    G::ScalarField::from_le_bytes_mod_order(&buf)
+    // reduces the bytes as a little-endian integer with field arithmetic, which equals
+    // `from_le_bytes_mod_order` but also supports fields whose modulus fills a limb less than
+    // their representation, as those of secp256k1 do
+    let base = G::ScalarField::from(256u64);
+    buf.iter().rev().fold(G::ScalarField::zero(), |acc, byte| {
+      acc * base + G::ScalarField::from(*byte as u64)
+    })
   }
 
   fn challenge_vector(&mut self, label: &'static [u8], len: usize) -> Vec<G::ScalarField> {
@@ -0,0 +1,398 @@
+use super::dense_mlpoly::EqPolynomial;
+use super::errors::{ProofVerifyError, R1CSError};
+use super::math::Math;
+use super::r1csinstance::{R1CSCommitmentGens, R1CSEvalProof, R1CSInstance};
+use super::r1csproof::{R1CSGens, R1CSProof};
+use super::timer::Timer;
+use super::transcript::ProofTranscript;
+use super::{
+  append_inst_evals, append_statement, start_proof, ComputationCommitment, ComputationDecommitment,
+  InputsAssignment, Instance, VarsAssignment,
+};
+use ark_ec::ProjectiveCurve;
+use ark_ff::PrimeField;
+use ark_serialize::*;
+use ark_std::{One, Zero};
+use core::cmp::max;
+use merlin::Transcript;
+
+/// `UniformSNARKGens` holds public parameters for producing and verifying proofs of the
+/// satisfiability of an R1CS instance made of many copies of the same step circuit
+pub struct UniformSNARKGens<G: ProjectiveCurve> {
+  gens_r1cs_sat: R1CSGens<G>,
+  gens_r1cs_eval: R1CSCommitmentGens<G>,
+  num_steps: usize,
+}
+
+impl<G: ProjectiveCurve> UniformSNARKGens<G> {
+  /// Constructs a new `UniformSNARKGens` given the size of the step circuit and the number
+  /// of copies of it, which must be a power of two, or else `R1CSError::InvalidNumberOfInstances`
+  /// is returned; only the witness commitment grows with the number of copies
+  pub fn new(
+    num_cons: usize,
+    num_vars: usize,
+    num_inputs: usize,
+    num_nz_entries: usize,
+    num_steps: usize,
+  ) -> Result<Self, R1CSError> {
+    if !num_steps.is_power_of_two() {
+      return Err(R1CSError::InvalidNumberOfInstances);
+    }
+    let num_vars_padded = max(num_vars, num_inputs + 1).next_power_of_two();
+    let num_cons_padded = max(num_cons, 2).next_power_of_two();
+
+    let gens_r1cs_sat = R1CSGens::<G>::new(
+      b"gens_r1cs_uniform_sat",
+      num_steps * num_cons_padded,
+      num_steps * num_vars_padded,
+    );
+    let gens_r1cs_eval = R1CSCommitmentGens::new(
+      b"gens_r1cs_eval",
+      num_cons,
+      num_vars_padded,
+      num_inputs,
+      num_nz_entries,
+    );
+    Ok(UniformSNARKGens {
+      gens_r1cs_sat,
+      gens_r1cs_eval,
+      num_steps,
+    })
+  }
+}
+
+/// `UniformSNARK` holds a proof of the satisfiability of a uniform R1CS instance, whose
+/// matrices are block-diagonal with one copy of the step circuit per step; every step has
+/// its own variables and all steps share the public inputs. Only the step circuit is
+/// committed, and the verifier evaluates the block-diagonal matrices from it
+#[derive(CanonicalSerialize, CanonicalDeserialize, Debug)]
+pub struct UniformSNARK<G: ProjectiveCurve> {
+  r1cs_sat_proof: R1CSProof<G>,
+  r: (Vec<G::ScalarField>, Vec<G::ScalarField>),
+  evals_vars: (G::ScalarField, G::ScalarField, G::ScalarField),
+  evals_inputs: (G::ScalarField, G::ScalarField, G::ScalarField),
+  r1cs_eval_proof: R1CSEvalProof<G>,
+}
+
+impl<G: ProjectiveCurve> UniformSNARK<G> {
+  fn protocol_name() -> &'static [u8] {
+    b"Spartan uniform SNARK proof"
+  }
+
+  /// A public computation to create a commitment to the step circuit of a uniform R1CS
+  /// instance; its cost does not depend on the number of steps
+  pub fn encode(
+    step: &Instance<G::ScalarField>,
+    gens: &UniformSNARKGens<G>,
+  ) -> (
+    ComputationCommitment<G>,
+    ComputationDecommitment<G::ScalarField>,
+  ) {
+    let timer_encode = Timer::new("UniformSNARK::encode");
+    let (comm, decomm) = step.inst.commit(&gens.gens_r1cs_eval);
+    timer_encode.stop();
+    (
+      ComputationCommitment {
+        comm,
+        lookups: step.lookups.clone(),
+        memories: step.memories.clone(),
+        phases: None,
+      },
+      ComputationDecommitment { decomm },
+    )
+  }
+
+  /// A method to produce a proof of the satisfiability of the uniform R1CS instance with
+  /// the given step circuit, given one assignment of variables per step and the shared
+  /// inputs; step circuits with lookups, memories or a two-phase witness are not supported
+  pub fn prove(
+    step: &Instance<G::ScalarField>,
+    comm: &ComputationCommitment<G>,
+    decomm: &ComputationDecommitment<G::ScalarField>,
+    vars: Vec<VarsAssignment<G::ScalarField>>,
+    inputs: &InputsAssignment<G::ScalarField>,
+    gens: &UniformSNARKGens<G>,
+    transcript: &mut Transcript,
+  ) -> Result<Self, R1CSError> {
+    let timer_prove = Timer::new("UniformSNARK::prove");
+    step.check_plain()?;
+    if vars.len() != gens.num_steps {
+      return Err(R1CSError::InvalidNumberOfInstances);
+    }
+    for vars in vars.iter() {
+      step.check_assignment(vars, inputs)?;
+    }
+
+    let mut random_tape = start_proof::<G, _>(
+      UniformSNARK::<G>::protocol_name(),
+      b"comm",
+      &comm.comm,
+      transcript,
+    );
+
+    // the prover works with the expanded instance, while the verifier never builds it
+    let inst = expand(&step.inst, gens.num_steps);
+    let num_padded_vars = step.inst.get_num_vars();
+    let padded_vars = vars
+      .into_iter()
+      .flat_map(|vars| vars.pad_to(num_padded_vars).assignment)
+      .collect::<Vec<_>>();
+
+    let (r1cs_sat_proof, rx, ry, _witness) = R1CSProof::prove(
+      &inst,
+      padded_vars,
+      &inputs.assignment,
+      &gens.gens_r1cs_sat,
+      transcript,
+      &mut random_tape,
+    );
+
+    // We send evaluations of the step matrices at the columns of the variables and at the
+    // columns of the inputs, from which the verifier evaluates the expanded matrices
+    let timer_eval = Timer::new("eval_sparse_polys");
+    let (rx_step, ry_step) = step_point(&rx, &ry, gens.num_steps);
+    let column = |b: G::ScalarField| [vec![b], ry_step.to_vec()].concat();
+    let evals_vars = step.inst.evaluate(rx_step, &column(G::ScalarField::zero()));
+    let evals_inputs = step.inst.evaluate(rx_step, &column(G::ScalarField::one()));
+    append_evals::<G>(transcript, &evals_vars, &evals_inputs);
+    timer_eval.stop();
+
+    // both claims are reduced to a single evaluation of the step matrices
+    let r_b = <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"challenge_b");
+    let evals = combine(&evals_vars, &evals_inputs, &r_b);
+    let r1cs_eval_proof = R1CSEvalProof::prove(
+      &decomm.decomm,
+      rx_step,
+      &column(r_b),
+      &evals,
+      &gens.gens_r1cs_eval,
+      transcript,
+      &mut random_tape,
+    );
+
+    timer_prove.stop();
+    Ok(UniformSNARK {
+      r1cs_sat_proof,
+      r: (rx, ry),
+      evals_vars,
+      evals_inputs,
+      r1cs_eval_proof,
+    })
+  }
+
+  /// A method to verify a proof of the satisfiability of the uniform R1CS instance with
+  /// the committed step circuit and the given inputs
+  pub fn verify(
+    &self,
+    comm: &ComputationCommitment<G>,
+    inputs: &InputsAssignment<G::ScalarField>,
+    transcript: &mut Transcript,
+    gens: &UniformSNARKGens<G>,
+  ) -> Result<(), ProofVerifyError> {
+    let timer_verify = Timer::new("UniformSNARK::verify");
+    if !comm.lookups.is_empty()
+      || !comm.memories.is_empty()
+      || inputs.assignment.len() != comm.comm.get_num_inputs()
+    {
+      return Err(ProofVerifyError::InternalError);
+    }
+
+    append_statement::<G, _>(
+      UniformSNARK::<G>::protocol_name(),
+      b"comm",
+      &comm.comm,
+      transcript,
+    );
+
+    // the expanded matrices are evaluated at the claimed point, which must be the one the
+    // sum-checks reduce to
+    let num_steps = gens.num_steps;
+    let (num_vars, num_cons) = (
+      num_steps * comm.comm.get_num_vars(),
+      num_steps * comm.comm.get_num_cons(),
+    );
+    let (claimed_rx, claimed_ry) = &self.r;
+    if claimed_rx.len() != num_cons.log_2() || claimed_ry.len() != (2 * num_vars).log_2() {
+      return Err(ProofVerifyError::InternalError);
+    }
+    let inst_evals = expanded_evals(
+      claimed_rx,
+      claimed_ry,
+      num_steps,
+      &self.evals_vars,
+      &self.evals_inputs,
+    );
+
+    let timer_sat_proof = Timer::new("verify_sat_proof");
+    let (rx, ry) = self.r1cs_sat_proof.verify(
+      num_vars,
+      num_cons,
+      &inputs.assignment,
+      &inst_evals,
+      transcript,
+      &gens.gens_r1cs_sat,
+    )?;
+    if rx != *claimed_rx || ry != *claimed_ry {
+      return Err(ProofVerifyError::InternalError);
+    }
+    timer_sat_proof.stop();
+
+    let timer_eval_proof = Timer::new("verify_eval_proof");
+    append_evals::<G>(transcript, &self.evals_vars, &self.evals_inputs);
+    let r_b = <Transcript as ProofTranscript<G>>::challenge_scalar(transcript, b"challenge_b");
+    let (rx_step, ry_step) = step_point(&rx, &ry, num_steps);
+    self.r1cs_eval_proof.verify(
+      &comm.comm,
+      rx_step,
+      &[vec![r_b], ry_step.to_vec()].concat(),
+      &combine(&self.evals_vars, &self.evals_inputs, &r_b),
+      &gens.gens_r1cs_eval,
+      transcript,
+    )?;
+    timer_eval_proof.stop();
+    timer_verify.stop();
+    Ok(())
+  }
+}
+
+// builds the instance with one copy of the step circuit per step: the variables of step t
+// occupy the t-th block of the witness, and the constant and the inputs are shared
+fn expand<F: PrimeField>(step: &R1CSInstance<F>, num_steps: usize) -> R1CSInstance<F> {
+  let (num_cons, num_vars) = (step.get_num_cons(), step.get_num_vars());
+  let (A, B, C) = step.get_matrices();
+  let copy = |M: &[(usize, usize, F)]| {
+    (0..num_steps)
+      .flat_map(|t| {
+        M.iter().map(move |&(row, col, val)| {
+          let col = if col < num_vars {
+            t * num_vars + col
+          } else {
+            num_steps * num_vars + col - num_vars
+          };
+          (t * num_cons + row, col, val)
+        })
+      })
+      .collect::<Vec<_>>()
+  };
+  R1CSInstance::new(
+    num_steps * num_cons,
+    num_steps * num_vars,
+    step.get_num_inputs(),
+    &copy(&A),
+    &copy(&B),
+    &copy(&C),
+  )
+}
+
+// splits off the variables selecting the step, leaving the row of the step circuit and the
+// column within the variables (or the inputs) of a step
+fn step_point<'a, F>(rx: &'a [F], ry: &'a [F], num_steps: usize) -> (&'a [F], &'a [F]) {
+  let num_rounds_t = num_steps.log_2();
+  (&rx[num_rounds_t..], &ry[1 + num_rounds_t..])
+}
+
+// with rx = (rx_t, rx_step) and ry = (b, ry_t, ry_step), the expanded matrices evaluate to
+// (1 - b) * eq(rx_t, ry_t) * M(rx_step, (0, ry_step)) + b * eq(ry_t, 0) * M(rx_step, (1, ry_step))
+fn expanded_evals<F: PrimeField>(
+  rx: &[F],
+  ry: &[F],
+  num_steps: usize,
+  evals_vars: &(F, F, F),
+  evals_inputs: &(F, F, F),
+) -> (F, F, F) {
+  let num_rounds_t = num_steps.log_2();
+  let (rx_t, ry_t) = (&rx[..num_rounds_t], &ry[1..1 + num_rounds_t]);
+  let eq_vars = (F::one() - ry[0]) * EqPolynomial::new(rx_t.to_vec()).evaluate(ry_t);
+  let eq_inputs = ry[0] * ry_t.iter().map(|r| F::one() - r).product::<F>();
+  (
+    eq_vars * evals_vars.0 + eq_inputs * evals_inputs.0,
+    eq_vars * evals_vars.1 + eq_inputs * evals_inputs.1,
+    eq_vars * evals_vars.2 + eq_inputs * evals_inputs.2,
+  )
+}
+
+fn combine<F: PrimeField>(evals_vars: &(F, F, F), evals_inputs: &(F, F, F), r_b: &F) -> (F, F, F) {
+  let one_minus_r_b = F::one() - r_b;
+  (
+    one_minus_r_b * evals_vars.0 + *r_b * evals_inputs.0,
+    one_minus_r_b * evals_vars.1 + *r_b * evals_inputs.1,
+    one_minus_r_b * evals_vars.2 + *r_b * evals_inputs.2,
+  )
+}
+
+fn append_evals<G: ProjectiveCurve>(
+  transcript: &mut Transcript,
+  evals_vars: &(G::ScalarField, G::ScalarField, G::ScalarField),
+  evals_inputs: &(G::ScalarField, G::ScalarField, G::ScalarField),
+) {
+  append_inst_evals::<G>(transcript, evals_vars);
+  append_inst_evals::<G>(transcript, evals_inputs);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // x^2 + c = w for a public c, over the variables (x, x^2, w)
+  fn square_plus<F: PrimeField>() -> Instance<F> {
+    let (num_cons, num_vars, num_inputs) = (2, 3, 1);
+    let one = F::one();
+    let A = vec![(0, 0, one), (1, 1, one), (1, 4, one)];
+    let B = vec![(0, 0, one), (1, 3, one)];
+    let C = vec![(0, 1, one), (1, 2, one)];
+    Instance::new(num_cons, num_vars, num_inputs, &A, &B, &C).unwrap()
+  }
+
+  crate::curves::curve_tests!(group: check_uniform_snark, check_uniform_snark_helper);
+
+  fn check_uniform_snark_helper<G: ProjectiveCurve>() {
+    let step = square_plus::<G::ScalarField>();
+    let c = 7u64;
+    let inputs = InputsAssignment::new(&[G::ScalarField::from(c)]).unwrap();
+    let vars = (1..5u64)
+      .map(|x| {
+        let vars = [x, x * x, x * x + c]
+          .iter()
+          .map(|v| G::ScalarField::from(*v))
+          .collect::<Vec<_>>();
+        VarsAssignment::new(&vars).unwrap()
+      })
+      .collect::<Vec<_>>();
+    for vars in vars.iter() {
+      assert!(step.is_sat(vars, &inputs).unwrap());
+    }
+
+    // the number of steps must be a power of two
+    assert!(matches!(
+      UniformSNARKGens::<G>::new(2, 3, 1, 3, 3),
+      Err(R1CSError::InvalidNumberOfInstances)
+    ));
+
+    let gens = UniformSNARKGens::<G>::new(2, 3, 1, 3, vars.len()).unwrap();
+    let (comm, decomm) = UniformSNARK::encode(&step, &gens);
+
+    let mut prover_transcript = Transcript::new(b"example");
+    let proof = UniformSNARK::prove(
+      &step,
+      &comm,
+      &decomm,
+      vars,
+      &inputs,
+      &gens,
+      &mut prover_transcript,
+    )
+    .unwrap();
+
+    let mut verifier_transcript = Transcript::new(b"example");
+    assert!(proof
+      .verify(&comm, &inputs, &mut verifier_transcript, &gens)
+      .is_ok());
+
+    // the proof does not verify with a different shared input
+    let wrong_inputs = InputsAssignment::new(&[G::ScalarField::from(c + 1)]).unwrap();
+    let mut verifier_transcript = Transcript::new(b"example");
+    assert!(proof
+      .verify(&comm, &wrong_inputs, &mut verifier_transcript, &gens)
+      .is_err());
+  }
+}
@@ -21,9 +21,11 @@ pub struct CompressedUniPoly<F: PrimeField> {
 
 impl<F: PrimeField> UniPoly<F> {
   pub fn from_evals(evals: &[F]) -> Self {
-    // we only support degree-2 or degree-3 univariate polynomials
-    assert!(evals.len() == 3 || evals.len() == 4);
-    let coeffs = if evals.len() == 3 {
+    // degree-2 and degree-3 univariate polynomials have closed forms; higher degrees are interpolated
+    assert!(evals.len() >= 3);
+    let coeffs = if evals.len() > 4 {
+      UniPoly::interpolate(evals)
+    } else if evals.len() == 3 {
       // ax^2 + bx + c
       let two_inv = F::from(2u64).inverse().unwrap();
 
@@ -53,6 +55,32 @@ impl<F: PrimeField> UniPoly<F> {
     UniPoly { coeffs }
   }
 
+  // Lagrange interpolation of the evaluations at 0, 1, ..., evals.len() - 1 into coefficients
+  fn interpolate(evals: &[F]) -> Vec<F> {
+    let n = evals.len();
+    let mut coeffs = vec![F::zero(); n];
+    for (i, eval) in evals.iter().enumerate() {
+      // the numerator prod_{j != i} (x - j) in coefficient form, and the denominator prod_{j != i} (i - j)
+      let mut basis = vec![F::one()];
+      let mut denom = F::one();
+      for j in (0..n).filter(|&j| j != i) {
+        let j_scalar = F::from(j as u64);
+        let mut next = vec![F::zero(); basis.len() + 1];
+        for (k, b) in basis.iter().enumerate() {
+          next[k + 1] += b;
+          next[k] -= j_scalar * b;
+        }
+        basis = next;
+        denom *= F::from(i as u64) - j_scalar;
+      }
+      let scale = *eval * denom.inverse().unwrap();
+      for (c, b) in coeffs.iter_mut().zip(basis.iter()) {
+        *c += scale * b;
+      }
+    }
+    coeffs
+  }
+
   pub fn degree(&self) -> usize {
     self.coeffs.len() - 1
   }
@@ -127,12 +155,8 @@ impl<G: ProjectiveCurve> AppendToTranscript<G> for UniPoly<G::ScalarField> {
 mod tests {
 
   use super::*;
-  use ark_bls12_381::Fr;
 
-  #[test]
-  fn test_from_evals_quad() {
-    test_from_evals_quad_helper::<Fr>()
-  }
+  crate::curves::curve_tests!(field: test_from_evals_quad, test_from_evals_quad_helper);
 
   fn test_from_evals_quad_helper<F: PrimeField>() {
     // polynomial is 2x^2 + 3x + 1
@@ -160,10 +184,7 @@ mod tests {
     assert_eq!(poly.evaluate(&F::from(3u64)), e3);
   }
 
-  #[test]
-  fn test_from_evals_cubic() {
-    test_from_evals_cubic_helper::<Fr>()
-  }
+  crate::curves::curve_tests!(field: test_from_evals_cubic, test_from_evals_cubic_helper);
   fn test_from_evals_cubic_helper<F: PrimeField>() {
     // polynomial is x^3 + 2x^2 + 3x + 1
     let e0 = F::one();
@@ -191,4 +212,34 @@ mod tests {
     let e4 = F::from(109u64);
     assert_eq!(poly.evaluate(&F::from(4u64)), e4);
   }
+
+  crate::curves::curve_tests!(
+    field: test_from_evals_quintic,
+    test_from_evals_quintic_helper
+  );
+
+  fn test_from_evals_quintic_helper<F: PrimeField>() {
+    // polynomial is x^5 + 3x^4 + 2x + 7
+    let coeffs = vec![
+      F::from(7u64),
+      F::from(2u64),
+      F::zero(),
+      F::zero(),
+      F::from(3u64),
+      F::one(),
+    ];
+    let poly = UniPoly {
+      coeffs: coeffs.clone(),
+    };
+    let evals = (0..6u64)
+      .map(|x| poly.evaluate(&F::from(x)))
+      .collect::<Vec<F>>();
+
+    let interpolated = UniPoly::from_evals(&evals);
+    assert_eq!(interpolated.coeffs, coeffs);
+
+    let hint = evals[0] + evals[1];
+    let decompressed_poly = interpolated.compress().decompress(&hint);
+    assert_eq!(decompressed_poly.coeffs, coeffs);
+  }
 }
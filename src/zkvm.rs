@@ -0,0 +1,621 @@
+use super::errors::R1CSError;
+use super::math::Math;
+use super::{InputsAssignment, Instance, LookupTable, VarsAssignment};
+use ark_ff::PrimeField;
+
+/// `Instruction` is an instruction of a minimal register machine whose registers and memory
+/// words hold field elements; memory is addressed by the value of a register
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+  /// sets `rd` to `rs1 + rs2`
+  Add {
+    /// the destination register
+    rd: usize,
+    /// the first source register
+    rs1: usize,
+    /// the second source register
+    rs2: usize,
+  },
+  /// sets `rd` to `rs1 - rs2`
+  Sub {
+    /// the destination register
+    rd: usize,
+    /// the first source register
+    rs1: usize,
+    /// the second source register
+    rs2: usize,
+  },
+  /// sets `rd` to `rs1 * rs2`
+  Mul {
+    /// the destination register
+    rd: usize,
+    /// the first source register
+    rs1: usize,
+    /// the second source register
+    rs2: usize,
+  },
+  /// sets `rd` to the constant `imm`
+  LoadImm {
+    /// the destination register
+    rd: usize,
+    /// the constant
+    imm: u64,
+  },
+  /// sets `rd` to the memory word at the address held in `rs1`
+  Load {
+    /// the destination register
+    rd: usize,
+    /// the register holding the address
+    rs1: usize,
+  },
+  /// sets the memory word at the address held in `rs1` to the value of `rs2`
+  Store {
+    /// the register holding the address
+    rs1: usize,
+    /// the register holding the value
+    rs2: usize,
+  },
+  /// jumps to the instruction at index `target` if `rs1` is not zero
+  Bnz {
+    /// the register holding the condition
+    rs1: usize,
+    /// the index of the instruction to jump to
+    target: usize,
+  },
+  /// stops the machine, which outputs the value of `rs1`
+  Halt {
+    /// the register holding the output
+    rs1: usize,
+  },
+}
+
+impl Instruction {
+  // the registers read into the first and second operand and the register written,
+  // each defaulting to register zero
+  fn registers(&self) -> (usize, usize, usize) {
+    match *self {
+      Instruction::Add { rd, rs1, rs2 }
+      | Instruction::Sub { rd, rs1, rs2 }
+      | Instruction::Mul { rd, rs1, rs2 } => (rs1, rs2, rd),
+      Instruction::LoadImm { rd, .. } => (0, 0, rd),
+      Instruction::Load { rd, rs1 } => (rs1, 0, rd),
+      Instruction::Store { rs1, rs2 } => (rs1, rs2, 0),
+      Instruction::Bnz { rs1, .. } | Instruction::Halt { rs1 } => (rs1, 0, 0),
+    }
+  }
+
+  fn writes_rd(&self) -> bool {
+    matches!(
+      self,
+      Instruction::Add { .. }
+        | Instruction::Sub { .. }
+        | Instruction::Mul { .. }
+        | Instruction::LoadImm { .. }
+        | Instruction::Load { .. }
+    )
+  }
+
+  fn accesses_memory(&self) -> bool {
+    matches!(self, Instruction::Load { .. } | Instruction::Store { .. })
+  }
+}
+
+// the number of auxiliary variables of a step in addition to the instruction selectors:
+// rs1 * rs2, the results of add, sub, mul, and load, the inverse of rs1, whether rs1 is not
+// zero, and the jump offset
+const NUM_STEP_AUX: usize = 8;
+
+/// `Program` holds the code and the initial memory of the register machine. Its execution for
+/// a fixed number of steps is proved with an `Instance` that constrains each step of the trace,
+/// a memory-checking argument over the registers and memory, and range lookups that order the
+/// memory accesses in time and keep addresses within the memory.
+///
+/// Each step reads the two source registers, accesses memory (a dummy access unless the step
+/// loads or stores), and writes the destination register, which is four memory accesses.
+/// The single public input is the output of the program, and the program must halt within the
+/// given number of steps; a halted machine stays at its `Halt` instruction.
+///
+/// **Proofs of a program's execution are not zero-knowledge**: the memory-checking argument and
+/// the range lookups reveal evaluations of the multilinear extensions of the trace at random
+/// points, so they only hide the trace to the extent that those evaluations do not determine it.
+#[derive(Clone, Debug)]
+pub struct Program<F> {
+  code: Vec<Instruction>,
+  num_regs: usize,
+  memory: Vec<F>,
+}
+
+// the variables holding the trace of a program, as laid out in the witness
+struct Layout {
+  num_steps: usize,
+  num_code: usize,
+}
+
+impl Layout {
+  fn num_ops(&self) -> usize {
+    4 * self.num_steps
+  }
+
+  // memory accesses, as four blocks of address, read value, read timestamp, and write value
+  fn addr(&self, op: usize) -> usize {
+    op
+  }
+
+  fn read_val(&self, op: usize) -> usize {
+    self.num_ops() + op
+  }
+
+  fn read_ts(&self, op: usize) -> usize {
+    2 * self.num_ops() + op
+  }
+
+  fn write_val(&self, op: usize) -> usize {
+    3 * self.num_ops() + op
+  }
+
+  // the distance between an access and the write it reads, range-checked to order accesses
+  fn ts_diff(&self, op: usize) -> usize {
+    4 * self.num_ops() + op
+  }
+
+  // the memory address accessed by a step, range-checked to lie within the memory
+  fn mem_addr(&self, step: usize) -> usize {
+    5 * self.num_ops() + step
+  }
+
+  fn selector(&self, step: usize, line: usize) -> usize {
+    5 * self.num_ops() + self.num_steps + step * (self.num_code + NUM_STEP_AUX) + line
+  }
+
+  fn aux(&self, step: usize, index: usize) -> usize {
+    self.selector(step, self.num_code) + index
+  }
+
+  fn num_vars(&self) -> usize {
+    self.selector(self.num_steps, 0)
+  }
+}
+
+impl<F: PrimeField> Program<F> {
+  /// Constructs a program from its code, the number of registers, and the initial memory,
+  /// which is padded with zeros to a power of two (at least 2) words
+  pub fn new(code: &[Instruction], num_regs: usize, memory: &[F]) -> Result<Self, R1CSError> {
+    let valid = |inst: &Instruction| {
+      let (rs1, rs2, rd) = inst.registers();
+      let target_valid = match inst {
+        Instruction::Bnz { target, .. } => *target < code.len(),
+        _ => true,
+      };
+      rs1 < num_regs && rs2 < num_regs && rd < num_regs && target_valid
+    };
+    if code.is_empty() || !code.iter().all(valid) {
+      return Err(R1CSError::InvalidProgram);
+    }
+
+    let mut memory = memory.to_vec();
+    memory.resize(memory.len().next_power_of_two().max(2), F::zero());
+    Ok(Program {
+      code: code.to_vec(),
+      num_regs,
+      memory,
+    })
+  }
+
+  fn layout(&self, num_steps: usize) -> Result<Layout, R1CSError> {
+    if num_steps < 2 || num_steps.next_power_of_two() != num_steps {
+      return Err(R1CSError::InvalidProgram);
+    }
+    Ok(Layout {
+      num_steps,
+      num_code: self.code.len(),
+    })
+  }
+
+  // registers are stored after the memory words
+  fn initial_cells(&self) -> Vec<F> {
+    let mut cells = self.memory.clone();
+    cells.extend(vec![F::zero(); self.num_regs]);
+    cells
+  }
+
+  /// Constructs an `Instance` whose satisfying assignments are the executions of the program
+  /// that halt within `num_steps` steps, a power of two (at least 2)
+  pub fn instance(&self, num_steps: usize) -> Result<Instance<F>, R1CSError> {
+    let layout = self.layout(num_steps)?;
+    let num_vars = layout.num_vars();
+    let (one, output) = (num_vars, num_vars + 1);
+    let reg_base = F::from(self.memory.len() as u64);
+    let f = |x: usize| F::from(x as u64);
+
+    let mut cons = Constraints::new();
+    for s in 0..num_steps {
+      let sel = |line: usize| layout.selector(s, line);
+      let aux = |index: usize| layout.aux(s, index);
+      let (p, t_add, t_sub, t_mul, t_load, inv, nz, jmp) = (
+        aux(0),
+        aux(1),
+        aux(2),
+        aux(3),
+        aux(4),
+        aux(5),
+        aux(6),
+        aux(7),
+      );
+
+      // linear combinations of the selectors that decode the current instruction
+      let decode = |field: &dyn Fn(usize, &Instruction) -> F| {
+        self
+          .code
+          .iter()
+          .enumerate()
+          .map(|(j, inst)| (sel(j), field(j, inst)))
+          .filter(|(_col, val)| !val.is_zero())
+          .collect::<Vec<(usize, F)>>()
+      };
+      let flag = |pred: &dyn Fn(&Instruction) -> bool| {
+        decode(&|_j, inst| if pred(inst) { F::one() } else { F::zero() })
+      };
+      let is_add = flag(&|inst| matches!(inst, Instruction::Add { .. }));
+      let is_sub = flag(&|inst| matches!(inst, Instruction::Sub { .. }));
+      let is_mul = flag(&|inst| matches!(inst, Instruction::Mul { .. }));
+      let is_load = flag(&|inst| matches!(inst, Instruction::Load { .. }));
+      let is_store = flag(&|inst| matches!(inst, Instruction::Store { .. }));
+      let is_halt = flag(&|inst| matches!(inst, Instruction::Halt { .. }));
+      let pc = decode(&|j, _inst| f(j));
+
+      // exactly one instruction is selected
+      for j in 0..self.code.len() {
+        cons.add(
+          &[(sel(j), F::one())],
+          &[(sel(j), F::one())],
+          &[(sel(j), F::one())],
+        );
+      }
+      cons.add(
+        &decode(&|_j, _inst| F::one()),
+        &[(one, F::one())],
+        &[(one, F::one())],
+      );
+
+      // every access reads a value written by an earlier access
+      let ops = (4 * s..4 * s + 4).collect::<Vec<usize>>();
+      for &i in ops.iter() {
+        cons.add(
+          &[(layout.ts_diff(i), F::one()), (layout.read_ts(i), F::one())],
+          &[(one, F::one())],
+          &[(one, f(i))],
+        );
+      }
+
+      // the first two accesses read the source registers
+      let (a, b) = (layout.read_val(ops[0]), layout.read_val(ops[1]));
+      for (k, field) in [(0, 0), (1, 1)] {
+        let mut reg = decode(&|_j, inst| {
+          let regs = inst.registers();
+          f(if field == 0 { regs.0 } else { regs.1 })
+        });
+        reg.push((one, reg_base));
+        cons.add(&[(layout.addr(ops[k]), F::one())], &[(one, F::one())], &reg);
+        cons.add(
+          &[(layout.write_val(ops[k]), F::one())],
+          &[(one, F::one())],
+          &[(layout.read_val(ops[k]), F::one())],
+        );
+      }
+
+      // the third access is to memory for loads and stores, and to word zero otherwise
+      let (m_read, m_write) = (layout.read_val(ops[2]), layout.write_val(ops[2]));
+      cons.add(
+        &flag(&|inst| inst.accesses_memory()),
+        &[(a, F::one())],
+        &[(layout.mem_addr(s), F::one())],
+      );
+      cons.add(
+        &[(layout.addr(ops[2]), F::one())],
+        &[(one, F::one())],
+        &[(layout.mem_addr(s), F::one())],
+      );
+      cons.add(
+        &is_store,
+        &[(b, F::one()), (m_read, -F::one())],
+        &[(m_write, F::one()), (m_read, -F::one())],
+      );
+
+      // results of the arithmetic instructions and loads
+      cons.add(&[(a, F::one())], &[(b, F::one())], &[(p, F::one())]);
+      cons.add(
+        &is_add,
+        &[(a, F::one()), (b, F::one())],
+        &[(t_add, F::one())],
+      );
+      cons.add(
+        &is_sub,
+        &[(a, F::one()), (b, -F::one())],
+        &[(t_sub, F::one())],
+      );
+      cons.add(&is_mul, &[(p, F::one())], &[(t_mul, F::one())]);
+      cons.add(&is_load, &[(m_read, F::one())], &[(t_load, F::one())]);
+
+      // the fourth access writes the result to the destination register, if any
+      let (rd_read, rd_write) = (layout.read_val(ops[3]), layout.write_val(ops[3]));
+      let mut rd = decode(&|_j, inst| f(inst.registers().2));
+      rd.push((one, reg_base));
+      cons.add(&[(layout.addr(ops[3]), F::one())], &[(one, F::one())], &rd);
+      let mut result = decode(&|_j, inst| match inst {
+        Instruction::LoadImm { imm, .. } => F::from(*imm),
+        _ => F::zero(),
+      });
+      result.extend([
+        (t_add, F::one()),
+        (t_sub, F::one()),
+        (t_mul, F::one()),
+        (t_load, F::one()),
+        (rd_read, -F::one()),
+      ]);
+      cons.add(
+        &flag(&|inst| inst.writes_rd()),
+        &result,
+        &[(rd_write, F::one()), (rd_read, -F::one())],
+      );
+
+      // nz is one if rs1 is not zero, and zero otherwise
+      cons.add(&[(a, F::one())], &[(inv, F::one())], &[(nz, F::one())]);
+      cons.add(&[(a, F::one())], &[(one, F::one()), (nz, -F::one())], &[]);
+
+      // control flow: a taken branch jumps to its target, halt stays, and other
+      // instructions fall through to the next one
+      let offsets = decode(&|j, inst| match inst {
+        Instruction::Bnz { target, .. } => f(*target) - f(j + 1),
+        _ => F::zero(),
+      });
+      cons.add(&[(nz, F::one())], &offsets, &[(jmp, F::one())]);
+      if s == 0 {
+        cons.add(&pc, &[(one, F::one())], &[]);
+      }
+      if s + 1 < num_steps {
+        let next_pc = self
+          .code
+          .iter()
+          .enumerate()
+          .map(|(j, _inst)| (layout.selector(s + 1, j), f(j)))
+          .collect::<Vec<(usize, F)>>();
+        let mut expected = pc.clone();
+        expected.push((one, F::one()));
+        expected.push((jmp, F::one()));
+        expected.extend(is_halt.iter().map(|&(col, val)| (col, -val)));
+        cons.add(&next_pc, &[(one, F::one())], &expected);
+      } else {
+        // the machine has halted and outputs rs1
+        cons.add(&is_halt, &[(one, F::one())], &[(one, F::one())]);
+        cons.add(&[(a, F::one())], &[(one, F::one())], &[(output, F::one())]);
+      }
+    }
+
+    let mut inst = Instance::new(cons.num_cons, num_vars, 1, &cons.A, &cons.B, &cons.C)?;
+    inst.add_memory(&self.initial_cells(), layout.addr(0), layout.num_ops())?;
+    inst.add_lookup(
+      LookupTable::range(layout.num_ops().log_2())?,
+      layout.ts_diff(0),
+      layout.num_ops(),
+    )?;
+    inst.add_lookup(
+      LookupTable::range(self.memory.len().log_2())?,
+      layout.mem_addr(0),
+      num_steps,
+    )?;
+    Ok(inst)
+  }
+
+  /// Runs the program for `num_steps` steps, a power of two (at least 2), and returns the
+  /// witness of its execution for the `Instance` produced by `Program::instance`, along with
+  /// the output of the program as the public input. Returns `R1CSError::ExecutionFault` if the
+  /// program accesses memory out of range, runs past its last instruction, or does not halt.
+  pub fn execute(
+    &self,
+    num_steps: usize,
+  ) -> Result<(VarsAssignment<F>, InputsAssignment<F>), R1CSError> {
+    let layout = self.layout(num_steps)?;
+    let mut vars = vec![F::zero(); layout.num_vars()];
+    let reg_base = self.memory.len();
+
+    let mut cells = self.initial_cells();
+    let mut ts = vec![0usize; cells.len()];
+    let mut access = |vars: &mut Vec<F>, i: usize, cell: usize, new: Option<F>| {
+      let old = cells[cell];
+      let val = new.unwrap_or(old);
+      vars[layout.addr(i)] = F::from(cell as u64);
+      vars[layout.read_val(i)] = old;
+      vars[layout.read_ts(i)] = F::from(ts[cell] as u64);
+      vars[layout.write_val(i)] = val;
+      vars[layout.ts_diff(i)] = F::from((i - ts[cell]) as u64);
+      cells[cell] = val;
+      ts[cell] = i + 1;
+      old
+    };
+
+    let mut pc = 0;
+    let mut output = F::zero();
+    for s in 0..num_steps {
+      let inst = self.code.get(pc).ok_or(R1CSError::ExecutionFault)?;
+      let (rs1, rs2, rd) = inst.registers();
+      vars[layout.selector(s, pc)] = F::one();
+
+      let a = access(&mut vars, 4 * s, reg_base + rs1, None);
+      let b = access(&mut vars, 4 * s + 1, reg_base + rs2, None);
+
+      let mem_addr = if inst.accesses_memory() {
+        to_index(&a, self.memory.len()).ok_or(R1CSError::ExecutionFault)?
+      } else {
+        0
+      };
+      vars[layout.mem_addr(s)] = F::from(mem_addr as u64);
+      let store = match inst {
+        Instruction::Store { .. } => Some(b),
+        _ => None,
+      };
+      let m = access(&mut vars, 4 * s + 2, mem_addr, store);
+
+      let aux = |index: usize| layout.aux(s, index);
+      vars[aux(0)] = a * b;
+      // the result, along with the auxiliary variable that holds it
+      let (index, result) = match *inst {
+        Instruction::Add { .. } => (Some(1), Some(a + b)),
+        Instruction::Sub { .. } => (Some(2), Some(a - b)),
+        Instruction::Mul { .. } => (Some(3), Some(a * b)),
+        Instruction::Load { .. } => (Some(4), Some(m)),
+        Instruction::LoadImm { imm, .. } => (None, Some(F::from(imm))),
+        _ => (None, None),
+      };
+      if let (Some(index), Some(val)) = (index, result) {
+        vars[aux(index)] = val;
+      }
+      access(&mut vars, 4 * s + 3, reg_base + rd, result);
+
+      let nz = !a.is_zero();
+      vars[aux(5)] = a.inverse().unwrap_or_else(F::zero);
+      vars[aux(6)] = if nz { F::one() } else { F::zero() };
+
+      pc = match *inst {
+        Instruction::Bnz { target, .. } if nz => {
+          vars[aux(7)] = F::from(target as u64) - F::from((pc + 1) as u64);
+          target
+        }
+        Instruction::Halt { .. } => {
+          output = a;
+          pc
+        }
+        _ => pc + 1,
+      };
+      if s + 1 == num_steps && !matches!(inst, Instruction::Halt { .. }) {
+        return Err(R1CSError::ExecutionFault);
+      }
+    }
+
+    Ok((
+      VarsAssignment { assignment: vars },
+      InputsAssignment {
+        assignment: vec![output],
+      },
+    ))
+  }
+}
+
+// returns the value as an index if it is smaller than bound
+fn to_index<F: PrimeField>(val: &F, bound: usize) -> Option<usize> {
+  let repr = val.into_repr();
+  let limbs = repr.as_ref();
+  if limbs[1..].iter().all(|&limb| limb == 0) && limbs[0] < bound as u64 {
+    Some(limbs[0] as usize)
+  } else {
+    None
+  }
+}
+
+// R1CS matrices built one constraint at a time
+struct Constraints<F> {
+  num_cons: usize,
+  A: Vec<(usize, usize, F)>,
+  B: Vec<(usize, usize, F)>,
+  C: Vec<(usize, usize, F)>,
+}
+
+impl<F: PrimeField> Constraints<F> {
+  fn new() -> Self {
+    Constraints {
+      num_cons: 0,
+      A: Vec::new(),
+      B: Vec::new(),
+      C: Vec::new(),
+    }
+  }
+
+  fn add(&mut self, a: &[(usize, F)], b: &[(usize, F)], c: &[(usize, F)]) {
+    let row = self.num_cons;
+    self.A.extend(a.iter().map(|&(col, val)| (row, col, val)));
+    self.B.extend(b.iter().map(|&(col, val)| (row, col, val)));
+    self.C.extend(c.iter().map(|&(col, val)| (row, col, val)));
+    self.num_cons += 1;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::{NIZKGens, NIZK};
+  use super::*;
+  use ark_ec::ProjectiveCurve;
+  use merlin::Transcript;
+
+  // computes the factorial of the first memory word through memory
+  fn factorial() -> Vec<Instruction> {
+    vec![
+      Instruction::LoadImm { rd: 3, imm: 0 },
+      Instruction::Load { rd: 0, rs1: 3 },
+      Instruction::LoadImm { rd: 1, imm: 1 },
+      Instruction::LoadImm { rd: 2, imm: 1 },
+      Instruction::Mul {
+        rd: 1,
+        rs1: 1,
+        rs2: 0,
+      },
+      Instruction::Sub {
+        rd: 0,
+        rs1: 0,
+        rs2: 2,
+      },
+      Instruction::Bnz { rs1: 0, target: 4 },
+      Instruction::LoadImm { rd: 3, imm: 1 },
+      Instruction::Store { rs1: 3, rs2: 1 },
+      Instruction::Load { rd: 0, rs1: 3 },
+      Instruction::Halt { rs1: 0 },
+    ]
+  }
+
+  crate::curves::curve_tests!(group: check_zkvm, check_zkvm_helper);
+
+  fn check_zkvm_helper<G: ProjectiveCurve>() {
+    let memory = [G::ScalarField::from(5u64)];
+    let program = Program::new(&factorial(), 4, &memory).unwrap();
+    let num_steps = 32;
+
+    let inst = program.instance(num_steps).unwrap();
+    let (vars, inputs) = program.execute(num_steps).unwrap();
+    assert_eq!(inputs.assignment, vec![G::ScalarField::from(120u64)]);
+    assert!(inst.is_sat(&vars, &inputs).unwrap());
+
+    // a different output does not satisfy the instance
+    let wrong = InputsAssignment::new(&[G::ScalarField::from(24u64)]).unwrap();
+    assert!(!inst.is_sat(&vars, &wrong).unwrap());
+
+    let gens = NIZKGens::<G>::new(
+      inst.inst.get_num_cons(),
+      inst.inst.get_num_vars(),
+      inst.inst.get_num_inputs(),
+    );
+    // the prover rejects a trace whose reads do not return the latest writes
+    let layout = program.layout(num_steps).unwrap();
+    let mut bad_vars = vars.clone();
+    bad_vars.assignment[layout.read_val(0)] += G::ScalarField::from(1u64);
+    assert!(matches!(
+      NIZK::prove(
+        &inst,
+        bad_vars,
+        &inputs,
+        &gens,
+        &mut Transcript::new(b"example")
+      ),
+      Err(R1CSError::InvalidMemory)
+    ));
+
+    let mut prover_transcript = Transcript::new(b"example");
+    let proof = NIZK::prove(&inst, vars, &inputs, &gens, &mut prover_transcript).unwrap();
+
+    let mut verifier_transcript = Transcript::new(b"example");
+    assert!(proof
+      .verify(&inst, &inputs, &mut verifier_transcript, &gens)
+      .is_ok());
+
+    // the program does not halt in fewer steps, and must be well-formed
+    assert!(program.execute(16).is_err());
+    assert!(Program::new(&[Instruction::Halt { rs1: 4 }], 4, &memory).is_err());
+  }
+}